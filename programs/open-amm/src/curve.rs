@@ -0,0 +1,365 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::stableswap::{
+    calc_d, calc_dy, calc_marginal_price, calc_x_at_price_ratio, get_token_decs_fac,
+    STABLESWAP_AMP_COEFFICIENT,
+};
+use crate::state::*;
+use crate::util::{
+    LADDER_STEP_DENOMINATOR, LP_FEE_BPS, ORDER_DENOMINATOR, ORDER_NUMERATORS, STABLESWAP_FEE_BPS,
+};
+use anchor_lang::prelude::*;
+use std::cmp;
+
+/// A bonding curve the pool's ladder is quoted along. Rungs walk outward from
+/// the current reserves, so `ladder_ask`/`ladder_bid` are called with
+/// increasing `i` and each returns the base and quote native amounts traded
+/// by that rung, or `None` if the side has nothing left to quote there. A
+/// curve that can't price a rung errors rather than quoting it at zero.
+pub trait Curve {
+    /// Fee charged on top of the curve's price, in bps.
+    fn fee_bps(&self) -> u16;
+
+    /// Quote native amount `base_amount` is worth at the curve's marginal
+    /// price for the reserves it was built from, before fees.
+    fn spot_value(&self, base_amount: u64) -> u64;
+
+    /// Base sold and quote received by the `i`th ask.
+    fn ladder_ask(&mut self, i: usize) -> Result<Option<(u64, u64)>>;
+
+    /// Base bought and quote paid by the `i`th bid.
+    fn ladder_bid(&mut self, i: usize) -> Result<Option<(u64, u64)>>;
+}
+
+/// Returns the curve for the pool's type, or `None` if either reserve is
+/// empty and there is nothing to quote. A depegged stable pool is quoted on
+/// its crisis amp, or on x*y=k if it has none, rather than near 1:1.
+pub fn get_curve(pool: &OpenAmmPool) -> Option<Box<dyn Curve>> {
+    match pool.pool_type {
+        PoolType::XYK => XykCurve::new(pool).map(|c| Box::new(c) as Box<dyn Curve>),
+        PoolType::STABLE if is_depegged(pool) => match pool.crisis_amp {
+            0 => XykCurve::new(pool).map(|c| Box::new(c) as Box<dyn Curve>),
+            amp => StableSwapCurve::new(pool, amp).map(|c| Box::new(c) as Box<dyn Curve>),
+        },
+        PoolType::STABLE => StableSwapCurve::new(pool, STABLESWAP_AMP_COEFFICIENT)
+            .map(|c| Box::new(c) as Box<dyn Curve>),
+    }
+}
+
+/// Fee of the curve `get_curve` quotes the pool on, in bps.
+pub fn get_curve_fee_bps(pool: &OpenAmmPool) -> u16 {
+    match pool.pool_type {
+        PoolType::STABLE if !is_depegged(pool) || pool.crisis_amp != 0 => STABLESWAP_FEE_BPS,
+        _ => LP_FEE_BPS,
+    }
+}
+
+/// Whether a stable pool's decimal-normalized reserves are further apart
+/// than its depeg threshold allows.
+pub fn is_depegged(pool: &OpenAmmPool) -> bool {
+    if pool.depeg_ratio_bps == 0 {
+        return false;
+    }
+    let (base_decs_fac, quote_decs_fac) =
+        get_token_decs_fac(pool.base_decimals, pool.quote_decimals);
+    let base_reserve = (pool.base_amount as u128)
+        .checked_mul(base_decs_fac.into())
+        .unwrap();
+    let quote_reserve = (pool.quote_amount as u128)
+        .checked_mul(quote_decs_fac.into())
+        .unwrap();
+    let (larger, smaller) = if base_reserve > quote_reserve {
+        (base_reserve, quote_reserve)
+    } else {
+        (quote_reserve, base_reserve)
+    };
+    larger.checked_mul(DEPEG_RATIO_DENOMINATOR.into()).unwrap()
+        > smaller.checked_mul(pool.depeg_ratio_bps.into()).unwrap()
+}
+
+pub struct XykCurve {
+    base_reserve: u64,
+    quote_reserve: u64,
+    ask_sizes: [u64; ORDER_NUMERATORS.len()],
+    bid_sizes: [u64; ORDER_NUMERATORS.len()],
+    last_ask_base: u64,
+    last_ask_quote: u64,
+    last_bid_base: u64,
+    last_bid_quote: u64,
+}
+
+impl XykCurve {
+    pub fn new(pool: &OpenAmmPool) -> Option<XykCurve> {
+        let base_reserve = pool.base_amount;
+        let quote_reserve = pool.quote_amount;
+        if base_reserve == 0 || quote_reserve == 0 {
+            return None;
+        }
+
+        Some(XykCurve {
+            base_reserve,
+            quote_reserve,
+            ask_sizes: get_xyk_ladder_sizes(
+                pool.ladder_spacing,
+                pool.ladder_step_bps,
+                base_reserve,
+            ),
+            bid_sizes: get_xyk_ladder_sizes(
+                pool.ladder_spacing,
+                pool.ladder_step_bps,
+                quote_reserve,
+            ),
+            last_ask_base: base_reserve,
+            last_ask_quote: quote_reserve,
+            last_bid_base: base_reserve,
+            last_bid_quote: quote_reserve,
+        })
+    }
+}
+
+impl Curve for XykCurve {
+    fn fee_bps(&self) -> u16 {
+        LP_FEE_BPS
+    }
+
+    fn spot_value(&self, base_amount: u64) -> u64 {
+        (base_amount as u128)
+            .checked_mul(self.quote_reserve.into())
+            .unwrap()
+            .checked_div(self.base_reserve.into())
+            .unwrap()
+            .try_into()
+            .unwrap_or(u64::MAX)
+    }
+
+    fn ladder_ask(&mut self, i: usize) -> Result<Option<(u64, u64)>> {
+        let a_size = self.ask_sizes[i];
+        let k = (self.last_ask_base as u128)
+            .checked_mul(self.last_ask_quote.into())
+            .unwrap();
+        let end_a_amount = self.last_ask_base.checked_sub(a_size).unwrap_or(0);
+        if end_a_amount == 0 || a_size == 0 {
+            return Ok(None);
+        }
+
+        let end_b_amount: u64 = k
+            .checked_div(end_a_amount.into())
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let b_size = end_b_amount.checked_sub(self.last_ask_quote).unwrap();
+
+        self.last_ask_base = end_a_amount;
+        self.last_ask_quote = end_b_amount;
+        Ok(Some((a_size, b_size)))
+    }
+
+    fn ladder_bid(&mut self, i: usize) -> Result<Option<(u64, u64)>> {
+        let b_size = self.bid_sizes[i];
+        let k = (self.last_bid_base as u128)
+            .checked_mul(self.last_bid_quote.into())
+            .unwrap();
+        let end_b_amount = self.last_bid_quote.checked_sub(b_size).unwrap_or(0);
+        if end_b_amount == 0 {
+            return Ok(None);
+        }
+
+        let end_a_amount: u64 = k
+            .checked_div(end_b_amount.into())
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let a_size = end_a_amount.checked_sub(self.last_bid_base).unwrap();
+        if a_size == 0 {
+            return Ok(None);
+        }
+
+        self.last_bid_base = end_a_amount;
+        self.last_bid_quote = end_b_amount;
+        Ok(Some((a_size, b_size)))
+    }
+}
+
+/// Walks the stableswap invariant in decimal-normalized units and converts
+/// each rung back to native amounts.
+pub struct StableSwapCurve {
+    amp: u64,
+    base_decs_fac: u64,
+    quote_decs_fac: u64,
+    d: u64,
+    base_reserve: u64,
+    quote_reserve: u64,
+    ask_sizes: [u64; ORDER_NUMERATORS.len()],
+    bid_sizes: [u64; ORDER_NUMERATORS.len()],
+    last_ask_base: u64,
+    last_ask_quote: u64,
+    last_bid_base: u64,
+    last_bid_quote: u64,
+}
+
+impl StableSwapCurve {
+    pub fn new(pool: &OpenAmmPool, amp: u64) -> Option<StableSwapCurve> {
+        let (base_decs_fac, quote_decs_fac) =
+            get_token_decs_fac(pool.base_decimals, pool.quote_decimals);
+        let base_reserve = pool.base_amount.checked_mul(base_decs_fac).unwrap();
+        let quote_reserve = pool.quote_amount.checked_mul(quote_decs_fac).unwrap();
+        if base_reserve == 0 || quote_reserve == 0 {
+            return None;
+        }
+
+        let d = calc_d(base_reserve, quote_reserve, amp).unwrap();
+        Some(StableSwapCurve {
+            amp,
+            base_decs_fac,
+            quote_decs_fac,
+            d,
+            base_reserve,
+            quote_reserve,
+            ask_sizes: get_stableswap_ladder_sizes(
+                pool.ladder_spacing,
+                pool.ladder_step_bps,
+                base_reserve,
+                quote_reserve,
+                amp,
+                d,
+            ),
+            bid_sizes: get_stableswap_ladder_sizes(
+                pool.ladder_spacing,
+                pool.ladder_step_bps,
+                quote_reserve,
+                base_reserve,
+                amp,
+                d,
+            ),
+            last_ask_base: base_reserve,
+            last_ask_quote: quote_reserve,
+            last_bid_base: base_reserve,
+            last_bid_quote: quote_reserve,
+        })
+    }
+}
+
+impl Curve for StableSwapCurve {
+    fn fee_bps(&self) -> u16 {
+        STABLESWAP_FEE_BPS
+    }
+
+    fn spot_value(&self, base_amount: u64) -> u64 {
+        let price = calc_marginal_price(self.base_reserve, self.quote_reserve, self.amp, self.d);
+        (base_amount.checked_mul(self.base_decs_fac).unwrap() as f64 * price
+            / self.quote_decs_fac as f64) as u64
+    }
+
+    fn ladder_ask(&mut self, i: usize) -> Result<Option<(u64, u64)>> {
+        let a_size = self.ask_sizes[i];
+        let end_a_amount = self.last_ask_base.checked_sub(a_size).unwrap_or(0);
+        if end_a_amount == 0 || a_size == 0 {
+            return Ok(None);
+        }
+
+        let b_size = calc_dy(
+            self.last_ask_base,
+            self.last_ask_quote,
+            self.amp,
+            self.d,
+            a_size,
+        )
+        .ok_or(OpenAmmErrorCode::NewtonNoConvergence)?;
+
+        self.last_ask_base = end_a_amount;
+        self.last_ask_quote += b_size;
+        let (a_size, b_size) = (a_size / self.base_decs_fac, b_size / self.quote_decs_fac);
+        if a_size == 0 {
+            return Ok(None);
+        }
+        Ok(Some((a_size, b_size)))
+    }
+
+    fn ladder_bid(&mut self, i: usize) -> Result<Option<(u64, u64)>> {
+        let b_size = self.bid_sizes[i];
+        let end_b_amount = self.last_bid_quote.checked_sub(b_size).unwrap_or(0);
+        if end_b_amount == 0 || b_size == 0 {
+            return Ok(None);
+        }
+
+        let a_size = calc_dy(
+            self.last_bid_quote,
+            self.last_bid_base,
+            self.amp,
+            self.d,
+            b_size,
+        )
+        .ok_or(OpenAmmErrorCode::NewtonNoConvergence)?;
+
+        self.last_bid_base += a_size;
+        self.last_bid_quote = end_b_amount;
+        let (a_size, b_size) = (a_size / self.base_decs_fac, b_size / self.quote_decs_fac);
+        if a_size == 0 {
+            return Ok(None);
+        }
+        Ok(Some((a_size, b_size)))
+    }
+}
+
+fn get_linear_ladder_sizes(reserve: u64) -> [u64; ORDER_NUMERATORS.len()] {
+    let mut sizes = [0; ORDER_NUMERATORS.len()];
+    for i in 0..ORDER_NUMERATORS.len() {
+        sizes[i] = (reserve as u128)
+            .checked_mul(ORDER_NUMERATORS[i].into())
+            .unwrap()
+            .checked_div(ORDER_DENOMINATOR.into())
+            .unwrap()
+            .try_into()
+            .unwrap();
+    }
+    sizes
+}
+
+/// On x*y=k, moving the price by a factor of r shrinks the reserve being sold
+/// by a factor of sqrt(r), so geometric rungs have a closed form.
+fn get_xyk_ladder_sizes(
+    ladder_spacing: LadderSpacing,
+    ladder_step_bps: u16,
+    reserve: u64,
+) -> [u64; ORDER_NUMERATORS.len()] {
+    match ladder_spacing {
+        LadderSpacing::LINEAR => get_linear_ladder_sizes(reserve),
+        LadderSpacing::GEOMETRIC => {
+            let step = 1.0 + ladder_step_bps as f64 / LADDER_STEP_DENOMINATOR as f64;
+            let mut sizes = [0; ORDER_NUMERATORS.len()];
+            let mut last_reserve = reserve;
+            for i in 0..ORDER_NUMERATORS.len() {
+                let next_reserve = (reserve as f64 / step.powi(i as i32 + 1).sqrt()) as u64;
+                sizes[i] = last_reserve.checked_sub(next_reserve).unwrap_or(0);
+                last_reserve = cmp::min(last_reserve, next_reserve);
+            }
+            sizes
+        }
+    }
+}
+
+/// Amounts passed in (and returned) must already have their decimals normalized.
+fn get_stableswap_ladder_sizes(
+    ladder_spacing: LadderSpacing,
+    ladder_step_bps: u16,
+    reserve: u64,
+    other_reserve: u64,
+    amp: u64,
+    d: u64,
+) -> [u64; ORDER_NUMERATORS.len()] {
+    match ladder_spacing {
+        LadderSpacing::LINEAR => get_linear_ladder_sizes(reserve),
+        LadderSpacing::GEOMETRIC => {
+            let step = 1.0 + ladder_step_bps as f64 / LADDER_STEP_DENOMINATOR as f64;
+            let mut sizes = [0; ORDER_NUMERATORS.len()];
+            let mut last_reserve = reserve;
+            for i in 0..ORDER_NUMERATORS.len() {
+                let next_reserve =
+                    calc_x_at_price_ratio(reserve, other_reserve, amp, d, step.powi(i as i32 + 1))
+                        .unwrap_or(last_reserve);
+                sizes[i] = last_reserve.checked_sub(next_reserve).unwrap_or(0);
+                last_reserve = cmp::min(last_reserve, next_reserve);
+            }
+            sizes
+        }
+    }
+}
@@ -20,4 +20,138 @@ pub enum OpenAmmErrorCode {
     MarketMakingAlreadyActive,
     #[msg("OpenAmmErrorCode::OpenOrdersTokensLocked - Open orders tokens are locked")]
     OpenOrdersTokensLocked,
+    #[msg("OpenAmmErrorCode::InvalidLadderStep - Ladder step is invalid for the spacing mode")]
+    InvalidLadderStep,
+    #[msg("OpenAmmErrorCode::FeeRouterDisabled - Fee router is not configured for pool")]
+    FeeRouterDisabled,
+    #[msg("OpenAmmErrorCode::FeeProcessingTooSoon - Fee processing interval has not elapsed")]
+    FeeProcessingTooSoon,
+    #[msg("OpenAmmErrorCode::WrongFeeDistributionAccount - Wrong fee distribution account for pool")]
+    WrongFeeDistributionAccount,
+    #[msg("OpenAmmErrorCode::InsufficientPositionLp - Position does not hold enough LP")]
+    InsufficientPositionLp,
+    #[msg("OpenAmmErrorCode::MissingOpenOrdersAuthority - Permissioned market requires its open orders authority to sign")]
+    MissingOpenOrdersAuthority,
+    #[msg("OpenAmmErrorCode::InvalidSecondaryShare - Secondary market share is out of range")]
+    InvalidSecondaryShare,
+    #[msg("OpenAmmErrorCode::SecondaryMarketAlreadyRegistered - Pool already has a secondary market")]
+    SecondaryMarketAlreadyRegistered,
+    #[msg("OpenAmmErrorCode::MissingBookPrice - Market has no bid or no ask to price against")]
+    MissingBookPrice,
+    #[msg("OpenAmmErrorCode::RebalanceNotNeeded - Pool inventory skew is below the threshold")]
+    RebalanceNotNeeded,
+    #[msg("OpenAmmErrorCode::PoolLocked - Pool is already in the middle of an instruction")]
+    PoolLocked,
+    #[msg("OpenAmmErrorCode::ReconciliationPending - Pool funds are still on the book, crank the event queue and retry")]
+    ReconciliationPending,
+    #[msg("OpenAmmErrorCode::RefreshTooSoon - Minimum slots between refreshes have not elapsed")]
+    RefreshTooSoon,
+    #[msg("OpenAmmErrorCode::MetadataTooLong - Pool metadata uri is too long")]
+    MetadataTooLong,
+    #[msg("OpenAmmErrorCode::PoolNotStale - Pool has been refreshed too recently for safe mode")]
+    PoolNotStale,
+    #[msg("OpenAmmErrorCode::TooManyKeeperPools - Keeper has no room to register another pool")]
+    TooManyKeeperPools,
+    #[msg("OpenAmmErrorCode::KeeperPoolAlreadyRegistered - Pool is already registered with this keeper")]
+    KeeperPoolAlreadyRegistered,
+    #[msg("OpenAmmErrorCode::WrongKeeperClaimAccounts - Keeper claim accounts don't match a registered pool")]
+    WrongKeeperClaimAccounts,
+    #[msg("OpenAmmErrorCode::InvalidOrdersPerSide - Orders per side must be between 2 and MAX_ORDERS_PER_SIDE")]
+    InvalidOrdersPerSide,
+    #[msg("OpenAmmErrorCode::NotLegacyPool - Pool account doesn't have the legacy layout")]
+    NotLegacyPool,
+    #[msg("OpenAmmErrorCode::MissingOracleAccount - Pool has an oracle configured but it wasn't passed")]
+    MissingOracleAccount,
+    #[msg("OpenAmmErrorCode::InvalidOracleBand - Oracle band must be between 1 and 10000 bps")]
+    InvalidOracleBand,
+    #[msg("OpenAmmErrorCode::InvalidJitter - Jitter must be at most MAX_JITTER_BPS")]
+    InvalidJitter,
+    #[msg("OpenAmmErrorCode::InvalidVolatilityFee - Max volatility fee must be at most MAX_VOLATILITY_FEE_BPS")]
+    InvalidVolatilityFee,
+    #[msg("OpenAmmErrorCode::InvalidDepegFallback - Depeg ratio must exceed 1:1 and the crisis amp must be below the pool's amp")]
+    InvalidDepegFallback,
+    #[msg("OpenAmmErrorCode::InitialLiquidityTooSmall - Initial deposit doesn't cover MINIMUM_LIQUIDITY")]
+    InitialLiquidityTooSmall,
+    #[msg("OpenAmmErrorCode::LpSupplyOverflow - Deposit would mint more LP tokens than the supply can hold")]
+    LpSupplyOverflow,
+    #[msg("OpenAmmErrorCode::ZeroLpMinted - Deposit is too small to mint any LP tokens")]
+    ZeroLpMinted,
+    #[msg("OpenAmmErrorCode::PoolNotBootstrapped - Pool has no liquidity yet, it must be initialized first")]
+    PoolNotBootstrapped,
+    #[msg("OpenAmmErrorCode::PoolAlreadyBootstrapped - Pool already has liquidity")]
+    PoolAlreadyBootstrapped,
+    #[msg("OpenAmmErrorCode::WrongBootstrapper - Signer isn't the pool's bootstrapper")]
+    WrongBootstrapper,
+    #[msg("OpenAmmErrorCode::WrongPendingAuthority - Signer isn't the pool's pending authority")]
+    WrongPendingAuthority,
+    #[msg("OpenAmmErrorCode::NotGuardian - Signer is neither the pool's guardian nor its authority")]
+    NotGuardian,
+    #[msg("OpenAmmErrorCode::WithdrawNotClaimable - No ladder has been placed since the withdraw was requested")]
+    WithdrawNotClaimable,
+    #[msg("OpenAmmErrorCode::InvalidKeeperRefund - Keeper refund must be at most MAX_KEEPER_REFUND_BPS")]
+    InvalidKeeperRefund,
+    #[msg("OpenAmmErrorCode::InvalidDeleverage - Deleverage is capped at MAX_DELEVERAGE_BPS")]
+    InvalidDeleverage,
+    #[msg("OpenAmmErrorCode::MissingAnchorPoolAccount - The pool's anchor pool was not passed")]
+    MissingAnchorPoolAccount,
+    #[msg("OpenAmmErrorCode::InvalidAnchorPool - Only x*y=k pools can anchor to another pool")]
+    InvalidAnchorPool,
+    #[msg("OpenAmmErrorCode::InvalidUnderlyingPool - Metapools need a stable pool quoted in its LP token")]
+    InvalidUnderlyingPool,
+    #[msg("OpenAmmErrorCode::UnderlyingPoolAlreadyRegistered - Pool already has an underlying pool")]
+    UnderlyingPoolAlreadyRegistered,
+    #[msg("OpenAmmErrorCode::WrongUnderlyingPool - Accounts don't match the pool's underlying pool")]
+    WrongUnderlyingPool,
+    #[msg("OpenAmmErrorCode::PoolAlreadyRegistered - Pool is already in the market's registry")]
+    PoolAlreadyRegistered,
+    #[msg("OpenAmmErrorCode::TooManyRegistryPools - Registry has no room for another pool")]
+    TooManyRegistryPools,
+    #[msg("OpenAmmErrorCode::DivisionByZero - Division by zero")]
+    DivisionByZero,
+    #[msg("OpenAmmErrorCode::PriceOverflow - Price does not fit in a u64")]
+    PriceOverflow,
+    #[msg("OpenAmmErrorCode::LotConversionUnderflow - Order grew between placement and reconciliation")]
+    LotConversionUnderflow,
+    #[msg("OpenAmmErrorCode::NewtonNoConvergence - Stableswap solver did not converge")]
+    NewtonNoConvergence,
+    #[msg("OpenAmmErrorCode::WrongEventQueueAccount - Event queue does not belong to the market")]
+    WrongEventQueueAccount,
+    #[msg("OpenAmmErrorCode::WrongRegistryPools - Accounts passed are not the registry's pools in order")]
+    WrongRegistryPools,
+    #[msg("OpenAmmErrorCode::InvalidQuotingSchedule - Quoting hours must be within a day and the cool-down fill at most 10000 bps")]
+    InvalidQuotingSchedule,
+    #[msg("OpenAmmErrorCode::InvalidDepositCap - Deposit cap must be at most 10000 bps of the LP supply")]
+    InvalidDepositCap,
+    #[msg("OpenAmmErrorCode::DepositCapExceeded - Deposit is above the pool's cap while its price is off the oracle")]
+    DepositCapExceeded,
+    #[msg("OpenAmmErrorCode::InvalidWithdrawDelay - Withdraw delay must be at most MAX_WITHDRAW_DELAY_SLOTS")]
+    InvalidWithdrawDelay,
+    #[msg("OpenAmmErrorCode::WithdrawDelayRequired - Pool has a withdraw delay, withdraw with request_withdraw instead")]
+    WithdrawDelayRequired,
+    #[msg("OpenAmmErrorCode::WithdrawDelayNotElapsed - The pool's withdraw delay has not elapsed since the request")]
+    WithdrawDelayNotElapsed,
+    #[msg("OpenAmmErrorCode::WrongMigrationPool - Liquidity can only move between two pools on the same pair, for the same signer")]
+    WrongMigrationPool,
+    #[msg("OpenAmmErrorCode::SlippageLpExceeded - Deposit minted fewer LP tokens than the minimum")]
+    SlippageLpExceeded,
+    #[msg("OpenAmmErrorCode::InvalidComputeBudget - Compute budget is above the transaction limit or prices placing orders at zero")]
+    InvalidComputeBudget,
+    #[msg("OpenAmmErrorCode::WrongDexProgram - Not the dex program the pool was created on")]
+    WrongDexProgram,
+    #[msg("OpenAmmErrorCode::WrongDelegate - Pool is not the token account's delegate")]
+    WrongDelegate,
+    #[msg("OpenAmmErrorCode::InvalidRungFees - Rung fees fall with depth or exceed the cap")]
+    InvalidRungFees,
+    #[msg("OpenAmmErrorCode::WrongSnapshotSupply - Snapshot's LP amounts must sum to the current LP supply")]
+    WrongSnapshotSupply,
+    #[msg("OpenAmmErrorCode::InvalidAirdropProof - Merkle proof does not match the snapshot's root")]
+    InvalidAirdropProof,
+    #[msg("OpenAmmErrorCode::WrongRelayer - Signer is not the relayer the deposit intent names")]
+    WrongRelayer,
+    #[msg("OpenAmmErrorCode::DepositIntentExpired - Deposit intent's expiry slot has passed")]
+    DepositIntentExpired,
+    #[msg("OpenAmmErrorCode::MarketMakingInactive - Market making is halted until it is restarted")]
+    MarketMakingInactive,
+    #[msg("OpenAmmErrorCode::LotSizeMismatch - Market's lot sizes do not match the ones cached on the pool")]
+    LotSizeMismatch,
 }
@@ -0,0 +1,441 @@
+//! Model-based fuzzing of the pool's accounting. Random sequences of
+//! deposits, withdrawals, taker fills and refreshes are replayed against the
+//! program's own reconciliation, ladder and LP math, and against a plain
+//! reference AMM that is told about every fill as it happens. After each step
+//! the two must agree on reserves, LP supply and keeper refunds, the program's
+//! reserves must add up to what its vaults hold, and the ladder it quotes must
+//! be one the reserves can pay for.
+//!
+//! The dex is simulated by a book of resting orders that takers walk from the
+//! best price. Withdrawals always take the cancel-everything path rather than
+//! trimming rungs. Set `OPENAMM_FUZZ_RUNS` to replay more seeds.
+
+use crate::state::*;
+use crate::util::{
+    get_ladder, get_xyk_lp_minted, reconcile_orders, CurrentOrder, PROTOCOL_FEE_BPS,
+};
+use anchor_lang::__private::bytemuck::Zeroable;
+use serum_dex::matching::Side;
+
+// Fine enough ticks that the LP fee always separates the best ask from the
+// best bid at the prices the harness trades at
+const BASE_LOT_SIZE: u64 = 10_000;
+const QUOTE_LOT_SIZE: u64 = 1;
+const DEFAULT_RUNS: u64 = 64;
+const STEPS_PER_RUN: usize = 200;
+
+/// xorshift64*, so every failure replays from its seed.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct RestingOrder {
+    side: Side,
+    client_order_id: u64,
+    limit_price: u64,
+    base_lots: u64,
+}
+
+/// What the pool is owed for one of its orders, accrued fill by fill.
+#[derive(Clone, Copy, Default)]
+struct ModelOrder {
+    client_order_id: u64,
+    sold_base: bool,
+    filled_base: u64,
+    filled_quote: u64,
+}
+
+/// The reference AMM: reserves and LP supply kept by the book rather than by
+/// diffing placed orders against what's left of them.
+#[derive(Default)]
+struct Model {
+    base_amount: u64,
+    quote_amount: u64,
+    lp_supply: u64,
+    refund_base_amount: u64,
+    refund_quote_amount: u64,
+    keeper_refund_bps: u64,
+    orders: Vec<ModelOrder>,
+}
+
+impl Model {
+    fn record_fill(&mut self, order: &RestingOrder, base_lots: u64) {
+        let model_order = match self
+            .orders
+            .iter_mut()
+            .find(|o| o.client_order_id == order.client_order_id)
+        {
+            Some(model_order) => model_order,
+            None => {
+                self.orders.push(ModelOrder {
+                    client_order_id: order.client_order_id,
+                    sold_base: order.side == Side::Ask,
+                    ..ModelOrder::default()
+                });
+                self.orders.last_mut().unwrap()
+            }
+        };
+        model_order.filled_base += base_lots * BASE_LOT_SIZE;
+        model_order.filled_quote += base_lots * order.limit_price * QUOTE_LOT_SIZE;
+    }
+
+    /// Credits every order's fills: the LP keeps the proceeds less the
+    /// protocol's cut and the keeper's refund, both taken from what came in.
+    fn settle(&mut self) -> (u64, u64) {
+        let mut protocol_fees = (0, 0);
+        for order in self.orders.drain(..) {
+            let amount_in = if order.sold_base {
+                order.filled_quote
+            } else {
+                order.filled_base
+            };
+            let protocol_fee = amount_in * PROTOCOL_FEE_BPS as u64 / 10_000;
+            let refund = amount_in * self.keeper_refund_bps / 10_000;
+            if order.sold_base {
+                self.base_amount -= order.filled_base;
+                self.quote_amount += amount_in - protocol_fee - refund;
+                self.refund_quote_amount += refund;
+                protocol_fees.1 += protocol_fee;
+            } else {
+                self.quote_amount -= order.filled_quote;
+                self.base_amount += amount_in - protocol_fee - refund;
+                self.refund_base_amount += refund;
+                protocol_fees.0 += protocol_fee;
+            }
+        }
+        protocol_fees
+    }
+
+    fn deposit(&mut self, base_amount: u64, quote_amount: u64) -> u64 {
+        let lp = std::cmp::min(
+            self.lp_supply as u128 * base_amount as u128 / self.base_amount as u128,
+            self.lp_supply as u128 * quote_amount as u128 / self.quote_amount as u128,
+        ) as u64;
+        self.base_amount += base_amount;
+        self.quote_amount += quote_amount;
+        self.lp_supply += lp;
+        lp
+    }
+
+    fn withdraw(&mut self, lp: u64) -> (u64, u64) {
+        let base = (self.base_amount as u128 * lp as u128 / self.lp_supply as u128) as u64;
+        let quote = (self.quote_amount as u128 * lp as u128 / self.lp_supply as u128) as u64;
+        self.base_amount -= base;
+        self.quote_amount -= quote;
+        self.lp_supply -= lp;
+        (base, quote)
+    }
+}
+
+struct Harness {
+    rng: Rng,
+    pool: OpenAmmPool,
+    lp_supply: u64,
+    book: Vec<RestingOrder>,
+    model: Model,
+    /// Tokens actually held by the pool, in its vaults or on the book.
+    vault_base: u64,
+    vault_quote: u64,
+}
+
+impl Harness {
+    fn new(seed: u64) -> Harness {
+        let mut rng = Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1);
+        let base_amount = 1_000_000 + rng.below(1_000_000_000);
+        let quote_amount = base_amount / 2 + rng.below(base_amount * 3 / 2);
+        let keeper_refund_bps = rng.below(11);
+
+        let mut pool = OpenAmmPool::zeroed();
+        pool.base_amount = base_amount;
+        pool.quote_amount = quote_amount;
+        pool.orders_per_side = LEGACY_ORDERS_PER_SIDE as u8;
+        pool.keeper_refund_bps = keeper_refund_bps as u16;
+        pool.mm_active = true;
+        let lp_supply = get_xyk_lp_minted(0, 0, 0, base_amount, quote_amount).unwrap();
+
+        let mut harness = Harness {
+            rng,
+            pool,
+            lp_supply,
+            book: vec![],
+            model: Model {
+                base_amount,
+                quote_amount,
+                lp_supply,
+                keeper_refund_bps,
+                ..Model::default()
+            },
+            vault_base: base_amount,
+            vault_quote: quote_amount,
+        };
+        harness.place_ladder();
+        harness
+    }
+
+    /// Reconciles and cancels the pool's orders, the way every deposit,
+    /// withdrawal and refresh starts.
+    fn cancel_and_settle(&mut self) {
+        let orders = self
+            .book
+            .drain(..)
+            .map(|o| CurrentOrder {
+                side: o.side,
+                client_order_id: o.client_order_id,
+                limit_price: o.limit_price,
+                base_qty: o.base_lots,
+            })
+            .collect::<Vec<_>>();
+        let reconciliation = reconcile_orders(
+            &orders,
+            BASE_LOT_SIZE,
+            QUOTE_LOT_SIZE,
+            false,
+            false,
+            &mut self.pool,
+        )
+        .unwrap();
+        let model_protocol_fees = self.model.settle();
+
+        // Protocol fees are swept to the fee vaults
+        assert_eq!(
+            (
+                reconciliation.protocol_fee_base_amount,
+                reconciliation.protocol_fee_quote_amount
+            ),
+            model_protocol_fees
+        );
+        self.vault_base -= reconciliation.protocol_fee_base_amount;
+        self.vault_quote -= reconciliation.protocol_fee_quote_amount;
+    }
+
+    fn place_ladder(&mut self) {
+        if !self.pool.mm_active {
+            return;
+        }
+        let ladder = get_ladder(&self.pool, BASE_LOT_SIZE, QUOTE_LOT_SIZE, None, None).unwrap();
+        self.check_ladder(&ladder);
+        for order in ladder {
+            let client_order_id = self.pool.client_order_id;
+            self.pool.client_order_id += 1;
+            let placed = PlacedOrder {
+                limit_price: order.limit_price,
+                base_qty: order.base_qty,
+                max_native_quote_qty_including_fees: order.max_native_quote_qty_including_fees,
+                client_order_id,
+                fee_bps: order.fee_bps.into(),
+            };
+            match order.side {
+                Side::Ask => self.pool.placed_asks.set(order.rung, placed),
+                Side::Bid => self.pool.placed_bids.set(order.rung, placed),
+            }
+            self.book.push(RestingOrder {
+                side: order.side,
+                client_order_id,
+                limit_price: order.limit_price,
+                base_lots: order.base_qty,
+            });
+        }
+    }
+
+    /// The ladder must never cross itself or promise more than the reserves.
+    fn check_ladder(&self, ladder: &[crate::util::LadderOrder]) {
+        let asks = ladder.iter().filter(|o| o.side == Side::Ask);
+        let bids = ladder.iter().filter(|o| o.side == Side::Bid);
+        let lowest_ask = asks.clone().map(|o| o.limit_price).min();
+        let highest_bid = bids.clone().map(|o| o.limit_price).max();
+        if let (Some(lowest_ask), Some(highest_bid)) = (lowest_ask, highest_bid) {
+            assert!(lowest_ask > highest_bid, "ladder crosses itself");
+        }
+        let ask_base: u64 = asks.map(|o| o.base_qty * BASE_LOT_SIZE).sum();
+        let bid_quote: u64 = bids
+            .map(|o| o.base_qty * o.limit_price * QUOTE_LOT_SIZE)
+            .sum();
+        assert!(
+            ask_base <= self.pool.base_amount,
+            "asks exceed base reserve"
+        );
+        assert!(
+            bid_quote <= self.pool.quote_amount,
+            "bids exceed quote reserve"
+        );
+    }
+
+    fn refresh(&mut self) {
+        if !self.pool.mm_active {
+            return;
+        }
+        self.cancel_and_settle();
+        self.place_ladder();
+    }
+
+    /// A taker walks the book from the best price on one side.
+    fn take(&mut self) {
+        let side = if self.rng.below(2) == 0 {
+            Side::Ask
+        } else {
+            Side::Bid
+        };
+        let mut lots = 1 + self.rng.below(20_000);
+        let mut resting = self
+            .book
+            .iter()
+            .enumerate()
+            .filter(|(_, o)| o.side == side)
+            .map(|(i, o)| (i, o.limit_price))
+            .collect::<Vec<_>>();
+        resting.sort_by_key(|&(_, price)| match side {
+            Side::Ask => price,
+            Side::Bid => u64::MAX - price,
+        });
+        for (i, _) in resting {
+            if lots == 0 {
+                break;
+            }
+            let order = self.book[i];
+            let filled = std::cmp::min(lots, order.base_lots);
+            lots -= filled;
+            self.book[i].base_lots -= filled;
+            self.model.record_fill(&order, filled);
+            let base = filled * BASE_LOT_SIZE;
+            let quote = filled * order.limit_price * QUOTE_LOT_SIZE;
+            match side {
+                Side::Ask => {
+                    self.vault_base -= base;
+                    self.vault_quote += quote;
+                }
+                Side::Bid => {
+                    self.vault_base += base;
+                    self.vault_quote -= quote;
+                }
+            }
+        }
+        // Fully filled orders leave the book
+        self.book.retain(|o| o.base_lots > 0);
+    }
+
+    fn deposit(&mut self) {
+        if !self.pool.mm_active {
+            return;
+        }
+        self.cancel_and_settle();
+        let base_amount = 1 + self.rng.below(self.pool.base_amount);
+        let quote_amount = (base_amount as u128 * self.pool.quote_amount as u128
+            / self.pool.base_amount as u128) as u64;
+        let lp = get_xyk_lp_minted(
+            self.lp_supply,
+            self.pool.base_amount,
+            self.pool.quote_amount,
+            base_amount,
+            quote_amount,
+        )
+        .unwrap();
+        if lp > 0 {
+            assert_eq!(lp, self.model.deposit(base_amount, quote_amount));
+            self.pool.base_amount += base_amount;
+            self.pool.quote_amount += quote_amount;
+            self.lp_supply += lp;
+            self.vault_base += base_amount;
+            self.vault_quote += quote_amount;
+        }
+        self.place_ladder();
+    }
+
+    fn withdraw(&mut self) {
+        if !self.pool.mm_active {
+            return;
+        }
+        self.cancel_and_settle();
+        // Leave some liquidity behind so there's always a pool to quote
+        let lp = self.rng.below(self.lp_supply / 2);
+        let (base_amount, quote_amount) = self.pool.get_lp_share_amounts(lp, self.lp_supply);
+        assert_eq!((base_amount, quote_amount), self.model.withdraw(lp));
+        self.pool.base_amount -= base_amount;
+        self.pool.quote_amount -= quote_amount;
+        self.lp_supply -= lp;
+        self.vault_base -= base_amount;
+        self.vault_quote -= quote_amount;
+        self.place_ladder();
+    }
+
+    /// What `restart_market_making` does once the book is clear and the
+    /// vaults are back in line with the reserves.
+    fn restart(&mut self) {
+        if self.pool.mm_active {
+            return;
+        }
+        self.cancel_and_settle();
+        self.pool.mm_active = true;
+        self.place_ladder();
+    }
+
+    fn step(&mut self) {
+        match self.rng.below(10) {
+            0..=3 => self.take(),
+            4..=5 => self.refresh(),
+            6 => self.deposit(),
+            7 => self.withdraw(),
+            _ => self.restart(),
+        }
+    }
+
+    /// Checked whenever no fills are waiting to be reconciled.
+    fn check_settled(&self) {
+        assert_eq!(
+            self.pool.base_amount, self.model.base_amount,
+            "base reserve"
+        );
+        assert_eq!(
+            self.pool.quote_amount, self.model.quote_amount,
+            "quote reserve"
+        );
+        assert_eq!(self.lp_supply, self.model.lp_supply, "lp supply");
+        assert_eq!(
+            self.pool.refund_base_amount, self.model.refund_base_amount,
+            "base refund"
+        );
+        assert_eq!(
+            self.pool.refund_quote_amount, self.model.refund_quote_amount,
+            "quote refund"
+        );
+        assert_eq!(
+            self.vault_base,
+            self.pool.base_amount + self.pool.refund_base_amount,
+            "base vault"
+        );
+        assert_eq!(
+            self.vault_quote,
+            self.pool.quote_amount + self.pool.refund_quote_amount,
+            "quote vault"
+        );
+    }
+}
+
+#[test]
+fn fuzz_against_reference_model() {
+    let runs = std::env::var("OPENAMM_FUZZ_RUNS")
+        .ok()
+        .and_then(|runs| runs.parse().ok())
+        .unwrap_or(DEFAULT_RUNS);
+    for seed in 0..runs {
+        let mut harness = Harness::new(seed);
+        for step in 0..STEPS_PER_RUN {
+            harness.step();
+            if harness.model.orders.is_empty() {
+                let result = std::panic::catch_unwind(|| harness.check_settled());
+                assert!(result.is_ok(), "seed {} diverged at step {}", seed, step);
+            }
+        }
+    }
+}
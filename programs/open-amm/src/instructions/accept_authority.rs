@@ -0,0 +1,23 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        mut,
+        constraint = pool.load()?.pending_authority == pending_authority.key()
+            @ OpenAmmErrorCode::WrongPendingAuthority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub pending_authority: Signer<'info>,
+}
+
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, AcceptAuthority<'info>>) -> Result<()> {
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.authority = ctx.accounts.pending_authority.key();
+    pool.pending_authority = Pubkey::default();
+    Ok(())
+}
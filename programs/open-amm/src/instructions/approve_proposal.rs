@@ -0,0 +1,57 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct ProposalApprovedEvent {
+    pool: Pubkey,
+    proposer: Pubkey,
+    proposal: Pubkey,
+    parameter: PoolParameter,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct ApproveProposal<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(
+        mut,
+        has_one = pool,
+        has_one = proposer,
+        close = proposer,
+    )]
+    pub proposal: Box<Account<'info, ParameterProposal>>,
+
+    /// CHECK: Only receives the proposal's rent
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Applies a queued proposal to the pool and closes it, refunding its rent to
+/// the proposer. Takes effect on the next refresh, as with the setters.
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, ApproveProposal<'info>>) -> Result<()> {
+    let parameter = ctx.accounts.proposal.parameter;
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    parameter.apply(&mut pool)?;
+    drop(pool);
+
+    let clock = Clock::get()?;
+    emit!(ProposalApprovedEvent {
+        pool: ctx.accounts.pool.key(),
+        proposer: ctx.accounts.proposer.key(),
+        proposal: ctx.accounts.proposal.key(),
+        parameter,
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    });
+    Ok(())
+}
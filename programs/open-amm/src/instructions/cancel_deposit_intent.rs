@@ -0,0 +1,25 @@
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct CancelDepositIntent<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = rent_payer,
+        close = rent_payer,
+    )]
+    pub intent: Box<Account<'info, DepositIntent>>,
+
+    pub owner: Signer<'info>,
+
+    /// CHECK: Only receives the intent's rent
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
+}
+
+/// Withdraws a deposit intent before a relayer uses it. Revoking the token
+/// approvals also stops it, but leaves the intent's rent behind.
+pub fn handler<'info>(_ctx: Context<'_, '_, '_, 'info, CancelDepositIntent<'info>>) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,170 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use crate::util::check_lot_sizes;
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+use serum_dex::critbit::*;
+use serum_dex::state::Market;
+
+pub const HEALTH_MM_INACTIVE: u32 = 1 << 0;
+pub const HEALTH_STALE: u32 = 1 << 1;
+pub const HEALTH_PRICE_DRIFT: u32 = 1 << 2;
+pub const HEALTH_LOCKED_FUNDS: u32 = 1 << 3;
+pub const HEALTH_UNRECONCILED_DUST: u32 = 1 << 4;
+
+const DRIFT_DENOMINATOR: u16 = 10_000;
+
+#[derive(Accounts)]
+pub struct CheckPoolHealth<'info> {
+    #[account(
+        has_one = base_vault,
+        has_one = quote_vault,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    /// CHECK:
+    #[account(
+        constraint = market.key() == pool.load()?.market
+            @ OpenAmmErrorCode::WrongMarketAccount,
+    )]
+    pub market: AccountInfo<'info>,
+
+    /// CHECK:
+    #[account(
+        constraint = open_orders.key() == pool.load()?.open_orders
+            @ OpenAmmErrorCode::WrongOpenOrdersAccount,
+    )]
+    pub open_orders: AccountInfo<'info>,
+
+    /// CHECK:
+    pub bids: AccountInfo<'info>,
+
+    /// CHECK:
+    pub asks: AccountInfo<'info>,
+
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+}
+
+/**
+ * Returns a bitfield of the HEALTH_* flags so keepers can decide which pools
+ * need action from a single simulated call. A zero result means the pool is
+ * healthy.
+ */
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, CheckPoolHealth<'info>>,
+    max_stale_slots: u64,
+    max_drift_bps: u16,
+) -> Result<u32> {
+    let pool = ctx.accounts.pool.load()?;
+    let mut health: u32 = 0;
+
+    if !pool.mm_active {
+        health |= HEALTH_MM_INACTIVE;
+    }
+
+    let slots_since_refresh = Clock::get()?
+        .slot
+        .checked_sub(pool.last_refresh_slot)
+        .unwrap_or(0);
+    if slots_since_refresh > max_stale_slots {
+        health |= HEALTH_STALE;
+    }
+
+    let market_state = Market::load(&ctx.accounts.market, &pool.dex_program, false).unwrap();
+    check_lot_sizes(&pool, &market_state)?;
+    let base_lot_size = pool.base_lot_size;
+    let quote_lot_size = pool.quote_lot_size;
+
+    let open_orders = Market::load_orders_mut(
+        &market_state,
+        &ctx.accounts.open_orders,
+        None,
+        &pool.dex_program,
+        None,
+        None,
+    )
+    .unwrap();
+    let native_base_total = open_orders.native_coin_total;
+    let native_quote_total = open_orders.native_pc_total;
+    let native_base_free = open_orders.native_coin_free;
+    let native_quote_free = open_orders.native_pc_free;
+    drop(open_orders);
+
+    if native_base_free > 0 || native_quote_free > 0 {
+        health |= HEALTH_LOCKED_FUNDS;
+    }
+
+    let held_base = ctx
+        .accounts
+        .base_vault
+        .amount
+        .checked_add(native_base_total)
+        .unwrap();
+    let held_quote = ctx
+        .accounts
+        .quote_vault
+        .amount
+        .checked_add(native_quote_total)
+        .unwrap();
+    let owed_base = pool
+        .base_amount
+        .checked_add(pool.refund_base_amount)
+        .unwrap()
+        .checked_add(pool.keeper_owed_base_amount)
+        .unwrap();
+    let owed_quote = pool
+        .quote_amount
+        .checked_add(pool.refund_quote_amount)
+        .unwrap()
+        .checked_add(pool.keeper_owed_quote_amount)
+        .unwrap();
+    if held_base > owed_base || held_quote > owed_quote {
+        health |= HEALTH_UNRECONCILED_DUST;
+    }
+
+    let bids = market_state.load_bids_mut(&ctx.accounts.bids).unwrap();
+    let asks = market_state.load_asks_mut(&ctx.accounts.asks).unwrap();
+    let best_bid_price = bids
+        .find_max()
+        .and_then(|h| bids.get(h))
+        .and_then(|n| n.as_leaf())
+        .map(|l| u64::from(l.price()));
+    let best_ask_price = asks
+        .find_min()
+        .and_then(|h| asks.get(h))
+        .and_then(|n| n.as_leaf())
+        .map(|l| u64::from(l.price()));
+    drop(bids);
+    drop(asks);
+    drop(market_state);
+
+    if let (Some(best_bid_price), Some(best_ask_price)) = (best_bid_price, best_ask_price) {
+        // Compare 2 * pool price against (bid + ask), both scaled to native units
+        let pool_price = (pool.quote_amount as u128)
+            .checked_mul(base_lot_size.into())
+            .unwrap()
+            .checked_mul(2)
+            .unwrap();
+        let book_price = (best_bid_price as u128)
+            .checked_add(best_ask_price.into())
+            .unwrap()
+            .checked_mul(pool.base_amount.into())
+            .unwrap()
+            .checked_mul(quote_lot_size.into())
+            .unwrap();
+        let drift = if pool_price > book_price {
+            pool_price - book_price
+        } else {
+            book_price - pool_price
+        };
+        if drift.checked_mul(DRIFT_DENOMINATOR.into()).unwrap()
+            > book_price.checked_mul(max_drift_bps.into()).unwrap()
+        {
+            health |= HEALTH_PRICE_DRIFT;
+        }
+    }
+
+    Ok(health)
+}
@@ -0,0 +1,136 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::create_pool::{get_pool_index_seed, POOL_SEED};
+use crate::instructions::create_snapshot::{SNAPSHOT_REWARDS_SEED, SNAPSHOT_SEED};
+use crate::state::*;
+use crate::util::{get_airdrop_leaf, pool_authority_seeds, verify_merkle_proof};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{transfer, Token, TokenAccount, Transfer};
+use std::mem::size_of;
+
+pub const AIRDROP_CLAIM_SEED: &str = "airdrop-claim";
+
+#[event]
+pub struct AirdropClaimedEvent {
+    pool: Pubkey,
+    snapshot: Pubkey,
+    owner: Pubkey,
+    lp_amount: u64,
+    reward_amount: u64,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct ClaimAirdrop<'info> {
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(
+        mut,
+        has_one = pool,
+        seeds = [
+            pool.key().as_ref(),
+            snapshot.index.to_le_bytes().as_ref(),
+            SNAPSHOT_SEED.as_bytes().as_ref(),
+        ],
+        bump = snapshot.bump,
+    )]
+    pub snapshot: Box<Account<'info, Snapshot>>,
+
+    #[account(
+        mut,
+        seeds = [snapshot.key().as_ref(), SNAPSHOT_REWARDS_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub snapshot_rewards: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        seeds = [snapshot.key().as_ref(), owner.key().as_ref(), AIRDROP_CLAIM_SEED.as_bytes().as_ref()],
+        bump,
+        payer = owner,
+        space = 8 + size_of::<AirdropClaim>(),
+    )]
+    pub claim: Box<Account<'info, AirdropClaim>>,
+
+    #[account(
+        mut,
+        token::authority = owner,
+        token::mint = snapshot.reward_mint,
+    )]
+    pub owner_rewards: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Pays the owner their share of a snapshot's rewards for the `lp_amount` LP
+/// tokens they held in it, proven against its merkle root. Each owner can
+/// claim once per snapshot; the LP tokens themselves aren't needed anymore.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, ClaimAirdrop<'info>>,
+    lp_amount: u64,
+    proof: Vec<[u8; 32]>,
+) -> Result<()> {
+    let owner_key = ctx.accounts.owner.key();
+    require!(
+        verify_merkle_proof(
+            &proof,
+            ctx.accounts.snapshot.merkle_root,
+            get_airdrop_leaf(&owner_key, lp_amount)
+        ),
+        OpenAmmErrorCode::InvalidAirdropProof
+    );
+
+    let snapshot = &mut ctx.accounts.snapshot;
+    let reward_amount = snapshot.get_airdrop_amount(lp_amount);
+    snapshot.claimed_amount = snapshot.claimed_amount.checked_add(reward_amount).unwrap();
+
+    let claim = &mut ctx.accounts.claim;
+    claim.snapshot = snapshot.key();
+    claim.owner = owner_key;
+    claim.lp_amount = lp_amount;
+    claim.reward_amount = reward_amount;
+    claim.bump = *ctx.bumps.get("claim").unwrap();
+
+    let pool = ctx.accounts.pool.load()?;
+    let market_key = pool.market;
+    let pool_type_bytes = (pool.pool_type as u8).to_le_bytes();
+    let pool_index_seed = get_pool_index_seed(pool.index);
+    let pool_bump = pool.bump;
+    drop(pool);
+    let seeds = pool_authority_seeds!(
+        market_key = market_key,
+        pool_type_bytes = pool_type_bytes,
+        index_seed = pool_index_seed,
+        bump = pool_bump
+    );
+    let pool_signer = &[&seeds[..]];
+
+    let transfer_rewards_cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.snapshot_rewards.to_account_info(),
+            to: ctx.accounts.owner_rewards.to_account_info(),
+            authority: ctx.accounts.pool.to_account_info(),
+        },
+        pool_signer,
+    );
+    transfer(transfer_rewards_cpi_ctx, reward_amount)?;
+
+    let clock = Clock::get()?;
+    emit!(AirdropClaimedEvent {
+        pool: ctx.accounts.pool.key(),
+        snapshot: ctx.accounts.snapshot.key(),
+        owner: owner_key,
+        lp_amount,
+        reward_amount,
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::create_pool::{get_pool_index_seed, POOL_SEED};
+use crate::state::*;
+use crate::util::pool_authority_seeds;
+use anchor_lang::prelude::*;
+use anchor_spl::token::{transfer, Token, Transfer};
+
+/// Accounts passed per pool claimed from, in remaining accounts: the pool,
+/// its base and quote vaults, and the operator's base and quote token
+/// accounts to pay into.
+pub const KEEPER_CLAIM_ACCOUNTS_PER_POOL: usize = 5;
+
+#[event]
+pub struct KeeperRewardsClaimedEvent {
+    keeper: Pubkey,
+    pool: Pubkey,
+    base_amount: u64,
+    quote_amount: u64,
+    signer: Pubkey,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct ClaimKeeperRewards<'info> {
+    #[account(mut, has_one = operator)]
+    pub keeper: Box<Account<'info, Keeper>>,
+
+    pub operator: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Pays out everything the keeper has accrued on each pool passed in
+/// remaining accounts.
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, ClaimKeeperRewards<'info>>) -> Result<()> {
+    require!(
+        ctx.remaining_accounts.len() % KEEPER_CLAIM_ACCOUNTS_PER_POOL == 0,
+        OpenAmmErrorCode::WrongKeeperClaimAccounts
+    );
+    let keeper = &mut ctx.accounts.keeper;
+    let cpi_token_program = ctx.accounts.token_program.to_account_info();
+
+    for accounts in ctx
+        .remaining_accounts
+        .chunks(KEEPER_CLAIM_ACCOUNTS_PER_POOL)
+    {
+        let (pool_info, base_vault, quote_vault, operator_base, operator_quote) = (
+            &accounts[0],
+            &accounts[1],
+            &accounts[2],
+            &accounts[3],
+            &accounts[4],
+        );
+        let pool_loader = AccountLoader::<OpenAmmPool>::try_from(pool_info)?;
+        let index = keeper
+            .pool_index(&pool_loader.key())
+            .ok_or(OpenAmmErrorCode::WrongKeeperClaimAccounts)?;
+
+        let mut pool = pool_loader.load_mut()?;
+        require!(!pool.in_progress, OpenAmmErrorCode::PoolLocked);
+        require!(
+            base_vault.key() == pool.base_vault && quote_vault.key() == pool.quote_vault,
+            OpenAmmErrorCode::WrongKeeperClaimAccounts
+        );
+        let base_amount = keeper.owed_base[index];
+        let quote_amount = keeper.owed_quote[index];
+        pool.keeper_owed_base_amount = pool
+            .keeper_owed_base_amount
+            .checked_sub(base_amount)
+            .unwrap();
+        pool.keeper_owed_quote_amount = pool
+            .keeper_owed_quote_amount
+            .checked_sub(quote_amount)
+            .unwrap();
+        keeper.owed_base[index] = 0;
+        keeper.owed_quote[index] = 0;
+
+        let market_key = pool.market;
+        let pool_type_bytes = (pool.pool_type as u8).to_le_bytes();
+        let pool_index_seed = get_pool_index_seed(pool.index);
+        let pool_bump = pool.bump;
+        drop(pool);
+        let seeds = pool_authority_seeds!(
+            market_key = market_key,
+            pool_type_bytes = pool_type_bytes,
+            index_seed = pool_index_seed,
+            bump = pool_bump
+        );
+        let pool_signer = &[&seeds[..]];
+
+        if base_amount > 0 {
+            let transfer_base_cpi_ctx = CpiContext::new_with_signer(
+                cpi_token_program.clone(),
+                Transfer {
+                    from: base_vault.clone(),
+                    to: operator_base.clone(),
+                    authority: pool_info.clone(),
+                },
+                pool_signer,
+            );
+            transfer(transfer_base_cpi_ctx, base_amount)?;
+        }
+        if quote_amount > 0 {
+            let transfer_quote_cpi_ctx = CpiContext::new_with_signer(
+                cpi_token_program.clone(),
+                Transfer {
+                    from: quote_vault.clone(),
+                    to: operator_quote.clone(),
+                    authority: pool_info.clone(),
+                },
+                pool_signer,
+            );
+            transfer(transfer_quote_cpi_ctx, quote_amount)?;
+        }
+
+        let clock = Clock::get()?;
+        emit!(KeeperRewardsClaimedEvent {
+            keeper: keeper.key(),
+            pool: pool_loader.key(),
+            base_amount,
+            quote_amount,
+            signer: ctx.accounts.operator.key(),
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+    }
+    Ok(())
+}
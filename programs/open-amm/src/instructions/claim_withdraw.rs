@@ -0,0 +1,198 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::create_pool::{get_pool_index_seed, POOL_SEED};
+use crate::instructions::request_withdraw::{WITHDRAW_TICKET_LP_SEED, WITHDRAW_TICKET_SEED};
+use crate::state::*;
+use crate::util::{debug_msg, pool_authority_seeds};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{
+    burn, close_account, transfer, Burn, CloseAccount, Mint, Token, TokenAccount, Transfer,
+};
+
+#[event]
+pub struct WithdrawClaimedEvent {
+    pool: Pubkey,
+    owner: Pubkey,
+    lp_amount: u64,
+    base_amount: u64,
+    quote_amount: u64,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct ClaimWithdraw<'info> {
+    #[account(
+        mut,
+        has_one = base_vault,
+        has_one = quote_vault,
+        has_one = lp_mint,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(
+        mut,
+        has_one = pool,
+        has_one = owner,
+        close = owner,
+        seeds = [pool.key().as_ref(), owner.key().as_ref(), WITHDRAW_TICKET_SEED.as_bytes().as_ref()],
+        bump = ticket.bump,
+    )]
+    pub ticket: Box<Account<'info, WithdrawTicket>>,
+
+    #[account(
+        mut,
+        seeds = [ticket.key().as_ref(), WITHDRAW_TICKET_LP_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub ticket_lp: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub lp_mint: Box<Account<'info, Mint>>,
+
+    #[account(mut)]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::authority = owner,
+        token::mint = base_vault.mint,
+    )]
+    pub owner_base: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::authority = owner,
+        token::mint = quote_vault.mint,
+    )]
+    pub owner_quote: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/**
+ * Second half of `request_withdraw`. Once a ladder has been placed since the
+ * request, or the pool's orders have been pulled by safe mode or a pause,
+ * and the pool's withdraw delay has passed, burns the ticket's LP tokens and
+ * pays their share of reserves from the vaults, closing the ticket.
+ *
+ * Reserves are as of the last reconcile, the same ones the queued share was
+ * held back from.
+ */
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, ClaimWithdraw<'info>>) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let mut pool = accounts.pool.load_mut()?;
+    require!(
+        pool.mm_active
+            && (pool.last_refresh_slot > accounts.ticket.requested_slot
+                || pool.safe_mode
+                || pool.paused),
+        OpenAmmErrorCode::WithdrawNotClaimable
+    );
+    require!(
+        Clock::get()?.slot
+            >= accounts
+                .ticket
+                .requested_slot
+                .checked_add(pool.withdraw_delay_slots)
+                .unwrap(),
+        OpenAmmErrorCode::WithdrawDelayNotElapsed
+    );
+
+    let lp_amount = accounts.ticket_lp.amount;
+    let lp_supply = accounts.lp_mint.supply;
+    let (base_amount, quote_amount) = pool.get_lp_share_amounts(lp_amount, lp_supply);
+    pool.scale_reserve_checkpoints(lp_supply.checked_sub(lp_amount).unwrap(), lp_supply);
+    pool.base_amount = pool.base_amount.checked_sub(base_amount).unwrap();
+    pool.quote_amount = pool.quote_amount.checked_sub(quote_amount).unwrap();
+    pool.queued_withdraw_base_amount = pool.queued_withdraw_base_amount.saturating_sub(base_amount);
+    pool.queued_withdraw_quote_amount = pool
+        .queued_withdraw_quote_amount
+        .saturating_sub(quote_amount);
+    pool.withdraw_log
+        .record(1, accounts.owner.key(), Clock::get()?.slot);
+    debug_msg!(
+        pool.debug_logging,
+        "openamm:claim_withdraw lp_burned={} base={} quote={}",
+        lp_amount,
+        base_amount,
+        quote_amount
+    );
+
+    let market_key = pool.market;
+    let pool_type_bytes = (pool.pool_type as u8).to_le_bytes();
+    let pool_index_seed = get_pool_index_seed(pool.index);
+    let pool_bump = pool.bump;
+    drop(pool);
+    let seeds = pool_authority_seeds!(
+        market_key = market_key,
+        pool_type_bytes = pool_type_bytes,
+        index_seed = pool_index_seed,
+        bump = pool_bump
+    );
+    let pool_signer = &[&seeds[..]];
+
+    let cpi_token_program = accounts.token_program.to_account_info();
+    let burn_lp_cpi_ctx = CpiContext::new_with_signer(
+        cpi_token_program.clone(),
+        Burn {
+            mint: accounts.lp_mint.to_account_info(),
+            from: accounts.ticket_lp.to_account_info(),
+            authority: accounts.pool.to_account_info(),
+        },
+        pool_signer,
+    );
+    burn(burn_lp_cpi_ctx, lp_amount)?;
+
+    let close_ticket_lp_cpi_ctx = CpiContext::new_with_signer(
+        cpi_token_program.clone(),
+        CloseAccount {
+            account: accounts.ticket_lp.to_account_info(),
+            destination: accounts.owner.to_account_info(),
+            authority: accounts.pool.to_account_info(),
+        },
+        pool_signer,
+    );
+    close_account(close_ticket_lp_cpi_ctx)?;
+
+    let transfer_base_to_owner_cpi_ctx = CpiContext::new_with_signer(
+        cpi_token_program.clone(),
+        Transfer {
+            from: accounts.base_vault.to_account_info(),
+            to: accounts.owner_base.to_account_info(),
+            authority: accounts.pool.to_account_info(),
+        },
+        pool_signer,
+    );
+    transfer(transfer_base_to_owner_cpi_ctx, base_amount)?;
+
+    let transfer_quote_to_owner_cpi_ctx = CpiContext::new_with_signer(
+        cpi_token_program,
+        Transfer {
+            from: accounts.quote_vault.to_account_info(),
+            to: accounts.owner_quote.to_account_info(),
+            authority: accounts.pool.to_account_info(),
+        },
+        pool_signer,
+    );
+    transfer(transfer_quote_to_owner_cpi_ctx, quote_amount)?;
+
+    let clock = Clock::get()?;
+    emit!(WithdrawClaimedEvent {
+        pool: accounts.pool.key(),
+        owner: accounts.owner.key(),
+        lp_amount,
+        base_amount,
+        quote_amount,
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
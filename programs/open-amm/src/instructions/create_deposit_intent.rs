@@ -0,0 +1,53 @@
+use crate::state::*;
+use anchor_lang::prelude::*;
+use std::mem::size_of;
+
+pub const DEPOSIT_INTENT_SEED: &str = "deposit-intent";
+
+#[derive(Accounts)]
+pub struct CreateDepositIntent<'info> {
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(
+        init,
+        seeds = [pool.key().as_ref(), owner.key().as_ref(), DEPOSIT_INTENT_SEED.as_bytes().as_ref()],
+        bump,
+        payer = rent_payer,
+        space = 8 + size_of::<DepositIntent>(),
+    )]
+    pub intent: Box<Account<'info, DepositIntent>>,
+
+    pub owner: Signer<'info>,
+
+    #[account(mut)]
+    pub rent_payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Records the deposit an owner wants `relayer` to make for them with
+/// `deposit_delegated`, including its slippage bounds, so the relayer can't
+/// choose them. Owners have one open intent per pool; it can sign alongside
+/// the token approvals, with the relayer paying the rent.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, CreateDepositIntent<'info>>,
+    relayer: Pubkey,
+    desired_base_amount: u64,
+    desired_quote_amount: u64,
+    min_base_amount: u64,
+    min_quote_amount: u64,
+    expiry_slot: u64,
+) -> Result<()> {
+    let intent = &mut ctx.accounts.intent;
+    intent.pool = ctx.accounts.pool.key();
+    intent.owner = ctx.accounts.owner.key();
+    intent.relayer = relayer;
+    intent.rent_payer = ctx.accounts.rent_payer.key();
+    intent.desired_base_amount = desired_base_amount;
+    intent.desired_quote_amount = desired_quote_amount;
+    intent.min_base_amount = min_base_amount;
+    intent.min_quote_amount = min_quote_amount;
+    intent.expiry_slot = expiry_slot;
+    intent.bump = *ctx.bumps.get("intent").unwrap();
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use crate::state::*;
+use anchor_lang::prelude::*;
+use std::mem::size_of;
+
+pub const KEEPER_SEED: &str = "keeper";
+
+#[derive(Accounts)]
+pub struct CreateKeeper<'info> {
+    #[account(
+        init,
+        seeds = [operator.key().as_ref(), KEEPER_SEED.as_bytes().as_ref()],
+        bump,
+        payer = operator,
+        space = 8 + size_of::<Keeper>(),
+    )]
+    pub keeper: Box<Account<'info, Keeper>>,
+
+    #[account(mut)]
+    pub operator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, CreateKeeper<'info>>) -> Result<()> {
+    let keeper = &mut ctx.accounts.keeper;
+    keeper.operator = ctx.accounts.operator.key();
+    keeper.bump = *ctx.bumps.get("keeper").unwrap();
+    Ok(())
+}
@@ -1,7 +1,10 @@
 use crate::errors::OpenAmmErrorCode;
 use crate::stableswap::calculate_stableswap_lp_minted;
 use crate::state::*;
-use crate::util::{get_orderbook, init, pool_authority_seeds};
+use crate::util::{
+    check_fee_tick_floor, get_orderbook, get_xyk_lp_minted, init, pool_authority_seeds,
+    DEFAULT_KEEPER_REFUND_BPS, MAX_LADDER_STEP_BPS,
+};
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token::{mint_to, transfer, Mint, MintTo, Token, TokenAccount, Transfer};
@@ -15,15 +18,35 @@ use std::mem::size_of;
 pub const LP_MINT_SEED: &str = "pool-lp-mint";
 pub const MINIMUM_LIQUIDITY: u16 = 1000;
 
-const QUOTE_VAULT_SEED: &str = "pool-quote-vault";
-const BASE_VAULT_SEED: &str = "pool-base-vault";
-const OPEN_ORDERS_SEED: &str = "pool-open-orders";
+pub const QUOTE_VAULT_SEED: &str = "pool-quote-vault";
+pub const BASE_VAULT_SEED: &str = "pool-base-vault";
+pub const OPEN_ORDERS_SEED: &str = "pool-open-orders";
+pub const BASE_FEE_VAULT_SEED: &str = "pool-base-fee-vault";
+pub const QUOTE_FEE_VAULT_SEED: &str = "pool-quote-fee-vault";
 pub const POOL_SEED: &str = "pool";
 
-const OPENBOOK_PADDING: usize = 12;
+/// Seed a pool's index adds between its type and `POOL_SEED`. Empty for the
+/// first pool of a type on a market, so its address is the one pools had
+/// before they were indexed.
+pub fn get_pool_index_seed(index: u16) -> Vec<u8> {
+    if index == 0 {
+        vec![]
+    } else {
+        index.to_le_bytes().to_vec()
+    }
+}
+
+pub const OPENBOOK_PADDING: usize = 12;
 
 #[derive(Accounts)]
-#[instruction(pool_type: u8)]
+#[instruction(
+    pool_type: u8,
+    initial_base_amount: u64,
+    initial_quote_amount: u64,
+    ladder_spacing: u8,
+    ladder_step_bps: u16,
+    index: u16,
+)]
 pub struct CreatePool<'info> {
     #[account(
         init,
@@ -44,6 +67,27 @@ pub struct CreatePool<'info> {
         token::authority = pool,
     )]
     pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        seeds = [pool.key().as_ref(), QUOTE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+        payer = signer,
+        token::mint = quote_mint,
+        token::authority = pool,
+    )]
+    pub quote_fee_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        seeds = [pool.key().as_ref(), BASE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+        payer = signer,
+        token::mint = base_mint,
+        token::authority = pool,
+    )]
+    pub base_fee_vault: Box<Account<'info, TokenAccount>>,
+
     #[account(
         mut,
         token::mint = base_mint,
@@ -90,6 +134,7 @@ pub struct CreatePool<'info> {
         seeds = [
             market_accounts.market.key().as_ref(),
             pool_type.to_le_bytes().as_ref(),
+            get_pool_index_seed(index).as_ref(),
             POOL_SEED.as_bytes().as_ref()
         ],
         bump,
@@ -107,7 +152,7 @@ pub struct CreatePool<'info> {
         seeds = [pool.key().as_ref(), OPEN_ORDERS_SEED.as_bytes().as_ref()],
         bump,
         payer = signer,
-        owner = dex::ID,
+        owner = dex_program.key(),
         space = size_of::<OpenOrders>() + OPENBOOK_PADDING
     )]
     pub open_orders: AccountInfo<'info>,
@@ -115,7 +160,8 @@ pub struct CreatePool<'info> {
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 
-    #[account(address = dex::ID)]
+    /// Stored as the pool's `dex_program`, which its dex instructions then
+    /// require.
     pub dex_program: Program<'info, dex::Dex>,
     pub rent: Sysvar<'info, Rent>,
 }
@@ -125,20 +171,40 @@ pub fn handler<'info>(
     pool_type: PoolType,
     initial_base_amount: u64,
     initial_quote_amount: u64,
+    ladder_spacing: LadderSpacing,
+    ladder_step_bps: u16,
+    index: u16,
 ) -> Result<()> {
+    require!(
+        match ladder_spacing {
+            LadderSpacing::LINEAR => true,
+            LadderSpacing::GEOMETRIC =>
+                ladder_step_bps > 0 && ladder_step_bps <= MAX_LADDER_STEP_BPS,
+        },
+        OpenAmmErrorCode::InvalidLadderStep,
+    );
+    // A pool can be listed empty and funded later through
+    // `initialize_liquidity`, but not half funded
+    require!(
+        (initial_base_amount == 0) == (initial_quote_amount == 0),
+        OpenAmmErrorCode::InitialLiquidityTooSmall,
+    );
+
     let cpi_token_program = ctx.accounts.token_program.to_account_info();
     let pool_bump = ctx.bumps.get("pool").unwrap().clone();
     let market_key = ctx.accounts.market_accounts.market.key();
     let pool_type_bytes = (pool_type as u8).to_le_bytes();
+    let pool_index_seed = get_pool_index_seed(index);
     let seeds = pool_authority_seeds!(
         market_key = market_key,
         pool_type_bytes = pool_type_bytes,
+        index_seed = pool_index_seed,
         bump = pool_bump
     );
     let pool_signer = &[&seeds[..]];
 
     let market = &ctx.accounts.market_accounts.market;
-    let market_state = Market::load(&market, &dex::ID, false).unwrap();
+    let market_state = Market::load(&market, &ctx.accounts.dex_program.key(), false).unwrap();
     require!(
         ctx.accounts.base_mint.key().as_ref()
             == transmute_to_bytes(&identity(market_state.coin_mint)),
@@ -149,7 +215,26 @@ pub fn handler<'info>(
             == transmute_to_bytes(&identity(market_state.pc_mint)),
         OpenAmmErrorCode::MarketQuoteMintMismatch,
     );
+    // Permissioned markets only let open orders accounts be created with the
+    // market's open orders authority co-signing; it is passed as the first
+    // remaining account. Placement, cancels and settlement need nothing extra.
+    let open_orders_authority = market_state.open_orders_authority().cloned();
+    let base_lot_size = market_state.coin_lot_size;
+    let quote_lot_size = market_state.pc_lot_size;
     drop(market_state);
+    let init_open_orders_remaining_accounts = match open_orders_authority {
+        Some(open_orders_authority) => {
+            let market_authority = ctx.remaining_accounts.first();
+            require!(
+                market_authority.map_or(false, |acc| {
+                    acc.key() == open_orders_authority && acc.is_signer
+                }),
+                OpenAmmErrorCode::MissingOpenOrdersAuthority,
+            );
+            vec![market_authority.unwrap().clone()]
+        }
+        None => vec![],
+    };
 
     let mut pool = ctx.accounts.pool.load_init()?;
 
@@ -164,6 +249,7 @@ pub fn handler<'info>(
             refund_quote_amount: 0,
             open_orders: ctx.accounts.open_orders.key(),
             lp_mint: ctx.accounts.lp_mint.clone().key(),
+            authority: ctx.accounts.signer.key(),
             pool_type: pool_type,
             client_order_id: 1,
             bump: pool_bump,
@@ -174,8 +260,108 @@ pub fn handler<'info>(
             quote_decimals: ctx.accounts.quote_mint.decimals,
             base_amount: initial_base_amount,
             quote_amount: initial_quote_amount,
-            placed_asks: [PlacedOrder::default(); 10],
-            placed_bids: [PlacedOrder::default(); 10],
+            placed_asks: PlacedOrders::default(),
+            placed_bids: PlacedOrders::default(),
+            last_refresh_slot: 0,
+            fee_distribution: Pubkey::default(),
+            fee_process_interval_slots: 0,
+            last_fee_process_slot: 0,
+            secondary_market: Pubkey::default(),
+            secondary_open_orders: Pubkey::default(),
+            secondary_client_order_id: 1,
+            secondary_placed_asks: PlacedOrders::default(),
+            secondary_placed_bids: PlacedOrders::default(),
+            keeper: Pubkey::default(),
+            min_refresh_interval_slots: 0,
+            safe_mode_after_slots: 0,
+            keeper_owed_base_amount: 0,
+            keeper_owed_quote_amount: 0,
+            eviction_base_exposure: 0,
+            eviction_quote_exposure: 0,
+            eviction_base_baseline: 0,
+            eviction_quote_baseline: 0,
+            base_dust_threshold: 0,
+            quote_dust_threshold: 0,
+            stranded_base_amount: 0,
+            stranded_quote_amount: 0,
+            base_lot_size: base_lot_size,
+            quote_lot_size: quote_lot_size,
+            name: [0; 32],
+            uri: [0; 128],
+            ladder_spacing: ladder_spacing,
+            ladder_step_bps: ladder_step_bps,
+            debug_logging: false,
+            fee_router_target: FeeRouterTarget::DISABLED,
+            secondary_share_bps: 0,
+            drift_alarm_bps: 0,
+            pause_on_drift: false,
+            in_progress: false,
+            safe_mode: false,
+            orders_per_side: LEGACY_ORDERS_PER_SIDE as u8,
+            skipped_rungs: 0,
+            crossed_rungs: 0,
+            crossing_policy: CrossingPolicy::REPRICE,
+            best_bid_price: 0,
+            best_ask_price: 0,
+            mid_price: 0,
+            mid_price_cumulative: 0,
+            mid_price_last_slot: 0,
+            oracle: Pubkey::default(),
+            oracle_band_bps: 0,
+            oracle_max_age_slots: 0,
+            max_quote_at_risk: 0,
+            jitter_bps: 0,
+            volatility_bps: 0,
+            volatility_fee_scale_bps: 0,
+            max_volatility_fee_bps: 0,
+            depeg_ratio_bps: 0,
+            crisis_amp: 0,
+            dex_maker_rebates_quote_amount: 0,
+            dex_taker_fees_quote_amount: 0,
+            unswept_rebates_quote_amount: 0,
+            bootstrapper: Pubkey::default(),
+            pending_authority: Pubkey::default(),
+            guardian: Pubkey::default(),
+            paused: false,
+            queued_withdraw_base_amount: 0,
+            queued_withdraw_quote_amount: 0,
+            reserve_checkpoints: [ReserveCheckpoint::default(); RESERVE_CHECKPOINTS],
+            reserve_checkpoint_index: 0,
+            keeper_refund_bps: DEFAULT_KEEPER_REFUND_BPS,
+            lifetime_keeper_paid_base_amount: 0,
+            lifetime_keeper_paid_quote_amount: 0,
+            base_utilization_bps: 0,
+            quote_utilization_bps: 0,
+            fill_velocity_bps: 0,
+            deleverage_velocity_bps: 0,
+            max_deleverage_bps: 0,
+            anchor_pool: Pubkey::default(),
+            anchor_max_age_slots: 0,
+            underlying_pool: Pubkey::default(),
+            index: index,
+            max_event_queue_backlog: 0,
+            quoting_open_secs: 0,
+            quoting_close_secs: 0,
+            cooldown_fill_bps: 0,
+            cooldown_slots: 0,
+            cooldown_until_slot: 0,
+            deposit_log: AccessLog::default(),
+            withdraw_log: AccessLog::default(),
+            refresh_log: AccessLog::default(),
+            swap_log: AccessLog::default(),
+            max_deposit_lp_bps: 0,
+            deposit_cap_deviation_bps: 0,
+            withdraw_delay_slots: 0,
+            daily_stats: [DailyStats::default(); STATS_DAYS],
+            compute_budget_units: 0,
+            base_compute_units: 0,
+            cancel_compute_units: 0,
+            place_compute_units: 0,
+            dex_program: ctx.accounts.dex_program.key(),
+            rung_fee_bps: [0; MAX_ORDERS_PER_SIDE],
+            quote_oracle: Pubkey::default(),
+            quote_oracle_max_age_slots: 0,
+            snapshot_count: 0,
         }
     }
     drop(pool);
@@ -194,9 +380,14 @@ pub fn handler<'info>(
             rent: ctx.accounts.rent.to_account_info(),
         },
         pool_signer,
-    );
+    )
+    .with_remaining_accounts(init_open_orders_remaining_accounts);
     dex::init_open_orders(init_open_orders_cpi_ctx)?;
 
+    if initial_base_amount == 0 {
+        return Ok(());
+    }
+
     let transfer_base_to_pool_cpi_ctx = CpiContext::new(
         cpi_token_program.clone(),
         Transfer {
@@ -216,30 +407,33 @@ pub fn handler<'info>(
         },
     );
     transfer(transfer_quote_to_pool_cpi_ctx, initial_quote_amount)?;
+    check_fee_tick_floor(&ctx.accounts.pool)?;
 
     let orderbook = get_orderbook(
         1,
         pool_bump,
         pool_type,
+        index,
         ctx.accounts.pool.clone(),
         ctx.accounts.market_accounts.clone(),
         *ctx.accounts.base_vault.clone(),
         *ctx.accounts.quote_vault.clone(),
+        *ctx.accounts.base_fee_vault.clone(),
+        *ctx.accounts.quote_fee_vault.clone(),
         ctx.accounts.dex_program.clone(),
         ctx.accounts.token_program.clone(),
         ctx.accounts.rent.clone(),
         false,
-    );
+    )?;
 
-    orderbook.place_new_orders(&ctx.accounts.base_vault, &ctx.accounts.quote_vault)?;
+    orderbook.place_new_orders(
+        &ctx.accounts.base_vault,
+        &ctx.accounts.quote_vault,
+        ctx.remaining_accounts,
+    )?;
 
     let lp_minted: u64 = match pool_type {
-        PoolType::XYK => ((initial_base_amount as u128)
-            .checked_mul(initial_quote_amount as u128)
-            .unwrap()
-            .checked_sub(MINIMUM_LIQUIDITY.into())
-            .unwrap() as f64)
-            .sqrt() as u64,
+        PoolType::XYK => get_xyk_lp_minted(0, 0, 0, initial_base_amount, initial_quote_amount)?,
         PoolType::STABLE => calculate_stableswap_lp_minted(
             0,
             0,
@@ -248,6 +442,7 @@ pub fn handler<'info>(
             initial_quote_amount,
             ctx.accounts.base_mint.decimals,
             ctx.accounts.quote_mint.decimals,
+            0,
         ),
     };
 
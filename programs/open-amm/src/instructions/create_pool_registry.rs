@@ -0,0 +1,32 @@
+use crate::state::*;
+use anchor_lang::prelude::*;
+use std::mem::size_of;
+
+pub const POOL_REGISTRY_SEED: &str = "pool-registry";
+
+#[derive(Accounts)]
+pub struct CreatePoolRegistry<'info> {
+    #[account(
+        init,
+        seeds = [market.key().as_ref(), POOL_REGISTRY_SEED.as_bytes().as_ref()],
+        bump,
+        payer = payer,
+        space = 8 + size_of::<PoolRegistry>(),
+    )]
+    pub pool_registry: Box<Account<'info, PoolRegistry>>,
+
+    /// CHECK: Only used as a seed
+    pub market: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, CreatePoolRegistry<'info>>) -> Result<()> {
+    let pool_registry = &mut ctx.accounts.pool_registry;
+    pool_registry.market = ctx.accounts.market.key();
+    pool_registry.bump = *ctx.bumps.get("pool_registry").unwrap();
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use crate::state::*;
+use anchor_lang::prelude::*;
+use std::mem::size_of;
+
+pub const RUNG_STATS_SEED: &str = "rung_stats";
+
+#[derive(Accounts)]
+pub struct CreateRungStats<'info> {
+    #[account(
+        init,
+        seeds = [pool.key().as_ref(), RUNG_STATS_SEED.as_bytes().as_ref()],
+        bump,
+        payer = payer,
+        space = 8 + size_of::<RungStats>(),
+    )]
+    pub rung_stats: Box<Account<'info, RungStats>>,
+
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/**
+ * Creates the account a pool's per-rung fill counts are kept in. Anyone may
+ * pay for it; fills are counted from then on by refreshes, deposits and
+ * withdrawals that pass it as a remaining account.
+ */
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, CreateRungStats<'info>>) -> Result<()> {
+    let rung_stats = &mut ctx.accounts.rung_stats;
+    rung_stats.pool = ctx.accounts.pool.key();
+    rung_stats.since_slot = Clock::get()?.slot;
+    rung_stats.bump = *ctx.bumps.get("rung_stats").unwrap();
+    Ok(())
+}
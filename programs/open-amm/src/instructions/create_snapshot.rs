@@ -0,0 +1,135 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+use anchor_spl::token::{transfer, Mint, Token, TokenAccount, Transfer};
+use std::mem::size_of;
+
+pub const SNAPSHOT_SEED: &str = "lp-snapshot";
+pub const SNAPSHOT_REWARDS_SEED: &str = "lp-snapshot-rewards";
+
+#[event]
+pub struct SnapshotCreatedEvent {
+    pool: Pubkey,
+    snapshot: Pubkey,
+    index: u64,
+    merkle_root: [u8; 32],
+    lp_supply: u64,
+    reward_mint: Pubkey,
+    reward_amount: u64,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct CreateSnapshot<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        has_one = lp_mint,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub lp_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        seeds = [
+            pool.key().as_ref(),
+            pool.load()?.snapshot_count.to_le_bytes().as_ref(),
+            SNAPSHOT_SEED.as_bytes().as_ref(),
+        ],
+        bump,
+        payer = authority,
+        space = 8 + size_of::<Snapshot>(),
+    )]
+    pub snapshot: Box<Account<'info, Snapshot>>,
+
+    pub reward_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        seeds = [snapshot.key().as_ref(), SNAPSHOT_REWARDS_SEED.as_bytes().as_ref()],
+        bump,
+        payer = authority,
+        token::mint = reward_mint,
+        token::authority = pool,
+    )]
+    pub snapshot_rewards: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::authority = authority,
+        token::mint = reward_mint,
+    )]
+    pub authority_rewards: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub system_program: Program<'info, System>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/**
+ * Checkpoints the pool's LP holders for an airdrop. The authority builds the
+ * merkle tree of `(owner, lp_amount)` off chain; its amounts must sum to the
+ * current LP supply, which is checked here, so take it in the same slot as
+ * the balances were read. `reward_amount` of the reward mint is escrowed and
+ * paid out pro rata by `claim_airdrop`.
+ */
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, CreateSnapshot<'info>>,
+    merkle_root: [u8; 32],
+    lp_supply: u64,
+    reward_amount: u64,
+) -> Result<()> {
+    require!(
+        lp_supply > 0 && lp_supply == ctx.accounts.lp_mint.supply,
+        OpenAmmErrorCode::WrongSnapshotSupply
+    );
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    let index = pool.snapshot_count;
+    pool.snapshot_count = pool.snapshot_count.checked_add(1).unwrap();
+    drop(pool);
+
+    let clock = Clock::get()?;
+    let snapshot = &mut ctx.accounts.snapshot;
+    snapshot.pool = ctx.accounts.pool.key();
+    snapshot.index = index;
+    snapshot.merkle_root = merkle_root;
+    snapshot.lp_supply = lp_supply;
+    snapshot.reward_mint = ctx.accounts.reward_mint.key();
+    snapshot.reward_amount = reward_amount;
+    snapshot.claimed_amount = 0;
+    snapshot.slot = clock.slot;
+    snapshot.bump = *ctx.bumps.get("snapshot").unwrap();
+
+    let transfer_rewards_cpi_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.authority_rewards.to_account_info(),
+            to: ctx.accounts.snapshot_rewards.to_account_info(),
+            authority: ctx.accounts.authority.to_account_info(),
+        },
+    );
+    transfer(transfer_rewards_cpi_ctx, reward_amount)?;
+
+    emit!(SnapshotCreatedEvent {
+        pool: ctx.accounts.pool.key(),
+        snapshot: ctx.accounts.snapshot.key(),
+        index,
+        merkle_root,
+        lp_supply,
+        reward_mint: ctx.accounts.reward_mint.key(),
+        reward_amount,
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
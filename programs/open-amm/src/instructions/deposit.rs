@@ -1,12 +1,18 @@
+use crate::curve::get_curve_fee_bps;
 use crate::errors::OpenAmmErrorCode;
-use crate::instructions::create_pool::{LP_MINT_SEED, MINIMUM_LIQUIDITY, POOL_SEED};
+use crate::instructions::create_pool::{
+    get_pool_index_seed, BASE_FEE_VAULT_SEED, LP_MINT_SEED, POOL_SEED, QUOTE_FEE_VAULT_SEED,
+};
+use crate::oracle::{get_oracle_price, is_reserve_price_within_band};
 use crate::stableswap::calculate_stableswap_lp_minted;
 use crate::state::*;
-use crate::util::{get_orderbook, pool_authority_seeds, same_fraction};
+use crate::util::{
+    check_lp_supply, debug_msg, get_orderbook, get_xyk_lp_minted, lock_pool, pool_authority_seeds,
+    same_fraction, unlock_pool,
+};
 use anchor_lang::prelude::*;
 use anchor_spl::dex;
 use anchor_spl::token::{mint_to, transfer, Mint, MintTo, Token, TokenAccount, Transfer};
-use std::cmp;
 use std::mem::drop;
 
 #[event]
@@ -18,6 +24,10 @@ pub struct DepositEvent {
     end_base: u64,
     end_quote: u64,
     end_lp: u64,
+    pool: Pubkey,
+    signer: Pubkey,
+    slot: u64,
+    unix_timestamp: i64,
 }
 
 #[derive(Accounts)]
@@ -44,6 +54,20 @@ pub struct Deposit<'info> {
     #[account(mut)]
     pub quote_vault: Box<Account<'info, TokenAccount>>,
 
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), BASE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub base_fee_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), QUOTE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub quote_fee_vault: Box<Account<'info, TokenAccount>>,
+
     #[account(
         mut,
         seeds = [pool.key().as_ref(), LP_MINT_SEED.as_bytes().as_ref()],
@@ -78,7 +102,7 @@ pub struct Deposit<'info> {
 
     pub token_program: Program<'info, Token>,
 
-    #[account(address = dex::ID)]
+    #[account(address = pool.load()?.dex_program @ OpenAmmErrorCode::WrongDexProgram)]
     pub dex_program: Program<'info, dex::Dex>,
 
     pub rent: Sysvar<'info, Rent>,
@@ -91,49 +115,114 @@ pub fn handler<'info>(
     min_base_amount: u64,
     min_quote_amount: u64,
 ) -> Result<()> {
-    let cpi_token_program = ctx.accounts.token_program.to_account_info().clone();
-    let pool = ctx.accounts.pool.load()?;
+    deposit_liquidity(
+        ctx.accounts,
+        ctx.remaining_accounts,
+        desired_base_amount,
+        desired_quote_amount,
+        min_base_amount,
+        min_quote_amount,
+        false,
+    )?;
+    Ok(())
+}
+
+/// Deposits into the pool and mints LP tokens to `signer_lp`. Returns the
+/// base and quote amounts actually deposited and the LP amount minted. Fails
+/// while market making is halted, since the reserves can't be priced until a
+/// restart has counted what came back.
+///
+/// A `passive` deposit leaves the pool's orders alone, and mints against what
+/// the reserves would reconcile to. The deposited tokens sit in the vaults
+/// until the next refresh quotes them.
+pub fn deposit_liquidity<'info>(
+    accounts: &Deposit<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    desired_base_amount: u64,
+    desired_quote_amount: u64,
+    min_base_amount: u64,
+    min_quote_amount: u64,
+    passive: bool,
+) -> Result<(u64, u64, u64)> {
+    require!(
+        accounts.lp_mint.supply > 0,
+        OpenAmmErrorCode::PoolNotBootstrapped
+    );
+    lock_pool(&accounts.pool)?;
+    let cpi_token_program = accounts.token_program.to_account_info().clone();
+    let pool = accounts.pool.load()?;
     let pool_bump = pool.bump;
     let order_id = pool.client_order_id;
     let pool_type = pool.pool_type;
+    let pool_index = pool.index;
+    let debug_logging = pool.debug_logging;
     drop(pool);
 
     let orderbook = get_orderbook(
         order_id,
         pool_bump,
         pool_type,
-        ctx.accounts.pool.clone(),
-        ctx.accounts.market_accounts.clone(),
-        *ctx.accounts.base_vault.clone(),
-        *ctx.accounts.quote_vault.clone(),
-        ctx.accounts.dex_program.clone(),
-        ctx.accounts.token_program.clone(),
-        ctx.accounts.rent.clone(),
-        false,
-    );
-
-    orderbook.cancel_all_and_settle()?;
+        pool_index,
+        accounts.pool.clone(),
+        accounts.market_accounts.clone(),
+        *accounts.base_vault.clone(),
+        *accounts.quote_vault.clone(),
+        *accounts.base_fee_vault.clone(),
+        *accounts.quote_fee_vault.clone(),
+        accounts.dex_program.clone(),
+        accounts.token_program.clone(),
+        accounts.rent.clone(),
+        debug_logging,
+    )?
+    .with_rung_stats(remaining_accounts);
 
-    let mut pool = ctx.accounts.pool.load_mut()?;
-    if !pool.mm_active {
-        return Ok(());
+    if !passive {
+        orderbook.cancel_all_and_settle()?;
     }
 
+    let mut pool = accounts.pool.load_mut()?;
+    require!(pool.mm_active, OpenAmmErrorCode::MarketMakingInactive);
+
     let reserve_base_amount = pool.base_amount;
     let reserve_quote_amount = pool.quote_amount;
-    let start_lp = ctx.accounts.lp_mint.supply;
+    let start_lp = accounts.lp_mint.supply;
+
+    // LP is minted against the reserves with every fill so far credited, fees
+    // included, so a deposit doesn't get a share of fees earned before it. A
+    // regular deposit has just reconciled them into the pool. A passive one
+    // leaves its orders on the book, so reconciles a copy of the pool instead.
+    let (settled_base_amount, settled_quote_amount) = if passive {
+        let mut settled = *pool;
+        orderbook.reconcile(&mut settled)?;
+        (settled.base_amount, settled.quote_amount)
+    } else {
+        (reserve_base_amount, reserve_quote_amount)
+    };
     let mut deposit_base_amount = desired_base_amount;
     let mut deposit_quote_amount = desired_quote_amount;
 
-    if reserve_base_amount != 0 && reserve_quote_amount != 0 {
+    // Deposits are split in the ratio of the reserves the pool quoted over its
+    // last few refreshes. Reserves pushed around by fills right before a
+    // refresh have quoted for next to no slots, so they barely move the price
+    // a deposit sandwiched between them gets. Where that ratio is off the
+    // settled reserves', the side that buys fewer LP sets the mint.
+    let (price_base_amount, price_quote_amount) = pool.get_twap_reserves(Clock::get()?.slot);
+    debug_msg!(
+        debug_logging,
+        "openamm:deposit twap_base={} twap_quote={}",
+        price_base_amount,
+        price_quote_amount
+    );
+
+    if price_base_amount != 0 && price_quote_amount != 0 {
         if !same_fraction(
             (desired_quote_amount, desired_base_amount),
-            (reserve_quote_amount, reserve_base_amount),
+            (price_quote_amount, price_base_amount),
         ) {
             let optimal_quote_amount: u64 = (desired_base_amount as u128)
-                .checked_mul(reserve_quote_amount.into())
+                .checked_mul(price_quote_amount.into())
                 .unwrap()
-                .checked_div(reserve_base_amount.into())
+                .checked_div(price_base_amount.into())
                 .unwrap()
                 .try_into()
                 .unwrap();
@@ -145,9 +234,9 @@ pub fn handler<'info>(
                 deposit_quote_amount = optimal_quote_amount;
             } else {
                 let optimal_base_amount: u64 = (desired_quote_amount as u128)
-                    .checked_mul(reserve_base_amount.into())
+                    .checked_mul(price_base_amount.into())
                     .unwrap()
-                    .checked_div(reserve_quote_amount.into())
+                    .checked_div(price_quote_amount.into())
                     .unwrap()
                     .try_into()
                     .unwrap();
@@ -162,9 +251,9 @@ pub fn handler<'info>(
         let transfer_base_to_pool_cpi_ctx = CpiContext::new(
             cpi_token_program.clone(),
             Transfer {
-                from: ctx.accounts.signer_base.to_account_info(),
-                to: ctx.accounts.base_vault.to_account_info(),
-                authority: ctx.accounts.signer.to_account_info(),
+                from: accounts.signer_base.to_account_info(),
+                to: accounts.base_vault.to_account_info(),
+                authority: accounts.signer.to_account_info(),
             },
         );
         transfer(transfer_base_to_pool_cpi_ctx, deposit_base_amount)?;
@@ -173,9 +262,9 @@ pub fn handler<'info>(
         let transfer_quote_to_pool_cpi_ctx = CpiContext::new(
             cpi_token_program.clone(),
             Transfer {
-                from: ctx.accounts.signer_quote.to_account_info(),
-                to: ctx.accounts.quote_vault.to_account_info(),
-                authority: ctx.accounts.signer.to_account_info(),
+                from: accounts.signer_quote.to_account_info(),
+                to: accounts.quote_vault.to_account_info(),
+                authority: accounts.signer.to_account_info(),
             },
         );
         transfer(transfer_quote_to_pool_cpi_ctx, deposit_quote_amount)?;
@@ -183,51 +272,70 @@ pub fn handler<'info>(
         pool.quote_amount = pool.quote_amount.checked_add(deposit_quote_amount).unwrap();
     }
 
-    let lp_mint_supply = ctx.accounts.lp_mint.supply;
+    let lp_mint_supply = accounts.lp_mint.supply;
     let lp_minted: u64 = match pool.pool_type {
-        PoolType::XYK => match lp_mint_supply {
-            0 => ((deposit_base_amount as u128)
-                .checked_mul(deposit_quote_amount as u128)
-                .unwrap()
-                .checked_sub(MINIMUM_LIQUIDITY.into())
-                .unwrap() as f64)
-                .sqrt() as u64,
-            lp_mint_supply => cmp::min(
-                (lp_mint_supply as u128)
-                    .checked_mul(deposit_base_amount.into())
-                    .unwrap()
-                    .checked_div(reserve_base_amount.into())
-                    .unwrap()
-                    .try_into()
-                    .unwrap(),
-                (lp_mint_supply as u128)
-                    .checked_mul(deposit_quote_amount.into())
-                    .unwrap()
-                    .checked_div(reserve_quote_amount.into())
-                    .unwrap()
-                    .try_into()
-                    .unwrap(),
-            ),
-        },
-        PoolType::STABLE => calculate_stableswap_lp_minted(
+        PoolType::XYK => get_xyk_lp_minted(
             lp_mint_supply,
-            reserve_base_amount,
-            reserve_quote_amount,
+            settled_base_amount,
+            settled_quote_amount,
             deposit_base_amount,
             deposit_quote_amount,
-            pool.base_decimals,
-            pool.quote_decimals,
-        ),
+        )?,
+        PoolType::STABLE => {
+            let lp_minted = calculate_stableswap_lp_minted(
+                lp_mint_supply,
+                settled_base_amount,
+                settled_quote_amount,
+                deposit_base_amount,
+                deposit_quote_amount,
+                pool.base_decimals,
+                pool.quote_decimals,
+                get_curve_fee_bps(&pool),
+            );
+            check_lp_supply(lp_mint_supply, lp_minted)?;
+            lp_minted
+        }
     };
+    require!(lp_minted > 0, OpenAmmErrorCode::ZeroLpMinted);
+    check_deposit_cap(
+        &pool,
+        remaining_accounts,
+        settled_base_amount,
+        settled_quote_amount,
+        lp_minted,
+        lp_mint_supply,
+    )?;
+    pool.deposit_log
+        .record(1, accounts.signer.key(), Clock::get()?.slot);
+    pool.scale_reserve_checkpoints(
+        lp_mint_supply.checked_add(lp_minted).unwrap(),
+        lp_mint_supply,
+    );
+    debug_msg!(
+        debug_logging,
+        "openamm:deposit base={} quote={} lp_minted={} passive={}",
+        deposit_base_amount,
+        deposit_quote_amount,
+        lp_minted,
+        passive
+    );
     drop(pool);
 
-    orderbook.place_new_orders(&ctx.accounts.base_vault, &ctx.accounts.quote_vault)?;
+    if !passive {
+        orderbook.place_new_orders(
+            &accounts.base_vault,
+            &accounts.quote_vault,
+            remaining_accounts,
+        )?;
+    }
 
-    let market_key = ctx.accounts.market_accounts.market.key();
+    let market_key = accounts.market_accounts.market.key();
     let pool_type_bytes = (pool_type as u8).to_le_bytes();
+    let pool_index_seed = get_pool_index_seed(pool_index);
     let seeds = pool_authority_seeds!(
         market_key = market_key,
         pool_type_bytes = pool_type_bytes,
+        index_seed = pool_index_seed,
         bump = pool_bump
     );
     let pool_signer = &[&seeds[..]];
@@ -235,16 +343,17 @@ pub fn handler<'info>(
     let lp_mint_cpi_ctx = CpiContext::new_with_signer(
         cpi_token_program,
         MintTo {
-            mint: ctx.accounts.lp_mint.to_account_info(),
-            to: ctx.accounts.signer_lp.to_account_info(),
-            authority: ctx.accounts.pool.to_account_info(),
+            mint: accounts.lp_mint.to_account_info(),
+            to: accounts.signer_lp.to_account_info(),
+            authority: accounts.pool.to_account_info(),
         },
         pool_signer,
     );
 
     mint_to(lp_mint_cpi_ctx, lp_minted)?;
 
-    let pool = ctx.accounts.pool.load()?;
+    let pool = accounts.pool.load()?;
+    let clock = Clock::get()?;
     emit!(DepositEvent {
         pool_type: pool.pool_type,
         start_base: reserve_base_amount,
@@ -252,9 +361,64 @@ pub fn handler<'info>(
         start_lp,
         end_base: pool.base_amount,
         end_quote: pool.quote_amount,
-        end_lp: ctx.accounts.lp_mint.supply,
+        end_lp: accounts.lp_mint.supply,
+        pool: accounts.pool.key(),
+        signer: accounts.signer.key(),
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
     });
+    drop(pool);
+
+    unlock_pool(&accounts.pool)?;
+    Ok((deposit_base_amount, deposit_quote_amount, lp_minted))
+}
+
+/// Rejects a deposit minting more than the pool's cap while the reserves it
+/// mints against are too far off the oracle, or the oracle has no usable
+/// price. Skewing the reserves right before a large deposit and trading them
+/// back after would otherwise let it mint LP at the skewed ratio.
+fn check_deposit_cap(
+    pool: &OpenAmmPool,
+    remaining_accounts: &[AccountInfo],
+    settled_base_amount: u64,
+    settled_quote_amount: u64,
+    lp_minted: u64,
+    lp_mint_supply: u64,
+) -> Result<()> {
+    if !pool.has_oracle()
+        || pool.max_deposit_lp_bps == 0
+        || (lp_minted as u128)
+            .checked_mul(DEPOSIT_CAP_DENOMINATOR.into())
+            .unwrap()
+            <= (lp_mint_supply as u128)
+                .checked_mul(pool.max_deposit_lp_bps.into())
+                .unwrap()
+    {
+        return Ok(());
+    }
 
+    let oracle_info = remaining_accounts
+        .iter()
+        .find(|a| a.key() == pool.oracle)
+        .ok_or(OpenAmmErrorCode::MissingOracleAccount)?;
+    let oracle_price = get_oracle_price(
+        pool,
+        oracle_info,
+        pool.base_lot_size,
+        pool.quote_lot_size,
+        Clock::get()?.slot,
+    );
+    let within_band = oracle_price.map_or(false, |oracle_price| {
+        is_reserve_price_within_band(
+            oracle_price,
+            pool.deposit_cap_deviation_bps,
+            settled_base_amount,
+            settled_quote_amount,
+            pool.base_lot_size,
+            pool.quote_lot_size,
+        )
+    });
+    require!(within_band, OpenAmmErrorCode::DepositCapExceeded);
     Ok(())
 }
 
@@ -0,0 +1,188 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::create_deposit_intent::DEPOSIT_INTENT_SEED;
+use crate::instructions::create_pool::{get_pool_index_seed, POOL_SEED};
+use crate::instructions::deposit::*;
+use crate::state::*;
+use crate::util::pool_authority_seeds;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token::{transfer, TokenAccount, Transfer};
+
+#[event]
+pub struct DelegatedDepositEvent {
+    pool: Pubkey,
+    owner: Pubkey,
+    relayer: Pubkey,
+    base_amount: u64,
+    quote_amount: u64,
+    lp_minted: u64,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct DepositDelegated<'info> {
+    /// Made by the relayer, through its own token accounts.
+    pub deposit: Deposit<'info>,
+
+    #[account(
+        mut,
+        token::mint = deposit.base_vault.mint,
+        constraint = owner_base.delegate == COption::Some(deposit.pool.key())
+            @ OpenAmmErrorCode::WrongDelegate,
+    )]
+    pub owner_base: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = deposit.quote_vault.mint,
+        token::authority = owner_base.owner,
+        constraint = owner_quote.delegate == COption::Some(deposit.pool.key())
+            @ OpenAmmErrorCode::WrongDelegate,
+    )]
+    pub owner_quote: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = deposit.lp_mint,
+        token::authority = owner_base.owner,
+    )]
+    pub owner_lp: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [
+            deposit.pool.key().as_ref(),
+            owner_base.owner.as_ref(),
+            DEPOSIT_INTENT_SEED.as_bytes().as_ref(),
+        ],
+        bump = intent.bump,
+        has_one = rent_payer,
+        close = rent_payer,
+        constraint = intent.relayer == deposit.signer.key() @ OpenAmmErrorCode::WrongRelayer,
+    )]
+    pub intent: Box<Account<'info, DepositIntent>>,
+
+    /// CHECK: Only receives the intent's rent
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
+}
+
+/**
+ * Deposits for an owner who approved the pool as delegate of their base and
+ * quote token accounts, so a relayer can execute it without the owner
+ * signing. The approved amounts are pulled into the relayer's token accounts
+ * and deposited from there, and the LP minted and anything the reserve ratio
+ * had no room for go back to the owner in the same instruction.
+ *
+ * The amounts and slippage bounds come from the owner's deposit intent,
+ * which only the relayer it names can use, once and before it expires.
+ */
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, DepositDelegated<'info>>) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let deposit_accounts = &accounts.deposit;
+    let intent = &accounts.intent;
+    require!(
+        Clock::get()?.slot <= intent.expiry_slot,
+        OpenAmmErrorCode::DepositIntentExpired
+    );
+    let desired_base_amount = intent.desired_base_amount;
+    let desired_quote_amount = intent.desired_quote_amount;
+    let min_base_amount = intent.min_base_amount;
+    let min_quote_amount = intent.min_quote_amount;
+    let pool = deposit_accounts.pool.load()?;
+    let market_key = pool.market;
+    let pool_type_bytes = (pool.pool_type as u8).to_le_bytes();
+    let pool_index_seed = get_pool_index_seed(pool.index);
+    let pool_bump = pool.bump;
+    drop(pool);
+    let seeds = pool_authority_seeds!(
+        market_key = market_key,
+        pool_type_bytes = pool_type_bytes,
+        index_seed = pool_index_seed,
+        bump = pool_bump
+    );
+    let pool_signer = &[&seeds[..]];
+
+    let cpi_token_program = deposit_accounts.token_program.to_account_info();
+    let pull_base_cpi_ctx = CpiContext::new_with_signer(
+        cpi_token_program.clone(),
+        Transfer {
+            from: accounts.owner_base.to_account_info(),
+            to: deposit_accounts.signer_base.to_account_info(),
+            authority: deposit_accounts.pool.to_account_info(),
+        },
+        pool_signer,
+    );
+    transfer(pull_base_cpi_ctx, desired_base_amount)?;
+
+    let pull_quote_cpi_ctx = CpiContext::new_with_signer(
+        cpi_token_program.clone(),
+        Transfer {
+            from: accounts.owner_quote.to_account_info(),
+            to: deposit_accounts.signer_quote.to_account_info(),
+            authority: deposit_accounts.pool.to_account_info(),
+        },
+        pool_signer,
+    );
+    transfer(pull_quote_cpi_ctx, desired_quote_amount)?;
+
+    let (base_amount, quote_amount, lp_minted) = deposit_liquidity(
+        deposit_accounts,
+        ctx.remaining_accounts,
+        desired_base_amount,
+        desired_quote_amount,
+        min_base_amount,
+        min_quote_amount,
+        false,
+    )?;
+
+    let return_base_cpi_ctx = CpiContext::new(
+        cpi_token_program.clone(),
+        Transfer {
+            from: deposit_accounts.signer_base.to_account_info(),
+            to: accounts.owner_base.to_account_info(),
+            authority: deposit_accounts.signer.to_account_info(),
+        },
+    );
+    transfer(
+        return_base_cpi_ctx,
+        desired_base_amount.checked_sub(base_amount).unwrap(),
+    )?;
+
+    let return_quote_cpi_ctx = CpiContext::new(
+        cpi_token_program.clone(),
+        Transfer {
+            from: deposit_accounts.signer_quote.to_account_info(),
+            to: accounts.owner_quote.to_account_info(),
+            authority: deposit_accounts.signer.to_account_info(),
+        },
+    );
+    transfer(
+        return_quote_cpi_ctx,
+        desired_quote_amount.checked_sub(quote_amount).unwrap(),
+    )?;
+
+    let return_lp_cpi_ctx = CpiContext::new(
+        cpi_token_program,
+        Transfer {
+            from: deposit_accounts.signer_lp.to_account_info(),
+            to: accounts.owner_lp.to_account_info(),
+            authority: deposit_accounts.signer.to_account_info(),
+        },
+    );
+    transfer(return_lp_cpi_ctx, lp_minted)?;
+
+    let clock = Clock::get()?;
+    emit!(DelegatedDepositEvent {
+        pool: deposit_accounts.pool.key(),
+        owner: accounts.owner_base.owner,
+        relayer: deposit_accounts.signer.key(),
+        base_amount,
+        quote_amount,
+        lp_minted,
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    });
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::deposit::*;
+use anchor_lang::prelude::*;
+
+/**
+ * Deposits what an earlier instruction in the same transaction paid into the
+ * signer's token accounts, such as a withdraw from another AMM, so liquidity
+ * can move into the pool without being exposed to price moves between
+ * transactions. The amounts are the signer's balances above
+ * `base_balance_before` and `quote_balance_before`, read when the
+ * transaction was built.
+ *
+ * The other AMM's withdraw bounds what it pays out, and `min_lp_amount`
+ * bounds what that buys here, which covers both legs of the migration.
+ * Whatever the pool's reserve ratio has no room for stays with the signer.
+ */
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, Deposit<'info>>,
+    base_balance_before: u64,
+    quote_balance_before: u64,
+    min_lp_amount: u64,
+) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let base_amount = accounts
+        .signer_base
+        .amount
+        .saturating_sub(base_balance_before);
+    let quote_amount = accounts
+        .signer_quote
+        .amount
+        .saturating_sub(quote_balance_before);
+
+    let (_, _, lp_minted) = deposit_liquidity(
+        accounts,
+        ctx.remaining_accounts,
+        base_amount,
+        quote_amount,
+        0,
+        0,
+        false,
+    )?;
+    require!(
+        lp_minted >= min_lp_amount,
+        OpenAmmErrorCode::SlippageLpExceeded
+    );
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use crate::instructions::deposit::*;
+use anchor_lang::prelude::*;
+
+/**
+ * Deposits at the pool's reserves as of the last refresh without cancelling
+ * and re-placing its orders, which costs a fraction of the compute and dex
+ * fees of a regular deposit. Fills since the last refresh are still priced
+ * in, by reconciling a copy of the pool, but the new liquidity isn't quoted
+ * until the next refresh, so this is meant for deposits that are small next
+ * to the pool.
+ */
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, Deposit<'info>>,
+    desired_base_amount: u64,
+    desired_quote_amount: u64,
+    min_base_amount: u64,
+    min_quote_amount: u64,
+) -> Result<()> {
+    deposit_liquidity(
+        ctx.accounts,
+        ctx.remaining_accounts,
+        desired_base_amount,
+        desired_quote_amount,
+        min_base_amount,
+        min_quote_amount,
+        true,
+    )?;
+    Ok(())
+}
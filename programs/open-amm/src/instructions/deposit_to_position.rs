@@ -0,0 +1,46 @@
+use crate::instructions::deposit::*;
+use crate::instructions::open_position::POSITION_SEED;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct DepositToPosition<'info> {
+    pub deposit: Deposit<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            deposit.pool.key().as_ref(),
+            deposit.signer.key().as_ref(),
+            POSITION_SEED.as_bytes().as_ref(),
+        ],
+        bump = position.bump,
+    )]
+    pub position: Box<Account<'info, Position>>,
+}
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, DepositToPosition<'info>>,
+    desired_base_amount: u64,
+    desired_quote_amount: u64,
+    min_base_amount: u64,
+    min_quote_amount: u64,
+) -> Result<()> {
+    let (base_amount, quote_amount, lp_amount) = deposit_liquidity(
+        &ctx.accounts.deposit,
+        ctx.remaining_accounts,
+        desired_base_amount,
+        desired_quote_amount,
+        min_base_amount,
+        min_quote_amount,
+        false,
+    )?;
+
+    ctx.accounts.position.record_deposit(
+        base_amount,
+        quote_amount,
+        lp_amount,
+        Clock::get()?.unix_timestamp,
+    );
+    Ok(())
+}
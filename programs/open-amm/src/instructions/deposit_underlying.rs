@@ -0,0 +1,66 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::deposit::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct DepositUnderlying<'info> {
+    /// The metapool, whose quote leg is the underlying pool's LP token.
+    pub deposit: Deposit<'info>,
+
+    pub underlying_deposit: Deposit<'info>,
+}
+
+/**
+ * Deposits the underlying pool's tokens into it, then deposits the LP tokens
+ * that minted alongside `desired_base_amount` of the metapool's base into the
+ * metapool. Underlying LP tokens the metapool's reserve ratio has no room
+ * for stay in the signer's account.
+ *
+ * `min_quote_amount` is the fewest underlying LP tokens the metapool deposit
+ * may take. `remaining_accounts` must include both pools' oracles and anchor
+ * pools, if they have them.
+ */
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, DepositUnderlying<'info>>,
+    desired_base_amount: u64,
+    desired_underlying_base_amount: u64,
+    desired_underlying_quote_amount: u64,
+    min_base_amount: u64,
+    min_quote_amount: u64,
+    min_underlying_base_amount: u64,
+    min_underlying_quote_amount: u64,
+) -> Result<()> {
+    let accounts = &ctx.accounts.deposit;
+    let underlying_accounts = &ctx.accounts.underlying_deposit;
+    require!(
+        accounts.pool.load()?.underlying_pool == underlying_accounts.pool.key(),
+        OpenAmmErrorCode::WrongUnderlyingPool
+    );
+    require!(
+        accounts.signer.key() == underlying_accounts.signer.key()
+            && accounts.signer_quote.key() == underlying_accounts.signer_lp.key(),
+        OpenAmmErrorCode::WrongUnderlyingPool
+    );
+
+    let (_, _, underlying_lp_minted) = deposit_liquidity(
+        underlying_accounts,
+        ctx.remaining_accounts,
+        desired_underlying_base_amount,
+        desired_underlying_quote_amount,
+        min_underlying_base_amount,
+        min_underlying_quote_amount,
+        false,
+    )?;
+    require!(underlying_lp_minted > 0, OpenAmmErrorCode::ZeroLpMinted);
+
+    deposit_liquidity(
+        accounts,
+        ctx.remaining_accounts,
+        desired_base_amount,
+        underlying_lp_minted,
+        min_base_amount,
+        min_quote_amount,
+        false,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use crate::instructions::create_pool::*;
+use crate::instructions::create_rung_stats::RUNG_STATS_SEED;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+/// The program addresses of a pool and the accounts created with it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct PoolAddresses {
+    pub pool: Pubkey,
+    pub bump: u8,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub base_fee_vault: Pubkey,
+    pub quote_fee_vault: Pubkey,
+    pub lp_mint: Pubkey,
+    pub open_orders: Pubkey,
+    /// Not created with the pool, see `create_rung_stats`.
+    pub rung_stats: Pubkey,
+}
+
+#[derive(Accounts)]
+pub struct DeriveAddresses {}
+
+/**
+ * Returns the addresses of the pool of `pool_type` and `index` on `market`,
+ * whether or not it's been created, so programs composing with pools don't
+ * have to copy the seeds.
+ */
+pub fn handler(
+    _ctx: Context<DeriveAddresses>,
+    market: Pubkey,
+    pool_type: PoolType,
+    index: u16,
+) -> Result<PoolAddresses> {
+    let (pool, bump) = Pubkey::find_program_address(
+        &[
+            market.as_ref(),
+            (pool_type as u8).to_le_bytes().as_ref(),
+            get_pool_index_seed(index).as_ref(),
+            POOL_SEED.as_bytes(),
+        ],
+        &crate::ID,
+    );
+    let get_pool_pda =
+        |seed: &str| Pubkey::find_program_address(&[pool.as_ref(), seed.as_bytes()], &crate::ID).0;
+    Ok(PoolAddresses {
+        pool,
+        bump,
+        base_vault: get_pool_pda(BASE_VAULT_SEED),
+        quote_vault: get_pool_pda(QUOTE_VAULT_SEED),
+        base_fee_vault: get_pool_pda(BASE_FEE_VAULT_SEED),
+        quote_fee_vault: get_pool_pda(QUOTE_FEE_VAULT_SEED),
+        lp_mint: get_pool_pda(LP_MINT_SEED),
+        open_orders: get_pool_pda(OPEN_ORDERS_SEED),
+        rung_stats: get_pool_pda(RUNG_STATS_SEED),
+    })
+}
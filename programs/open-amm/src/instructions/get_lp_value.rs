@@ -0,0 +1,35 @@
+use crate::state::*;
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct LpValue {
+    pub base_amount: u64,
+    pub quote_amount: u64,
+}
+
+#[derive(Accounts)]
+pub struct GetLpValue<'info> {
+    #[account(has_one = lp_mint)]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub lp_mint: Box<Account<'info, Mint>>,
+}
+
+/**
+ * Returns the reserves `lp_amount` LP tokens would withdraw, so integrators
+ * can value LP tokens without decoding the pool account. Reserves are as of
+ * the last reconciliation, the same ones a withdraw starts from.
+ */
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, GetLpValue<'info>>,
+    lp_amount: u64,
+) -> Result<LpValue> {
+    let pool = ctx.accounts.pool.load()?;
+    let (base_amount, quote_amount) =
+        pool.get_lp_share_amounts(lp_amount, ctx.accounts.lp_mint.supply);
+    Ok(LpValue {
+        base_amount,
+        quote_amount,
+    })
+}
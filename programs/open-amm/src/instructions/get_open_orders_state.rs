@@ -0,0 +1,169 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::create_pool::{BASE_FEE_VAULT_SEED, QUOTE_FEE_VAULT_SEED};
+use crate::state::*;
+use crate::util::{get_orderbook, get_secondary_orderbook, CurrentOrder};
+use anchor_lang::prelude::*;
+use anchor_spl::dex;
+use anchor_spl::token::{Token, TokenAccount};
+use serum_dex::matching::Side;
+
+/// A rung the pool placed at its last refresh, and how much of it is still
+/// resting on the book.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct OpenOrderState {
+    pub rung: u8,
+    pub client_order_id: u64,
+    pub limit_price: u64,
+    /// Base lots the rung was placed with.
+    pub placed_base_qty: u64,
+    /// Base lots still resting, 0 once the rung has filled in full.
+    pub resting_base_qty: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct OpenOrdersState {
+    pub market: Pubkey,
+    pub base_lot_size: u64,
+    pub quote_lot_size: u64,
+    pub asks: Vec<OpenOrderState>,
+    pub bids: Vec<OpenOrderState>,
+}
+
+#[derive(Accounts)]
+pub struct GetOpenOrdersState<'info> {
+    #[account(
+        has_one = base_vault,
+        has_one = quote_vault,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub market_accounts: MarketAccounts<'info>,
+
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [pool.key().as_ref(), BASE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub base_fee_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [pool.key().as_ref(), QUOTE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub quote_fee_vault: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    #[account(address = pool.load()?.dex_program @ OpenAmmErrorCode::WrongDexProgram)]
+    pub dex_program: Program<'info, dex::Dex>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/**
+ * Returns the ladder the pool placed at its last refresh on either of its
+ * markets, with what's left of each rung on the book, so market data
+ * services can tell how much of the book's depth is the pool's. Nothing is
+ * reconciled or written, so fills since the last refresh show up only as
+ * rungs resting less than they were placed with.
+ */
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, GetOpenOrdersState<'info>>,
+) -> Result<OpenOrdersState> {
+    let pool = ctx.accounts.pool.load()?;
+    let market_key = ctx.accounts.market_accounts.market.key();
+    let open_orders_key = ctx.accounts.market_accounts.open_orders.key();
+    let secondary = pool.has_secondary_market() && market_key == pool.secondary_market;
+    if secondary {
+        require!(
+            open_orders_key == pool.secondary_open_orders,
+            OpenAmmErrorCode::WrongOpenOrdersAccount
+        );
+    } else {
+        require!(
+            market_key == pool.market,
+            OpenAmmErrorCode::WrongMarketAccount
+        );
+        require!(
+            open_orders_key == pool.open_orders,
+            OpenAmmErrorCode::WrongOpenOrdersAccount
+        );
+    }
+
+    let orderbook = if secondary {
+        get_secondary_orderbook(
+            pool.secondary_client_order_id,
+            pool.bump,
+            pool.pool_type,
+            pool.index,
+            pool.market,
+            ctx.accounts.pool.clone(),
+            ctx.accounts.market_accounts.clone(),
+            *ctx.accounts.base_vault.clone(),
+            *ctx.accounts.quote_vault.clone(),
+            *ctx.accounts.base_fee_vault.clone(),
+            *ctx.accounts.quote_fee_vault.clone(),
+            ctx.accounts.dex_program.clone(),
+            ctx.accounts.token_program.clone(),
+            ctx.accounts.rent.clone(),
+            pool.debug_logging,
+        )?
+    } else {
+        get_orderbook(
+            pool.client_order_id,
+            pool.bump,
+            pool.pool_type,
+            pool.index,
+            ctx.accounts.pool.clone(),
+            ctx.accounts.market_accounts.clone(),
+            *ctx.accounts.base_vault.clone(),
+            *ctx.accounts.quote_vault.clone(),
+            *ctx.accounts.base_fee_vault.clone(),
+            *ctx.accounts.quote_fee_vault.clone(),
+            ctx.accounts.dex_program.clone(),
+            ctx.accounts.token_program.clone(),
+            ctx.accounts.rent.clone(),
+            pool.debug_logging,
+        )?
+    };
+
+    let (placed_asks, placed_bids) = if secondary {
+        (&pool.secondary_placed_asks, &pool.secondary_placed_bids)
+    } else {
+        (&pool.placed_asks, &pool.placed_bids)
+    };
+    Ok(OpenOrdersState {
+        market: market_key,
+        base_lot_size: orderbook.base_lot_size,
+        quote_lot_size: orderbook.quote_lot_size,
+        asks: get_rung_states(placed_asks, Side::Ask, &orderbook.orders),
+        bids: get_rung_states(placed_bids, Side::Bid, &orderbook.orders),
+    })
+}
+
+fn get_rung_states(
+    placed: &PlacedOrders,
+    side: Side,
+    orders: &[CurrentOrder],
+) -> Vec<OpenOrderState> {
+    placed
+        .as_slice()
+        .iter()
+        .enumerate()
+        // Rungs that weren't placed at the last refresh are left zeroed
+        .filter(|(_, o)| o.client_order_id != 0)
+        .map(|(rung, o)| OpenOrderState {
+            rung: rung as u8,
+            client_order_id: o.client_order_id,
+            limit_price: o.limit_price,
+            placed_base_qty: o.base_qty,
+            resting_base_qty: orders
+                .iter()
+                .find(|c| c.side == side && c.client_order_id == o.client_order_id)
+                .map_or(0, |c| c.base_qty),
+        })
+        .collect()
+}
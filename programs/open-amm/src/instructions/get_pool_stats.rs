@@ -0,0 +1,83 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::oracle::get_quote_usd_value;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+/// A pool's activity over the last `STATS_DAYS` UTC days, in quote atoms.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct PoolStats {
+    /// Reserves valued in quote at the most recent ladder placement recorded.
+    pub tvl_quote_amount: u64,
+    /// Quote notional filled today, and over the whole window.
+    pub today_volume_quote_amount: u64,
+    pub volume_quote_amount: u64,
+    pub lp_fee_quote_amount: u64,
+    /// LP fees annualized against the window's average TVL, in bps.
+    pub fee_apr_bps: u64,
+    /// The quote amounts above in USD atoms of `USD_DECIMALS`, at the quote
+    /// oracle's current price. 0 without a usable quote oracle.
+    pub tvl_usd_amount: u64,
+    pub today_volume_usd_amount: u64,
+    pub volume_usd_amount: u64,
+    pub lp_fee_usd_amount: u64,
+}
+
+#[derive(Accounts)]
+pub struct GetPoolStats<'info> {
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+}
+
+/**
+ * Sums the pool's daily stats buckets, so frontends can show volume, TVL and
+ * a fee APR without an indexer. Everything is as of the last reconciliation
+ * and ladder placement, and quote values use the pool's own price. Pools
+ * with a quote oracle must pass it as a remaining account, and also get
+ * every amount in USD.
+ */
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, GetPoolStats<'info>>) -> Result<PoolStats> {
+    let pool = ctx.accounts.pool.load()?;
+    let clock = Clock::get()?;
+    let unix_timestamp = clock.unix_timestamp;
+    let today = unix_timestamp.div_euclid(SECONDS_PER_DAY.into()) as u64;
+    let recent = pool.get_recent_daily_stats(unix_timestamp);
+
+    let mut stats = PoolStats {
+        fee_apr_bps: pool.get_fee_apr_bps(unix_timestamp),
+        ..PoolStats::default()
+    };
+    let mut tvl_day = 0;
+    for day_stats in recent.iter() {
+        stats.volume_quote_amount = stats
+            .volume_quote_amount
+            .checked_add(day_stats.volume_quote_amount)
+            .unwrap();
+        stats.lp_fee_quote_amount = stats
+            .lp_fee_quote_amount
+            .checked_add(day_stats.lp_fee_quote_amount)
+            .unwrap();
+        if day_stats.day == today {
+            stats.today_volume_quote_amount = day_stats.volume_quote_amount;
+        }
+        if day_stats.tvl_quote_amount > 0 && day_stats.day >= tvl_day {
+            stats.tvl_quote_amount = day_stats.tvl_quote_amount;
+            tvl_day = day_stats.day;
+        }
+    }
+
+    if pool.has_quote_oracle() {
+        let quote_oracle_info = ctx
+            .remaining_accounts
+            .iter()
+            .find(|a| a.key() == pool.quote_oracle)
+            .ok_or(OpenAmmErrorCode::MissingOracleAccount)?;
+        let to_usd = |quote_amount: u64| {
+            get_quote_usd_value(&pool, quote_oracle_info, quote_amount, clock.slot).unwrap_or(0)
+        };
+        stats.tvl_usd_amount = to_usd(stats.tvl_quote_amount);
+        stats.today_volume_usd_amount = to_usd(stats.today_volume_quote_amount);
+        stats.volume_usd_amount = to_usd(stats.volume_quote_amount);
+        stats.lp_fee_usd_amount = to_usd(stats.lp_fee_quote_amount);
+    }
+
+    Ok(stats)
+}
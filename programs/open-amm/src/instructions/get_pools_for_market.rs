@@ -0,0 +1,72 @@
+use crate::curve::get_curve_fee_bps;
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::create_pool_registry::POOL_REGISTRY_SEED;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+/// A registered pool and what a router needs to pick between it and the
+/// market's other pools. Reserves are as of the last reconciliation.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct PoolSummary {
+    pub pool: Pubkey,
+    pub pool_type: PoolType,
+    pub index: u16,
+    /// The curve's fee plus any volatility fee, in bps.
+    pub fee_bps: u16,
+    /// Whether the pool is quoting: market making is on and it isn't paused.
+    pub active: bool,
+    pub base_amount: u64,
+    pub quote_amount: u64,
+}
+
+#[derive(Accounts)]
+pub struct GetPoolsForMarket<'info> {
+    #[account(
+        seeds = [market.key().as_ref(), POOL_REGISTRY_SEED.as_bytes().as_ref()],
+        bump = pool_registry.bump,
+    )]
+    pub pool_registry: Box<Account<'info, PoolRegistry>>,
+
+    /// CHECK: Only used as a seed
+    pub market: AccountInfo<'info>,
+}
+
+/**
+ * Returns every pool in the market's registry, in registry order, so routers
+ * can find each venue on a market without deriving a PDA per pool type and
+ * index. The registered pools must be passed as remaining accounts in the
+ * same order.
+ */
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, GetPoolsForMarket<'info>>,
+) -> Result<Vec<PoolSummary>> {
+    let pool_registry = &ctx.accounts.pool_registry;
+    let pools = &pool_registry.pools[..pool_registry.pool_count as usize];
+    require!(
+        ctx.remaining_accounts.len() == pools.len(),
+        OpenAmmErrorCode::WrongRegistryPools
+    );
+
+    let mut summaries = Vec::with_capacity(pools.len());
+    for (pool_key, pool_info) in pools.iter().zip(ctx.remaining_accounts) {
+        require!(
+            pool_info.key() == *pool_key,
+            OpenAmmErrorCode::WrongRegistryPools
+        );
+        let pool_loader = AccountLoader::<OpenAmmPool>::try_from(pool_info)?;
+        let pool = pool_loader.load()?;
+        summaries.push(PoolSummary {
+            pool: *pool_key,
+            pool_type: pool.pool_type,
+            index: pool.index,
+            fee_bps: get_curve_fee_bps(&pool)
+                .checked_add(pool.get_volatility_fee_bps())
+                .unwrap(),
+            active: pool.mm_active && !pool.paused,
+            base_amount: pool.base_amount,
+            quote_amount: pool.quote_amount,
+        });
+    }
+
+    Ok(summaries)
+}
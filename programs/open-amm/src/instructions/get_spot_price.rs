@@ -0,0 +1,60 @@
+use crate::curve::get_curve;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+const FEE_DENOMINATOR: u16 = 10_000;
+
+/// Prices are in the market's units, quote lots per base lot, like the
+/// limit prices of the pool's orders. All zero if the pool has nothing to
+/// quote.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct SpotPrice {
+    /// Marginal price of the pool's curve at its current reserves.
+    pub price: u64,
+    /// Where the pool's best bid and ask would sit after fees.
+    pub best_bid_price: u64,
+    pub best_ask_price: u64,
+    pub fee_bps: u16,
+}
+
+#[derive(Accounts)]
+pub struct GetSpotPrice<'info> {
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+}
+
+/**
+ * Returns the pool's instantaneous price from its reserves alone, so price
+ * readers don't need to fetch and decode the whole pool account. The reserves
+ * are as of the last reconciliation.
+ */
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, GetSpotPrice<'info>>) -> Result<SpotPrice> {
+    let pool = ctx.accounts.pool.load()?;
+    let curve = match get_curve(&pool) {
+        Some(curve) => curve,
+        None => return Ok(SpotPrice::default()),
+    };
+    let fee_bps = curve
+        .fee_bps()
+        .checked_add(pool.get_volatility_fee_bps())
+        .unwrap();
+
+    let lot_value = curve.spot_value(pool.base_lot_size);
+    let get_price = |fee_numerator: u16| -> u64 {
+        (lot_value as u128)
+            .checked_mul(fee_numerator.into())
+            .unwrap()
+            .checked_div(pool.quote_lot_size.into())
+            .unwrap()
+            .checked_div(FEE_DENOMINATOR.into())
+            .unwrap()
+            .try_into()
+            .unwrap()
+    };
+
+    Ok(SpotPrice {
+        price: get_price(FEE_DENOMINATOR),
+        best_bid_price: get_price(FEE_DENOMINATOR.checked_sub(fee_bps).unwrap()),
+        best_ask_price: get_price(FEE_DENOMINATOR.checked_add(fee_bps).unwrap()),
+        fee_bps,
+    })
+}
@@ -0,0 +1,80 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use crate::util::get_base_value_in_quote;
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+use serum_dex::state::Market;
+
+#[derive(Accounts)]
+pub struct GetTvl<'info> {
+    #[account(
+        has_one = base_vault,
+        has_one = quote_vault,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    /// CHECK:
+    #[account(
+        constraint = market.key() == pool.load()?.market
+            @ OpenAmmErrorCode::WrongMarketAccount,
+    )]
+    pub market: AccountInfo<'info>,
+
+    /// CHECK:
+    #[account(
+        constraint = open_orders.key() == pool.load()?.open_orders
+            @ OpenAmmErrorCode::WrongOpenOrdersAccount,
+    )]
+    pub open_orders: AccountInfo<'info>,
+
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+}
+
+/**
+ * Returns the pool's total value locked in quote atoms: everything held in
+ * its vaults and open orders account, less refunds owed to keepers, with base
+ * valued at the pool's own marginal price.
+ */
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, GetTvl<'info>>) -> Result<u64> {
+    get_pool_tvl(ctx.accounts)
+}
+
+pub fn get_pool_tvl<'info>(accounts: &GetTvl<'info>) -> Result<u64> {
+    let pool = accounts.pool.load()?;
+
+    let market_state = Market::load(&accounts.market, &pool.dex_program, false).unwrap();
+    let open_orders = Market::load_orders_mut(
+        &market_state,
+        &accounts.open_orders,
+        None,
+        &pool.dex_program,
+        None,
+        None,
+    )
+    .unwrap();
+    let native_base_total = open_orders.native_coin_total;
+    let native_quote_total = open_orders.native_pc_total;
+    drop(open_orders);
+    drop(market_state);
+
+    let base_held = accounts
+        .base_vault
+        .amount
+        .checked_add(native_base_total)
+        .unwrap()
+        .saturating_sub(pool.refund_base_amount)
+        .saturating_sub(pool.keeper_owed_base_amount);
+    let quote_held = accounts
+        .quote_vault
+        .amount
+        .checked_add(native_quote_total)
+        .unwrap()
+        .saturating_sub(pool.refund_quote_amount)
+        .saturating_sub(pool.keeper_owed_quote_amount);
+
+    Ok(get_base_value_in_quote(&pool, base_held)
+        .checked_add(quote_held)
+        .unwrap())
+}
@@ -0,0 +1,32 @@
+use crate::instructions::get_tvl::*;
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+#[derive(Accounts)]
+pub struct GetWrappedLpPrice<'info> {
+    pub tvl: GetTvl<'info>,
+
+    #[account(address = tvl.pool.load()?.lp_mint)]
+    pub lp_mint: Box<Account<'info, Mint>>,
+}
+
+/**
+ * Returns the quote atoms one whole wrapped LP token, or LP token, is worth:
+ * the pool's total value locked per LP token, scaled by the LP decimals.
+ * Lending protocols can read it through simulation or CPI to value wrapped
+ * LP collateral. Zero while the pool has no LP supply.
+ */
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, GetWrappedLpPrice<'info>>) -> Result<u64> {
+    let lp_supply = ctx.accounts.lp_mint.supply;
+    if lp_supply == 0 {
+        return Ok(0);
+    }
+    let tvl = get_pool_tvl(&ctx.accounts.tvl)?;
+    Ok((tvl as u128)
+        .checked_mul(10u128.pow(ctx.accounts.lp_mint.decimals.into()))
+        .unwrap()
+        .checked_div(lp_supply.into())
+        .unwrap()
+        .try_into()
+        .unwrap_or(u64::MAX))
+}
@@ -0,0 +1,76 @@
+use crate::instructions::create_pool::LP_MINT_SEED;
+use crate::state::*;
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+use solana_program::program::invoke;
+use solana_program::program_pack::Pack;
+
+pub const WRAPPED_LP_MINT_SEED: &str = "pool-wrapped-lp-mint";
+pub const WRAPPED_LP_VAULT_SEED: &str = "pool-wrapped-lp-vault";
+
+#[derive(Accounts)]
+pub struct InitWrappedLp<'info> {
+    #[account(has_one = lp_mint)]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(
+        seeds = [pool.key().as_ref(), LP_MINT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub lp_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: Initialized as a Token-2022 mint in the handler
+    #[account(
+        init,
+        seeds = [pool.key().as_ref(), WRAPPED_LP_MINT_SEED.as_bytes().as_ref()],
+        bump,
+        payer = payer,
+        owner = spl_token_2022::ID,
+        space = spl_token_2022::state::Mint::LEN,
+    )]
+    pub wrapped_lp_mint: AccountInfo<'info>,
+
+    #[account(
+        init,
+        token::mint = lp_mint,
+        token::authority = pool,
+        seeds = [pool.key().as_ref(), WRAPPED_LP_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+        payer = payer,
+    )]
+    pub wrapped_lp_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: The Token-2022 program
+    #[account(address = spl_token_2022::ID)]
+    pub token_2022_program: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// Creates the pool's wrapped LP mint, a Token-2022 mint the pool mints one
+/// of for each LP token locked in its wrapped LP vault. Anyone may pay for
+/// it.
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, InitWrappedLp<'info>>) -> Result<()> {
+    let instruction = spl_token_2022::instruction::initialize_mint2(
+        &spl_token_2022::ID,
+        &ctx.accounts.wrapped_lp_mint.key(),
+        &ctx.accounts.pool.key(),
+        None,
+        ctx.accounts.lp_mint.decimals,
+    )?;
+    invoke(
+        &instruction,
+        &[
+            ctx.accounts.wrapped_lp_mint.clone(),
+            ctx.accounts.token_2022_program.clone(),
+        ],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::create_pool::{get_pool_index_seed, POOL_SEED};
+use crate::instructions::deposit::*;
+use crate::stableswap::calculate_stableswap_lp_minted;
+use crate::state::*;
+use crate::util::{
+    check_fee_tick_floor, debug_msg, get_orderbook, get_xyk_lp_minted, lock_pool,
+    pool_authority_seeds, unlock_pool,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{mint_to, transfer, MintTo, Transfer};
+
+#[event]
+pub struct LiquidityInitializedEvent {
+    pool: Pubkey,
+    signer: Pubkey,
+    base_amount: u64,
+    quote_amount: u64,
+    lp_minted: u64,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+/**
+ * Makes the first deposit into a pool that has no LP supply, either because
+ * it was created empty or because every LP has withdrawn. Only the pool's
+ * bootstrapper may call it, since the amounts set the pool's starting price.
+ */
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, Deposit<'info>>,
+    base_amount: u64,
+    quote_amount: u64,
+) -> Result<()> {
+    let accounts = &ctx.accounts;
+    require!(
+        accounts.lp_mint.supply == 0,
+        OpenAmmErrorCode::PoolAlreadyBootstrapped
+    );
+    let pool = accounts.pool.load()?;
+    require!(
+        accounts.signer.key() == pool.get_bootstrapper(),
+        OpenAmmErrorCode::WrongBootstrapper
+    );
+    let pool_bump = pool.bump;
+    let order_id = pool.client_order_id;
+    let pool_type = pool.pool_type;
+    let pool_index = pool.index;
+    let debug_logging = pool.debug_logging;
+    let lp_minted: u64 = match pool_type {
+        PoolType::XYK => get_xyk_lp_minted(0, 0, 0, base_amount, quote_amount)?,
+        PoolType::STABLE => calculate_stableswap_lp_minted(
+            0,
+            0,
+            0,
+            base_amount,
+            quote_amount,
+            pool.base_decimals,
+            pool.quote_decimals,
+            0,
+        ),
+    };
+    require!(lp_minted > 0, OpenAmmErrorCode::ZeroLpMinted);
+    drop(pool);
+
+    lock_pool(&accounts.pool)?;
+    let cpi_token_program = accounts.token_program.to_account_info();
+    let transfer_base_to_pool_cpi_ctx = CpiContext::new(
+        cpi_token_program.clone(),
+        Transfer {
+            from: accounts.signer_base.to_account_info(),
+            to: accounts.base_vault.to_account_info(),
+            authority: accounts.signer.to_account_info(),
+        },
+    );
+    transfer(transfer_base_to_pool_cpi_ctx, base_amount)?;
+
+    let transfer_quote_to_pool_cpi_ctx = CpiContext::new(
+        cpi_token_program.clone(),
+        Transfer {
+            from: accounts.signer_quote.to_account_info(),
+            to: accounts.quote_vault.to_account_info(),
+            authority: accounts.signer.to_account_info(),
+        },
+    );
+    transfer(transfer_quote_to_pool_cpi_ctx, quote_amount)?;
+
+    let mut pool = accounts.pool.load_mut()?;
+    pool.base_amount = pool.base_amount.checked_add(base_amount).unwrap();
+    pool.quote_amount = pool.quote_amount.checked_add(quote_amount).unwrap();
+    pool.mm_active = true;
+    pool.deposit_log
+        .record(1, accounts.signer.key(), Clock::get()?.slot);
+    // Ladders placed while the pool was empty checkpointed no reserves
+    pool.reset_reserve_checkpoints();
+    drop(pool);
+
+    debug_msg!(
+        debug_logging,
+        "openamm:initialize_liquidity base={} quote={} lp_minted={}",
+        base_amount,
+        quote_amount,
+        lp_minted
+    );
+
+    check_fee_tick_floor(&accounts.pool)?;
+
+    let orderbook = get_orderbook(
+        order_id,
+        pool_bump,
+        pool_type,
+        pool_index,
+        accounts.pool.clone(),
+        accounts.market_accounts.clone(),
+        *accounts.base_vault.clone(),
+        *accounts.quote_vault.clone(),
+        *accounts.base_fee_vault.clone(),
+        *accounts.quote_fee_vault.clone(),
+        accounts.dex_program.clone(),
+        accounts.token_program.clone(),
+        accounts.rent.clone(),
+        debug_logging,
+    )?;
+    orderbook.place_new_orders(
+        &accounts.base_vault,
+        &accounts.quote_vault,
+        ctx.remaining_accounts,
+    )?;
+
+    let market_key = accounts.market_accounts.market.key();
+    let pool_type_bytes = (pool_type as u8).to_le_bytes();
+    let pool_index_seed = get_pool_index_seed(pool_index);
+    let seeds = pool_authority_seeds!(
+        market_key = market_key,
+        pool_type_bytes = pool_type_bytes,
+        index_seed = pool_index_seed,
+        bump = pool_bump
+    );
+    let pool_signer = &[&seeds[..]];
+
+    let lp_mint_cpi_ctx = CpiContext::new_with_signer(
+        cpi_token_program,
+        MintTo {
+            mint: accounts.lp_mint.to_account_info(),
+            to: accounts.signer_lp.to_account_info(),
+            authority: accounts.pool.to_account_info(),
+        },
+        pool_signer,
+    );
+    mint_to(lp_mint_cpi_ctx, lp_minted)?;
+
+    let clock = Clock::get()?;
+    emit!(LiquidityInitializedEvent {
+        pool: accounts.pool.key(),
+        signer: accounts.signer.key(),
+        base_amount,
+        quote_amount,
+        lp_minted,
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    });
+
+    unlock_pool(&accounts.pool)?;
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::deposit::*;
+use crate::instructions::withdraw::*;
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct LiquidityMigratedEvent {
+    from_pool: Pubkey,
+    to_pool: Pubkey,
+    signer: Pubkey,
+    lp_burned: u64,
+    /// Paid out of `from_pool` and deposited into `to_pool`. Whatever
+    /// `to_pool`'s reserve ratio had no room for stays with the signer.
+    withdrawn_base_amount: u64,
+    withdrawn_quote_amount: u64,
+    deposited_base_amount: u64,
+    deposited_quote_amount: u64,
+    lp_minted: u64,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct MigrateLiquidity<'info> {
+    pub withdraw: Withdraw<'info>,
+
+    pub deposit: Deposit<'info>,
+}
+
+/**
+ * Burns `lp_amt` of one pool's LP tokens and deposits what they paid out into
+ * another pool on the same pair, such as an XYK pool's liquidity into a
+ * stable pool or an old fee tier's into a new one, in one transaction. The
+ * deposit is bounded by `min_base_amount` and `min_quote_amount` like any
+ * other.
+ *
+ * `remaining_accounts` must include both pools' oracles and anchor pools, if
+ * they have them.
+ */
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, MigrateLiquidity<'info>>,
+    lp_amt: u64,
+    min_base_amount: u64,
+    min_quote_amount: u64,
+) -> Result<()> {
+    let withdraw_accounts = &ctx.accounts.withdraw;
+    let deposit_accounts = &ctx.accounts.deposit;
+    let from_pool = withdraw_accounts.pool.load()?;
+    let to_pool = deposit_accounts.pool.load()?;
+    require!(
+        withdraw_accounts.pool.key() != deposit_accounts.pool.key()
+            && from_pool.base_mint == to_pool.base_mint
+            && from_pool.quote_mint == to_pool.quote_mint,
+        OpenAmmErrorCode::WrongMigrationPool
+    );
+    require!(
+        withdraw_accounts.signer.key() == deposit_accounts.signer.key()
+            && withdraw_accounts.signer_base.key() == deposit_accounts.signer_base.key()
+            && withdraw_accounts.signer_quote.key() == deposit_accounts.signer_quote.key(),
+        OpenAmmErrorCode::WrongMigrationPool
+    );
+    drop(from_pool);
+    drop(to_pool);
+
+    let (withdrawn_base_amount, withdrawn_quote_amount, _, _) =
+        withdraw_liquidity(withdraw_accounts, ctx.remaining_accounts, lp_amt)?;
+    if withdrawn_base_amount == 0 && withdrawn_quote_amount == 0 {
+        return Ok(());
+    }
+
+    // The payout lands in the signer's token accounts, which the deposit
+    // then draws from
+    let (deposited_base_amount, deposited_quote_amount, lp_minted) = deposit_liquidity(
+        deposit_accounts,
+        ctx.remaining_accounts,
+        withdrawn_base_amount,
+        withdrawn_quote_amount,
+        min_base_amount,
+        min_quote_amount,
+        false,
+    )?;
+
+    let clock = Clock::get()?;
+    emit!(LiquidityMigratedEvent {
+        from_pool: withdraw_accounts.pool.key(),
+        to_pool: deposit_accounts.pool.key(),
+        signer: withdraw_accounts.signer.key(),
+        lp_burned: lp_amt,
+        withdrawn_base_amount,
+        withdrawn_quote_amount,
+        deposited_base_amount,
+        deposited_quote_amount,
+        lp_minted,
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    });
+    Ok(())
+}
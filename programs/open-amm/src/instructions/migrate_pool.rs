@@ -0,0 +1,191 @@
+use crate::curve::get_curve_fee_bps;
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use crate::util::DEFAULT_KEEPER_REFUND_BPS;
+use anchor_lang::__private::bytemuck::{self, Pod, Zeroable};
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_lang::Discriminator;
+use anchor_spl::dex;
+use serum_dex::state::Market;
+use std::mem::size_of;
+
+/// A placed order as pools stored it before orders recorded their fee.
+#[zero_copy]
+pub struct LegacyPlacedOrder {
+    pub limit_price: u64,
+    pub base_qty: u64,
+    pub max_native_quote_qty_including_fees: u64,
+    pub client_order_id: u64,
+}
+
+/// The pool layout from before placed orders became `PlacedOrders`, field for
+/// field, with its padding spelled out.
+#[zero_copy]
+pub struct LegacyOpenAmmPool {
+    pub base_amount: u64,
+    pub quote_amount: u64,
+    pub cumulative_quote_volume: u64,
+    pub cumulative_base_volume: u64,
+    pub refund_base_amount: u64,
+    pub refund_quote_amount: u64,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub market: Pubkey,
+    pub open_orders: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub lp_mint: Pubkey,
+    pub client_order_id: u64,
+    pub pool_type: PoolType,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    pub bump: u8,
+    pub padding: [u8; 4],
+    pub placed_asks: [LegacyPlacedOrder; LEGACY_ORDERS_PER_SIDE],
+    pub placed_bids: [LegacyPlacedOrder; LEGACY_ORDERS_PER_SIDE],
+    pub mm_active: bool,
+    pub tail_padding: [u8; 7],
+}
+
+unsafe impl Pod for LegacyPlacedOrder {}
+unsafe impl Zeroable for LegacyPlacedOrder {}
+unsafe impl Pod for LegacyOpenAmmPool {}
+unsafe impl Zeroable for LegacyOpenAmmPool {}
+
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    /// CHECK: Still has the legacy layout, so it can't be loaded as a pool
+    /// until migrated. Checked by owner and discriminator.
+    #[account(mut, owner = crate::ID)]
+    pub pool: AccountInfo<'info>,
+
+    /// CHECK: Checked against the legacy pool's market, and read for the lot
+    /// sizes the current layout stores.
+    #[account(owner = dex::ID)]
+    pub market: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Grows a pool created with the original layout to the current one. Fields
+/// the original layout had carry over, and every field added since is set as
+/// `create_pool` would set it. Anyone may migrate a pool; the payer funds the
+/// extra rent.
+///
+/// The original layout had no pool authority, so a migrated pool has none
+/// either and its settings stay at their defaults.
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, MigratePool<'info>>) -> Result<()> {
+    let pool_info = &ctx.accounts.pool;
+    let new_len = 8 + size_of::<OpenAmmPool>();
+    let legacy = {
+        let data = pool_info.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == OpenAmmPool::discriminator(),
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        require!(
+            data.len() == 8 + size_of::<LegacyOpenAmmPool>(),
+            OpenAmmErrorCode::NotLegacyPool
+        );
+        bytemuck::pod_read_unaligned::<LegacyOpenAmmPool>(&data[8..])
+    };
+    require!(
+        ctx.accounts.market.key() == legacy.market,
+        OpenAmmErrorCode::WrongMarketAccount
+    );
+
+    let market_state = Market::load(&ctx.accounts.market, &dex::ID, false).unwrap();
+    let base_lot_size = market_state.coin_lot_size;
+    let quote_lot_size = market_state.pc_lot_size;
+    drop(market_state);
+
+    let rent_shortfall = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(pool_info.lamports());
+    if rent_shortfall > 0 {
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.payer.to_account_info(),
+                to: pool_info.to_account_info(),
+            },
+        );
+        transfer(cpi_ctx, rent_shortfall)?;
+    }
+    pool_info.realloc(new_len, true)?;
+
+    let pool = migrate_legacy_pool(&legacy, base_lot_size, quote_lot_size);
+    let mut data = pool_info.try_borrow_mut_data()?;
+    data[8..].copy_from_slice(bytemuck::bytes_of(&pool));
+    Ok(())
+}
+
+/// Legacy orders were all priced with the curve's fee, as legacy pools had no
+/// volatility or rung fees.
+fn get_placed_orders(
+    legacy: &[LegacyPlacedOrder; LEGACY_ORDERS_PER_SIDE],
+    fee_bps: u16,
+) -> PlacedOrders {
+    let mut placed_orders = PlacedOrders::default();
+    for (rung, order) in legacy.iter().enumerate() {
+        placed_orders.set(
+            rung,
+            PlacedOrder {
+                limit_price: order.limit_price,
+                base_qty: order.base_qty,
+                max_native_quote_qty_including_fees: order.max_native_quote_qty_including_fees,
+                client_order_id: order.client_order_id,
+                fee_bps: fee_bps.into(),
+            },
+        );
+    }
+    placed_orders
+}
+
+/// The current layout of a legacy pool on a market with the given lot sizes.
+pub fn migrate_legacy_pool(
+    legacy: &LegacyOpenAmmPool,
+    base_lot_size: u64,
+    quote_lot_size: u64,
+) -> OpenAmmPool {
+    let mut pool = OpenAmmPool::zeroed();
+    pool.base_amount = legacy.base_amount;
+    pool.quote_amount = legacy.quote_amount;
+    pool.cumulative_quote_volume = legacy.cumulative_quote_volume;
+    pool.cumulative_base_volume = legacy.cumulative_base_volume;
+    pool.refund_base_amount = legacy.refund_base_amount;
+    pool.refund_quote_amount = legacy.refund_quote_amount;
+    pool.base_mint = legacy.base_mint;
+    pool.quote_mint = legacy.quote_mint;
+    pool.market = legacy.market;
+    pool.open_orders = legacy.open_orders;
+    pool.base_vault = legacy.base_vault;
+    pool.quote_vault = legacy.quote_vault;
+    pool.lp_mint = legacy.lp_mint;
+    pool.client_order_id = legacy.client_order_id;
+    pool.pool_type = legacy.pool_type;
+    pool.base_decimals = legacy.base_decimals;
+    pool.quote_decimals = legacy.quote_decimals;
+    pool.bump = legacy.bump;
+    pool.mm_active = legacy.mm_active;
+
+    // Added since, with the values create_pool gives a new pool. Anything
+    // not set here starts at zero there too.
+    pool.secondary_client_order_id = 1;
+    pool.base_lot_size = base_lot_size;
+    pool.quote_lot_size = quote_lot_size;
+    pool.orders_per_side = LEGACY_ORDERS_PER_SIDE as u8;
+    pool.keeper_refund_bps = DEFAULT_KEEPER_REFUND_BPS;
+    // Legacy pools could only be created on the one dex
+    pool.dex_program = dex::ID;
+    // Once the rest is in place, so the fee is the one the orders were
+    // priced with
+    let fee_bps = get_curve_fee_bps(&pool);
+    pool.placed_asks = get_placed_orders(&legacy.placed_asks, fee_bps);
+    pool.placed_bids = get_placed_orders(&legacy.placed_bids, fee_bps);
+    pool
+}
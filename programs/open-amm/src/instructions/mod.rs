@@ -1,11 +1,165 @@
+pub mod accept_authority;
+pub mod approve_proposal;
+pub mod cancel_deposit_intent;
+pub mod check_pool_health;
+pub mod claim_airdrop;
+pub mod claim_keeper_rewards;
+pub mod claim_withdraw;
+pub mod create_deposit_intent;
+pub mod create_keeper;
 pub mod create_pool;
+pub mod create_pool_registry;
+pub mod create_rung_stats;
+pub mod create_snapshot;
 pub mod deposit;
+pub mod deposit_delegated;
+pub mod deposit_migrated;
+pub mod deposit_passive;
+pub mod deposit_to_position;
+pub mod deposit_underlying;
+pub mod derive_addresses;
+pub mod get_lp_value;
+pub mod get_open_orders_state;
+pub mod get_pool_stats;
+pub mod get_pools_for_market;
+pub mod get_spot_price;
+pub mod get_tvl;
+pub mod get_wrapped_lp_price;
+pub mod init_wrapped_lp;
+pub mod initialize_liquidity;
+pub mod migrate_liquidity;
+pub mod migrate_pool;
+pub mod open_position;
+pub mod pause_pool;
+pub mod preview_ladder;
+pub mod process_fees;
+pub mod propose_parameters;
+pub mod rebalance;
 pub mod refresh_orders;
+pub mod refresh_secondary_orders;
+pub mod register_keeper_pool;
+pub mod register_pool;
+pub mod register_secondary_market;
+pub mod register_underlying_pool;
+pub mod reject_proposal;
+pub mod request_withdraw;
+pub mod rescue;
+pub mod resume_pool;
+pub mod safe_mode;
+pub mod set_anchor_pool;
+pub mod set_authority;
+pub mod set_bootstrapper;
+pub mod set_compute_budget;
+pub mod set_crossing_policy;
+pub mod set_debug_logging;
+pub mod set_deleverage;
+pub mod set_depeg_fallback;
+pub mod set_deposit_cap;
+pub mod set_drift_alarm;
+pub mod set_dust_thresholds;
+pub mod set_event_queue_limit;
+pub mod set_fee_router;
+pub mod set_guardian;
+pub mod set_jitter;
+pub mod set_keeper_refund;
+pub mod set_max_quote_at_risk;
+pub mod set_oracle;
+pub mod set_orders_per_side;
+pub mod set_pool_metadata;
+pub mod set_quote_oracle;
+pub mod set_quoting_schedule;
+pub mod set_refresh_limits;
+pub mod set_rung_fees;
+pub mod set_volatility_spread;
+pub mod set_withdraw_delay;
+pub mod simulate_refresh;
+pub mod sync_reserves;
+pub mod unwrap_lp;
 pub mod withdraw;
+pub mod withdraw_all;
+pub mod withdraw_from_position;
+pub mod withdraw_underlying;
+pub mod wrap_lp;
 pub mod restart_market_making;
 
+pub use accept_authority::*;
+pub use approve_proposal::*;
+pub use cancel_deposit_intent::*;
+pub use check_pool_health::*;
+pub use claim_airdrop::*;
+pub use claim_keeper_rewards::*;
+pub use claim_withdraw::*;
+pub use create_deposit_intent::*;
+pub use create_keeper::*;
 pub use create_pool::*;
+pub use create_pool_registry::*;
+pub use create_rung_stats::*;
+pub use create_snapshot::*;
 pub use deposit::*;
+pub use deposit_delegated::*;
+pub use deposit_to_position::*;
+pub use deposit_underlying::*;
+pub use derive_addresses::*;
+pub use get_lp_value::*;
+pub use get_open_orders_state::*;
+pub use get_pool_stats::*;
+pub use get_pools_for_market::*;
+pub use get_spot_price::*;
+pub use get_tvl::*;
+pub use get_wrapped_lp_price::*;
+pub use init_wrapped_lp::*;
+pub use initialize_liquidity::*;
+pub use migrate_liquidity::*;
+pub use migrate_pool::*;
+pub use open_position::*;
+pub use pause_pool::*;
+pub use preview_ladder::*;
+pub use process_fees::*;
+pub use propose_parameters::*;
+pub use rebalance::*;
 pub use refresh_orders::*;
+pub use refresh_secondary_orders::*;
+pub use register_keeper_pool::*;
+pub use register_pool::*;
+pub use register_secondary_market::*;
+pub use register_underlying_pool::*;
+pub use reject_proposal::*;
+pub use request_withdraw::*;
+pub use rescue::*;
+pub use resume_pool::*;
+pub use safe_mode::*;
+pub use set_anchor_pool::*;
+pub use set_authority::*;
+pub use set_bootstrapper::*;
+pub use set_compute_budget::*;
+pub use set_crossing_policy::*;
+pub use set_debug_logging::*;
+pub use set_deleverage::*;
+pub use set_depeg_fallback::*;
+pub use set_deposit_cap::*;
+pub use set_drift_alarm::*;
+pub use set_dust_thresholds::*;
+pub use set_event_queue_limit::*;
+pub use set_fee_router::*;
+pub use set_guardian::*;
+pub use set_jitter::*;
+pub use set_keeper_refund::*;
+pub use set_max_quote_at_risk::*;
+pub use set_oracle::*;
+pub use set_orders_per_side::*;
+pub use set_pool_metadata::*;
+pub use set_quote_oracle::*;
+pub use set_quoting_schedule::*;
+pub use set_refresh_limits::*;
+pub use set_rung_fees::*;
+pub use set_volatility_spread::*;
+pub use set_withdraw_delay::*;
+pub use simulate_refresh::*;
+pub use sync_reserves::*;
+pub use unwrap_lp::*;
 pub use withdraw::*;
+pub use withdraw_all::*;
+pub use withdraw_from_position::*;
+pub use withdraw_underlying::*;
+pub use wrap_lp::*;
 pub use restart_market_making::*;
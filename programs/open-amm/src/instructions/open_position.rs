@@ -0,0 +1,33 @@
+use crate::state::*;
+use anchor_lang::prelude::*;
+use std::mem::size_of;
+
+pub const POSITION_SEED: &str = "pool-position";
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(
+        init,
+        seeds = [pool.key().as_ref(), owner.key().as_ref(), POSITION_SEED.as_bytes().as_ref()],
+        bump,
+        payer = owner,
+        space = 8 + size_of::<Position>(),
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, OpenPosition<'info>>) -> Result<()> {
+    let position = &mut ctx.accounts.position;
+    position.pool = ctx.accounts.pool.key();
+    position.owner = ctx.accounts.owner.key();
+    position.opened_at = Clock::get()?.unix_timestamp;
+    position.bump = *ctx.bumps.get("position").unwrap();
+    Ok(())
+}
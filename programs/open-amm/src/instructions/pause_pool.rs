@@ -0,0 +1,69 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::safe_mode::SafeMode;
+use crate::util::{debug_msg, get_orderbook, lock_pool, unlock_pool};
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct PoolPausedEvent {
+    pool: Pubkey,
+    signer: Pubkey,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+/**
+ * Pulls the pool's orders off the book and keeps them off until the
+ * authority calls `resume_pool`. Callable by the guardian as well as the
+ * authority. Pausing only ever cancels orders and settles into the pool's
+ * own vaults, so the guardian key can't move funds or change parameters.
+ * Deposits and withdrawals keep working while paused.
+ */
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, SafeMode<'info>>) -> Result<()> {
+    lock_pool(&ctx.accounts.pool)?;
+    let pool = ctx.accounts.pool.load()?;
+    let signer = ctx.accounts.signer.key();
+    require!(
+        signer == pool.authority || (pool.guardian != Pubkey::default() && signer == pool.guardian),
+        OpenAmmErrorCode::NotGuardian
+    );
+    let pool_bump = pool.bump;
+    let order_id = pool.client_order_id;
+    let pool_type = pool.pool_type;
+    let pool_index = pool.index;
+    let debug_logging = pool.debug_logging;
+    drop(pool);
+
+    let orderbook = get_orderbook(
+        order_id,
+        pool_bump,
+        pool_type,
+        pool_index,
+        ctx.accounts.pool.clone(),
+        ctx.accounts.market_accounts.clone(),
+        *ctx.accounts.base_vault.clone(),
+        *ctx.accounts.quote_vault.clone(),
+        *ctx.accounts.base_fee_vault.clone(),
+        *ctx.accounts.quote_fee_vault.clone(),
+        ctx.accounts.dex_program.clone(),
+        ctx.accounts.token_program.clone(),
+        ctx.accounts.rent.clone(),
+        debug_logging,
+    )?;
+    orderbook.cancel_all_and_settle()?;
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.paused = true;
+    debug_msg!(debug_logging, "openamm:pause_pool signer={}", signer);
+    drop(pool);
+
+    let clock = Clock::get()?;
+    emit!(PoolPausedEvent {
+        pool: ctx.accounts.pool.key(),
+        signer,
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    });
+
+    unlock_pool(&ctx.accounts.pool)?;
+    Ok(())
+}
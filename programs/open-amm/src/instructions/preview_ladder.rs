@@ -0,0 +1,44 @@
+use crate::state::*;
+use crate::util::get_ladder;
+use anchor_lang::prelude::*;
+use serum_dex::matching::Side;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct LadderRung {
+    pub limit_price: u64,
+    pub base_qty: u64,
+    pub max_native_quote_qty_including_fees: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct LadderPreview {
+    pub asks: Vec<LadderRung>,
+    pub bids: Vec<LadderRung>,
+}
+
+#[derive(Accounts)]
+pub struct PreviewLadder<'info> {
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+}
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, PreviewLadder<'info>>,
+) -> Result<LadderPreview> {
+    let pool = ctx.accounts.pool.load()?;
+    let ladder = get_ladder(&pool, pool.base_lot_size, pool.quote_lot_size, None, None)?;
+
+    let mut preview = LadderPreview::default();
+    for order in ladder.iter() {
+        let rung = LadderRung {
+            limit_price: order.limit_price,
+            base_qty: order.base_qty,
+            max_native_quote_qty_including_fees: order.max_native_quote_qty_including_fees,
+        };
+        match order.side {
+            Side::Ask => preview.asks.push(rung),
+            Side::Bid => preview.bids.push(rung),
+        }
+    }
+
+    Ok(preview)
+}
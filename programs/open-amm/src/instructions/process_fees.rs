@@ -0,0 +1,304 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::create_pool::{
+    get_pool_index_seed, BASE_FEE_VAULT_SEED, POOL_SEED, QUOTE_FEE_VAULT_SEED,
+};
+use crate::oracle::{get_oracle_price, is_reserve_price_within_band};
+use crate::state::*;
+use crate::util::{
+    debug_msg, get_base_value_in_quote, get_orderbook, get_swap_amount_out, lock_pool,
+    pool_authority_seeds, unlock_pool,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::dex;
+use anchor_spl::token::{transfer, Token, TokenAccount, Transfer};
+use std::cmp;
+
+/// Most of the fees a run swaps, in bps of the reserve they're swapped into,
+/// so the price impact LPs absorb per run stays small. The rest waits for
+/// later runs.
+pub const MAX_FEE_SWAP_RESERVE_BPS: u16 = 50;
+pub const FEE_SWAP_DENOMINATOR: u16 = 10_000;
+
+#[event]
+pub struct FeesProcessedEvent {
+    pool: Pubkey,
+    target: FeeRouterTarget,
+    swapped_in: u64,
+    swapped_out: u64,
+    distributed: u64,
+    signer: Pubkey,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct ProcessFees<'info> {
+    #[account(
+        mut,
+        has_one = base_vault,
+        has_one = quote_vault,
+        has_one = fee_distribution @ OpenAmmErrorCode::WrongFeeDistributionAccount,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(
+        constraint = market_accounts.market.key() == pool.load()?.market
+            @ OpenAmmErrorCode::WrongMarketAccount,
+        constraint = market_accounts.open_orders.key() == pool.load()?.open_orders
+            @ OpenAmmErrorCode::WrongOpenOrdersAccount,
+    )]
+    pub market_accounts: MarketAccounts<'info>,
+
+    #[account(mut)]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), BASE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub base_fee_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), QUOTE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub quote_fee_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub fee_distribution: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    #[account(address = pool.load()?.dex_program @ OpenAmmErrorCode::WrongDexProgram)]
+    pub dex_program: Program<'info, dex::Dex>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, ProcessFees<'info>>) -> Result<()> {
+    lock_pool(&ctx.accounts.pool)?;
+    let pool = ctx.accounts.pool.load()?;
+    let pool_bump = pool.bump;
+    let order_id = pool.client_order_id;
+    let pool_type = pool.pool_type;
+    let pool_index = pool.index;
+    let debug_logging = pool.debug_logging;
+    let target = pool.fee_router_target;
+    let current_slot = Clock::get()?.slot;
+    require!(
+        !matches!(target, FeeRouterTarget::DISABLED),
+        OpenAmmErrorCode::FeeRouterDisabled
+    );
+    require!(
+        current_slot
+            >= pool
+                .last_fee_process_slot
+                .checked_add(pool.fee_process_interval_slots)
+                .unwrap(),
+        OpenAmmErrorCode::FeeProcessingTooSoon
+    );
+    drop(pool);
+
+    let orderbook = get_orderbook(
+        order_id,
+        pool_bump,
+        pool_type,
+        pool_index,
+        ctx.accounts.pool.clone(),
+        ctx.accounts.market_accounts.clone(),
+        *ctx.accounts.base_vault.clone(),
+        *ctx.accounts.quote_vault.clone(),
+        *ctx.accounts.base_fee_vault.clone(),
+        *ctx.accounts.quote_fee_vault.clone(),
+        ctx.accounts.dex_program.clone(),
+        ctx.accounts.token_program.clone(),
+        ctx.accounts.rent.clone(),
+        debug_logging,
+    )?;
+
+    // Reconcile first so the swap is priced off up-to-date reserves and any
+    // fees from the latest fills are already sitting in the fee vaults.
+    orderbook.cancel_all_and_settle()?;
+
+    let pool = ctx.accounts.pool.load()?;
+    if !pool.mm_active {
+        drop(pool);
+        return unlock_pool(&ctx.accounts.pool);
+    }
+    drop(pool);
+
+    ctx.accounts.base_fee_vault.reload()?;
+    ctx.accounts.quote_fee_vault.reload()?;
+
+    // Fees on the non-target side are swapped against the pool's own curve:
+    // they are deposited into the pool's vault and the curve's output is
+    // paid from the opposite vault.
+    let base_in = matches!(target, FeeRouterTarget::QUOTE);
+    let (swap_from, swap_into, pay_from, forward_from) = if base_in {
+        (
+            &ctx.accounts.base_fee_vault,
+            &ctx.accounts.base_vault,
+            &ctx.accounts.quote_vault,
+            &ctx.accounts.quote_fee_vault,
+        )
+    } else {
+        (
+            &ctx.accounts.quote_fee_vault,
+            &ctx.accounts.quote_vault,
+            &ctx.accounts.base_vault,
+            &ctx.accounts.base_fee_vault,
+        )
+    };
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    let reserve_in = if base_in {
+        pool.base_amount
+    } else {
+        pool.quote_amount
+    };
+    let max_swap_in = (reserve_in as u128)
+        .checked_mul(MAX_FEE_SWAP_RESERVE_BPS.into())
+        .unwrap()
+        .checked_div(FEE_SWAP_DENOMINATOR.into())
+        .unwrap() as u64;
+    let mut swapped_in = cmp::min(swap_from.amount, max_swap_in);
+    // Anyone can pick when this runs, such as right after a fill has pushed
+    // the reserves off fair value. With an oracle, only swap while the
+    // curve's price is within the pool's oracle band.
+    if pool.has_oracle() {
+        let oracle_info = ctx
+            .remaining_accounts
+            .iter()
+            .find(|a| a.key() == pool.oracle)
+            .ok_or(OpenAmmErrorCode::MissingOracleAccount)?;
+        let oracle_price = get_oracle_price(
+            &pool,
+            oracle_info,
+            pool.base_lot_size,
+            pool.quote_lot_size,
+            current_slot,
+        );
+        let within_band = oracle_price.map_or(false, |oracle_price| {
+            is_reserve_price_within_band(
+                oracle_price,
+                pool.oracle_band_bps,
+                pool.base_amount,
+                get_base_value_in_quote(&pool, pool.base_amount),
+                pool.base_lot_size,
+                pool.quote_lot_size,
+            )
+        });
+        debug_msg!(
+            debug_logging,
+            "openamm:fees oracle_price={:?} band_bps={} within_band={}",
+            oracle_price,
+            pool.oracle_band_bps,
+            within_band
+        );
+        if !within_band {
+            swapped_in = 0;
+        }
+    }
+    let swapped_out = get_swap_amount_out(&pool, swapped_in, base_in);
+    if swapped_out == 0 {
+        // Too small to move the curve, or held back above; leave it accruing
+        // for the next run.
+        swapped_in = 0;
+    } else if base_in {
+        pool.base_amount = pool.base_amount.checked_add(swapped_in).unwrap();
+        pool.quote_amount = pool.quote_amount.checked_sub(swapped_out).unwrap();
+    } else {
+        pool.quote_amount = pool.quote_amount.checked_add(swapped_in).unwrap();
+        pool.base_amount = pool.base_amount.checked_sub(swapped_out).unwrap();
+    }
+    let forwarded = forward_from.amount;
+    pool.last_fee_process_slot = current_slot;
+    drop(pool);
+
+    let market_key = ctx.accounts.market_accounts.market.key();
+    let pool_type_bytes = (pool_type as u8).to_le_bytes();
+    let pool_index_seed = get_pool_index_seed(pool_index);
+    let seeds = pool_authority_seeds!(
+        market_key = market_key,
+        pool_type_bytes = pool_type_bytes,
+        index_seed = pool_index_seed,
+        bump = pool_bump
+    );
+    let pool_signer = &[&seeds[..]];
+
+    let cpi_token_program = ctx.accounts.token_program.to_account_info();
+    if swapped_out > 0 {
+        let swap_in_cpi_ctx = CpiContext::new_with_signer(
+            cpi_token_program.clone(),
+            Transfer {
+                from: swap_from.to_account_info(),
+                to: swap_into.to_account_info(),
+                authority: ctx.accounts.pool.to_account_info(),
+            },
+            pool_signer,
+        );
+        transfer(swap_in_cpi_ctx, swapped_in)?;
+
+        let swap_out_cpi_ctx = CpiContext::new_with_signer(
+            cpi_token_program.clone(),
+            Transfer {
+                from: pay_from.to_account_info(),
+                to: ctx.accounts.fee_distribution.to_account_info(),
+                authority: ctx.accounts.pool.to_account_info(),
+            },
+            pool_signer,
+        );
+        transfer(swap_out_cpi_ctx, swapped_out)?;
+    }
+
+    if forwarded > 0 {
+        let forward_cpi_ctx = CpiContext::new_with_signer(
+            cpi_token_program,
+            Transfer {
+                from: forward_from.to_account_info(),
+                to: ctx.accounts.fee_distribution.to_account_info(),
+                authority: ctx.accounts.pool.to_account_info(),
+            },
+            pool_signer,
+        );
+        transfer(forward_cpi_ctx, forwarded)?;
+    }
+
+    debug_msg!(
+        debug_logging,
+        "openamm:fees swapped_in={} swapped_out={} forwarded={}",
+        swapped_in,
+        swapped_out,
+        forwarded
+    );
+
+    orderbook.place_new_orders(
+        &ctx.accounts.base_vault,
+        &ctx.accounts.quote_vault,
+        ctx.remaining_accounts,
+    )?;
+
+    let distributed = swapped_out.checked_add(forwarded).unwrap();
+    let clock = Clock::get()?;
+    emit!(FeesProcessedEvent {
+        pool: ctx.accounts.pool.key(),
+        target,
+        swapped_in,
+        swapped_out,
+        distributed,
+        signer: ctx.accounts.signer.key(),
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    });
+
+    unlock_pool(&ctx.accounts.pool)?;
+    Ok(())
+}
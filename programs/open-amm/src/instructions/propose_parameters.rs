@@ -0,0 +1,62 @@
+use crate::state::*;
+use anchor_lang::prelude::*;
+use std::mem::size_of;
+
+pub const PROPOSAL_SEED: &str = "pool-proposal";
+
+#[event]
+pub struct ParametersProposedEvent {
+    pool: Pubkey,
+    proposer: Pubkey,
+    proposal: Pubkey,
+    parameter: PoolParameter,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct ProposeParameters<'info> {
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(
+        init,
+        seeds = [pool.key().as_ref(), proposer.key().as_ref(), PROPOSAL_SEED.as_bytes().as_ref()],
+        bump,
+        payer = proposer,
+        space = 8 + size_of::<ParameterProposal>(),
+    )]
+    pub proposal: Box<Account<'info, ParameterProposal>>,
+
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Queues a change to one of the pool's settings for its authority to approve.
+/// Anyone may propose; the value is checked against the setter's bounds up
+/// front so the authority only ever sees changes it could make itself.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, ProposeParameters<'info>>,
+    parameter: PoolParameter,
+) -> Result<()> {
+    parameter.validate()?;
+
+    let clock = Clock::get()?;
+    let proposal = &mut ctx.accounts.proposal;
+    proposal.pool = ctx.accounts.pool.key();
+    proposal.proposer = ctx.accounts.proposer.key();
+    proposal.parameter = parameter;
+    proposal.proposed_at = clock.unix_timestamp;
+    proposal.bump = *ctx.bumps.get("proposal").unwrap();
+
+    emit!(ParametersProposedEvent {
+        pool: proposal.pool,
+        proposer: proposal.proposer,
+        proposal: proposal.key(),
+        parameter,
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    });
+    Ok(())
+}
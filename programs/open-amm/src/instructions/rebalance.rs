@@ -0,0 +1,307 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::create_pool::{BASE_FEE_VAULT_SEED, QUOTE_FEE_VAULT_SEED};
+use crate::state::*;
+use crate::util::{debug_msg, get_orderbook, lock_pool, unlock_pool};
+use anchor_lang::prelude::*;
+use anchor_spl::dex;
+use anchor_spl::token::{Token, TokenAccount};
+use serum_dex::critbit::*;
+use serum_dex::instruction::{NewOrderInstructionV3, SelfTradeBehavior};
+use serum_dex::matching::{OrderType, Side};
+use serum_dex::state::Market;
+use std::num::NonZeroU64;
+
+const SKEW_DENOMINATOR: u16 = 10_000;
+
+#[event]
+pub struct RebalanceEvent {
+    pool: Pubkey,
+    sold_base: bool,
+    skew_bps: u64,
+    base_amount_change: i64,
+    quote_amount_change: i64,
+    signer: Pubkey,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct Rebalance<'info> {
+    #[account(
+        mut,
+        has_one = base_vault,
+        has_one = quote_vault,
+        has_one = authority,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(
+        constraint = market_accounts.market.key() == pool.load()?.market
+            @ OpenAmmErrorCode::WrongMarketAccount,
+        constraint = market_accounts.open_orders.key() == pool.load()?.open_orders
+            @ OpenAmmErrorCode::WrongOpenOrdersAccount,
+    )]
+    pub market_accounts: MarketAccounts<'info>,
+
+    #[account(mut)]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), BASE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub base_fee_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), QUOTE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub quote_fee_vault: Box<Account<'info, TokenAccount>>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    #[account(address = pool.load()?.dex_program @ OpenAmmErrorCode::WrongDexProgram)]
+    pub dex_program: Program<'info, dex::Dex>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// Trades the pool back towards equal value on both sides at the book's mid
+/// price with an immediate-or-cancel order, if its inventory is skewed by more
+/// than `min_skew_bps`. `limit_price` bounds the trade, in lots, and is
+/// expected to come from an oracle: it is the lowest price base is sold at or
+/// the highest price it is bought at.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, Rebalance<'info>>,
+    min_skew_bps: u16,
+    limit_price: u64,
+) -> Result<()> {
+    lock_pool(&ctx.accounts.pool)?;
+    let pool = ctx.accounts.pool.load()?;
+    let pool_bump = pool.bump;
+    let order_id = pool.client_order_id;
+    let pool_type = pool.pool_type;
+    let pool_index = pool.index;
+    let debug_logging = pool.debug_logging;
+    drop(pool);
+
+    let orderbook = get_orderbook(
+        order_id,
+        pool_bump,
+        pool_type,
+        pool_index,
+        ctx.accounts.pool.clone(),
+        ctx.accounts.market_accounts.clone(),
+        *ctx.accounts.base_vault.clone(),
+        *ctx.accounts.quote_vault.clone(),
+        *ctx.accounts.base_fee_vault.clone(),
+        *ctx.accounts.quote_fee_vault.clone(),
+        ctx.accounts.dex_program.clone(),
+        ctx.accounts.token_program.clone(),
+        ctx.accounts.rent.clone(),
+        debug_logging,
+    )?;
+
+    orderbook.cancel_all_and_settle()?;
+
+    let pool = ctx.accounts.pool.load()?;
+    if !pool.mm_active {
+        drop(pool);
+        return unlock_pool(&ctx.accounts.pool);
+    }
+    let base_amount = pool.base_amount;
+    let quote_amount = pool.quote_amount;
+    let base_lot_size = pool.base_lot_size;
+    let quote_lot_size = pool.quote_lot_size;
+    drop(pool);
+
+    // With the pool's own orders cancelled, what's left on the book is
+    // external liquidity.
+    let market_state = Market::load(
+        &ctx.accounts.market_accounts.market,
+        &ctx.accounts.dex_program.key(),
+        false,
+    )
+    .unwrap();
+    let bids = market_state
+        .load_bids_mut(&ctx.accounts.market_accounts.bids)
+        .unwrap();
+    let asks = market_state
+        .load_asks_mut(&ctx.accounts.market_accounts.asks)
+        .unwrap();
+    let best_bid_price = bids
+        .find_max()
+        .and_then(|h| bids.get(h))
+        .and_then(|n| n.as_leaf())
+        .map(|l| u64::from(l.price()));
+    let best_ask_price = asks
+        .find_min()
+        .and_then(|h| asks.get(h))
+        .and_then(|n| n.as_leaf())
+        .map(|l| u64::from(l.price()));
+    drop(bids);
+    drop(asks);
+    drop(market_state);
+
+    let (best_bid_price, best_ask_price) = match (best_bid_price, best_ask_price) {
+        (Some(best_bid_price), Some(best_ask_price)) => (best_bid_price, best_ask_price),
+        _ => return err!(OpenAmmErrorCode::MissingBookPrice),
+    };
+
+    // Base reserve valued in native quote at the mid price
+    let base_value: u128 = (base_amount as u128)
+        .checked_mul(
+            (best_bid_price as u128)
+                .checked_add(best_ask_price.into())
+                .unwrap(),
+        )
+        .unwrap()
+        .checked_mul(quote_lot_size.into())
+        .unwrap()
+        .checked_div((base_lot_size as u128).checked_mul(2).unwrap())
+        .unwrap();
+    let quote_value = quote_amount as u128;
+    let total_value = base_value.checked_add(quote_value).unwrap();
+    require!(total_value > 0, OpenAmmErrorCode::RebalanceNotNeeded);
+
+    let sold_base = base_value > quote_value;
+    // Half the difference in value moves the pool back to an even split
+    let excess_value = if sold_base {
+        base_value - quote_value
+    } else {
+        quote_value - base_value
+    }
+    .checked_div(2)
+    .unwrap();
+    let skew_bps: u64 = excess_value
+        .checked_mul(2)
+        .unwrap()
+        .checked_mul(SKEW_DENOMINATOR.into())
+        .unwrap()
+        .checked_div(total_value)
+        .unwrap()
+        .try_into()
+        .unwrap();
+    require!(
+        skew_bps > min_skew_bps.into(),
+        OpenAmmErrorCode::RebalanceNotNeeded
+    );
+
+    let (side, base_qty, max_native_quote_qty_including_fees) = if sold_base {
+        let base_qty: u64 = excess_value
+            .checked_mul(2)
+            .unwrap()
+            .checked_div(
+                (best_bid_price as u128)
+                    .checked_add(best_ask_price.into())
+                    .unwrap()
+                    .checked_mul(quote_lot_size.into())
+                    .unwrap(),
+            )
+            .unwrap()
+            .try_into()
+            .unwrap();
+        (Side::Ask, base_qty, u64::MAX)
+    } else {
+        let base_qty: u64 = excess_value
+            .checked_div(
+                (limit_price as u128)
+                    .checked_mul(quote_lot_size.into())
+                    .unwrap(),
+            )
+            .unwrap()
+            .try_into()
+            .unwrap();
+        (Side::Bid, base_qty, excess_value.try_into().unwrap())
+    };
+
+    let base_vault_before = ctx.accounts.base_vault.amount;
+    let quote_vault_before = ctx.accounts.quote_vault.amount;
+
+    if let (Some(limit_price), Some(base_qty), Some(max_native_quote_qty_including_fees)) = (
+        NonZeroU64::new(limit_price),
+        NonZeroU64::new(base_qty),
+        NonZeroU64::new(max_native_quote_qty_including_fees),
+    ) {
+        let mut pool = ctx.accounts.pool.load_mut()?;
+        let client_order_id = pool.client_order_id;
+        pool.client_order_id += 1;
+        drop(pool);
+
+        let rebalance_ix = NewOrderInstructionV3 {
+            side,
+            limit_price,
+            max_coin_qty: base_qty,
+            max_native_pc_qty_including_fees: max_native_quote_qty_including_fees,
+            self_trade_behavior: SelfTradeBehavior::AbortTransaction,
+            order_type: OrderType::ImmediateOrCancel,
+            client_order_id,
+            limit: 10,
+            max_ts: i64::MAX,
+        };
+        orderbook.place_orders(
+            vec![rebalance_ix],
+            ctx.accounts.base_vault.to_account_info(),
+            ctx.accounts.quote_vault.to_account_info(),
+        )?;
+        orderbook.settle()?;
+    }
+
+    ctx.accounts.base_vault.reload()?;
+    ctx.accounts.quote_vault.reload()?;
+    let base_amount_change = (ctx.accounts.base_vault.amount as i64)
+        .checked_sub(base_vault_before as i64)
+        .unwrap();
+    let quote_amount_change = (ctx.accounts.quote_vault.amount as i64)
+        .checked_sub(quote_vault_before as i64)
+        .unwrap();
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.base_amount = (pool.base_amount as i64)
+        .checked_add(base_amount_change)
+        .unwrap()
+        .try_into()
+        .unwrap();
+    pool.quote_amount = (pool.quote_amount as i64)
+        .checked_add(quote_amount_change)
+        .unwrap()
+        .try_into()
+        .unwrap();
+    drop(pool);
+
+    debug_msg!(
+        debug_logging,
+        "openamm:rebalance skew_bps={} base_change={} quote_change={}",
+        skew_bps,
+        base_amount_change,
+        quote_amount_change
+    );
+
+    orderbook.place_new_orders(
+        &ctx.accounts.base_vault,
+        &ctx.accounts.quote_vault,
+        ctx.remaining_accounts,
+    )?;
+
+    let clock = Clock::get()?;
+    emit!(RebalanceEvent {
+        pool: ctx.accounts.pool.key(),
+        sold_base,
+        skew_bps,
+        base_amount_change,
+        quote_amount_change,
+        signer: ctx.accounts.authority.key(),
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    });
+
+    unlock_pool(&ctx.accounts.pool)?;
+    Ok(())
+}
@@ -1,7 +1,12 @@
 use crate::errors::OpenAmmErrorCode;
-use crate::instructions::create_pool::POOL_SEED;
+use crate::instructions::create_pool::{
+    get_pool_index_seed, BASE_FEE_VAULT_SEED, POOL_SEED, QUOTE_FEE_VAULT_SEED,
+};
 use crate::state::*;
-use crate::util::{get_orderbook, pool_authority_seeds};
+use crate::util::{
+    check_reserve_drift, credit_keeper_payout, debug_msg, get_keeper_payout, get_orderbook,
+    lock_pool, pool_authority_seeds, record_keeper_payment, unlock_pool,
+};
 use anchor_lang::prelude::*;
 use anchor_spl::dex;
 use anchor_spl::token::{transfer, Token, TokenAccount, Transfer};
@@ -29,6 +34,20 @@ pub struct RefreshOrders<'info> {
     #[account(mut)]
     pub quote_vault: Box<Account<'info, TokenAccount>>,
 
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), BASE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub base_fee_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), QUOTE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub quote_fee_vault: Box<Account<'info, TokenAccount>>,
+
     #[account(
         mut,
         token::mint = base_vault.mint,
@@ -48,55 +67,141 @@ pub struct RefreshOrders<'info> {
 
     pub token_program: Program<'info, Token>,
 
-    #[account(address = dex::ID)]
+    #[account(address = pool.load()?.dex_program @ OpenAmmErrorCode::WrongDexProgram)]
     pub dex_program: Program<'info, dex::Dex>,
 
     pub rent: Sysvar<'info, Rent>,
 }
 
 pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, RefreshOrders<'info>>) -> Result<()> {
+    lock_pool(&ctx.accounts.pool)?;
     let pool = ctx.accounts.pool.load()?;
+    let signer = ctx.accounts.signer.key();
+    if signer != pool.authority && signer != pool.keeper {
+        require!(
+            Clock::get()?.slot
+                >= pool
+                    .last_refresh_slot
+                    .checked_add(pool.min_refresh_interval_slots)
+                    .unwrap(),
+            OpenAmmErrorCode::RefreshTooSoon
+        );
+    }
     let pool_bump = pool.bump;
     let order_id = pool.client_order_id;
     let pool_type = pool.pool_type;
+    let pool_index = pool.index;
+    let debug_logging = pool.debug_logging;
     drop(pool);
 
     let orderbook = get_orderbook(
         order_id,
         pool_bump,
         pool_type,
+        pool_index,
         ctx.accounts.pool.clone(),
         ctx.accounts.market_accounts.clone(),
         *ctx.accounts.base_vault.clone(),
         *ctx.accounts.quote_vault.clone(),
+        *ctx.accounts.base_fee_vault.clone(),
+        *ctx.accounts.quote_fee_vault.clone(),
         ctx.accounts.dex_program.clone(),
         ctx.accounts.token_program.clone(),
         ctx.accounts.rent.clone(),
-        false,
-    );
+        debug_logging,
+    )?
+    .with_rung_stats(ctx.remaining_accounts);
 
-    orderbook.cancel_all_and_settle()?;
+    let reconciled_quote_amount = orderbook.cancel_all_and_settle()?;
+
+    ctx.accounts.base_vault.reload()?;
+    ctx.accounts.quote_vault.reload()?;
+    check_reserve_drift(
+        &ctx.accounts.pool,
+        ctx.accounts.base_vault.amount,
+        ctx.accounts.quote_vault.amount,
+    )?;
 
     let pool = ctx.accounts.pool.load()?;
     if !pool.mm_active {
-        return Ok(());
+        drop(pool);
+        return unlock_pool(&ctx.accounts.pool);
     }
     drop(pool);
 
-    orderbook.place_new_orders(&ctx.accounts.base_vault, &ctx.accounts.quote_vault)?;
+    // Leave the ladder off until the crank catches up. The pool stays in safe
+    // mode if it was, since it isn't quoting.
+    if orderbook.check_event_queue_backlog()? {
+        return unlock_pool(&ctx.accounts.pool);
+    }
+    ctx.accounts.pool.load_mut()?.safe_mode = false;
+
+    let placement = orderbook.place_new_orders(
+        &ctx.accounts.base_vault,
+        &ctx.accounts.quote_vault,
+        ctx.remaining_accounts,
+    )?;
+    let useful_notional = reconciled_quote_amount
+        .checked_add(placement.repriced_notional)
+        .unwrap();
 
     let mut pool = ctx.accounts.pool.load_mut()?;
-    let refund_quote_amount = pool.refund_quote_amount;
-    let refund_base_amount = pool.refund_base_amount;
-    pool.refund_quote_amount = 0;
-    pool.refund_base_amount = 0;
+    let refund_quote_amount = get_keeper_payout(
+        pool.refund_quote_amount,
+        useful_notional,
+        placement.placed_notional,
+    );
+    let refund_base_amount = get_keeper_payout(
+        pool.refund_base_amount,
+        useful_notional,
+        placement.placed_notional,
+    );
+    pool.refund_quote_amount = pool
+        .refund_quote_amount
+        .checked_sub(refund_quote_amount)
+        .unwrap();
+    pool.refund_base_amount = pool
+        .refund_base_amount
+        .checked_sub(refund_base_amount)
+        .unwrap();
+    pool.refresh_log
+        .record(1, ctx.accounts.signer.key(), Clock::get()?.slot);
     drop(pool);
 
+    debug_msg!(
+        debug_logging,
+        "openamm:refresh useful_notional={} placed_notional={} refund_base={} refund_quote={}",
+        useful_notional,
+        placement.placed_notional,
+        refund_base_amount,
+        refund_quote_amount
+    );
+
+    let credited = credit_keeper_payout(
+        ctx.remaining_accounts,
+        ctx.accounts.signer.key(),
+        &ctx.accounts.pool,
+        refund_base_amount,
+        refund_quote_amount,
+    )?;
+    record_keeper_payment(
+        &ctx.accounts.pool,
+        ctx.accounts.signer.key(),
+        refund_base_amount,
+        refund_quote_amount,
+        credited,
+    )?;
+    if credited {
+        return unlock_pool(&ctx.accounts.pool);
+    }
+
     let market_key = ctx.accounts.market_accounts.market.key();
     let pool_type_bytes = (pool_type as u8).to_le_bytes();
+    let pool_index_seed = get_pool_index_seed(pool_index);
     let seeds = pool_authority_seeds!(
         market_key = market_key,
         pool_type_bytes = pool_type_bytes,
+        index_seed = pool_index_seed,
         bump = pool_bump
     );
     let pool_signer = &[&seeds[..]];
@@ -123,5 +228,7 @@ pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, RefreshOrders<'info>>) ->
         pool_signer,
     );
     transfer(transfer_quote_to_signer_cpi_ctx, refund_quote_amount)?;
+
+    unlock_pool(&ctx.accounts.pool)?;
     Ok(())
 }
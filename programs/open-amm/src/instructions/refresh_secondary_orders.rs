@@ -0,0 +1,217 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::create_pool::{
+    get_pool_index_seed, BASE_FEE_VAULT_SEED, POOL_SEED, QUOTE_FEE_VAULT_SEED,
+};
+use crate::state::*;
+use crate::util::{
+    credit_keeper_payout, debug_msg, get_keeper_payout, get_secondary_orderbook, lock_pool,
+    pool_authority_seeds, record_keeper_payment, unlock_pool,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::dex;
+use anchor_spl::token::{transfer, Token, TokenAccount, Transfer};
+
+#[derive(Accounts)]
+pub struct RefreshSecondaryOrders<'info> {
+    #[account(
+        mut,
+        has_one = base_vault,
+        has_one = quote_vault,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(
+        constraint = market_accounts.market.key() == pool.load()?.secondary_market
+            @ OpenAmmErrorCode::WrongMarketAccount,
+        constraint = market_accounts.open_orders.key() == pool.load()?.secondary_open_orders
+            @ OpenAmmErrorCode::WrongOpenOrdersAccount,
+    )]
+    pub market_accounts: MarketAccounts<'info>,
+
+    #[account(mut)]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), BASE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub base_fee_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), QUOTE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub quote_fee_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = base_vault.mint,
+        token::authority = signer,
+    )]
+    pub signer_base: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = quote_vault.mint,
+        token::authority = signer,
+    )]
+    pub signer_quote: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    #[account(address = pool.load()?.dex_program @ OpenAmmErrorCode::WrongDexProgram)]
+    pub dex_program: Program<'info, dex::Dex>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, RefreshSecondaryOrders<'info>>,
+) -> Result<()> {
+    lock_pool(&ctx.accounts.pool)?;
+    let pool = ctx.accounts.pool.load()?;
+    let pool_bump = pool.bump;
+    let order_id = pool.secondary_client_order_id;
+    let pool_type = pool.pool_type;
+    let pool_index = pool.index;
+    let pool_market = pool.market;
+    let debug_logging = pool.debug_logging;
+    drop(pool);
+
+    let orderbook = get_secondary_orderbook(
+        order_id,
+        pool_bump,
+        pool_type,
+        pool_index,
+        pool_market,
+        ctx.accounts.pool.clone(),
+        ctx.accounts.market_accounts.clone(),
+        *ctx.accounts.base_vault.clone(),
+        *ctx.accounts.quote_vault.clone(),
+        *ctx.accounts.base_fee_vault.clone(),
+        *ctx.accounts.quote_fee_vault.clone(),
+        ctx.accounts.dex_program.clone(),
+        ctx.accounts.token_program.clone(),
+        ctx.accounts.rent.clone(),
+        debug_logging,
+    )?
+    .with_rung_stats(ctx.remaining_accounts);
+
+    let reconciled_quote_amount = orderbook.cancel_all_and_settle()?;
+
+    let pool = ctx.accounts.pool.load()?;
+    if !pool.mm_active {
+        drop(pool);
+        return unlock_pool(&ctx.accounts.pool);
+    }
+    drop(pool);
+
+    // Leave the ladder off until the crank catches up
+    if orderbook.check_event_queue_backlog()? {
+        return unlock_pool(&ctx.accounts.pool);
+    }
+
+    let placement = orderbook.place_new_orders(
+        &ctx.accounts.base_vault,
+        &ctx.accounts.quote_vault,
+        ctx.remaining_accounts,
+    )?;
+    let useful_notional = reconciled_quote_amount
+        .checked_add(placement.repriced_notional)
+        .unwrap();
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    let refund_quote_amount = get_keeper_payout(
+        pool.refund_quote_amount,
+        useful_notional,
+        placement.placed_notional,
+    );
+    let refund_base_amount = get_keeper_payout(
+        pool.refund_base_amount,
+        useful_notional,
+        placement.placed_notional,
+    );
+    pool.refund_quote_amount = pool
+        .refund_quote_amount
+        .checked_sub(refund_quote_amount)
+        .unwrap();
+    pool.refund_base_amount = pool
+        .refund_base_amount
+        .checked_sub(refund_base_amount)
+        .unwrap();
+    pool.refresh_log
+        .record(1, ctx.accounts.signer.key(), Clock::get()?.slot);
+    drop(pool);
+
+    debug_msg!(
+        debug_logging,
+        "openamm:refresh_secondary useful_notional={} placed_notional={} refund_base={} refund_quote={}",
+        useful_notional,
+        placement.placed_notional,
+        refund_base_amount,
+        refund_quote_amount
+    );
+
+    let credited = credit_keeper_payout(
+        ctx.remaining_accounts,
+        ctx.accounts.signer.key(),
+        &ctx.accounts.pool,
+        refund_base_amount,
+        refund_quote_amount,
+    )?;
+    record_keeper_payment(
+        &ctx.accounts.pool,
+        ctx.accounts.signer.key(),
+        refund_base_amount,
+        refund_quote_amount,
+        credited,
+    )?;
+    if credited {
+        return unlock_pool(&ctx.accounts.pool);
+    }
+
+    let market_key = pool_market;
+    let pool_type_bytes = (pool_type as u8).to_le_bytes();
+    let pool_index_seed = get_pool_index_seed(pool_index);
+    let seeds = pool_authority_seeds!(
+        market_key = market_key,
+        pool_type_bytes = pool_type_bytes,
+        index_seed = pool_index_seed,
+        bump = pool_bump
+    );
+    let pool_signer = &[&seeds[..]];
+
+    let cpi_token_program = ctx.accounts.token_program.to_account_info();
+    let transfer_base_to_signer_cpi_ctx = CpiContext::new_with_signer(
+        cpi_token_program.clone(),
+        Transfer {
+            from: ctx.accounts.base_vault.to_account_info(),
+            to: ctx.accounts.signer_base.to_account_info(),
+            authority: ctx.accounts.pool.to_account_info(),
+        },
+        pool_signer,
+    );
+    transfer(transfer_base_to_signer_cpi_ctx, refund_base_amount)?;
+
+    let transfer_quote_to_signer_cpi_ctx = CpiContext::new_with_signer(
+        cpi_token_program,
+        Transfer {
+            from: ctx.accounts.quote_vault.to_account_info(),
+            to: ctx.accounts.signer_quote.to_account_info(),
+            authority: ctx.accounts.pool.to_account_info(),
+        },
+        pool_signer,
+    );
+    transfer(transfer_quote_to_signer_cpi_ctx, refund_quote_amount)?;
+
+    unlock_pool(&ctx.accounts.pool)?;
+    Ok(())
+}
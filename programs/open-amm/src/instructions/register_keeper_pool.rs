@@ -0,0 +1,30 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct RegisterKeeperPool<'info> {
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(mut, has_one = operator)]
+    pub keeper: Box<Account<'info, Keeper>>,
+
+    pub operator: Signer<'info>,
+}
+
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, RegisterKeeperPool<'info>>) -> Result<()> {
+    let pool = ctx.accounts.pool.key();
+    let keeper = &mut ctx.accounts.keeper;
+    require!(
+        keeper.pool_index(&pool).is_none(),
+        OpenAmmErrorCode::KeeperPoolAlreadyRegistered
+    );
+    let index = keeper.pool_count as usize;
+    require!(
+        index < MAX_KEEPER_POOLS,
+        OpenAmmErrorCode::TooManyKeeperPools
+    );
+    keeper.pools[index] = pool;
+    keeper.pool_count += 1;
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct RegisterPool<'info> {
+    #[account(
+        constraint = pool.load()?.market == pool_registry.market
+            @ OpenAmmErrorCode::WrongMarketAccount,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(mut)]
+    pub pool_registry: Box<Account<'info, PoolRegistry>>,
+}
+
+/// Adds a pool to its market's registry. Anyone may, since only pools on
+/// the registry's market are accepted.
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, RegisterPool<'info>>) -> Result<()> {
+    let pool = ctx.accounts.pool.key();
+    let pool_registry = &mut ctx.accounts.pool_registry;
+    require!(
+        pool_registry.pool_index(&pool).is_none(),
+        OpenAmmErrorCode::PoolAlreadyRegistered
+    );
+    let index = pool_registry.pool_count as usize;
+    require!(
+        index < MAX_REGISTRY_POOLS,
+        OpenAmmErrorCode::TooManyRegistryPools
+    );
+    pool_registry.pools[index] = pool;
+    pool_registry.pool_count += 1;
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::create_pool::{get_pool_index_seed, OPENBOOK_PADDING, POOL_SEED};
+use crate::state::*;
+use crate::util::{lock_pool, pool_authority_seeds, unlock_pool, SECONDARY_SHARE_DENOMINATOR};
+use anchor_lang::prelude::*;
+use anchor_spl::dex;
+use safe_transmute::to_bytes::transmute_to_bytes;
+use serum_dex::state::{Market, OpenOrders};
+use std::convert::identity;
+use std::mem::size_of;
+
+pub const SECONDARY_OPEN_ORDERS_SEED: &str = "pool-secondary-open-orders";
+
+#[derive(Accounts)]
+pub struct RegisterSecondaryMarket<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Checked against the pool's mints in the handler
+    pub secondary_market: AccountInfo<'info>,
+
+    /// CHECK
+    #[account(
+        init,
+        seeds = [pool.key().as_ref(), SECONDARY_OPEN_ORDERS_SEED.as_bytes().as_ref()],
+        bump,
+        payer = authority,
+        owner = dex_program.key(),
+        space = size_of::<OpenOrders>() + OPENBOOK_PADDING
+    )]
+    pub secondary_open_orders: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(address = pool.load()?.dex_program @ OpenAmmErrorCode::WrongDexProgram)]
+    pub dex_program: Program<'info, dex::Dex>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, RegisterSecondaryMarket<'info>>,
+    share_bps: u16,
+) -> Result<()> {
+    require!(
+        share_bps > 0 && share_bps < SECONDARY_SHARE_DENOMINATOR,
+        OpenAmmErrorCode::InvalidSecondaryShare
+    );
+
+    lock_pool(&ctx.accounts.pool)?;
+    let pool = ctx.accounts.pool.load()?;
+    require!(
+        !pool.has_secondary_market(),
+        OpenAmmErrorCode::SecondaryMarketAlreadyRegistered
+    );
+    require!(
+        ctx.accounts.secondary_market.key() != pool.market,
+        OpenAmmErrorCode::WrongMarketAccount
+    );
+    let market_key = pool.market;
+    let pool_type = pool.pool_type;
+    let pool_index = pool.index;
+    let pool_bump = pool.bump;
+
+    let market_state = Market::load(
+        &ctx.accounts.secondary_market,
+        &ctx.accounts.dex_program.key(),
+        false,
+    )
+    .unwrap();
+    require!(
+        pool.base_mint.as_ref() == transmute_to_bytes(&identity(market_state.coin_mint)),
+        OpenAmmErrorCode::MarketBaseMintMismatch,
+    );
+    require!(
+        pool.quote_mint.as_ref() == transmute_to_bytes(&identity(market_state.pc_mint)),
+        OpenAmmErrorCode::MarketQuoteMintMismatch,
+    );
+    drop(market_state);
+    drop(pool);
+
+    let pool_type_bytes = (pool_type as u8).to_le_bytes();
+
+    let pool_index_seed = get_pool_index_seed(pool_index);
+    let seeds = pool_authority_seeds!(
+        market_key = market_key,
+        pool_type_bytes = pool_type_bytes,
+        index_seed = pool_index_seed,
+        bump = pool_bump
+    );
+    let pool_signer = &[&seeds[..]];
+
+    let init_open_orders_cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.dex_program.to_account_info(),
+        dex::InitOpenOrders {
+            open_orders: ctx.accounts.secondary_open_orders.clone(),
+            authority: ctx.accounts.pool.to_account_info(),
+            market: ctx.accounts.secondary_market.clone(),
+            rent: ctx.accounts.rent.to_account_info(),
+        },
+        pool_signer,
+    );
+    dex::init_open_orders(init_open_orders_cpi_ctx)?;
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.secondary_market = ctx.accounts.secondary_market.key();
+    pool.secondary_open_orders = ctx.accounts.secondary_open_orders.key();
+    pool.secondary_share_bps = share_bps;
+    drop(pool);
+
+    unlock_pool(&ctx.accounts.pool)
+}
@@ -0,0 +1,45 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct RegisterUnderlyingPool<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(
+        constraint = underlying_pool.key() != pool.key() @ OpenAmmErrorCode::InvalidUnderlyingPool,
+    )]
+    pub underlying_pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Makes a stable pool quoted against the LP token of another stable pool a
+/// metapool of it, so `deposit_underlying` and `withdraw_underlying` can move
+/// liquidity in and out as the underlying pool's tokens. The LP leg is
+/// quoted at par like any other stable token.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, RegisterUnderlyingPool<'info>>,
+) -> Result<()> {
+    let underlying_pool = ctx.accounts.underlying_pool.load()?;
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    require!(
+        !pool.has_underlying_pool(),
+        OpenAmmErrorCode::UnderlyingPoolAlreadyRegistered
+    );
+    require!(
+        matches!(pool.pool_type, PoolType::STABLE)
+            && matches!(underlying_pool.pool_type, PoolType::STABLE)
+            && pool.quote_mint == underlying_pool.lp_mint
+            && !underlying_pool.has_underlying_pool(),
+        OpenAmmErrorCode::InvalidUnderlyingPool
+    );
+
+    pool.underlying_pool = ctx.accounts.underlying_pool.key();
+    Ok(())
+}
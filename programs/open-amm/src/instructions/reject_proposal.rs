@@ -0,0 +1,28 @@
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct RejectProposal<'info> {
+    #[account(has_one = authority)]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(
+        mut,
+        has_one = pool,
+        has_one = proposer,
+        close = proposer,
+    )]
+    pub proposal: Box<Account<'info, ParameterProposal>>,
+
+    /// CHECK: Only receives the proposal's rent
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Closes a proposal without applying it, refunding its rent to the proposer
+/// so they can put forward another.
+pub fn handler<'info>(_ctx: Context<'_, '_, '_, 'info, RejectProposal<'info>>) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use crate::util::debug_msg;
+use anchor_lang::prelude::*;
+use anchor_spl::token::{transfer, Mint, Token, TokenAccount, Transfer};
+use std::mem::size_of;
+
+pub const WITHDRAW_TICKET_SEED: &str = "withdraw-ticket";
+pub const WITHDRAW_TICKET_LP_SEED: &str = "withdraw-ticket-lp";
+
+#[event]
+pub struct WithdrawRequestedEvent {
+    pool: Pubkey,
+    owner: Pubkey,
+    lp_amount: u64,
+    /// Reserves the LP tokens were worth when queued. What's paid out on
+    /// claiming also counts fills reconciled in between.
+    base_amount: u64,
+    quote_amount: u64,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct RequestWithdraw<'info> {
+    #[account(
+        mut,
+        has_one = lp_mint,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub lp_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::authority = owner,
+        token::mint = lp_mint.key(),
+    )]
+    pub owner_lp: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        seeds = [pool.key().as_ref(), owner.key().as_ref(), WITHDRAW_TICKET_SEED.as_bytes().as_ref()],
+        bump,
+        payer = owner,
+        space = 8 + size_of::<WithdrawTicket>(),
+    )]
+    pub ticket: Box<Account<'info, WithdrawTicket>>,
+
+    #[account(
+        init,
+        seeds = [ticket.key().as_ref(), WITHDRAW_TICKET_LP_SEED.as_bytes().as_ref()],
+        bump,
+        payer = owner,
+        token::mint = lp_mint,
+        token::authority = pool,
+    )]
+    pub ticket_lp: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub system_program: Program<'info, System>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/**
+ * First half of a withdraw too large for the vaults' free balances. Escrows
+ * `lp_amount` LP tokens in a ticket and queues their share of reserves, which
+ * the next ladder placed leaves off the book. `claim_withdraw` pays it out
+ * afterwards without a cancel, settle and replace of its own.
+ *
+ * The escrowed tokens still count towards the LP supply, so they keep
+ * earning on fills reconciled before the claim.
+ */
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, RequestWithdraw<'info>>,
+    lp_amount: u64,
+) -> Result<()> {
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    let (base_amount, quote_amount) =
+        pool.get_lp_share_amounts(lp_amount, ctx.accounts.lp_mint.supply);
+    pool.queued_withdraw_base_amount = pool
+        .queued_withdraw_base_amount
+        .checked_add(base_amount)
+        .unwrap();
+    pool.queued_withdraw_quote_amount = pool
+        .queued_withdraw_quote_amount
+        .checked_add(quote_amount)
+        .unwrap();
+    debug_msg!(
+        pool.debug_logging,
+        "openamm:request_withdraw lp={} base={} quote={} queued_base={} queued_quote={}",
+        lp_amount,
+        base_amount,
+        quote_amount,
+        pool.queued_withdraw_base_amount,
+        pool.queued_withdraw_quote_amount
+    );
+    drop(pool);
+
+    let clock = Clock::get()?;
+    let ticket = &mut ctx.accounts.ticket;
+    ticket.pool = ctx.accounts.pool.key();
+    ticket.owner = ctx.accounts.owner.key();
+    ticket.lp_amount = lp_amount;
+    ticket.requested_slot = clock.slot;
+    ticket.bump = *ctx.bumps.get("ticket").unwrap();
+
+    let transfer_lp_to_ticket_cpi_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.owner_lp.to_account_info(),
+            to: ctx.accounts.ticket_lp.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        },
+    );
+    transfer(transfer_lp_to_ticket_cpi_ctx, lp_amount)?;
+
+    emit!(WithdrawRequestedEvent {
+        pool: ctx.accounts.pool.key(),
+        owner: ctx.accounts.owner.key(),
+        lp_amount,
+        base_amount,
+        quote_amount,
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::create_pool::{BASE_FEE_VAULT_SEED, QUOTE_FEE_VAULT_SEED};
+use crate::state::*;
+use crate::util::{debug_msg, get_orderbook, lock_pool, unlock_pool};
+use anchor_lang::prelude::*;
+use anchor_spl::dex;
+use anchor_spl::token::{Token, TokenAccount};
+
+#[event]
+pub struct PoolRescuedEvent {
+    pool: Pubkey,
+    event_queue_len_before: u64,
+    event_queue_len_after: u64,
+    mm_active: bool,
+    paused: bool,
+    signer: Pubkey,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct Rescue<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        has_one = base_vault,
+        has_one = quote_vault,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(
+        constraint = market_accounts.market.key() == pool.load()?.market
+            @ OpenAmmErrorCode::WrongMarketAccount,
+        constraint = market_accounts.open_orders.key() == pool.load()?.open_orders
+            @ OpenAmmErrorCode::WrongOpenOrdersAccount,
+    )]
+    pub market_accounts: MarketAccounts<'info>,
+
+    #[account(mut)]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), BASE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub base_fee_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), QUOTE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub quote_fee_vault: Box<Account<'info, TokenAccount>>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    #[account(address = pool.load()?.dex_program @ OpenAmmErrorCode::WrongDexProgram)]
+    pub dex_program: Program<'info, dex::Dex>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/**
+ * Recovers a pool wedged by a rare dex state in one transaction. Cranks up
+ * to `consume_events_limit` events for the open orders accounts passed as
+ * remaining accounts, then cancels every order, settles and reconciles, and
+ * pauses the pool if `pause` is set. Only the authority may call it.
+ *
+ * Checks the routine paths rely on are relaxed: the vaults aren't checked for
+ * drift, and it runs whether or not market making is active. The pool's lock
+ * is still taken, since a failed instruction never leaves it held. It never places orders,
+ * so the next refresh, or `restart_market_making` after an eviction, quotes
+ * again.
+ */
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, Rescue<'info>>,
+    consume_events_limit: u16,
+    pause: bool,
+) -> Result<()> {
+    lock_pool(&ctx.accounts.pool)?;
+    let pool = ctx.accounts.pool.load()?;
+    let pool_bump = pool.bump;
+    let pool_type = pool.pool_type;
+    let pool_index = pool.index;
+    let debug_logging = pool.debug_logging;
+    drop(pool);
+
+    let get_pool_orderbook = || {
+        get_orderbook(
+            ctx.accounts.pool.load().unwrap().client_order_id,
+            pool_bump,
+            pool_type,
+            pool_index,
+            ctx.accounts.pool.clone(),
+            ctx.accounts.market_accounts.clone(),
+            *ctx.accounts.base_vault.clone(),
+            *ctx.accounts.quote_vault.clone(),
+            *ctx.accounts.base_fee_vault.clone(),
+            *ctx.accounts.quote_fee_vault.clone(),
+            ctx.accounts.dex_program.clone(),
+            ctx.accounts.token_program.clone(),
+            ctx.accounts.rent.clone(),
+            debug_logging,
+        )
+    };
+
+    let orderbook = get_pool_orderbook()?;
+    let event_queue_len_before = orderbook.load_event_queue_len()?;
+    if consume_events_limit > 0 && !ctx.remaining_accounts.is_empty() {
+        orderbook.consume_events(ctx.remaining_accounts, consume_events_limit)?;
+    }
+    drop(orderbook);
+
+    // Cranking credited fills to the open orders account, so read it again
+    let orderbook = get_pool_orderbook()?;
+    orderbook.cancel_all_and_settle()?;
+    let event_queue_len_after = orderbook.load_event_queue_len()?;
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    if pause {
+        pool.paused = true;
+    }
+    let mm_active = pool.mm_active;
+    let paused = pool.paused;
+    debug_msg!(
+        debug_logging,
+        "openamm:rescue event_queue_before={} event_queue_after={} mm_active={} paused={}",
+        event_queue_len_before,
+        event_queue_len_after,
+        mm_active,
+        paused
+    );
+    drop(pool);
+
+    let clock = Clock::get()?;
+    emit!(PoolRescuedEvent {
+        pool: ctx.accounts.pool.key(),
+        event_queue_len_before,
+        event_queue_len_after,
+        mm_active,
+        paused,
+        signer: ctx.accounts.authority.key(),
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    });
+
+    unlock_pool(&ctx.accounts.pool)
+}
@@ -1,9 +1,11 @@
 use crate::errors::OpenAmmErrorCode;
+use crate::instructions::create_pool::{BASE_FEE_VAULT_SEED, QUOTE_FEE_VAULT_SEED};
 use crate::state::*;
-use crate::util::get_orderbook;
+use crate::util::{debug_msg, get_orderbook, lock_pool, unlock_pool};
 use anchor_lang::prelude::*;
 use anchor_spl::dex;
 use anchor_spl::token::{Token, TokenAccount};
+use std::cmp;
 
 #[derive(Accounts)]
 pub struct RestartMarketMaking<'info> {
@@ -28,12 +30,26 @@ pub struct RestartMarketMaking<'info> {
     #[account(mut)]
     pub quote_vault: Box<Account<'info, TokenAccount>>,
 
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), BASE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub base_fee_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), QUOTE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub quote_fee_vault: Box<Account<'info, TokenAccount>>,
+
     #[account(mut)]
     pub signer: Signer<'info>,
 
     pub token_program: Program<'info, Token>,
 
-    #[account(address = dex::ID)]
+    #[account(address = pool.load()?.dex_program @ OpenAmmErrorCode::WrongDexProgram)]
     pub dex_program: Program<'info, dex::Dex>,
 
     pub rent: Sysvar<'info, Rent>,
@@ -48,9 +64,12 @@ pub struct RestartMarketMaking<'info> {
  */
 pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, RestartMarketMaking<'info>>) -> Result<()> {
     let pool_bump = ctx.bumps.get("pool").unwrap().clone();
+    lock_pool(&ctx.accounts.pool)?;
     let pool = ctx.accounts.pool.load()?;
     let order_id = pool.client_order_id;
     let pool_type = pool.pool_type;
+    let pool_index = pool.index;
+    let debug_logging = pool.debug_logging;
     require!(!pool.mm_active, OpenAmmErrorCode::MarketMakingAlreadyActive);
     drop(pool);
 
@@ -58,26 +77,101 @@ pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, RestartMarketMaking<'info>
         order_id,
         pool_bump,
         pool_type,
+        pool_index,
         ctx.accounts.pool.clone(),
         ctx.accounts.market_accounts.clone(),
         *ctx.accounts.base_vault.clone(),
         *ctx.accounts.quote_vault.clone(),
+        *ctx.accounts.base_fee_vault.clone(),
+        *ctx.accounts.quote_fee_vault.clone(),
         ctx.accounts.dex_program.clone(),
         ctx.accounts.token_program.clone(),
         ctx.accounts.rent.clone(),
-        false,
-    );
+        debug_logging,
+    )?;
 
     orderbook.cancel_all_and_settle()?;
-
-    require!(
-        orderbook.native_base_total == 0 && orderbook.native_quote_total == 0,
-        OpenAmmErrorCode::OpenOrdersTokensLocked,
-    );
+    ctx.accounts.base_vault.reload()?;
+    ctx.accounts.quote_vault.reload()?;
 
     let mut pool = ctx.accounts.pool.load_mut()?;
-    pool.base_amount = ctx.accounts.base_vault.amount;
-    pool.quote_amount = ctx.accounts.quote_vault.amount;
+    let evicted = pool.eviction_base_exposure > 0 || pool.eviction_quote_exposure > 0;
+    // Reserves can grow back by what an eviction left stuck, but no further
+    let max_base_amount = pool
+        .base_amount
+        .checked_add(pool.eviction_base_exposure)
+        .unwrap();
+    let max_quote_amount = pool
+        .quote_amount
+        .checked_add(pool.eviction_quote_exposure)
+        .unwrap();
+    if evicted {
+        // Funds stuck by an eviction count as returned once the vaults have
+        // grown by that much, whatever dust is still in open orders.
+        let returned_base = ctx
+            .accounts
+            .base_vault
+            .amount
+            .checked_add(pool.keeper_owed_base_amount)
+            .unwrap()
+            .saturating_sub(pool.eviction_base_baseline);
+        let returned_quote = ctx
+            .accounts
+            .quote_vault
+            .amount
+            .checked_add(pool.keeper_owed_quote_amount)
+            .unwrap()
+            .saturating_sub(pool.eviction_quote_baseline);
+        require!(
+            returned_base >= pool.eviction_base_exposure
+                && returned_quote >= pool.eviction_quote_exposure,
+            OpenAmmErrorCode::OpenOrdersTokensLocked,
+        );
+        pool.eviction_base_exposure = 0;
+        pool.eviction_quote_exposure = 0;
+    }
+
+    // An event that never gets consumed can leave a little behind in open
+    // orders for good. Tolerate it up to the configured thresholds and keep
+    // it out of reserves, since withdrawals can't pay it out.
+    let (stranded_base, stranded_quote) = orderbook.load_open_orders_totals()?;
+    if !evicted {
+        require!(
+            stranded_base <= pool.base_dust_threshold
+                && stranded_quote <= pool.quote_dust_threshold,
+            OpenAmmErrorCode::OpenOrdersTokensLocked,
+        );
+    }
+    pool.stranded_base_amount = stranded_base;
+    pool.stranded_quote_amount = stranded_quote;
+
+    // Refunds and keeper payouts owed sit in the same vaults, but aren't
+    // reserves. Nor are donations, which only `sync_reserves` adds.
+    pool.base_amount = cmp::min(
+        ctx.accounts
+            .base_vault
+            .amount
+            .saturating_sub(pool.refund_base_amount)
+            .saturating_sub(pool.keeper_owed_base_amount),
+        max_base_amount,
+    );
+    pool.quote_amount = cmp::min(
+        ctx.accounts
+            .quote_vault
+            .amount
+            .saturating_sub(pool.refund_quote_amount)
+            .saturating_sub(pool.keeper_owed_quote_amount),
+        max_quote_amount,
+    );
     pool.mm_active = true;
-    Ok(())
+
+    debug_msg!(
+        debug_logging,
+        "openamm:restart base={} quote={}",
+        pool.base_amount,
+        pool.quote_amount
+    );
+    drop(pool);
+
+    unlock_pool(&ctx.accounts.pool)
 }
@@ -0,0 +1,22 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct ResumePool<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Lifts a pause. Orders go back on the book at the next refresh.
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, ResumePool<'info>>) -> Result<()> {
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.paused = false;
+    Ok(())
+}
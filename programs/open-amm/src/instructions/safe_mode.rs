@@ -0,0 +1,127 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::create_pool::{BASE_FEE_VAULT_SEED, QUOTE_FEE_VAULT_SEED};
+use crate::state::*;
+use crate::util::{debug_msg, get_orderbook, lock_pool, unlock_pool};
+use anchor_lang::prelude::*;
+use anchor_spl::dex;
+use anchor_spl::token::{Token, TokenAccount};
+
+#[event]
+pub struct SafeModeEvent {
+    pool: Pubkey,
+    last_refresh_slot: u64,
+    signer: Pubkey,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct SafeMode<'info> {
+    #[account(
+        mut,
+        has_one = base_vault,
+        has_one = quote_vault,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(
+        constraint = market_accounts.market.key() == pool.load()?.market
+            @ OpenAmmErrorCode::WrongMarketAccount,
+        constraint = market_accounts.open_orders.key() == pool.load()?.open_orders
+            @ OpenAmmErrorCode::WrongOpenOrdersAccount,
+    )]
+    pub market_accounts: MarketAccounts<'info>,
+
+    #[account(mut)]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), BASE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub base_fee_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), QUOTE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub quote_fee_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    #[account(address = pool.load()?.dex_program @ OpenAmmErrorCode::WrongDexProgram)]
+    pub dex_program: Program<'info, dex::Dex>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// Dead-man's switch: once a pool has gone `safe_mode_after_slots` without a
+/// refresh, anyone may pull its orders off the book. Quoting stays suspended
+/// until the next `refresh_orders`.
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, SafeMode<'info>>) -> Result<()> {
+    lock_pool(&ctx.accounts.pool)?;
+    let pool = ctx.accounts.pool.load()?;
+    let slot = Clock::get()?.slot;
+    let last_refresh_slot = pool.last_refresh_slot;
+    require!(
+        pool.safe_mode_after_slots > 0
+            && slot
+                >= last_refresh_slot
+                    .checked_add(pool.safe_mode_after_slots)
+                    .unwrap(),
+        OpenAmmErrorCode::PoolNotStale
+    );
+    let pool_bump = pool.bump;
+    let order_id = pool.client_order_id;
+    let pool_type = pool.pool_type;
+    let pool_index = pool.index;
+    let debug_logging = pool.debug_logging;
+    drop(pool);
+
+    let orderbook = get_orderbook(
+        order_id,
+        pool_bump,
+        pool_type,
+        pool_index,
+        ctx.accounts.pool.clone(),
+        ctx.accounts.market_accounts.clone(),
+        *ctx.accounts.base_vault.clone(),
+        *ctx.accounts.quote_vault.clone(),
+        *ctx.accounts.base_fee_vault.clone(),
+        *ctx.accounts.quote_fee_vault.clone(),
+        ctx.accounts.dex_program.clone(),
+        ctx.accounts.token_program.clone(),
+        ctx.accounts.rent.clone(),
+        debug_logging,
+    )?;
+    orderbook.cancel_all_and_settle()?;
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.safe_mode = true;
+    debug_msg!(
+        debug_logging,
+        "openamm:safe_mode last_refresh_slot={} slot={}",
+        last_refresh_slot,
+        slot
+    );
+    drop(pool);
+
+    emit!(SafeModeEvent {
+        pool: ctx.accounts.pool.key(),
+        last_refresh_slot,
+        signer: ctx.accounts.signer.key(),
+        slot,
+        unix_timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    unlock_pool(&ctx.accounts.pool)?;
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetAnchorPool<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Centres an x*y=k pool's ladder on the mid of another openAMM pool with the
+/// same base, such as the deep USDC pool of a cross-listed asset, or removes
+/// the anchor if `anchor_pool` is the default key. Every instruction that
+/// places orders must then pass the anchor pool as a remaining account.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetAnchorPool<'info>>,
+    anchor_pool: Pubkey,
+    max_age_slots: u64,
+) -> Result<()> {
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    if anchor_pool != Pubkey::default() {
+        require!(
+            matches!(pool.pool_type, PoolType::XYK) && anchor_pool != ctx.accounts.pool.key(),
+            OpenAmmErrorCode::InvalidAnchorPool
+        );
+    }
+
+    pool.anchor_pool = anchor_pool;
+    pool.anchor_max_age_slots = max_age_slots;
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetAuthority<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Proposes `new_authority` as the pool's authority. Nothing changes until
+/// it signs `accept_authority`, so a wrong key can't lock the pool's admin
+/// instructions. Any account that can sign a CPI can hold the role, such as
+/// a governance program's native treasury, which signs through
+/// `invoke_signed` when a proposal is executed. `Pubkey::default()` cancels
+/// a pending proposal.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetAuthority<'info>>,
+    new_authority: Pubkey,
+) -> Result<()> {
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.pending_authority = new_authority;
+    Ok(())
+}
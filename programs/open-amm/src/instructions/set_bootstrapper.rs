@@ -0,0 +1,27 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetBootstrapper<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Hands the first deposit into an empty pool to `bootstrapper`, e.g. a
+/// market maker funding a pool a listing team created. `Pubkey::default()`
+/// leaves it with the authority.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetBootstrapper<'info>>,
+    bootstrapper: Pubkey,
+) -> Result<()> {
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.bootstrapper = bootstrapper;
+    Ok(())
+}
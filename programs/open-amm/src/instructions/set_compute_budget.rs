@@ -0,0 +1,40 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetComputeBudget<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Sets the compute units instructions placing the pool's ladder may spend,
+/// and the cost model used to fit the ladder in them: the units spent
+/// besides the dex CPIs, and per cancel CPI and order placed. Keepers should
+/// request at least `compute_budget_units`. 0 places the whole ladder.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetComputeBudget<'info>>,
+    compute_budget_units: u32,
+    base_compute_units: u32,
+    cancel_compute_units: u32,
+    place_compute_units: u32,
+) -> Result<()> {
+    require!(
+        compute_budget_units <= MAX_COMPUTE_UNITS
+            && (compute_budget_units == 0 || place_compute_units > 0),
+        OpenAmmErrorCode::InvalidComputeBudget
+    );
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.compute_budget_units = compute_budget_units;
+    pool.base_compute_units = base_compute_units;
+    pool.cancel_compute_units = cancel_compute_units;
+    pool.place_compute_units = place_compute_units;
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use crate::util::MAX_DELEVERAGE_BPS;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetDeleverage<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Sets the fill velocity past which the pool's ladders shrink and the most
+/// they shrink by. A zero velocity turns deleveraging off.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetDeleverage<'info>>,
+    deleverage_velocity_bps: u64,
+    max_deleverage_bps: u16,
+) -> Result<()> {
+    require!(
+        max_deleverage_bps <= MAX_DELEVERAGE_BPS,
+        OpenAmmErrorCode::InvalidDeleverage
+    );
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.deleverage_velocity_bps = deleverage_velocity_bps;
+    pool.max_deleverage_bps = max_deleverage_bps;
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::stableswap::STABLESWAP_AMP_COEFFICIENT;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetDepegFallback<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Sets the reserve ratio past which a stable pool's ladder switches to its
+/// crisis amp (or x*y=k if `crisis_amp` is 0). A zero ratio disables the
+/// fallback.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetDepegFallback<'info>>,
+    depeg_ratio_bps: u16,
+    crisis_amp: u64,
+) -> Result<()> {
+    require!(
+        depeg_ratio_bps == 0 || depeg_ratio_bps > DEPEG_RATIO_DENOMINATOR,
+        OpenAmmErrorCode::InvalidDepegFallback
+    );
+    require!(
+        crisis_amp < STABLESWAP_AMP_COEFFICIENT,
+        OpenAmmErrorCode::InvalidDepegFallback
+    );
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.depeg_ratio_bps = depeg_ratio_bps;
+    pool.crisis_amp = crisis_amp;
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetDepositCap<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Caps each deposit at `max_deposit_lp_bps` of the LP supply while the
+/// reserves are more than `deposit_cap_deviation_bps` off the pool's oracle.
+/// Pools without an oracle aren't capped, and a cap of 0 disables it.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetDepositCap<'info>>,
+    max_deposit_lp_bps: u16,
+    deposit_cap_deviation_bps: u16,
+) -> Result<()> {
+    require!(
+        max_deposit_lp_bps <= DEPOSIT_CAP_DENOMINATOR,
+        OpenAmmErrorCode::InvalidDepositCap
+    );
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.max_deposit_lp_bps = max_deposit_lp_bps;
+    pool.deposit_cap_deviation_bps = deposit_cap_deviation_bps;
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetDustThresholds<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Sets how much base and quote may be left stranded in the open orders
+/// account when restarting market making.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetDustThresholds<'info>>,
+    base_dust_threshold: u64,
+    quote_dust_threshold: u64,
+) -> Result<()> {
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.base_dust_threshold = base_dust_threshold;
+    pool.quote_dust_threshold = quote_dust_threshold;
+    Ok(())
+}
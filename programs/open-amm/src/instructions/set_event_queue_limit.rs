@@ -0,0 +1,26 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetEventQueueLimit<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Sets how many unconsumed events a market's queue may hold before refreshes
+/// stop placing ladders on it. 0 turns the check off.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetEventQueueLimit<'info>>,
+    max_event_queue_backlog: u64,
+) -> Result<()> {
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.max_event_queue_backlog = max_event_queue_backlog;
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+
+#[derive(Accounts)]
+pub struct SetFeeRouter<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+
+    pub fee_distribution: Box<Account<'info, TokenAccount>>,
+}
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetFeeRouter<'info>>,
+    target: FeeRouterTarget,
+    interval_slots: u64,
+) -> Result<()> {
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    let distribution_mint = ctx.accounts.fee_distribution.mint;
+    match target {
+        FeeRouterTarget::DISABLED => {}
+        FeeRouterTarget::BASE => require!(
+            distribution_mint == pool.base_mint,
+            OpenAmmErrorCode::WrongFeeDistributionAccount
+        ),
+        FeeRouterTarget::QUOTE => require!(
+            distribution_mint == pool.quote_mint,
+            OpenAmmErrorCode::WrongFeeDistributionAccount
+        ),
+    }
+
+    pool.fee_router_target = target;
+    pool.fee_distribution = ctx.accounts.fee_distribution.key();
+    pool.fee_process_interval_slots = interval_slots;
+    Ok(())
+}
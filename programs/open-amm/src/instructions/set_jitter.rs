@@ -0,0 +1,30 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use crate::util::MAX_JITTER_BPS;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetJitter<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetJitter<'info>>,
+    jitter_bps: u16,
+) -> Result<()> {
+    require!(
+        jitter_bps <= MAX_JITTER_BPS,
+        OpenAmmErrorCode::InvalidJitter
+    );
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.jitter_bps = jitter_bps;
+    Ok(())
+}
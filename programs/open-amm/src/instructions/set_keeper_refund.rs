@@ -0,0 +1,32 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use crate::util::MAX_KEEPER_REFUND_BPS;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetKeeperRefund<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Sets the share of each reconciled fill refunded to whoever triggers the
+/// reconcile. Pools cranked by their own keepers can turn it off with 0.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetKeeperRefund<'info>>,
+    keeper_refund_bps: u16,
+) -> Result<()> {
+    require!(
+        keeper_refund_bps <= MAX_KEEPER_REFUND_BPS,
+        OpenAmmErrorCode::InvalidKeeperRefund
+    );
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.keeper_refund_bps = keeper_refund_bps;
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetMaxQuoteAtRisk<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Caps the quote notional the pool's ladder may rest on the book, so very
+/// large pools on thin markets keep most of their reserves uncommitted. 0
+/// removes the cap.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetMaxQuoteAtRisk<'info>>,
+    max_quote_at_risk: u64,
+) -> Result<()> {
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.max_quote_at_risk = max_quote_at_risk;
+    Ok(())
+}
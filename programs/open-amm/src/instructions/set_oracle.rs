@@ -0,0 +1,39 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::oracle::ORACLE_BAND_DENOMINATOR;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetOracle<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Points the pool at a Pyth price account, or removes its oracle if `oracle`
+/// is the default key. Every instruction that places orders must then pass
+/// the oracle as a remaining account.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetOracle<'info>>,
+    oracle: Pubkey,
+    band_bps: u16,
+    max_age_slots: u64,
+) -> Result<()> {
+    if oracle != Pubkey::default() {
+        require!(
+            band_bps > 0 && band_bps <= ORACLE_BAND_DENOMINATOR,
+            OpenAmmErrorCode::InvalidOracleBand
+        );
+    }
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.oracle = oracle;
+    pool.oracle_band_bps = band_bps;
+    pool.oracle_max_age_slots = max_age_slots;
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetOrdersPerSide<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Sets how many rungs the pool quotes on each side. Bids stop one rung short
+/// of asks. Takes effect on the next refresh.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetOrdersPerSide<'info>>,
+    orders_per_side: u8,
+) -> Result<()> {
+    require!(
+        orders_per_side >= 2 && orders_per_side as usize <= MAX_ORDERS_PER_SIDE,
+        OpenAmmErrorCode::InvalidOrdersPerSide
+    );
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.orders_per_side = orders_per_side;
+    Ok(())
+}
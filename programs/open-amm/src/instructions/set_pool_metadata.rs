@@ -0,0 +1,33 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetPoolMetadata<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Labels the pool for frontends. `uri` is stored zero-padded.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetPoolMetadata<'info>>,
+    name: [u8; 32],
+    uri: String,
+) -> Result<()> {
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    require!(
+        uri.len() <= pool.uri.len(),
+        OpenAmmErrorCode::MetadataTooLong
+    );
+
+    pool.name = name;
+    pool.uri = [0; 128];
+    pool.uri[..uri.len()].copy_from_slice(uri.as_bytes());
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetQuoteOracle<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Points the pool at a Pyth USD price account for its quote token, or
+/// removes it if `quote_oracle` is the default key. `get_pool_stats` then
+/// needs the quote oracle as a remaining account to report USD amounts.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetQuoteOracle<'info>>,
+    quote_oracle: Pubkey,
+    max_age_slots: u64,
+) -> Result<()> {
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.quote_oracle = quote_oracle;
+    pool.quote_oracle_max_age_slots = max_age_slots;
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetQuotingSchedule<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Sets the UTC hours the pool quotes in, and how long it stops quoting after
+/// reconciling a fill larger than `cooldown_fill_bps` of its quote reserves.
+/// Equal open and close times quote around the clock, and a `cooldown_fill_bps`
+/// of 0 turns the cool-down off. Changing the schedule ends any cool-down.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetQuotingSchedule<'info>>,
+    quoting_open_secs: u32,
+    quoting_close_secs: u32,
+    cooldown_fill_bps: u16,
+    cooldown_slots: u64,
+) -> Result<()> {
+    require!(
+        quoting_open_secs < SECONDS_PER_DAY
+            && quoting_close_secs < SECONDS_PER_DAY
+            && cooldown_fill_bps <= COOLDOWN_FILL_DENOMINATOR,
+        OpenAmmErrorCode::InvalidQuotingSchedule
+    );
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.quoting_open_secs = quoting_open_secs;
+    pool.quoting_close_secs = quoting_close_secs;
+    pool.cooldown_fill_bps = cooldown_fill_bps;
+    pool.cooldown_slots = cooldown_slots;
+    pool.cooldown_until_slot = 0;
+    Ok(())
+}
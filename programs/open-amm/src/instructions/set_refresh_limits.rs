@@ -0,0 +1,31 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetRefreshLimits<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Sets how often anyone but the authority and `keeper` may refresh orders,
+/// and how many slots without a refresh let anyone put the pool in safe mode
+/// (0 disables safe mode).
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetRefreshLimits<'info>>,
+    keeper: Pubkey,
+    min_refresh_interval_slots: u64,
+    safe_mode_after_slots: u64,
+) -> Result<()> {
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.keeper = keeper;
+    pool.min_refresh_interval_slots = min_refresh_interval_slots;
+    pool.safe_mode_after_slots = safe_mode_after_slots;
+    Ok(())
+}
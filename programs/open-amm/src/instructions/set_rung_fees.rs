@@ -0,0 +1,29 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetRungFees<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Sets the fee each rung adds on top of the ladder's, nearest rung first.
+/// Fees may not fall with depth and the deepest is capped at
+/// `MAX_RUNG_FEE_BPS`. All zero quotes every rung at the same fee.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetRungFees<'info>>,
+    rung_fee_bps: [u16; MAX_ORDERS_PER_SIDE],
+) -> Result<()> {
+    PoolParameter::RungFees { rung_fee_bps }.validate()?;
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.rung_fee_bps = rung_fee_bps;
+    Ok(())
+}
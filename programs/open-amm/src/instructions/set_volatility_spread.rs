@@ -0,0 +1,36 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use crate::util::MAX_VOLATILITY_FEE_BPS;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetVolatilitySpread<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Sets how far the ladder's fee widens with the pool's volatility estimate:
+/// `volatility_fee_scale_bps` of fee per bps of volatility, up to
+/// `max_volatility_fee_bps`. Orders already resting keep the fee they were
+/// placed with until the next refresh replaces them.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetVolatilitySpread<'info>>,
+    volatility_fee_scale_bps: u16,
+    max_volatility_fee_bps: u16,
+) -> Result<()> {
+    require!(
+        max_volatility_fee_bps <= MAX_VOLATILITY_FEE_BPS,
+        OpenAmmErrorCode::InvalidVolatilityFee
+    );
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.volatility_fee_scale_bps = volatility_fee_scale_bps;
+    pool.max_volatility_fee_bps = max_volatility_fee_bps;
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetWithdrawDelay<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = !pool.load()?.in_progress @ OpenAmmErrorCode::PoolLocked,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Sets how many slots LPs must wait between requesting and claiming a
+/// withdraw. Any delay disables instant withdraws, 0 turns them back on.
+/// Applies to tickets already requested as well.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetWithdrawDelay<'info>>,
+    withdraw_delay_slots: u64,
+) -> Result<()> {
+    require!(
+        withdraw_delay_slots <= MAX_WITHDRAW_DELAY_SLOTS,
+        OpenAmmErrorCode::InvalidWithdrawDelay
+    );
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    pool.withdraw_delay_slots = withdraw_delay_slots;
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::create_pool::{BASE_FEE_VAULT_SEED, QUOTE_FEE_VAULT_SEED};
+use crate::state::*;
+use crate::util::{get_orderbook, Reconciliation};
+use anchor_lang::prelude::*;
+use anchor_spl::dex;
+use anchor_spl::token::{Token, TokenAccount};
+use serum_dex::matching::Side;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct SimulatedRung {
+    pub rung: u8,
+    pub limit_price: u64,
+    pub base_qty: u64,
+    /// Would be sent as an IOC order under the pool's crossing policy.
+    pub take: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct RefreshSimulation {
+    pub cancel_client_order_ids: Vec<u64>,
+    pub reconciled_quote_amount: u64,
+    /// Reserves after reconciling, which the ladder is quoted from.
+    pub base_amount: u64,
+    pub quote_amount: u64,
+    pub mm_active: bool,
+    pub asks: Vec<SimulatedRung>,
+    pub bids: Vec<SimulatedRung>,
+}
+
+#[derive(Accounts)]
+pub struct SimulateRefresh<'info> {
+    #[account(
+        has_one = base_vault,
+        has_one = quote_vault,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(
+        constraint = market_accounts.market.key() == pool.load()?.market
+            @ OpenAmmErrorCode::WrongMarketAccount,
+        constraint = market_accounts.open_orders.key() == pool.load()?.open_orders
+            @ OpenAmmErrorCode::WrongOpenOrdersAccount,
+    )]
+    pub market_accounts: MarketAccounts<'info>,
+
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [pool.key().as_ref(), BASE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub base_fee_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [pool.key().as_ref(), QUOTE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub quote_fee_vault: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    #[account(address = pool.load()?.dex_program @ OpenAmmErrorCode::WrongDexProgram)]
+    pub dex_program: Program<'info, dex::Dex>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/**
+ * Meant to be run through RPC simulation. Reconciles and builds the primary
+ * market's ladder the way `refresh_orders` would, against a copy of the pool,
+ * and returns the orders it would cancel and place without making any CPIs
+ * or writing to the pool.
+ *
+ * The pool's own orders are still on the book, so a side whose best order is
+ * the pool's is priced against the top of book stored at the last refresh.
+ */
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SimulateRefresh<'info>>,
+) -> Result<RefreshSimulation> {
+    let mut pool = *ctx.accounts.pool.load()?;
+
+    let orderbook = get_orderbook(
+        pool.client_order_id,
+        pool.bump,
+        pool.pool_type,
+        pool.index,
+        ctx.accounts.pool.clone(),
+        ctx.accounts.market_accounts.clone(),
+        *ctx.accounts.base_vault.clone(),
+        *ctx.accounts.quote_vault.clone(),
+        *ctx.accounts.base_fee_vault.clone(),
+        *ctx.accounts.quote_fee_vault.clone(),
+        ctx.accounts.dex_program.clone(),
+        ctx.accounts.token_program.clone(),
+        ctx.accounts.rent.clone(),
+        pool.debug_logging,
+    )?;
+
+    let Reconciliation {
+        reconciled_quote_amount,
+        cancel_client_order_ids,
+        ..
+    } = orderbook.reconcile(&mut pool)?;
+    let mut simulation = RefreshSimulation {
+        reconciled_quote_amount,
+        base_amount: pool.base_amount,
+        quote_amount: pool.quote_amount,
+        mm_active: pool.mm_active,
+        ..RefreshSimulation::default()
+    };
+    // Cancelling the pool's orders frees their slots for the new ladder
+    let free_slots = orderbook
+        .load_free_order_slots()?
+        .checked_add(cancel_client_order_ids.len())
+        .unwrap();
+    simulation.cancel_client_order_ids = cancel_client_order_ids;
+    let clock = Clock::get()?;
+    if !pool.mm_active
+        || pool.safe_mode
+        || pool.paused
+        || !pool.is_quoting_scheduled(clock.slot, clock.unix_timestamp)
+    {
+        return Ok(simulation);
+    }
+
+    let (best_bid_price, best_ask_price) = orderbook.load_best_prices()?;
+    let best_bid_price = best_bid_price.or((pool.best_bid_price != 0).then(|| pool.best_bid_price));
+    let best_ask_price = best_ask_price.or((pool.best_ask_price != 0).then(|| pool.best_ask_price));
+    pool.record_book_top(best_bid_price, best_ask_price, Clock::get()?.slot);
+
+    let ladder = orderbook.build_ladder(
+        &mut pool,
+        best_bid_price,
+        best_ask_price,
+        free_slots,
+        ctx.remaining_accounts,
+    )?;
+    for order in ladder.iter() {
+        let rung = SimulatedRung {
+            rung: order.rung as u8,
+            limit_price: order.limit_price,
+            base_qty: order.base_qty,
+            take: order.take,
+        };
+        match order.side {
+            Side::Ask => simulation.asks.push(rung),
+            Side::Bid => simulation.bids.push(rung),
+        }
+    }
+
+    Ok(simulation)
+}
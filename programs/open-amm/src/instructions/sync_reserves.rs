@@ -0,0 +1,162 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::create_pool::{BASE_FEE_VAULT_SEED, QUOTE_FEE_VAULT_SEED};
+use crate::state::*;
+use crate::util::{debug_msg, get_orderbook, get_unrecorded_surplus, lock_pool, unlock_pool};
+use anchor_lang::prelude::*;
+use anchor_spl::dex;
+use anchor_spl::token::{Token, TokenAccount};
+
+#[event]
+pub struct ReservesSyncedEvent {
+    pool: Pubkey,
+    base_amount: u64,
+    quote_amount: u64,
+    end_base: u64,
+    end_quote: u64,
+    signer: Pubkey,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct SyncReserves<'info> {
+    #[account(
+        mut,
+        has_one = base_vault,
+        has_one = quote_vault,
+        has_one = authority,
+    )]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(
+        constraint = market_accounts.market.key() == pool.load()?.market
+            @ OpenAmmErrorCode::WrongMarketAccount,
+        constraint = market_accounts.open_orders.key() == pool.load()?.open_orders
+            @ OpenAmmErrorCode::WrongOpenOrdersAccount,
+    )]
+    pub market_accounts: MarketAccounts<'info>,
+
+    #[account(mut)]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), BASE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub base_fee_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), QUOTE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub quote_fee_vault: Box<Account<'info, TokenAccount>>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    #[account(address = pool.load()?.dex_program @ OpenAmmErrorCode::WrongDexProgram)]
+    pub dex_program: Program<'info, dex::Dex>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/**
+ * Folds tokens the pool holds beyond its recorded reserves and what it owes
+ * into the reserves, then re-places the ladder on them. Deposits and
+ * withdrawals only ever price LP tokens off recorded reserves, so this is the
+ * one way donations reach LPs. It is left to the pool authority so a
+ * donation can't be used to move the share price at a moment of the
+ * donor's choosing.
+ */
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, SyncReserves<'info>>) -> Result<()> {
+    lock_pool(&ctx.accounts.pool)?;
+    let pool = ctx.accounts.pool.load()?;
+    let pool_bump = pool.bump;
+    let order_id = pool.client_order_id;
+    let pool_type = pool.pool_type;
+    let pool_index = pool.index;
+    let debug_logging = pool.debug_logging;
+    drop(pool);
+
+    let orderbook = get_orderbook(
+        order_id,
+        pool_bump,
+        pool_type,
+        pool_index,
+        ctx.accounts.pool.clone(),
+        ctx.accounts.market_accounts.clone(),
+        *ctx.accounts.base_vault.clone(),
+        *ctx.accounts.quote_vault.clone(),
+        *ctx.accounts.base_fee_vault.clone(),
+        *ctx.accounts.quote_fee_vault.clone(),
+        ctx.accounts.dex_program.clone(),
+        ctx.accounts.token_program.clone(),
+        ctx.accounts.rent.clone(),
+        debug_logging,
+    )?;
+
+    orderbook.cancel_all_and_settle()?;
+
+    ctx.accounts.base_vault.reload()?;
+    ctx.accounts.quote_vault.reload()?;
+    let (native_base_total, native_quote_total) = orderbook.load_open_orders_totals()?;
+
+    let mut pool = ctx.accounts.pool.load_mut()?;
+    if !pool.mm_active {
+        drop(pool);
+        return unlock_pool(&ctx.accounts.pool);
+    }
+    let (base_surplus, quote_surplus) = get_unrecorded_surplus(
+        &pool,
+        ctx.accounts
+            .base_vault
+            .amount
+            .checked_add(native_base_total)
+            .unwrap(),
+        ctx.accounts
+            .quote_vault
+            .amount
+            .checked_add(native_quote_total)
+            .unwrap(),
+    );
+    pool.base_amount = pool.base_amount.checked_add(base_surplus).unwrap();
+    pool.quote_amount = pool.quote_amount.checked_add(quote_surplus).unwrap();
+    pool.unswept_rebates_quote_amount = 0;
+    let end_base = pool.base_amount;
+    let end_quote = pool.quote_amount;
+    drop(pool);
+
+    debug_msg!(
+        debug_logging,
+        "openamm:sync_reserves base={} quote={}",
+        base_surplus,
+        quote_surplus
+    );
+
+    orderbook.place_new_orders(
+        &ctx.accounts.base_vault,
+        &ctx.accounts.quote_vault,
+        ctx.remaining_accounts,
+    )?;
+
+    let clock = Clock::get()?;
+    emit!(ReservesSyncedEvent {
+        pool: ctx.accounts.pool.key(),
+        base_amount: base_surplus,
+        quote_amount: quote_surplus,
+        end_base,
+        end_quote,
+        signer: ctx.accounts.authority.key(),
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    });
+
+    unlock_pool(&ctx.accounts.pool)?;
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use crate::instructions::create_pool::{get_pool_index_seed, LP_MINT_SEED, POOL_SEED};
+use crate::instructions::init_wrapped_lp::{WRAPPED_LP_MINT_SEED, WRAPPED_LP_VAULT_SEED};
+use crate::state::*;
+use crate::util::pool_authority_seeds;
+use anchor_lang::prelude::*;
+use anchor_spl::token::{transfer, Mint, Token, TokenAccount, Transfer};
+use solana_program::program::invoke;
+
+#[derive(Accounts)]
+pub struct UnwrapLp<'info> {
+    #[account(has_one = lp_mint)]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(
+        seeds = [pool.key().as_ref(), LP_MINT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub lp_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: Created by `init_wrapped_lp`
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), WRAPPED_LP_MINT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub wrapped_lp_mint: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), WRAPPED_LP_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub wrapped_lp_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = lp_mint,
+        token::authority = signer,
+    )]
+    pub signer_lp: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: A Token-2022 account for the wrapped LP mint owned by the
+    /// signer, which the burn CPI checks
+    #[account(mut)]
+    pub signer_wrapped_lp: AccountInfo<'info>,
+
+    pub signer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: The Token-2022 program
+    #[account(address = spl_token_2022::ID)]
+    pub token_2022_program: AccountInfo<'info>,
+}
+
+/// Burns `amount` wrapped LP tokens and releases as many LP tokens from the
+/// wrapped LP vault.
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, UnwrapLp<'info>>, amount: u64) -> Result<()> {
+    let instruction = spl_token_2022::instruction::burn(
+        &spl_token_2022::ID,
+        &ctx.accounts.signer_wrapped_lp.key(),
+        &ctx.accounts.wrapped_lp_mint.key(),
+        &ctx.accounts.signer.key(),
+        &[],
+        amount,
+    )?;
+    invoke(
+        &instruction,
+        &[
+            ctx.accounts.signer_wrapped_lp.clone(),
+            ctx.accounts.wrapped_lp_mint.clone(),
+            ctx.accounts.signer.to_account_info(),
+            ctx.accounts.token_2022_program.clone(),
+        ],
+    )?;
+
+    let pool = ctx.accounts.pool.load()?;
+    let market_key = pool.market;
+    let pool_type_bytes = (pool.pool_type as u8).to_le_bytes();
+    let pool_index_seed = get_pool_index_seed(pool.index);
+    let pool_bump = pool.bump;
+    drop(pool);
+    let seeds = pool_authority_seeds!(
+        market_key = market_key,
+        pool_type_bytes = pool_type_bytes,
+        index_seed = pool_index_seed,
+        bump = pool_bump
+    );
+    let pool_signer = &[&seeds[..]];
+
+    let transfer_lp_cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.wrapped_lp_vault.to_account_info(),
+            to: ctx.accounts.signer_lp.to_account_info(),
+            authority: ctx.accounts.pool.to_account_info(),
+        },
+        pool_signer,
+    );
+    transfer(transfer_lp_cpi_ctx, amount)?;
+    Ok(())
+}
@@ -1,10 +1,17 @@
 use crate::errors::OpenAmmErrorCode;
-use crate::instructions::create_pool::{LP_MINT_SEED, POOL_SEED};
+use crate::instructions::create_pool::{
+    get_pool_index_seed, BASE_FEE_VAULT_SEED, LP_MINT_SEED, POOL_SEED, QUOTE_FEE_VAULT_SEED,
+};
 use crate::state::*;
-use crate::util::{get_orderbook, pool_authority_seeds};
+use crate::util::{debug_msg, get_orderbook, lock_pool, pool_authority_seeds, unlock_pool};
 use anchor_lang::prelude::*;
 use anchor_spl::dex;
-use anchor_spl::token::{burn, transfer, Burn, Mint, Token, TokenAccount, Transfer};
+use anchor_spl::token::{self, burn, transfer, Burn, Mint, Token, TokenAccount, Transfer};
+
+/// Largest share of the LP supply, in bps, a withdraw pays out by trimming
+/// the ladder's deepest rungs rather than cancelling and replacing all of it.
+pub const TRIM_WITHDRAW_MAX_BPS: u64 = 1_000;
+pub const TRIM_WITHDRAW_DENOMINATOR: u64 = 10_000;
 
 #[event]
 pub struct WithdrawEvent {
@@ -15,6 +22,10 @@ pub struct WithdrawEvent {
     end_base: u64,
     end_quote: u64,
     end_lp: u64,
+    pool: Pubkey,
+    signer: Pubkey,
+    slot: u64,
+    unix_timestamp: i64,
 }
 
 #[derive(Accounts)]
@@ -41,6 +52,20 @@ pub struct Withdraw<'info> {
     #[account(mut)]
     pub quote_vault: Box<Account<'info, TokenAccount>>,
 
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), BASE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub base_fee_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), QUOTE_FEE_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub quote_fee_vault: Box<Account<'info, TokenAccount>>,
+
     #[account(
         mut,
         mint::authority = pool,
@@ -75,91 +100,182 @@ pub struct Withdraw<'info> {
 
     pub token_program: Program<'info, Token>,
 
-    #[account(address = dex::ID)]
+    #[account(address = pool.load()?.dex_program @ OpenAmmErrorCode::WrongDexProgram)]
     pub dex_program: Program<'info, dex::Dex>,
 
     pub rent: Sysvar<'info, Rent>,
 }
 
 pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>, lp_amt: u64) -> Result<()> {
-    let pool = ctx.accounts.pool.load()?;
+    withdraw_liquidity(ctx.accounts, ctx.remaining_accounts, lp_amt)?;
+    Ok(())
+}
+
+/// Burns `lp_amt` LP tokens and pays out the matching share of reserves.
+/// Returns the base and quote amounts paid out followed by the base and quote
+/// reserves they were priced against.
+///
+/// While market making is halted, such as after an eviction, reserves may
+/// count tokens still stuck in the open orders account. The share is then
+/// of what the vaults hold instead, leaving the stuck tokens to the LPs who
+/// stay, and nothing is put back on the book.
+pub fn withdraw_liquidity<'info>(
+    accounts: &Withdraw<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    lp_amt: u64,
+) -> Result<(u64, u64, u64, u64)> {
+    let pool = accounts.pool.load()?;
+    require!(
+        pool.withdraw_delay_slots == 0,
+        OpenAmmErrorCode::WithdrawDelayRequired
+    );
+    drop(pool);
+    lock_pool(&accounts.pool)?;
+    let pool = accounts.pool.load()?;
     let pool_bump = pool.bump;
     let order_id = pool.client_order_id;
     let pool_type = pool.pool_type;
+    let pool_index = pool.index;
+    let debug_logging = pool.debug_logging;
     drop(pool);
 
     let orderbook = get_orderbook(
         order_id,
         pool_bump,
         pool_type,
-        ctx.accounts.pool.clone(),
-        ctx.accounts.market_accounts.clone(),
-        *ctx.accounts.base_vault.clone(),
-        *ctx.accounts.quote_vault.clone(),
-        ctx.accounts.dex_program.clone(),
-        ctx.accounts.token_program.clone(),
-        ctx.accounts.rent.clone(),
-        false,
-    );
-    orderbook.cancel_all_and_settle()?;
+        pool_index,
+        accounts.pool.clone(),
+        accounts.market_accounts.clone(),
+        *accounts.base_vault.clone(),
+        *accounts.quote_vault.clone(),
+        *accounts.base_fee_vault.clone(),
+        *accounts.quote_fee_vault.clone(),
+        accounts.dex_program.clone(),
+        accounts.token_program.clone(),
+        accounts.rent.clone(),
+        debug_logging,
+    )?
+    .with_rung_stats(remaining_accounts);
+    // A routine exit only needs enough inventory freed to pay it, which the
+    // deepest rungs can usually provide while the top of the book stays live
+    let start_lp = accounts.lp_mint.supply;
+    let trimmed = if (lp_amt as u128)
+        .checked_mul(TRIM_WITHDRAW_DENOMINATOR.into())
+        .unwrap()
+        <= (start_lp as u128)
+            .checked_mul(TRIM_WITHDRAW_MAX_BPS.into())
+            .unwrap()
+    {
+        orderbook.trim_and_settle(lp_amt, start_lp)?
+    } else {
+        orderbook.cancel_all_and_settle()?;
+        false
+    };
 
-    let mut pool = ctx.accounts.pool.load_mut()?;
-    if !pool.mm_active {
-        return Ok(());
+    // Reserves already count every fill, but the tokens for fills that haven't
+    // been cranked, or for orders whose cancel failed, are still in the open
+    // orders account rather than the vaults. Paying out shares now would draw
+    // on other LPs' funds, so make the caller retry once they've landed. Dust a
+    // restart left stranded there is already outside reserves. A trimmed
+    // ladder still has orders resting, and was trimmed until the vaults
+    // covered the payout.
+    let mm_active = accounts.pool.load()?.mm_active;
+    if !trimmed && mm_active {
+        let (pending_base, pending_quote) = orderbook.load_open_orders_totals()?;
+        let pool = accounts.pool.load()?;
+        require!(
+            pending_base <= pool.stranded_base_amount
+                && pending_quote <= pool.stranded_quote_amount,
+            OpenAmmErrorCode::ReconciliationPending
+        );
+        drop(pool);
     }
-    let cpi_token_program = ctx.accounts.token_program.to_account_info();
-    let base_reserve = pool.base_amount;
-    let quote_reserve = pool.quote_amount;
-    let start_lp = ctx.accounts.lp_mint.supply;
+
+    let mut pool = accounts.pool.load_mut()?;
+    let cpi_token_program = accounts.token_program.to_account_info();
+    let (base_reserve, quote_reserve) = if mm_active {
+        (pool.base_amount, pool.quote_amount)
+    } else {
+        let base_vault_amount = token::accessor::amount(&accounts.base_vault.to_account_info())?;
+        let quote_vault_amount =
+            token::accessor::amount(&accounts.quote_vault.to_account_info())?;
+        (
+            base_vault_amount
+                .saturating_sub(pool.refund_base_amount)
+                .saturating_sub(pool.keeper_owed_base_amount),
+            quote_vault_amount
+                .saturating_sub(pool.refund_quote_amount)
+                .saturating_sub(pool.keeper_owed_quote_amount),
+        )
+    };
 
     let burn_lp_cpi_ctx = CpiContext::new(
         cpi_token_program.clone(),
         Burn {
-            mint: ctx.accounts.lp_mint.to_account_info(),
-            from: ctx.accounts.signer_lp.to_account_info(),
-            authority: ctx.accounts.signer.to_account_info(),
+            mint: accounts.lp_mint.to_account_info(),
+            from: accounts.signer_lp.to_account_info(),
+            authority: accounts.signer.to_account_info(),
         },
     );
     burn(burn_lp_cpi_ctx, lp_amt)?;
 
-    let withdraw_base_amount: u64 = (lp_amt as u128)
-        .checked_mul(base_reserve.into())
-        .unwrap()
-        .checked_div(start_lp.into())
-        .unwrap()
-        .try_into()
-        .unwrap();
+    let (withdraw_base_amount, withdraw_quote_amount) = if mm_active {
+        pool.get_lp_share_amounts(lp_amt, start_lp)
+    } else {
+        let mut vault_pool = *pool;
+        vault_pool.base_amount = base_reserve;
+        vault_pool.quote_amount = quote_reserve;
+        vault_pool.get_lp_share_amounts(lp_amt, start_lp)
+    };
+    pool.scale_reserve_checkpoints(start_lp.checked_sub(lp_amt).unwrap(), start_lp);
+    pool.withdraw_log
+        .record(1, accounts.signer.key(), Clock::get()?.slot);
 
-    let withdraw_quote_amount: u64 = (lp_amt as u128)
-        .checked_mul(quote_reserve.into())
-        .unwrap()
-        .checked_div(start_lp.into())
-        .unwrap()
-        .try_into()
-        .unwrap();
+    debug_msg!(
+        debug_logging,
+        "openamm:withdraw lp_burned={} base={} quote={}",
+        lp_amt,
+        withdraw_base_amount,
+        withdraw_quote_amount
+    );
 
-    let market_key = ctx.accounts.market_accounts.market.key();
+    let market_key = accounts.market_accounts.market.key();
     let pool_type_bytes = (pool_type as u8).to_le_bytes();
+    let pool_index_seed = get_pool_index_seed(pool_index);
     let seeds = pool_authority_seeds!(
         market_key = market_key,
         pool_type_bytes = pool_type_bytes,
+        index_seed = pool_index_seed,
         bump = pool_bump
     );
     let pool_signer = &[&seeds[..]];
 
-    pool.base_amount = pool.base_amount.checked_sub(withdraw_base_amount).unwrap();
-    pool.quote_amount = pool
-        .quote_amount
-        .checked_sub(withdraw_quote_amount)
-        .unwrap();
+    if mm_active {
+        pool.base_amount = pool.base_amount.checked_sub(withdraw_base_amount).unwrap();
+        pool.quote_amount = pool
+            .quote_amount
+            .checked_sub(withdraw_quote_amount)
+            .unwrap();
+    } else {
+        // A restart resets reserves to the vaults, and needs them to grow by
+        // the eviction's exposure from where this payout leaves them
+        pool.base_amount = pool.base_amount.saturating_sub(withdraw_base_amount);
+        pool.quote_amount = pool.quote_amount.saturating_sub(withdraw_quote_amount);
+        pool.eviction_base_baseline = pool
+            .eviction_base_baseline
+            .saturating_sub(withdraw_base_amount);
+        pool.eviction_quote_baseline = pool
+            .eviction_quote_baseline
+            .saturating_sub(withdraw_quote_amount);
+    }
 
     drop(pool);
     let transfer_base_to_signer_cpi_ctx = CpiContext::new_with_signer(
         cpi_token_program.clone(),
         Transfer {
-            from: ctx.accounts.base_vault.to_account_info(),
-            to: ctx.accounts.signer_base.to_account_info(),
-            authority: ctx.accounts.pool.to_account_info(),
+            from: accounts.base_vault.to_account_info(),
+            to: accounts.signer_base.to_account_info(),
+            authority: accounts.pool.to_account_info(),
         },
         pool_signer,
     );
@@ -168,17 +284,25 @@ pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>, lp_amt:
     let transfer_quote_to_signer_cpi_ctx = CpiContext::new_with_signer(
         cpi_token_program,
         Transfer {
-            from: ctx.accounts.quote_vault.to_account_info(),
-            to: ctx.accounts.signer_quote.to_account_info(),
-            authority: ctx.accounts.pool.to_account_info(),
+            from: accounts.quote_vault.to_account_info(),
+            to: accounts.signer_quote.to_account_info(),
+            authority: accounts.pool.to_account_info(),
         },
         pool_signer,
     );
     transfer(transfer_quote_to_signer_cpi_ctx, withdraw_quote_amount)?;
 
-    orderbook.place_new_orders(&ctx.accounts.base_vault, &ctx.accounts.quote_vault)?;
+    // Trimmed rungs stay off the book until the next refresh
+    if !trimmed && mm_active {
+        orderbook.place_new_orders(
+            &accounts.base_vault,
+            &accounts.quote_vault,
+            remaining_accounts,
+        )?;
+    }
 
-    let pool = ctx.accounts.pool.load()?;
+    let pool = accounts.pool.load()?;
+    let clock = Clock::get()?;
     emit!(WithdrawEvent {
         pool_type: pool.pool_type,
         start_base: base_reserve,
@@ -186,8 +310,19 @@ pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>, lp_amt:
         start_lp,
         end_base: pool.base_amount,
         end_quote: pool.quote_amount,
-        end_lp: ctx.accounts.lp_mint.supply,
+        end_lp: accounts.lp_mint.supply,
+        pool: accounts.pool.key(),
+        signer: accounts.signer.key(),
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
     });
+    drop(pool);
 
-    Ok(())
+    unlock_pool(&accounts.pool)?;
+    Ok((
+        withdraw_base_amount,
+        withdraw_quote_amount,
+        base_reserve,
+        quote_reserve,
+    ))
 }
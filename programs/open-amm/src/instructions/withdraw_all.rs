@@ -0,0 +1,136 @@
+use crate::instructions::create_pool::{get_pool_index_seed, POOL_SEED};
+use crate::instructions::withdraw::*;
+use crate::util::{
+    debug_msg, get_unrecorded_surplus, lock_pool, pool_authority_seeds, unlock_pool,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, transfer, Transfer};
+use serum_dex::state::Market;
+use std::cmp;
+
+/// Emitted when `withdraw_all` pays an LP their share of the maker rebates
+/// the pool holds beyond its reserves.
+#[event]
+pub struct DustSweptEvent {
+    pool: Pubkey,
+    owner: Pubkey,
+    quote_amount: u64,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawAll<'info> {
+    pub withdraw: Withdraw<'info>,
+}
+
+/**
+ * Burns the signer's whole LP balance, then pays them the same share of the
+ * maker rebates the pool holds outside its reserves. Rebates are the only
+ * surplus the pool can tell apart from a donation, which stays put until the
+ * authority syncs it. Keeper refunds and payouts and stranded open orders
+ * balances aren't dust and stay where they are too.
+ */
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, WithdrawAll<'info>>) -> Result<()> {
+    let accounts = &ctx.accounts.withdraw;
+    let lp_amt = accounts.signer_lp.amount;
+    if lp_amt == 0 {
+        return Ok(());
+    }
+    let start_lp = accounts.lp_mint.supply;
+
+    withdraw_liquidity(accounts, ctx.remaining_accounts, lp_amt)?;
+    if !accounts.pool.load()?.mm_active {
+        // Reserves still count what's stranded on the book, so there's no
+        // dust to tell apart from it until market making restarts.
+        return Ok(());
+    }
+
+    lock_pool(&accounts.pool)?;
+    let dex_program = accounts.pool.load()?.dex_program;
+    let market_state = Market::load(&accounts.market_accounts.market, &dex_program, false).unwrap();
+    let open_orders = Market::load_orders_mut(
+        &market_state,
+        &accounts.market_accounts.open_orders,
+        None,
+        &dex_program,
+        None,
+        None,
+    )
+    .unwrap();
+    let native_quote_total = open_orders.native_pc_total;
+    drop(open_orders);
+    drop(market_state);
+
+    let quote_vault_amount = token::accessor::amount(&accounts.quote_vault.to_account_info())?;
+
+    let mut pool = accounts.pool.load_mut()?;
+    let (_, quote_surplus) = get_unrecorded_surplus(
+        &pool,
+        0,
+        quote_vault_amount.checked_add(native_quote_total).unwrap(),
+    );
+    // Donations are surplus too, but only reach LPs through `sync_reserves`
+    let quote_dust = cmp::min(quote_surplus, pool.unswept_rebates_quote_amount);
+    // Only what has already settled into the vaults can be paid out
+    let sweep_quote_amount = cmp::min(
+        (quote_dust as u128)
+            .checked_mul(lp_amt.into())
+            .unwrap()
+            .checked_div(start_lp.into())
+            .unwrap() as u64,
+        quote_vault_amount,
+    );
+    pool.unswept_rebates_quote_amount = pool
+        .unswept_rebates_quote_amount
+        .checked_sub(sweep_quote_amount)
+        .unwrap();
+    let debug_logging = pool.debug_logging;
+    let pool_bump = pool.bump;
+    let pool_type = pool.pool_type;
+    let pool_index = pool.index;
+    drop(pool);
+
+    debug_msg!(
+        debug_logging,
+        "openamm:withdraw_all quote_dust={} swept_quote={}",
+        quote_dust,
+        sweep_quote_amount
+    );
+
+    let market_key = accounts.market_accounts.market.key();
+    let pool_type_bytes = (pool_type as u8).to_le_bytes();
+    let pool_index_seed = get_pool_index_seed(pool_index);
+    let seeds = pool_authority_seeds!(
+        market_key = market_key,
+        pool_type_bytes = pool_type_bytes,
+        index_seed = pool_index_seed,
+        bump = pool_bump
+    );
+    let pool_signer = &[&seeds[..]];
+
+    if sweep_quote_amount > 0 {
+        let transfer_quote_to_signer_cpi_ctx = CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: accounts.quote_vault.to_account_info(),
+                to: accounts.signer_quote.to_account_info(),
+                authority: accounts.pool.to_account_info(),
+            },
+            pool_signer,
+        );
+        transfer(transfer_quote_to_signer_cpi_ctx, sweep_quote_amount)?;
+    }
+
+    let clock = Clock::get()?;
+    emit!(DustSweptEvent {
+        pool: accounts.pool.key(),
+        owner: accounts.signer.key(),
+        quote_amount: sweep_quote_amount,
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    });
+
+    unlock_pool(&accounts.pool)?;
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::open_position::POSITION_SEED;
+use crate::instructions::withdraw::*;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+/// Realized impermanent loss for a position withdrawal. All values are in
+/// quote atoms, priced at the pool's reserve ratio at the time of withdrawal.
+#[event]
+pub struct ImpermanentLossEvent {
+    pool: Pubkey,
+    owner: Pubkey,
+    lp_amount: u64,
+    hold_value: u64,
+    withdraw_value: u64,
+    /// `withdraw_value - hold_value`; negative when the LP did worse than holding.
+    pnl_vs_hold: i64,
+    /// `pnl_vs_hold` relative to `hold_value`, in basis points.
+    pnl_vs_hold_bps: i64,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFromPosition<'info> {
+    pub withdraw: Withdraw<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            withdraw.pool.key().as_ref(),
+            withdraw.signer.key().as_ref(),
+            POSITION_SEED.as_bytes().as_ref(),
+        ],
+        bump = position.bump,
+    )]
+    pub position: Box<Account<'info, Position>>,
+}
+
+fn value_in_quote(
+    base_amount: u64,
+    quote_amount: u64,
+    base_reserve: u64,
+    quote_reserve: u64,
+) -> u64 {
+    let base_value: u64 = (base_amount as u128)
+        .checked_mul(quote_reserve.into())
+        .unwrap()
+        .checked_div(base_reserve.into())
+        .unwrap()
+        .try_into()
+        .unwrap();
+    base_value.checked_add(quote_amount).unwrap()
+}
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, WithdrawFromPosition<'info>>,
+    lp_amt: u64,
+) -> Result<()> {
+    require!(
+        lp_amt <= ctx.accounts.position.lp_amount,
+        OpenAmmErrorCode::InsufficientPositionLp
+    );
+
+    let (base_out, quote_out, base_reserve, quote_reserve) =
+        withdraw_liquidity(&ctx.accounts.withdraw, ctx.remaining_accounts, lp_amt)?;
+    let (base_in, quote_in) = ctx.accounts.position.record_withdraw(lp_amt);
+    if base_reserve == 0 {
+        // Vaults emptied by an eviction leave nothing to price against
+        return Ok(());
+    }
+
+    let hold_value = value_in_quote(base_in, quote_in, base_reserve, quote_reserve);
+    let withdraw_value = value_in_quote(base_out, quote_out, base_reserve, quote_reserve);
+    let pnl_vs_hold = (withdraw_value as i64)
+        .checked_sub(hold_value as i64)
+        .unwrap();
+    let pnl_vs_hold_bps = match hold_value {
+        0 => 0,
+        hold_value => (pnl_vs_hold as i128)
+            .checked_mul(10_000)
+            .unwrap()
+            .checked_div(hold_value.into())
+            .unwrap()
+            .try_into()
+            .unwrap(),
+    };
+
+    let clock = Clock::get()?;
+    emit!(ImpermanentLossEvent {
+        pool: ctx.accounts.position.pool,
+        owner: ctx.accounts.position.owner,
+        lp_amount: lp_amt,
+        hold_value,
+        withdraw_value,
+        pnl_vs_hold,
+        pnl_vs_hold_bps,
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::withdraw::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct WithdrawUnderlying<'info> {
+    /// The metapool, whose quote leg is the underlying pool's LP token.
+    pub withdraw: Withdraw<'info>,
+
+    pub underlying_withdraw: Withdraw<'info>,
+}
+
+/**
+ * Burns `lp_amt` of the metapool's LP tokens, then burns the underlying LP
+ * tokens they paid out for the underlying pool's tokens, so the signer
+ * leaves with the metapool's base and both underlying tokens.
+ *
+ * `remaining_accounts` must include both pools' oracles and anchor pools, if
+ * they have them.
+ */
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, WithdrawUnderlying<'info>>,
+    lp_amt: u64,
+) -> Result<()> {
+    let accounts = &ctx.accounts.withdraw;
+    let underlying_accounts = &ctx.accounts.underlying_withdraw;
+    require!(
+        accounts.pool.load()?.underlying_pool == underlying_accounts.pool.key(),
+        OpenAmmErrorCode::WrongUnderlyingPool
+    );
+    require!(
+        accounts.signer.key() == underlying_accounts.signer.key()
+            && accounts.signer_quote.key() == underlying_accounts.signer_lp.key(),
+        OpenAmmErrorCode::WrongUnderlyingPool
+    );
+
+    let (_, underlying_lp_amount, _, _) =
+        withdraw_liquidity(accounts, ctx.remaining_accounts, lp_amt)?;
+    if underlying_lp_amount == 0 {
+        return Ok(());
+    }
+
+    withdraw_liquidity(
+        underlying_accounts,
+        ctx.remaining_accounts,
+        underlying_lp_amount,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use crate::instructions::create_pool::{get_pool_index_seed, LP_MINT_SEED, POOL_SEED};
+use crate::instructions::init_wrapped_lp::{WRAPPED_LP_MINT_SEED, WRAPPED_LP_VAULT_SEED};
+use crate::state::*;
+use crate::util::pool_authority_seeds;
+use anchor_lang::prelude::*;
+use anchor_spl::token::{transfer, Mint, Token, TokenAccount, Transfer};
+use solana_program::program::invoke_signed;
+
+#[derive(Accounts)]
+pub struct WrapLp<'info> {
+    #[account(has_one = lp_mint)]
+    pub pool: AccountLoader<'info, OpenAmmPool>,
+
+    #[account(
+        seeds = [pool.key().as_ref(), LP_MINT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub lp_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: Created by `init_wrapped_lp`
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), WRAPPED_LP_MINT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub wrapped_lp_mint: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [pool.key().as_ref(), WRAPPED_LP_VAULT_SEED.as_bytes().as_ref()],
+        bump,
+    )]
+    pub wrapped_lp_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = lp_mint,
+        token::authority = signer,
+    )]
+    pub signer_lp: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: A Token-2022 account for the wrapped LP mint, which the mint
+    /// CPI checks
+    #[account(mut)]
+    pub signer_wrapped_lp: AccountInfo<'info>,
+
+    pub signer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: The Token-2022 program
+    #[account(address = spl_token_2022::ID)]
+    pub token_2022_program: AccountInfo<'info>,
+}
+
+/// Locks `lp_amount` LP tokens in the wrapped LP vault and mints as many
+/// wrapped LP tokens. A wrapped token is always backed by one LP token, so
+/// its supply never rebases and `get_wrapped_lp_price` prices it.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, WrapLp<'info>>,
+    lp_amount: u64,
+) -> Result<()> {
+    let transfer_lp_cpi_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.signer_lp.to_account_info(),
+            to: ctx.accounts.wrapped_lp_vault.to_account_info(),
+            authority: ctx.accounts.signer.to_account_info(),
+        },
+    );
+    transfer(transfer_lp_cpi_ctx, lp_amount)?;
+
+    let pool = ctx.accounts.pool.load()?;
+    let market_key = pool.market;
+    let pool_type_bytes = (pool.pool_type as u8).to_le_bytes();
+    let pool_index_seed = get_pool_index_seed(pool.index);
+    let pool_bump = pool.bump;
+    drop(pool);
+    let seeds = pool_authority_seeds!(
+        market_key = market_key,
+        pool_type_bytes = pool_type_bytes,
+        index_seed = pool_index_seed,
+        bump = pool_bump
+    );
+
+    let instruction = spl_token_2022::instruction::mint_to(
+        &spl_token_2022::ID,
+        &ctx.accounts.wrapped_lp_mint.key(),
+        &ctx.accounts.signer_wrapped_lp.key(),
+        &ctx.accounts.pool.key(),
+        &[],
+        lp_amount,
+    )?;
+    invoke_signed(
+        &instruction,
+        &[
+            ctx.accounts.wrapped_lp_mint.clone(),
+            ctx.accounts.signer_wrapped_lp.clone(),
+            ctx.accounts.pool.to_account_info(),
+            ctx.accounts.token_2022_program.clone(),
+        ],
+        &[&seeds[..]],
+    )?;
+    Ok(())
+}
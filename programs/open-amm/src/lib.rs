@@ -8,6 +8,11 @@ pub mod state;
 
 use instructions::*;
 use state::*;
+#[cfg(test)]
+mod fuzz;
+pub(crate) mod curve;
+pub(crate) mod math;
+pub(crate) mod oracle;
 pub(crate) mod stableswap;
 pub(crate) mod util;
 
@@ -20,15 +25,60 @@ pub mod open_amm {
         pool_type: PoolType,
         initial_base_amount: u64,
         initial_quote_amount: u64,
+        ladder_spacing: LadderSpacing,
+        ladder_step_bps: u16,
+        index: u16,
     ) -> Result<()> {
         return instructions::create_pool::handler(
             ctx,
             pool_type,
             initial_base_amount,
             initial_quote_amount,
+            ladder_spacing,
+            ladder_step_bps,
+            index,
         );
     }
 
+    pub fn create_pool_registry<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreatePoolRegistry<'info>>,
+    ) -> Result<()> {
+        return instructions::create_pool_registry::handler(ctx);
+    }
+
+    pub fn register_pool<'info>(
+        ctx: Context<'_, '_, '_, 'info, RegisterPool<'info>>,
+    ) -> Result<()> {
+        return instructions::register_pool::handler(ctx);
+    }
+
+    pub fn get_pools_for_market<'info>(
+        ctx: Context<'_, '_, '_, 'info, GetPoolsForMarket<'info>>,
+    ) -> Result<Vec<PoolSummary>> {
+        return instructions::get_pools_for_market::handler(ctx);
+    }
+
+    pub fn derive_addresses(
+        ctx: Context<DeriveAddresses>,
+        market: Pubkey,
+        pool_type: PoolType,
+        index: u16,
+    ) -> Result<PoolAddresses> {
+        return instructions::derive_addresses::handler(ctx, market, pool_type, index);
+    }
+
+    pub fn get_open_orders_state<'info>(
+        ctx: Context<'_, '_, '_, 'info, GetOpenOrdersState<'info>>,
+    ) -> Result<OpenOrdersState> {
+        return instructions::get_open_orders_state::handler(ctx);
+    }
+
+    pub fn get_pool_stats<'info>(
+        ctx: Context<'_, '_, '_, 'info, GetPoolStats<'info>>,
+    ) -> Result<PoolStats> {
+        return instructions::get_pool_stats::handler(ctx);
+    }
+
     pub fn deposit<'info>(
         ctx: Context<'_, '_, '_, 'info, Deposit<'info>>,
         desired_base_amount: u64,
@@ -45,6 +95,120 @@ pub mod open_amm {
         );
     }
 
+    pub fn initialize_liquidity<'info>(
+        ctx: Context<'_, '_, '_, 'info, Deposit<'info>>,
+        base_amount: u64,
+        quote_amount: u64,
+    ) -> Result<()> {
+        return instructions::initialize_liquidity::handler(ctx, base_amount, quote_amount);
+    }
+
+    pub fn deposit_passive<'info>(
+        ctx: Context<'_, '_, '_, 'info, Deposit<'info>>,
+        desired_base_amount: u64,
+        desired_quote_amount: u64,
+        min_base_amount: u64,
+        min_quote_amount: u64,
+    ) -> Result<()> {
+        return instructions::deposit_passive::handler(
+            ctx,
+            desired_base_amount,
+            desired_quote_amount,
+            min_base_amount,
+            min_quote_amount,
+        );
+    }
+
+    pub fn deposit_migrated<'info>(
+        ctx: Context<'_, '_, '_, 'info, Deposit<'info>>,
+        base_balance_before: u64,
+        quote_balance_before: u64,
+        min_lp_amount: u64,
+    ) -> Result<()> {
+        return instructions::deposit_migrated::handler(
+            ctx,
+            base_balance_before,
+            quote_balance_before,
+            min_lp_amount,
+        );
+    }
+
+    pub fn create_deposit_intent<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateDepositIntent<'info>>,
+        relayer: Pubkey,
+        desired_base_amount: u64,
+        desired_quote_amount: u64,
+        min_base_amount: u64,
+        min_quote_amount: u64,
+        expiry_slot: u64,
+    ) -> Result<()> {
+        return instructions::create_deposit_intent::handler(
+            ctx,
+            relayer,
+            desired_base_amount,
+            desired_quote_amount,
+            min_base_amount,
+            min_quote_amount,
+            expiry_slot,
+        );
+    }
+
+    pub fn cancel_deposit_intent<'info>(
+        ctx: Context<'_, '_, '_, 'info, CancelDepositIntent<'info>>,
+    ) -> Result<()> {
+        return instructions::cancel_deposit_intent::handler(ctx);
+    }
+
+    pub fn deposit_delegated<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositDelegated<'info>>,
+    ) -> Result<()> {
+        return instructions::deposit_delegated::handler(ctx);
+    }
+
+    pub fn open_position<'info>(
+        ctx: Context<'_, '_, '_, 'info, OpenPosition<'info>>,
+    ) -> Result<()> {
+        return instructions::open_position::handler(ctx);
+    }
+
+    pub fn deposit_to_position<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositToPosition<'info>>,
+        desired_base_amount: u64,
+        desired_quote_amount: u64,
+        min_base_amount: u64,
+        min_quote_amount: u64,
+    ) -> Result<()> {
+        return instructions::deposit_to_position::handler(
+            ctx,
+            desired_base_amount,
+            desired_quote_amount,
+            min_base_amount,
+            min_quote_amount,
+        );
+    }
+
+    pub fn deposit_underlying<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositUnderlying<'info>>,
+        desired_base_amount: u64,
+        desired_underlying_base_amount: u64,
+        desired_underlying_quote_amount: u64,
+        min_base_amount: u64,
+        min_quote_amount: u64,
+        min_underlying_base_amount: u64,
+        min_underlying_quote_amount: u64,
+    ) -> Result<()> {
+        return instructions::deposit_underlying::handler(
+            ctx,
+            desired_base_amount,
+            desired_underlying_base_amount,
+            desired_underlying_quote_amount,
+            min_base_amount,
+            min_quote_amount,
+            min_underlying_base_amount,
+            min_underlying_quote_amount,
+        );
+    }
+
     pub fn withdraw<'info>(
         ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>,
         lp_amt: u64,
@@ -52,15 +216,485 @@ pub mod open_amm {
         return instructions::withdraw::handler(ctx, lp_amt);
     }
 
+    pub fn withdraw_all<'info>(ctx: Context<'_, '_, '_, 'info, WithdrawAll<'info>>) -> Result<()> {
+        return instructions::withdraw_all::handler(ctx);
+    }
+
+    pub fn withdraw_from_position<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawFromPosition<'info>>,
+        lp_amt: u64,
+    ) -> Result<()> {
+        return instructions::withdraw_from_position::handler(ctx, lp_amt);
+    }
+
+    pub fn withdraw_underlying<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawUnderlying<'info>>,
+        lp_amt: u64,
+    ) -> Result<()> {
+        return instructions::withdraw_underlying::handler(ctx, lp_amt);
+    }
+
+    pub fn migrate_liquidity<'info>(
+        ctx: Context<'_, '_, '_, 'info, MigrateLiquidity<'info>>,
+        lp_amt: u64,
+        min_base_amount: u64,
+        min_quote_amount: u64,
+    ) -> Result<()> {
+        return instructions::migrate_liquidity::handler(
+            ctx,
+            lp_amt,
+            min_base_amount,
+            min_quote_amount,
+        );
+    }
+
+    pub fn request_withdraw<'info>(
+        ctx: Context<'_, '_, '_, 'info, RequestWithdraw<'info>>,
+        lp_amount: u64,
+    ) -> Result<()> {
+        return instructions::request_withdraw::handler(ctx, lp_amount);
+    }
+
+    pub fn claim_withdraw<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimWithdraw<'info>>,
+    ) -> Result<()> {
+        return instructions::claim_withdraw::handler(ctx);
+    }
+
     pub fn refresh_orders<'info>(
         ctx: Context<'_, '_, '_, 'info, RefreshOrders<'info>>,
     ) -> Result<()> {
         return instructions::refresh_orders::handler(ctx);
     }
 
+    pub fn sync_reserves<'info>(
+        ctx: Context<'_, '_, '_, 'info, SyncReserves<'info>>,
+    ) -> Result<()> {
+        return instructions::sync_reserves::handler(ctx);
+    }
+
+    pub fn rebalance<'info>(
+        ctx: Context<'_, '_, '_, 'info, Rebalance<'info>>,
+        min_skew_bps: u16,
+        limit_price: u64,
+    ) -> Result<()> {
+        return instructions::rebalance::handler(ctx, min_skew_bps, limit_price);
+    }
+
+    pub fn register_secondary_market<'info>(
+        ctx: Context<'_, '_, '_, 'info, RegisterSecondaryMarket<'info>>,
+        share_bps: u16,
+    ) -> Result<()> {
+        return instructions::register_secondary_market::handler(ctx, share_bps);
+    }
+
+    pub fn register_underlying_pool<'info>(
+        ctx: Context<'_, '_, '_, 'info, RegisterUnderlyingPool<'info>>,
+    ) -> Result<()> {
+        return instructions::register_underlying_pool::handler(ctx);
+    }
+
+    pub fn refresh_secondary_orders<'info>(
+        ctx: Context<'_, '_, '_, 'info, RefreshSecondaryOrders<'info>>,
+    ) -> Result<()> {
+        return instructions::refresh_secondary_orders::handler(ctx);
+    }
+
+    pub fn rescue<'info>(
+        ctx: Context<'_, '_, '_, 'info, Rescue<'info>>,
+        consume_events_limit: u16,
+        pause: bool,
+    ) -> Result<()> {
+        return instructions::rescue::handler(ctx, consume_events_limit, pause);
+    }
+
     pub fn restart_market_making<'info>(
         ctx: Context<'_, '_, '_, 'info, RestartMarketMaking<'info>>,
     ) -> Result<()> {
         return instructions::restart_market_making::handler(ctx);
     }
+
+    pub fn preview_ladder<'info>(
+        ctx: Context<'_, '_, '_, 'info, PreviewLadder<'info>>,
+    ) -> Result<LadderPreview> {
+        return instructions::preview_ladder::handler(ctx);
+    }
+
+    pub fn simulate_refresh<'info>(
+        ctx: Context<'_, '_, '_, 'info, SimulateRefresh<'info>>,
+    ) -> Result<RefreshSimulation> {
+        return instructions::simulate_refresh::handler(ctx);
+    }
+
+    pub fn set_compute_budget<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetComputeBudget<'info>>,
+        compute_budget_units: u32,
+        base_compute_units: u32,
+        cancel_compute_units: u32,
+        place_compute_units: u32,
+    ) -> Result<()> {
+        return instructions::set_compute_budget::handler(
+            ctx,
+            compute_budget_units,
+            base_compute_units,
+            cancel_compute_units,
+            place_compute_units,
+        );
+    }
+
+    pub fn set_crossing_policy<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetCrossingPolicy<'info>>,
+        crossing_policy: CrossingPolicy,
+    ) -> Result<()> {
+        return instructions::set_crossing_policy::handler(ctx, crossing_policy);
+    }
+
+    pub fn set_authority<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetAuthority<'info>>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        return instructions::set_authority::handler(ctx, new_authority);
+    }
+
+    pub fn accept_authority<'info>(
+        ctx: Context<'_, '_, '_, 'info, AcceptAuthority<'info>>,
+    ) -> Result<()> {
+        return instructions::accept_authority::handler(ctx);
+    }
+
+    pub fn set_bootstrapper<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetBootstrapper<'info>>,
+        bootstrapper: Pubkey,
+    ) -> Result<()> {
+        return instructions::set_bootstrapper::handler(ctx, bootstrapper);
+    }
+
+    pub fn set_guardian<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetGuardian<'info>>,
+        guardian: Pubkey,
+    ) -> Result<()> {
+        return instructions::set_guardian::handler(ctx, guardian);
+    }
+
+    pub fn pause_pool<'info>(ctx: Context<'_, '_, '_, 'info, SafeMode<'info>>) -> Result<()> {
+        return instructions::pause_pool::handler(ctx);
+    }
+
+    pub fn resume_pool<'info>(ctx: Context<'_, '_, '_, 'info, ResumePool<'info>>) -> Result<()> {
+        return instructions::resume_pool::handler(ctx);
+    }
+
+    pub fn set_debug_logging<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetDebugLogging<'info>>,
+        debug_logging: bool,
+    ) -> Result<()> {
+        return instructions::set_debug_logging::handler(ctx, debug_logging);
+    }
+
+    pub fn set_drift_alarm<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetDriftAlarm<'info>>,
+        drift_alarm_bps: u16,
+        pause_on_drift: bool,
+    ) -> Result<()> {
+        return instructions::set_drift_alarm::handler(ctx, drift_alarm_bps, pause_on_drift);
+    }
+
+    pub fn set_deleverage<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetDeleverage<'info>>,
+        deleverage_velocity_bps: u64,
+        max_deleverage_bps: u16,
+    ) -> Result<()> {
+        return instructions::set_deleverage::handler(
+            ctx,
+            deleverage_velocity_bps,
+            max_deleverage_bps,
+        );
+    }
+
+    pub fn set_deposit_cap<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetDepositCap<'info>>,
+        max_deposit_lp_bps: u16,
+        deposit_cap_deviation_bps: u16,
+    ) -> Result<()> {
+        return instructions::set_deposit_cap::handler(
+            ctx,
+            max_deposit_lp_bps,
+            deposit_cap_deviation_bps,
+        );
+    }
+
+    pub fn set_depeg_fallback<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetDepegFallback<'info>>,
+        depeg_ratio_bps: u16,
+        crisis_amp: u64,
+    ) -> Result<()> {
+        return instructions::set_depeg_fallback::handler(ctx, depeg_ratio_bps, crisis_amp);
+    }
+
+    pub fn set_dust_thresholds<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetDustThresholds<'info>>,
+        base_dust_threshold: u64,
+        quote_dust_threshold: u64,
+    ) -> Result<()> {
+        return instructions::set_dust_thresholds::handler(
+            ctx,
+            base_dust_threshold,
+            quote_dust_threshold,
+        );
+    }
+
+    pub fn set_event_queue_limit<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetEventQueueLimit<'info>>,
+        max_event_queue_backlog: u64,
+    ) -> Result<()> {
+        return instructions::set_event_queue_limit::handler(ctx, max_event_queue_backlog);
+    }
+
+    pub fn set_jitter<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetJitter<'info>>,
+        jitter_bps: u16,
+    ) -> Result<()> {
+        return instructions::set_jitter::handler(ctx, jitter_bps);
+    }
+
+    pub fn set_keeper_refund<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetKeeperRefund<'info>>,
+        keeper_refund_bps: u16,
+    ) -> Result<()> {
+        return instructions::set_keeper_refund::handler(ctx, keeper_refund_bps);
+    }
+
+    pub fn set_rung_fees<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetRungFees<'info>>,
+        rung_fee_bps: [u16; MAX_ORDERS_PER_SIDE],
+    ) -> Result<()> {
+        return instructions::set_rung_fees::handler(ctx, rung_fee_bps);
+    }
+
+    pub fn set_max_quote_at_risk<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetMaxQuoteAtRisk<'info>>,
+        max_quote_at_risk: u64,
+    ) -> Result<()> {
+        return instructions::set_max_quote_at_risk::handler(ctx, max_quote_at_risk);
+    }
+
+    pub fn set_oracle<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetOracle<'info>>,
+        oracle: Pubkey,
+        band_bps: u16,
+        max_age_slots: u64,
+    ) -> Result<()> {
+        return instructions::set_oracle::handler(ctx, oracle, band_bps, max_age_slots);
+    }
+
+    pub fn set_quote_oracle<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetQuoteOracle<'info>>,
+        quote_oracle: Pubkey,
+        max_age_slots: u64,
+    ) -> Result<()> {
+        return instructions::set_quote_oracle::handler(ctx, quote_oracle, max_age_slots);
+    }
+
+    pub fn set_anchor_pool<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetAnchorPool<'info>>,
+        anchor_pool: Pubkey,
+        max_age_slots: u64,
+    ) -> Result<()> {
+        return instructions::set_anchor_pool::handler(ctx, anchor_pool, max_age_slots);
+    }
+
+    pub fn set_orders_per_side<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetOrdersPerSide<'info>>,
+        orders_per_side: u8,
+    ) -> Result<()> {
+        return instructions::set_orders_per_side::handler(ctx, orders_per_side);
+    }
+
+    pub fn migrate_pool<'info>(ctx: Context<'_, '_, '_, 'info, MigratePool<'info>>) -> Result<()> {
+        return instructions::migrate_pool::handler(ctx);
+    }
+
+    pub fn set_quoting_schedule<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetQuotingSchedule<'info>>,
+        quoting_open_secs: u32,
+        quoting_close_secs: u32,
+        cooldown_fill_bps: u16,
+        cooldown_slots: u64,
+    ) -> Result<()> {
+        return instructions::set_quoting_schedule::handler(
+            ctx,
+            quoting_open_secs,
+            quoting_close_secs,
+            cooldown_fill_bps,
+            cooldown_slots,
+        );
+    }
+
+    pub fn set_refresh_limits<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetRefreshLimits<'info>>,
+        keeper: Pubkey,
+        min_refresh_interval_slots: u64,
+        safe_mode_after_slots: u64,
+    ) -> Result<()> {
+        return instructions::set_refresh_limits::handler(
+            ctx,
+            keeper,
+            min_refresh_interval_slots,
+            safe_mode_after_slots,
+        );
+    }
+
+    pub fn set_volatility_spread<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetVolatilitySpread<'info>>,
+        volatility_fee_scale_bps: u16,
+        max_volatility_fee_bps: u16,
+    ) -> Result<()> {
+        return instructions::set_volatility_spread::handler(
+            ctx,
+            volatility_fee_scale_bps,
+            max_volatility_fee_bps,
+        );
+    }
+
+    pub fn set_withdraw_delay<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetWithdrawDelay<'info>>,
+        withdraw_delay_slots: u64,
+    ) -> Result<()> {
+        return instructions::set_withdraw_delay::handler(ctx, withdraw_delay_slots);
+    }
+
+    pub fn safe_mode<'info>(ctx: Context<'_, '_, '_, 'info, SafeMode<'info>>) -> Result<()> {
+        return instructions::safe_mode::handler(ctx);
+    }
+
+    pub fn create_keeper<'info>(ctx: Context<'_, '_, '_, 'info, CreateKeeper<'info>>) -> Result<()> {
+        return instructions::create_keeper::handler(ctx);
+    }
+
+    pub fn create_rung_stats<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateRungStats<'info>>,
+    ) -> Result<()> {
+        return instructions::create_rung_stats::handler(ctx);
+    }
+
+    pub fn register_keeper_pool<'info>(
+        ctx: Context<'_, '_, '_, 'info, RegisterKeeperPool<'info>>,
+    ) -> Result<()> {
+        return instructions::register_keeper_pool::handler(ctx);
+    }
+
+    pub fn claim_keeper_rewards<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimKeeperRewards<'info>>,
+    ) -> Result<()> {
+        return instructions::claim_keeper_rewards::handler(ctx);
+    }
+
+    pub fn check_pool_health<'info>(
+        ctx: Context<'_, '_, '_, 'info, CheckPoolHealth<'info>>,
+        max_stale_slots: u64,
+        max_drift_bps: u16,
+    ) -> Result<u32> {
+        return instructions::check_pool_health::handler(ctx, max_stale_slots, max_drift_bps);
+    }
+
+    pub fn get_tvl<'info>(ctx: Context<'_, '_, '_, 'info, GetTvl<'info>>) -> Result<u64> {
+        return instructions::get_tvl::handler(ctx);
+    }
+
+    pub fn get_lp_value<'info>(
+        ctx: Context<'_, '_, '_, 'info, GetLpValue<'info>>,
+        lp_amount: u64,
+    ) -> Result<LpValue> {
+        return instructions::get_lp_value::handler(ctx, lp_amount);
+    }
+
+    pub fn get_spot_price<'info>(
+        ctx: Context<'_, '_, '_, 'info, GetSpotPrice<'info>>,
+    ) -> Result<SpotPrice> {
+        return instructions::get_spot_price::handler(ctx);
+    }
+
+    pub fn init_wrapped_lp<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitWrappedLp<'info>>,
+    ) -> Result<()> {
+        return instructions::init_wrapped_lp::handler(ctx);
+    }
+
+    pub fn wrap_lp<'info>(
+        ctx: Context<'_, '_, '_, 'info, WrapLp<'info>>,
+        lp_amount: u64,
+    ) -> Result<()> {
+        return instructions::wrap_lp::handler(ctx, lp_amount);
+    }
+
+    pub fn unwrap_lp<'info>(
+        ctx: Context<'_, '_, '_, 'info, UnwrapLp<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        return instructions::unwrap_lp::handler(ctx, amount);
+    }
+
+    pub fn get_wrapped_lp_price<'info>(
+        ctx: Context<'_, '_, '_, 'info, GetWrappedLpPrice<'info>>,
+    ) -> Result<u64> {
+        return instructions::get_wrapped_lp_price::handler(ctx);
+    }
+
+    pub fn set_fee_router<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetFeeRouter<'info>>,
+        target: FeeRouterTarget,
+        interval_slots: u64,
+    ) -> Result<()> {
+        return instructions::set_fee_router::handler(ctx, target, interval_slots);
+    }
+
+    pub fn set_pool_metadata<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetPoolMetadata<'info>>,
+        name: [u8; 32],
+        uri: String,
+    ) -> Result<()> {
+        return instructions::set_pool_metadata::handler(ctx, name, uri);
+    }
+
+    pub fn process_fees<'info>(
+        ctx: Context<'_, '_, '_, 'info, ProcessFees<'info>>,
+    ) -> Result<()> {
+        return instructions::process_fees::handler(ctx);
+    }
+
+    pub fn propose_parameters<'info>(
+        ctx: Context<'_, '_, '_, 'info, ProposeParameters<'info>>,
+        parameter: PoolParameter,
+    ) -> Result<()> {
+        return instructions::propose_parameters::handler(ctx, parameter);
+    }
+
+    pub fn approve_proposal<'info>(
+        ctx: Context<'_, '_, '_, 'info, ApproveProposal<'info>>,
+    ) -> Result<()> {
+        return instructions::approve_proposal::handler(ctx);
+    }
+
+    pub fn reject_proposal<'info>(
+        ctx: Context<'_, '_, '_, 'info, RejectProposal<'info>>,
+    ) -> Result<()> {
+        return instructions::reject_proposal::handler(ctx);
+    }
+
+    pub fn create_snapshot<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateSnapshot<'info>>,
+        merkle_root: [u8; 32],
+        lp_supply: u64,
+        reward_amount: u64,
+    ) -> Result<()> {
+        return instructions::create_snapshot::handler(ctx, merkle_root, lp_supply, reward_amount);
+    }
+
+    pub fn claim_airdrop<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimAirdrop<'info>>,
+        lp_amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        return instructions::claim_airdrop::handler(ctx, lp_amount, proof);
+    }
 }
@@ -0,0 +1,49 @@
+use uint::construct_uint;
+
+construct_uint! {
+    /// 256-bit unsigned integer, wide enough for the cubed invariant terms of
+    /// the stableswap curve.
+    pub struct U256(4);
+}
+
+/// Floor of the square root of `n`. Share issuance goes through this rather
+/// than f64 so every validator mints exactly the same amount.
+pub fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    // Start from a power of two at or above the root so Newton's method
+    // decreases monotonically onto the floor.
+    let mut x = 1u128 << ((128 - n.leading_zeros() + 1) / 2);
+    let mut y = (x + n / x) / 2;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+#[cfg(test)]
+mod math_tests {
+    use super::*;
+
+    #[test]
+    fn isqrt_test() {
+        for n in 0..10_000u128 {
+            let root = isqrt(n);
+            assert!(root * root <= n && (root + 1) * (root + 1) > n);
+        }
+        for root in [1e9 as u128, u32::MAX.into(), u64::MAX.into()] {
+            assert_eq!(isqrt(root * root), root);
+            assert_eq!(isqrt(root * root - 1), root - 1);
+        }
+        assert_eq!(isqrt(u128::MAX), u64::MAX.into());
+    }
+
+    #[test]
+    /// The initial LP mint for a 1e9/1e9 pool, matching what the f64 version
+    /// minted.
+    fn initial_lp_test() {
+        assert_eq!(isqrt(1e18 as u128 - 1000), 999_999_999);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::math::U256;
+use crate::state::*;
+use anchor_lang::prelude::*;
+use serum_dex::matching::Side;
+use std::cmp;
+use std::convert::TryInto;
+
+// Pyth v2 price account layout
+const PYTH_MAGIC: u32 = 0xa1b2c3d4;
+const PYTH_PRICE_ACCOUNT_TYPE: u32 = 3;
+const PYTH_STATUS_TRADING: u32 = 1;
+const PYTH_MAGIC_OFFSET: usize = 0;
+const PYTH_ACCOUNT_TYPE_OFFSET: usize = 8;
+const PYTH_EXPO_OFFSET: usize = 20;
+const PYTH_AGG_PRICE_OFFSET: usize = 208;
+const PYTH_AGG_STATUS_OFFSET: usize = 224;
+const PYTH_AGG_PUB_SLOT_OFFSET: usize = 232;
+const PYTH_PRICE_ACCOUNT_MIN_LEN: usize = PYTH_AGG_PUB_SLOT_OFFSET + 8;
+
+pub const ORACLE_BAND_DENOMINATOR: u16 = 10_000;
+
+/// Decimals of the USD amounts quote values are normalized to.
+pub const USD_DECIMALS: u8 = 6;
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+/// Reads a Pyth price account's aggregate price and exponent. Returns `None`
+/// if the account isn't a price account, the price isn't trading or positive,
+/// or it was published more than `max_age_slots` before `slot`.
+fn read_pyth_price(oracle_info: &AccountInfo, max_age_slots: u64, slot: u64) -> Option<(u64, i32)> {
+    let data = oracle_info.try_borrow_data().ok()?;
+    if data.len() < PYTH_PRICE_ACCOUNT_MIN_LEN
+        || read_u32(&data, PYTH_MAGIC_OFFSET) != PYTH_MAGIC
+        || read_u32(&data, PYTH_ACCOUNT_TYPE_OFFSET) != PYTH_PRICE_ACCOUNT_TYPE
+        || read_u32(&data, PYTH_AGG_STATUS_OFFSET) != PYTH_STATUS_TRADING
+    {
+        return None;
+    }
+    let pub_slot = read_u64(&data, PYTH_AGG_PUB_SLOT_OFFSET);
+    if slot.saturating_sub(pub_slot) > max_age_slots {
+        return None;
+    }
+    let expo = read_u32(&data, PYTH_EXPO_OFFSET) as i32;
+    let price = read_u64(&data, PYTH_AGG_PRICE_OFFSET) as i64;
+    if price <= 0 {
+        return None;
+    }
+    Some((price as u64, expo))
+}
+
+/// Reads a Pyth price account and converts its aggregate price into a
+/// market's price units, quote lots per base lot. Returns `None` if the
+/// account isn't a price account, the price isn't trading, or it was
+/// published more than `pool.oracle_max_age_slots` ago.
+pub fn get_oracle_price(
+    pool: &OpenAmmPool,
+    oracle_info: &AccountInfo,
+    base_lot_size: u64,
+    quote_lot_size: u64,
+    slot: u64,
+) -> Option<u64> {
+    let (price, expo) = read_pyth_price(oracle_info, pool.oracle_max_age_slots, slot)?;
+
+    // price * 10^expo quote per base, scaled to native units and then lots
+    let mut numerator = (price as u128)
+        .checked_mul(base_lot_size.into())?
+        .checked_mul(10u128.checked_pow(pool.quote_decimals.into())?)?;
+    let mut denominator =
+        (quote_lot_size as u128).checked_mul(10u128.checked_pow(pool.base_decimals.into())?)?;
+    if expo < 0 {
+        denominator = denominator.checked_mul(10u128.checked_pow(expo.unsigned_abs())?)?;
+    } else {
+        numerator = numerator.checked_mul(10u128.checked_pow(expo as u32)?)?;
+    }
+    numerator.checked_div(denominator)?.try_into().ok()
+}
+
+/// Values `quote_amount` quote atoms in USD atoms of `USD_DECIMALS` with the
+/// pool's quote oracle, a Pyth USD price account for the quote token. Returns
+/// `None` under the same conditions as `get_oracle_price`, with the quote
+/// oracle's own max age.
+pub fn get_quote_usd_value(
+    pool: &OpenAmmPool,
+    quote_oracle_info: &AccountInfo,
+    quote_amount: u64,
+    slot: u64,
+) -> Option<u64> {
+    let (price, expo) = read_pyth_price(quote_oracle_info, pool.quote_oracle_max_age_slots, slot)?;
+
+    // quote_amount / 10^quote_decimals * price * 10^expo * 10^USD_DECIMALS
+    let mut numerator = (quote_amount as u128)
+        .checked_mul(price.into())?
+        .checked_mul(10u128.checked_pow(USD_DECIMALS.into())?)?;
+    let mut denominator = 10u128.checked_pow(pool.quote_decimals.into())?;
+    if expo < 0 {
+        denominator = denominator.checked_mul(10u128.checked_pow(expo.unsigned_abs())?)?;
+    } else {
+        numerator = numerator.checked_mul(10u128.checked_pow(expo as u32)?)?;
+    }
+    numerator.checked_div(denominator)?.try_into().ok()
+}
+
+/// Whether an order at `limit_price` stays on the right side of the oracle
+/// band: asks may not sell below it and bids may not buy above it. Orders
+/// further from fair value than the band in the pool's favour are fine.
+pub fn is_within_oracle_band(
+    oracle_price: u64,
+    band_bps: u16,
+    side: Side,
+    limit_price: u64,
+) -> bool {
+    let scaled_price = (limit_price as u128)
+        .checked_mul(ORACLE_BAND_DENOMINATOR.into())
+        .unwrap();
+    if side == Side::Ask {
+        let lower_bound = (oracle_price as u128)
+            .checked_mul(ORACLE_BAND_DENOMINATOR.saturating_sub(band_bps).into())
+            .unwrap();
+        scaled_price >= lower_bound
+    } else {
+        let upper_bound = (oracle_price as u128)
+            .checked_mul(
+                (ORACLE_BAND_DENOMINATOR as u32)
+                    .checked_add(band_bps.into())
+                    .unwrap()
+                    .into(),
+            )
+            .unwrap();
+        scaled_price <= upper_bound
+    }
+}
+
+/// Whether the price of `base_amount` and `quote_amount`, in quote lots per
+/// base lot on a market with the given lot sizes, is within `band_bps` of
+/// `oracle_price` either way.
+pub fn is_reserve_price_within_band(
+    oracle_price: u64,
+    band_bps: u16,
+    base_amount: u64,
+    quote_amount: u64,
+    base_lot_size: u64,
+    quote_lot_size: u64,
+) -> bool {
+    let reserve_value = U256::from(quote_amount) * U256::from(base_lot_size);
+    let oracle_value =
+        U256::from(oracle_price) * U256::from(base_amount) * U256::from(quote_lot_size);
+    let deviation = if reserve_value > oracle_value {
+        reserve_value - oracle_value
+    } else {
+        oracle_value - reserve_value
+    };
+    deviation * U256::from(ORACLE_BAND_DENOMINATOR) <= oracle_value * U256::from(band_bps)
+}
+
+/// Reads the mid of the openAMM pool `pool` is anchored to and converts it
+/// into `pool`'s native units, as a numerator and denominator of quote per
+/// base. Returns `None` if the account isn't a pool on the same base mint,
+/// its book had no mid at its last refresh, or that refresh was more than
+/// `pool.anchor_max_age_slots` ago.
+pub fn get_anchor_price(
+    pool: &OpenAmmPool,
+    anchor_info: &AccountInfo,
+    slot: u64,
+) -> Option<(u128, u128)> {
+    let anchor_loader = AccountLoader::<OpenAmmPool>::try_from(anchor_info).ok()?;
+    let anchor = anchor_loader.load().ok()?;
+    if anchor.base_mint != pool.base_mint
+        || anchor.mid_price == 0
+        || slot.saturating_sub(anchor.mid_price_last_slot) > pool.anchor_max_age_slots
+    {
+        return None;
+    }
+
+    // The anchor's quote lots per base lot, in its native units and then
+    // rescaled to this pool's quote decimals
+    let mut numerator = (anchor.mid_price as u128).checked_mul(anchor.quote_lot_size.into())?;
+    let mut denominator = anchor.base_lot_size as u128;
+    if pool.quote_decimals >= anchor.quote_decimals {
+        numerator = numerator.checked_mul(
+            10u128.checked_pow((pool.quote_decimals - anchor.quote_decimals).into())?,
+        )?;
+    } else {
+        denominator = denominator.checked_mul(
+            10u128.checked_pow((anchor.quote_decimals - pool.quote_decimals).into())?,
+        )?;
+    }
+    if denominator == 0 {
+        return None;
+    }
+    Some((numerator, denominator))
+}
+
+/// The most of `base_amount` and `quote_amount` that can be quoted together
+/// at `price`, a numerator and denominator of quote per base, so the ladder
+/// is centred on it without offering more of either side than the pool has.
+pub fn get_anchored_reserves(
+    base_amount: u64,
+    quote_amount: u64,
+    price: (u128, u128),
+) -> Option<(u64, u64)> {
+    let (numerator, denominator) = price;
+    if numerator == 0 {
+        return None;
+    }
+    let base_at_price = (quote_amount as u128)
+        .checked_mul(denominator)?
+        .checked_div(numerator)?;
+    let anchored_base_amount = cmp::min(base_amount as u128, base_at_price);
+    let anchored_quote_amount = anchored_base_amount
+        .checked_mul(numerator)?
+        .checked_div(denominator)?;
+    Some((
+        anchored_base_amount.try_into().ok()?,
+        anchored_quote_amount.try_into().ok()?,
+    ))
+}
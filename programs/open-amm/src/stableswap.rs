@@ -1,8 +1,13 @@
+use crate::math::U256;
+use std::cmp;
+
 // Max iters for Newton's method when calculating D
 pub const D_NM_MAX_ITERS: u64 = 8;
 // Max/expected iters for Newton's method when calculating
 pub const DY_NM_MAX_ITERS: u64 = 8;
 pub const DY_NM_EXP_ITERS: u64 = 4;
+// A Newton step smaller than this no longer changes the rounded result
+pub const NEWTON_CONVERGED_STEP: f64 = 0.5;
 
 pub const STABLESWAP_AMP_COEFFICIENT: u64 = 5;
 
@@ -23,12 +28,15 @@ pub const STABLESWAP_AMP_COEFFICIENT: u64 = 5;
 // pool). These starting values tend to be very good approximations and allows
 // us to do few iterations of NM until we get close to a root.
 //
-// This fails when the pool is highly imbalanced (x/y > ~1000), in which case
-// Newton's method can overshoot the real value of dy and reach the other root,
-// which might be negative. To fix this, we just need to make sure y never goes
-// below its original value (+1, so that we don't end up pricing things at 0).
-// When this happens, we also give Newton's method more iterations to converge,
-// since it's basically restarted at a worse approximation.
+// D still comes from Newton's method, but once D is fixed the invariant is a
+// quadratic in y (see below), so dy is solved exactly in 256-bit integers by
+// `calc_y`. Newton's method for dy is only kept as a fallback for when the
+// quadratic's terms don't fit in a U256. When the pool is highly imbalanced
+// (x/y > ~1000), Newton can overshoot the real value of dy and reach the other
+// root, which might be negative; so in the fallback y never goes below its
+// original value (+1, so that we don't end up pricing things at 0), and gets
+// more iterations to converge, since it's basically restarted at a worse
+// approximation.
 
 /// Calculate the value of D in the Stableswap invariant.
 /// Returns None in the case that D could not be calculated.
@@ -37,40 +45,133 @@ pub const STABLESWAP_AMP_COEFFICIENT: u64 = 5;
 /// that each X token should be equal in price to each Y token. Make sure to
 /// account for decimals BEFORE calling.
 pub fn calc_d(x: u64, y: u64, a: u64) -> Option<u64> {
+    let (d, _stats) = calc_d_with_stats(x, y, a);
+    #[cfg(feature = "newton-telemetry")]
+    log_marginal_convergence("calc_d", &_stats, D_NM_MAX_ITERS);
+    d
+}
+
+/// Same as `calc_d`, but also reports how Newton's method converged.
+pub fn calc_d_with_stats(x: u64, y: u64, a: u64) -> (Option<u64>, NewtonStats) {
     // calc_d(1000000000+20000000, 1000000000-10000000) -> 548 compute units (4 iters)
     let x = x as f64;
     let y = y as f64;
     let a = a as f64;
 
     let mut d = x + y;
-    for _ in 0..D_NM_MAX_ITERS {
+    let mut stats = NewtonStats {
+        iterations: D_NM_MAX_ITERS,
+        residual: 0.0,
+    };
+    for i in 0..D_NM_MAX_ITERS {
         let d2 = d * d;
         let f = 4.0 * a * (x + y - d) + d - d * d2 / (4.0 * x * y);
         let f_ = 1.0 - 4.0 * a - 3.0 * d2 / (4.0 * x * y);
         d = d - f / f_;
+        stats.residual = (f / f_).abs();
+        if stats.iterations == D_NM_MAX_ITERS && stats.residual < NEWTON_CONVERGED_STEP {
+            stats.iterations = i + 1;
+        }
     }
 
     if d > u64::MAX as f64 {
-        return None;
+        return (None, stats);
     }
     // let d = d.round();
-    Some((0.5 + d) as u64)
+    (Some((0.5 + d) as u64), stats)
 }
 
 /// Calculate the value of dy - the amount to deposit into y after withdrawing
 /// dx from x.
 /// Formally, ensure that the invariant holds for (x, y) -> (x-dx, y+dy).
-/// Returns None in the case that dy could not be calculated, which might happen
-/// if there isn't enough compute time available to converge to a good solution.
+/// Returns None in the case that dy could not be calculated.
 ///
 /// Note that this is the raw Stableswap calculation - it relies on the assumption
 /// that each X token should be equal in price to each Y token. Make sure to
 /// account for decimals BEFORE calling.
 pub fn calc_dy(x: u64, y: u64, a: u64, d: u64, dx: u64) -> Option<u64> {
-    // Note: calc_dy(1000000000+20000000, 1000000000-20000000, d, 20000000) -> 402 compute units (4 iters)
     if dx >= x {
         return None;
     }
+    if let Some(y_) = calc_y(x - dx, a, d) {
+        // Never price a withdrawal at 0
+        return Some(cmp::max(y_, y.checked_add(1)?) - y);
+    }
+
+    let (dy, _stats) = calc_dy_with_stats(x, y, a, d, dx);
+    #[cfg(feature = "newton-telemetry")]
+    log_marginal_convergence("calc_dy", &_stats, DY_NM_MAX_ITERS);
+    dy
+}
+
+/// Solve for y on the curve with invariant D at reserve x, exactly. Dividing
+/// the invariant's quadratic in y (see below) through by 4x leaves
+///   4A y^2 + (4Ax + D - 4AD) y - D^3/(4x) = 0 ,
+/// whose positive root is taken in U256, rounding up at every step so the pool
+/// never under-charges. Returns None if the terms don't fit.
+pub fn calc_y(x: u64, a: u64, d: u64) -> Option<u64> {
+    if x == 0 || a == 0 {
+        return None;
+    }
+    let x = U256::from(x);
+    let a4 = U256::from(a).checked_mul(4.into())?;
+    let d = U256::from(d);
+
+    // b = 4Ax + D - 4AD, which is negative whenever x < D - D/(4A)
+    let b_pos = a4.checked_mul(x)?.checked_add(d)?;
+    let b_neg = a4.checked_mul(d)?;
+    let d3 = d.checked_mul(d)?.checked_mul(d)?;
+    let c = ceil_div(d3, x.checked_mul(4.into())?)?;
+
+    let b_abs = if b_pos >= b_neg {
+        b_pos - b_neg
+    } else {
+        b_neg - b_pos
+    };
+    let disc = b_abs
+        .checked_mul(b_abs)?
+        .checked_add(a4.checked_mul(c)?.checked_mul(4.into())?)?;
+    let mut root = disc.integer_sqrt();
+    if root * root < disc {
+        root = root + 1;
+    }
+
+    let num = if b_pos >= b_neg {
+        root.checked_sub(b_abs)?
+    } else {
+        root.checked_add(b_abs)?
+    };
+    let y = ceil_div(num, a4.checked_mul(2.into())?)?;
+    if y > U256::from(u64::MAX) {
+        return None;
+    }
+    Some(y.as_u64())
+}
+
+fn ceil_div(n: U256, d: U256) -> Option<U256> {
+    if d.is_zero() {
+        return None;
+    }
+    let (q, r) = n.div_mod(d);
+    if r.is_zero() {
+        Some(q)
+    } else {
+        q.checked_add(1.into())
+    }
+}
+
+/// Newton's method fallback for `calc_dy`, which also reports how it converged.
+/// Returns None if there isn't enough compute time available to converge to a
+/// good solution.
+pub fn calc_dy_with_stats(x: u64, y: u64, a: u64, d: u64, dx: u64) -> (Option<u64>, NewtonStats) {
+    // Note: calc_dy(1000000000+20000000, 1000000000-20000000, d, 20000000) -> 402 compute units (4 iters)
+    let mut stats = NewtonStats {
+        iterations: 0,
+        residual: 0.0,
+    };
+    if dx >= x {
+        return (None, stats);
+    }
 
     let x = (x - dx) as f64;
     let a = a as f64;
@@ -89,6 +190,7 @@ pub fn calc_dy(x: u64, y: u64, a: u64, d: u64, dx: u64) -> Option<u64> {
         let f_ = 4.0 * a + d3 / (4.0 * x * y_ * y_);
         y_ = y_ - f / f_;
         last_move = f / f_;
+        stats.iterations = i + 1;
 
         // If y' goes below y, it'll take a little longer to converge
         if y_ < y_min {
@@ -97,21 +199,141 @@ pub fn calc_dy(x: u64, y: u64, a: u64, d: u64, dx: u64) -> Option<u64> {
         }
     }
 
+    stats.residual = last_move.abs();
+
     if last_move.abs() > 1.0 {
-        return None;
+        return (None, stats);
     }
     if y_ > u64::MAX as f64 {
-        return None;
+        return (None, stats);
     }
     // let dy = (y_ - y as f64).round() as u64;
     let dy = (0.5 + y_ - y as f64) as u64;
+    (Some(dy), stats)
+}
+
+/// How a Newton's method solve went: the number of iterations it took for the
+/// step to fall under `NEWTON_CONVERGED_STEP` (or every iteration it ran), and
+/// the size of the final step, in token units.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NewtonStats {
+    pub iterations: u64,
+    pub residual: f64,
+}
+
+impl NewtonStats {
+    /// Convergence is marginal when the solver used its whole iteration budget,
+    /// or its last step was still big enough to move the rounded result.
+    #[cfg(any(test, feature = "newton-telemetry"))]
+    pub fn is_marginal(&self, max_iters: u64) -> bool {
+        self.iterations >= max_iters || self.residual >= NEWTON_CONVERGED_STEP
+    }
+}
+
+#[cfg(feature = "newton-telemetry")]
+fn log_marginal_convergence(solver: &str, stats: &NewtonStats, max_iters: u64) {
+    if stats.is_marginal(max_iters) {
+        solana_program::msg!(
+            "{} converged marginally: {} iters, residual {}",
+            solver,
+            stats.iterations,
+            stats.residual
+        );
+    }
+}
+
+// Once D is fixed, multiplying the invariant through by 4xy leaves a quadratic
+// in y:
+//   16Ax y^2 + (16Ax^2 + 4Dx - 16ADx) y - D^3 = 0 ,
+// so y can be found directly for any x. Differentiating the invariant also gives
+// the marginal price of x in terms of y:
+//   p(x, y) = (4A + D^3/(4x^2y)) / (4A + D^3/(4xy^2)) ,
+// which grows monotonically as x is drained from the pool. That lets us bisect
+// on x to find the reserve at which the pool quotes a given price.
+
+// Iters of bisection when searching for the reserve at a target price
+pub const PRICE_BISECTION_ITERS: u64 = 32;
+
+fn calc_y_f64(x: f64, a: f64, d: f64) -> f64 {
+    let qa = 16.0 * a * x;
+    let qb = 16.0 * a * x * x + 4.0 * d * x - 16.0 * a * d * x;
+    let d3 = d * d * d;
+    (-qb + (qb * qb + 4.0 * qa * d3).sqrt()) / (2.0 * qa)
+}
+
+fn marginal_price_f64(x: f64, y: f64, a: f64, d: f64) -> f64 {
+    let d3 = d * d * d;
+    (4.0 * a + d3 / (4.0 * x * x * y)) / (4.0 * a + d3 / (4.0 * x * y * y))
+}
+
+/// Calculate the reserve of x at which the marginal price of x (in terms of y)
+/// is `price_ratio` times the marginal price at (x, y).
+/// Returns None in the case that the price can't be reached on the curve.
+///
+/// Note that this is the raw Stableswap calculation - make sure to account for
+/// decimals BEFORE calling.
+pub fn calc_x_at_price_ratio(x: u64, y: u64, a: u64, d: u64, price_ratio: f64) -> Option<u64> {
+    if x == 0 || y == 0 || price_ratio < 1.0 {
+        return None;
+    }
+
+    let a = a as f64;
+    let d = d as f64;
+    let target = marginal_price_f64(x as f64, y as f64, a, d) * price_ratio;
+
+    let mut lo = 1.0;
+    let mut hi = x as f64;
+    for _ in 0..PRICE_BISECTION_ITERS {
+        let mid = (lo + hi) / 2.0;
+        let price = marginal_price_f64(mid, calc_y_f64(mid, a, d), a, d);
+        if price > target {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+
+    if !hi.is_finite() {
+        return None;
+    }
+    Some(hi as u64)
+}
+
+/// Calculate the marginal price of x in terms of y at (x, y), i.e. -dy/dx.
+///
+/// Note that this is the raw Stableswap calculation - make sure to account for
+/// decimals BEFORE calling.
+pub fn calc_marginal_price(x: u64, y: u64, a: u64, d: u64) -> f64 {
+    marginal_price_f64(x as f64, y as f64, a as f64, d as f64)
+}
+
+/// Calculate the amount of y withdrawn when depositing dx into x.
+/// Formally, ensure that the invariant holds for (x, y) -> (x+dx, y-dy).
+/// Returns None in the case that dy could not be calculated.
+///
+/// Note that this is the raw Stableswap calculation - make sure to account for
+/// decimals BEFORE calling.
+pub fn calc_swap_out(x: u64, y: u64, a: u64, d: u64, dx: u64) -> Option<u64> {
+    let a = a as f64;
+    let d = d as f64;
+
+    // Measure both ends on the curve so rounding in D can't pay out extra
+    let y_0 = calc_y_f64(x as f64, a, d);
+    let y_1 = calc_y_f64(x.checked_add(dx)? as f64, a, d);
+    if !y_0.is_finite() || !y_1.is_finite() || y_1 > y_0 {
+        return None;
+    }
+    // Round down so the pool never pays out more than the curve allows
+    let dy = (y_0 - y_1) as u64;
+    if dy >= y {
+        return None;
+    }
     Some(dy)
 }
 
 #[cfg(test)]
 mod stableswap_tests {
     use super::*;
-    use std::cmp;
     use std::fmt;
 
     #[derive(Debug, Clone)]
@@ -529,6 +751,105 @@ mod stableswap_tests {
         println!("{}", dy / withdrawal as u64);
     }
 
+    #[test]
+    /// Make sure the reserve found for a price ratio actually quotes that price,
+    /// and that it moves further along the curve as the ratio grows.
+    fn price_ratio_test() {
+        let (x, y, a) = (1e9 as u64, 1e9 as u64, STABLESWAP_AMP_COEFFICIENT);
+        let d = calc_d(x, y, a).unwrap();
+
+        let mut last_x = x;
+        for ratio in [1.0025, 1.005, 1.01, 1.05] {
+            let x_ = calc_x_at_price_ratio(x, y, a, d, ratio).unwrap();
+            assert!(x_ < last_x);
+
+            let y_ = calc_y_f64(x_ as f64, a as f64, d as f64);
+            let price = marginal_price_f64(x_ as f64, y_, a as f64, d as f64);
+            assert!((price / ratio - 1.0).abs() < 1e-4);
+            last_x = x_;
+        }
+    }
+
+    #[test]
+    fn swap_out_test() {
+        let (x, y, a) = (1e9 as u64, 1e9 as u64, STABLESWAP_AMP_COEFFICIENT);
+        let d = calc_d(x, y, a).unwrap();
+
+        // Near balance the curve pays out almost 1:1, never more.
+        let dy = calc_swap_out(x, y, a, d, 1e6 as u64).unwrap();
+        assert!(dy <= 1e6 as u64);
+        assert!(dy > 999_000);
+
+        // Draining the other side entirely is impossible.
+        assert!(calc_swap_out(x, y, a, d, 1e12 as u64).unwrap() < y);
+    }
+
+    #[test]
+    fn closed_form_dy_test() {
+        let a = STABLESWAP_AMP_COEFFICIENT;
+
+        // Agrees with Newton's method to within rounding where Newton converges.
+        let (x, y) = (1e9 as u64 + 2e7 as u64, 1e9 as u64 - 2e7 as u64);
+        let d = calc_d(x, y, a).unwrap();
+        let exact = calc_dy(x, y, a, d, 2e7 as u64).unwrap();
+        let (newton, _) = calc_dy_with_stats(x, y, a, d, 2e7 as u64);
+        assert!(exact.abs_diff(newton.unwrap()) <= 1);
+
+        // Still prices badly imbalanced pools, where Newton used to give up.
+        let (x, y) = (1e12 as u64, 1e6 as u64);
+        let d = calc_d(x, y, a).unwrap();
+        let dy = calc_dy(x, y, a, d, 1e11 as u64).unwrap();
+        assert!(dy > 0);
+        assert!(calc_y(x - 1e11 as u64, a, d).unwrap() >= y);
+    }
+
+    #[test]
+    fn imbalance_fee_test() {
+        let supply = 1e9 as u64;
+        let (x, y) = (1e9 as u64, 1e9 as u64);
+
+        // An on-ratio deposit pays no imbalance fee.
+        let (dx, dy) = (1e6 as u64, 1e6 as u64);
+        let balanced = calculate_stableswap_lp_minted(supply, x, y, dx, dy, 6, 6, 4);
+        assert!(balanced.abs_diff(1e6 as u64) <= 1);
+
+        // A one-sided deposit is partly a swap, and pays for it.
+        let free = calculate_stableswap_lp_minted(supply, x, y, 2e6 as u64, 0, 6, 6, 0);
+        let charged = calculate_stableswap_lp_minted(supply, x, y, 2e6 as u64, 0, 6, 6, 4);
+        assert!(charged < free);
+
+        // Taking the same side straight back out costs more LP than it minted.
+        let burned = calculate_stableswap_lp_burned(
+            supply + charged,
+            x + 2e6 as u64,
+            y,
+            2e6 as u64,
+            0,
+            6,
+            6,
+            4,
+        );
+        assert!(burned > charged);
+    }
+
+    #[test]
+    fn newton_stats_test() {
+        let a = STABLESWAP_AMP_COEFFICIENT;
+
+        // A balanced pool converges well inside the iteration budget.
+        let (d, stats) = calc_d_with_stats(1e9 as u64, 1e9 as u64, a);
+        assert!(!stats.is_marginal(D_NM_MAX_ITERS));
+        let (dy, stats) = calc_dy_with_stats(1e9 as u64, 1e9 as u64, a, d.unwrap(), 1e6 as u64);
+        assert!(dy.is_some());
+        assert!(!stats.is_marginal(DY_NM_MAX_ITERS));
+
+        // A badly imbalanced pool restarts dy from its floor and burns the budget.
+        let (x, y) = (1e12 as u64, 1e6 as u64);
+        let d = calc_d(x, y, a).unwrap();
+        let (_, stats) = calc_dy_with_stats(x, y, a, d, 1e11 as u64);
+        assert!(stats.is_marginal(DY_NM_MAX_ITERS));
+    }
+
     /// Test the ppUSDC-USDC exploit. Brute-forces many sequences of random swaps
     /// in order to see if it's possible to reduce the pool's equilibrium value.
     #[test]
@@ -624,6 +945,10 @@ fn normalize_decimals(
     )
 }
 
+/// Mints LP for a deposit in proportion to the growth in D. A deposit off the
+/// reserves' ratio is partly a swap, so like Curve, the part of each side that
+/// strays from where D's growth would put it pays half the swap fee before D
+/// is measured, rather than getting full credit for it.
 pub fn calculate_stableswap_lp_minted(
     lp_mint_supply: u64,
     reserve_base_amount: u64,
@@ -632,6 +957,7 @@ pub fn calculate_stableswap_lp_minted(
     deposit_quote_amount: u64,
     base_decimals: u8,
     quote_decimals: u8,
+    fee_bps: u16,
 ) -> u64 {
     let (norm_reserve_base, norm_reserve_quote) = normalize_decimals(
         reserve_base_amount,
@@ -645,28 +971,129 @@ pub fn calculate_stableswap_lp_minted(
         deposit_quote_amount,
         quote_decimals,
     );
+    let new_base = norm_reserve_base.checked_add(norm_deposit_base).unwrap();
+    let new_quote = norm_reserve_quote.checked_add(norm_deposit_quote).unwrap();
 
+    let d_1 = calc_d(new_base, new_quote, STABLESWAP_AMP_COEFFICIENT).unwrap();
+    if lp_mint_supply == 0 {
+        return d_1;
+    }
     let d_0 = calc_d(
         norm_reserve_base,
         norm_reserve_quote,
         STABLESWAP_AMP_COEFFICIENT,
     )
     .unwrap();
-    let d_1 = calc_d(
-        norm_reserve_base.checked_add(norm_deposit_base).unwrap(),
-        norm_reserve_quote.checked_add(norm_deposit_quote).unwrap(),
+    let d_2 = calc_imbalance_adjusted_d(
+        (norm_reserve_base, norm_reserve_quote),
+        (new_base, new_quote),
+        d_0,
+        d_1,
+        fee_bps,
+    );
+
+    (lp_mint_supply as u128)
+        .checked_mul(d_2.saturating_sub(d_0).into())
+        .unwrap()
+        .checked_div(d_0.into())
+        .unwrap()
+        .try_into()
+        .unwrap()
+}
+
+/// The mirror of `calculate_stableswap_lp_minted`: the LP to burn for taking
+/// `withdraw_base_amount`/`withdraw_quote_amount` out of the reserves. An
+/// off-ratio withdrawal pays the same imbalance fee, and the burn rounds up.
+// Every withdrawal pays out pro rata today, so nothing takes this path yet.
+#[allow(dead_code)]
+pub fn calculate_stableswap_lp_burned(
+    lp_mint_supply: u64,
+    reserve_base_amount: u64,
+    reserve_quote_amount: u64,
+    withdraw_base_amount: u64,
+    withdraw_quote_amount: u64,
+    base_decimals: u8,
+    quote_decimals: u8,
+    fee_bps: u16,
+) -> u64 {
+    let (norm_reserve_base, norm_reserve_quote) = normalize_decimals(
+        reserve_base_amount,
+        base_decimals,
+        reserve_quote_amount,
+        quote_decimals,
+    );
+    let (norm_withdraw_base, norm_withdraw_quote) = normalize_decimals(
+        withdraw_base_amount,
+        base_decimals,
+        withdraw_quote_amount,
+        quote_decimals,
+    );
+    let new_base = norm_reserve_base.checked_sub(norm_withdraw_base).unwrap();
+    let new_quote = norm_reserve_quote.checked_sub(norm_withdraw_quote).unwrap();
+
+    let d_0 = calc_d(
+        norm_reserve_base,
+        norm_reserve_quote,
         STABLESWAP_AMP_COEFFICIENT,
     )
     .unwrap();
+    let d_1 = calc_d(new_base, new_quote, STABLESWAP_AMP_COEFFICIENT).unwrap();
+    let d_2 = calc_imbalance_adjusted_d(
+        (norm_reserve_base, norm_reserve_quote),
+        (new_base, new_quote),
+        d_0,
+        d_1,
+        fee_bps,
+    );
+
+    let burned = (lp_mint_supply as u128)
+        .checked_mul(d_0.saturating_sub(d_2).into())
+        .unwrap();
+    burned
+        .checked_add(d_0.checked_sub(1).unwrap().into())
+        .unwrap()
+        .checked_div(d_0.into())
+        .unwrap()
+        .try_into()
+        .unwrap()
+}
 
-    match lp_mint_supply {
-        0 => d_1,
-        lp_mint_supply => (lp_mint_supply as u128)
-            .checked_mul(d_1.checked_sub(d_0).unwrap().into())
+// A two-coin pool charges its off-ratio liquidity fee * n / (4(n - 1)), i.e.
+// half the swap fee.
+const IMBALANCE_FEE_DENOMINATOR: u128 = 20_000;
+
+/// D of the reserves moved from `old` to `new`, after each side pays the
+/// imbalance fee on how far it ended up from `old` scaled by `d_1 / d_0`.
+fn calc_imbalance_adjusted_d(
+    old: (u64, u64),
+    new: (u64, u64),
+    d_0: u64,
+    d_1: u64,
+    fee_bps: u16,
+) -> u64 {
+    let charge_fee = |old: u64, new: u64| -> u64 {
+        let ideal: u64 = (old as u128)
+            .checked_mul(d_1.into())
             .unwrap()
             .checked_div(d_0.into())
             .unwrap()
             .try_into()
-            .unwrap(),
-    }
+            .unwrap();
+        let fee: u64 = (new.abs_diff(ideal) as u128)
+            .checked_mul(fee_bps.into())
+            .unwrap()
+            .checked_add(IMBALANCE_FEE_DENOMINATOR - 1)
+            .unwrap()
+            .checked_div(IMBALANCE_FEE_DENOMINATOR)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        new.saturating_sub(fee)
+    };
+    calc_d(
+        charge_fee(old.0, new.0),
+        charge_fee(old.1, new.1),
+        STABLESWAP_AMP_COEFFICIENT,
+    )
+    .unwrap()
 }
@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+
+/// A deposit an owner signed off on for `deposit_delegated` to execute: who
+/// may relay it, how much to pull from their approved token accounts, and
+/// the least of each that must go in. Used once, and only up to
+/// `expiry_slot`.
+#[account]
+#[derive(Default)]
+pub struct DepositIntent {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub relayer: Pubkey,
+    /// Gets the intent's rent back when it's used or cancelled, so a relayer
+    /// can sponsor it.
+    pub rent_payer: Pubkey,
+    pub desired_base_amount: u64,
+    pub desired_quote_amount: u64,
+    pub min_base_amount: u64,
+    pub min_quote_amount: u64,
+    pub expiry_slot: u64,
+    pub bump: u8,
+}
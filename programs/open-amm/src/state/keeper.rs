@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+pub const MAX_KEEPER_POOLS: usize = 16;
+
+/// An operator's registry of the pools they crank. Refreshes that pass the
+/// operator's keeper account accrue their payout here per pool instead of
+/// transferring it, so it can all be claimed at once.
+#[account]
+#[derive(Default)]
+pub struct Keeper {
+    pub operator: Pubkey,
+    pub pool_count: u8,
+    pub pools: [Pubkey; MAX_KEEPER_POOLS],
+    pub owed_base: [u64; MAX_KEEPER_POOLS],
+    pub owed_quote: [u64; MAX_KEEPER_POOLS],
+    pub bump: u8,
+}
+
+impl Keeper {
+    pub fn pool_index(&self, pool: &Pubkey) -> Option<usize> {
+        self.pools[..self.pool_count as usize]
+            .iter()
+            .position(|p| p == pool)
+    }
+}
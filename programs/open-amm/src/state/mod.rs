@@ -1,5 +1,21 @@
+mod deposit_intent;
+mod keeper;
 mod market_accounts;
 mod openamm_pool;
+mod parameter_proposal;
+mod pool_registry;
+mod position;
+mod rung_stats;
+mod snapshot;
+mod withdraw_ticket;
 
+pub use deposit_intent::*;
+pub use keeper::*;
 pub use market_accounts::*;
 pub use openamm_pool::*;
+pub use parameter_proposal::*;
+pub use pool_registry::*;
+pub use position::*;
+pub use rung_stats::*;
+pub use snapshot::*;
+pub use withdraw_ticket::*;
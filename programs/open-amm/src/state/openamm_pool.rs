@@ -1,5 +1,6 @@
 use anchor_lang::prelude::*;
 use num_derive::{FromPrimitive, ToPrimitive};
+use std::cmp;
 
 #[derive(AnchorSerialize, Default, AnchorDeserialize, Copy, Clone, FromPrimitive, ToPrimitive)]
 pub enum PoolType {
@@ -8,6 +9,54 @@ pub enum PoolType {
     STABLE = 1,
 }
 
+#[derive(AnchorSerialize, Default, AnchorDeserialize, Copy, Clone, FromPrimitive, ToPrimitive)]
+pub enum LadderSpacing {
+    #[default]
+    LINEAR = 0,
+    GEOMETRIC = 1,
+}
+
+#[derive(AnchorSerialize, Default, AnchorDeserialize, Copy, Clone, FromPrimitive, ToPrimitive)]
+pub enum FeeRouterTarget {
+    #[default]
+    DISABLED = 0,
+    BASE = 1,
+    QUOTE = 2,
+}
+
+/// Weight a new mid move gets in `OpenAmmPool::volatility_bps`, in bps.
+pub const VOLATILITY_EWMA_WEIGHT_BPS: u64 = 2_000;
+pub const VOLATILITY_DENOMINATOR: u64 = 10_000;
+
+pub const DEPEG_RATIO_DENOMINATOR: u16 = 10_000;
+
+/// Slots fill velocity is measured over, about a minute.
+pub const FILL_VELOCITY_SLOTS: u64 = 150;
+/// Weight a new reading gets in `OpenAmmPool::fill_velocity_bps`, in bps.
+pub const FILL_VELOCITY_EWMA_WEIGHT_BPS: u64 = 2_000;
+pub const UTILIZATION_DENOMINATOR: u64 = 10_000;
+pub const DELEVERAGE_DENOMINATOR: u16 = 10_000;
+pub const COOLDOWN_FILL_DENOMINATOR: u16 = 10_000;
+pub const SECONDS_PER_DAY: u32 = 86_400;
+pub const DEPOSIT_CAP_DENOMINATOR: u16 = 10_000;
+/// Longest withdraw delay a pool can set, about a day of slots, so LPs can't
+/// be locked in indefinitely.
+pub const MAX_WITHDRAW_DELAY_SLOTS: u64 = 216_000;
+/// Most compute units a transaction can request.
+pub const MAX_COMPUTE_UNITS: u32 = 1_400_000;
+
+/// What a rung does when its curve price would cross the top of the book.
+#[derive(AnchorSerialize, Default, AnchorDeserialize, Copy, Clone, FromPrimitive, ToPrimitive)]
+pub enum CrossingPolicy {
+    /// Move the rung to one tick inside the touch.
+    #[default]
+    REPRICE = 0,
+    /// Leave the rung unquoted.
+    SKIP = 1,
+    /// Take resting liquidity up to the rung's own price with an IOC order.
+    TAKE = 2,
+}
+
 #[zero_copy]
 #[derive(Default)]
 pub struct PlacedOrder {
@@ -15,6 +64,87 @@ pub struct PlacedOrder {
     pub base_qty: u64,
     pub max_native_quote_qty_including_fees: u64,
     pub client_order_id: u64,
+    /// Spread the order was priced with, in bps: the curve, volatility and
+    /// rung fees at placement. Fills are split with this rather than the
+    /// pool's current fees.
+    pub fee_bps: u64,
+}
+
+/// Reserves a ladder was quoted from, and the slot it was placed in.
+#[zero_copy]
+#[derive(Default)]
+pub struct ReserveCheckpoint {
+    pub base_amount: u64,
+    pub quote_amount: u64,
+    pub slot: u64,
+}
+
+/// How many times an instruction has run on a pool, and who ran it last.
+#[zero_copy]
+#[derive(Default)]
+pub struct AccessLog {
+    pub count: u64,
+    pub last_caller: Pubkey,
+    pub last_slot: u64,
+}
+
+impl AccessLog {
+    pub fn record(&mut self, calls: u64, caller: Pubkey, slot: u64) {
+        self.count = self.count.checked_add(calls).unwrap();
+        self.last_caller = caller;
+        self.last_slot = slot;
+    }
+}
+
+/// One UTC day of a pool's activity, in quote terms.
+#[zero_copy]
+#[derive(Default)]
+pub struct DailyStats {
+    /// Days since the Unix epoch the bucket covers.
+    pub day: u64,
+    /// Quote notional of the fills reconciled that day.
+    pub volume_quote_amount: u64,
+    /// LP fees earned that day, with fees paid in base valued at the pool's
+    /// price when they were reconciled.
+    pub lp_fee_quote_amount: u64,
+    /// Reserves valued in quote at the day's last primary ladder placement.
+    pub tvl_quote_amount: u64,
+}
+
+/// Days of `DailyStats` a pool keeps.
+pub const STATS_DAYS: usize = 7;
+pub const FEE_APR_DENOMINATOR: u16 = 10_000;
+
+/// Refreshes LP mints are priced over.
+pub const RESERVE_CHECKPOINTS: usize = 8;
+
+/// Most rungs a pool can quote on each side of the book.
+pub const MAX_ORDERS_PER_SIDE: usize = 16;
+
+/// Rungs per side pools have always quoted, and the size of the fixed placed
+/// order arrays pools were created with before `PlacedOrders`.
+pub const LEGACY_ORDERS_PER_SIDE: usize = 10;
+
+/// One side's placed orders, indexed by rung. Only the first `len` entries
+/// are in use.
+#[zero_copy]
+#[derive(Default)]
+pub struct PlacedOrders {
+    pub len: u64,
+    pub orders: [PlacedOrder; MAX_ORDERS_PER_SIDE],
+}
+
+impl PlacedOrders {
+    pub fn as_slice(&self) -> &[PlacedOrder] {
+        &self.orders[..self.len as usize]
+    }
+
+    pub fn set(&mut self, rung: usize, order: PlacedOrder) {
+        self.orders[rung] = order;
+        if rung as u64 >= self.len {
+            self.len = rung as u64 + 1;
+        }
+    }
 }
 
 #[account(zero_copy)]
@@ -32,19 +162,626 @@ pub struct OpenAmmPool {
     pub base_vault: Pubkey,
     pub quote_vault: Pubkey,
     pub lp_mint: Pubkey,
+    pub authority: Pubkey,
     pub client_order_id: u64,
     pub pool_type: PoolType,
     pub base_decimals: u8,
     pub quote_decimals: u8,
     pub bump: u8,
-    pub placed_asks: [PlacedOrder; 10],
-    pub placed_bids: [PlacedOrder; 10],
+    pub placed_asks: PlacedOrders,
+    pub placed_bids: PlacedOrders,
+    pub last_refresh_slot: u64,
+    pub fee_distribution: Pubkey,
+    pub fee_process_interval_slots: u64,
+    pub last_fee_process_slot: u64,
+    pub secondary_market: Pubkey,
+    pub secondary_open_orders: Pubkey,
+    pub secondary_client_order_id: u64,
+    pub secondary_placed_asks: PlacedOrders,
+    pub secondary_placed_bids: PlacedOrders,
+    pub keeper: Pubkey,
+    pub min_refresh_interval_slots: u64,
+    pub safe_mode_after_slots: u64,
+    pub keeper_owed_base_amount: u64,
+    pub keeper_owed_quote_amount: u64,
+    /// Base and quote left in the open orders account after the settle that
+    /// followed an eviction, and the vault balances (plus keeper payouts owed)
+    /// at that point. Restarting needs the vaults to have grown by the exposure.
+    pub eviction_base_exposure: u64,
+    pub eviction_quote_exposure: u64,
+    pub eviction_base_baseline: u64,
+    pub eviction_quote_baseline: u64,
+    /// Open orders residue a restart tolerates, and the residue the last
+    /// restart left behind. Stranded amounts are excluded from reserves.
+    pub base_dust_threshold: u64,
+    pub quote_dust_threshold: u64,
+    pub stranded_base_amount: u64,
+    pub stranded_quote_amount: u64,
+    /// The primary market's lot sizes, which Serum never changes after the
+    /// market is created. Prices are whole quote lots per base lot, so these
+    /// also fix the price tick.
+    pub base_lot_size: u64,
+    pub quote_lot_size: u64,
+    pub name: [u8; 32],
+    pub uri: [u8; 128],
     pub mm_active: bool,
+    pub ladder_spacing: LadderSpacing,
+    pub ladder_step_bps: u16,
+    pub debug_logging: bool,
+    pub fee_router_target: FeeRouterTarget,
+    pub secondary_share_bps: u16,
+    pub drift_alarm_bps: u16,
+    pub pause_on_drift: bool,
+    pub in_progress: bool,
+    pub safe_mode: bool,
+    pub orders_per_side: u8,
+    /// Rungs left off the last ladder placed because the open orders account
+    /// ran out of slots or they didn't fit in the compute budget.
+    pub skipped_rungs: u8,
+    /// Rungs of the last ladder placed left off because their post-only
+    /// order would have crossed the book.
+    pub crossed_rungs: u8,
+    pub crossing_policy: CrossingPolicy,
+    /// Top of the primary market's book as of the last refresh, in quote lots
+    /// per base lot, ignoring the pool's own orders. Zero when a side is
+    /// empty, and the mid is zero unless both sides have orders.
+    pub best_bid_price: u64,
+    pub best_ask_price: u64,
+    pub mid_price: u64,
+    /// Sum of `mid_price` times the slots it was in effect for. Wraps, so a
+    /// TWAP is the difference between two readings over the slots between
+    /// them.
+    pub mid_price_cumulative: u64,
+    pub mid_price_last_slot: u64,
+    /// Pyth price account rung prices are checked against, or the default
+    /// key if there is none. Asks below the band around its price and bids
+    /// above it aren't placed.
+    pub oracle: Pubkey,
+    pub oracle_band_bps: u16,
+    pub oracle_max_age_slots: u64,
+    /// Most quote notional the pool's resting orders may add up to across
+    /// both markets, or 0 for no limit.
+    pub max_quote_at_risk: u64,
+    /// Largest size trim applied to each order by jitter, in bps. Jitter also
+    /// moves prices up to a tick away from the touch. 0 disables it.
+    pub jitter_bps: u16,
+    /// EWMA of the mid's move between refreshes, in bps of the mid.
+    pub volatility_bps: u64,
+    /// Ladder fee added per bps of `volatility_bps`, in bps, and the most it
+    /// may add. A zero scale disables volatility spreads.
+    pub volatility_fee_scale_bps: u16,
+    pub max_volatility_fee_bps: u16,
+    /// Ratio of a stable pool's larger normalized reserve to its smaller one,
+    /// in bps, past which its ladder stops quoting near 1:1. 0 disables it.
+    pub depeg_ratio_bps: u16,
+    /// Amp a depegged stable pool's ladder uses, or 0 for x*y=k.
+    pub crisis_amp: u64,
+    /// Quote received in maker rebates and paid in taker fees on the pool's
+    /// markets, so yield can be compared net of venue fees.
+    pub dex_maker_rebates_quote_amount: u64,
+    pub dex_taker_fees_quote_amount: u64,
+    /// Maker rebates credited since they were last swept by `withdraw_all`
+    /// or synced into reserves. The only surplus the dust sweep pays out, so
+    /// donations stay out of it.
+    pub unswept_rebates_quote_amount: u64,
+    /// Who may provide the first liquidity to a pool created empty. The
+    /// authority if unset.
+    pub bootstrapper: Pubkey,
+    /// Authority proposed by the current one, which takes over once it
+    /// accepts.
+    pub pending_authority: Pubkey,
+    /// May pause the pool and nothing else, so it can be a hot key held by
+    /// monitoring.
+    pub guardian: Pubkey,
+    /// Set by `pause_pool`. Unlike safe mode it survives refreshes, and only
+    /// the authority can lift it.
+    pub paused: bool,
+    /// Share of reserves backing LP tokens escrowed in withdraw tickets. The
+    /// ladder isn't quoted from it, so it stays in the vaults to be claimed.
+    /// Scaled with reserves as fills are reconciled.
+    pub queued_withdraw_base_amount: u64,
+    pub queued_withdraw_quote_amount: u64,
+    /// Reserves at the last `RESERVE_CHECKPOINTS` primary ladder placements,
+    /// written round robin at `reserve_checkpoint_index` modulo their count.
+    /// Kept in terms of the current LP supply.
+    pub reserve_checkpoints: [ReserveCheckpoint; RESERVE_CHECKPOINTS],
+    pub reserve_checkpoint_index: u64,
+    /// Share of each reconciled fill refunded to the keeper, in bps. 0 for
+    /// pools that run their own keepers.
+    pub keeper_refund_bps: u16,
+    /// Keeper refunds paid out or credited to keeper accounts over the
+    /// pool's life.
+    pub lifetime_keeper_paid_base_amount: u64,
+    pub lifetime_keeper_paid_quote_amount: u64,
+    /// Share of base and quote reserves the primary ladder committed to
+    /// resting orders when it was last placed, in bps.
+    pub base_utilization_bps: u16,
+    pub quote_utilization_bps: u16,
+    /// EWMA of the quote notional filled on the primary market between
+    /// refreshes, in bps of quote reserves per `FILL_VELOCITY_SLOTS`.
+    pub fill_velocity_bps: u64,
+    /// Fill velocity past which ladders are shrunk, by 1 bps of size per bps
+    /// of velocity over it up to `max_deleverage_bps`. 0 disables it.
+    pub deleverage_velocity_bps: u64,
+    pub max_deleverage_bps: u16,
+    /// openAMM pool on the same base whose mid the ladder is centred on, or
+    /// the default key to quote from the pool's own reserves. Used while its
+    /// mid is at most `anchor_max_age_slots` old.
+    pub anchor_pool: Pubkey,
+    pub anchor_max_age_slots: u64,
+    /// Stable pool whose LP token is this pool's quote mint, making this a
+    /// metapool of it, or the default key.
+    pub underlying_pool: Pubkey,
+    /// Tells apart pools of the same type on the same market. Part of the
+    /// pool's seeds unless it is 0.
+    pub index: u16,
+    /// Unconsumed events a market's queue may hold before refreshes stop
+    /// placing ladders on it, or 0 for no limit.
+    pub max_event_queue_backlog: u64,
+    /// Seconds into the UTC day quoting starts and stops. The window wraps
+    /// past midnight if it opens after it closes, and is always open if the
+    /// two are equal.
+    pub quoting_open_secs: u32,
+    pub quoting_close_secs: u32,
+    /// Reconciling fills worth more than this share of quote reserves, in
+    /// bps, suspends quoting for `cooldown_slots`. 0 disables it.
+    pub cooldown_fill_bps: u16,
+    pub cooldown_slots: u64,
+    /// Slot quoting resumes after the last cool-down was triggered.
+    pub cooldown_until_slot: u64,
+    /// Calls to the pool's deposit, withdraw and refresh instructions, for
+    /// analytics and rate limits. Withdrawals count once claimed, and swaps
+    /// are fills reconciled against the pool's orders. Takers fill on the dex
+    /// directly, so `swap_log` has no last caller.
+    pub deposit_log: AccessLog,
+    pub withdraw_log: AccessLog,
+    pub refresh_log: AccessLog,
+    pub swap_log: AccessLog,
+    /// Most LP a single deposit may mint, in bps of the supply, while the
+    /// reserves are more than `deposit_cap_deviation_bps` off the oracle or
+    /// it has no usable price. Only applies to pools with an oracle, and 0
+    /// disables it.
+    pub max_deposit_lp_bps: u16,
+    pub deposit_cap_deviation_bps: u16,
+    /// Slots a withdraw must wait between `request_withdraw` and
+    /// `claim_withdraw`. While it's set, instant withdraws are disabled so
+    /// exits can't front-run a managed strategy's rebalancing.
+    pub withdraw_delay_slots: u64,
+    /// The last `STATS_DAYS` days of activity, written round robin by day.
+    /// Buckets for days the pool saw no fill or refresh are left holding an
+    /// older day.
+    pub daily_stats: [DailyStats; STATS_DAYS],
+    /// Compute units an instruction placing the ladder may spend, and what
+    /// it's estimated to cost besides the dex CPIs, per cancel CPI and per
+    /// order placed. Ladders are cut to the innermost rungs that fit, and a
+    /// zero budget disables it.
+    pub compute_budget_units: u32,
+    pub base_compute_units: u32,
+    pub cancel_compute_units: u32,
+    pub place_compute_units: u32,
+    /// Dex program the pool's markets run on. Instructions that call the dex
+    /// only accept this program, and markets and open orders are loaded with
+    /// it. Those instructions take it as `Program<Dex>`, so for now it is
+    /// always `dex::ID`.
+    pub dex_program: Pubkey,
+    /// Fee each rung adds on top of the ladder's, nearest rung first, so the
+    /// deeper rungs that large fills reach pay LPs more. Never decreasing.
+    pub rung_fee_bps: [u16; MAX_ORDERS_PER_SIDE],
+    /// Pyth USD price account for the quote token, or the default key if
+    /// there is none. Only used to report the pool's quote amounts in USD so
+    /// pools on different quote tokens can be compared.
+    pub quote_oracle: Pubkey,
+    pub quote_oracle_max_age_slots: u64,
+    /// LP snapshots taken so far, which also indexes the next one's PDA.
+    pub snapshot_count: u64,
 }
 
 impl OpenAmmPool {
     pub fn reset_placed_orders(&mut self) -> () {
-        self.placed_asks = [PlacedOrder::default(); 10];
-        self.placed_bids = [PlacedOrder::default(); 10];
+        self.placed_asks = PlacedOrders::default();
+        self.placed_bids = PlacedOrders::default();
+    }
+
+    pub fn reset_secondary_placed_orders(&mut self) -> () {
+        self.secondary_placed_asks = PlacedOrders::default();
+        self.secondary_placed_bids = PlacedOrders::default();
+    }
+
+    pub fn has_secondary_market(&self) -> bool {
+        self.secondary_market != Pubkey::default()
+    }
+
+    pub fn get_bootstrapper(&self) -> Pubkey {
+        if self.bootstrapper == Pubkey::default() {
+            self.authority
+        } else {
+            self.bootstrapper
+        }
+    }
+
+    pub fn has_oracle(&self) -> bool {
+        self.oracle != Pubkey::default()
+    }
+
+    pub fn has_quote_oracle(&self) -> bool {
+        self.quote_oracle != Pubkey::default()
+    }
+
+    pub fn has_anchor_pool(&self) -> bool {
+        self.anchor_pool != Pubkey::default()
+    }
+
+    pub fn has_underlying_pool(&self) -> bool {
+        self.underlying_pool != Pubkey::default()
+    }
+
+    /// Base and quote reserves backing `lp_amount` of an LP supply of
+    /// `lp_supply`, as every withdraw path pays them out.
+    pub fn get_lp_share_amounts(&self, lp_amount: u64, lp_supply: u64) -> (u64, u64) {
+        if lp_supply == 0 {
+            return (0, 0);
+        }
+        let get_share = |reserve: u64| -> u64 {
+            (reserve as u128)
+                .checked_mul(lp_amount.into())
+                .unwrap()
+                .checked_div(lp_supply.into())
+                .unwrap()
+                .try_into()
+                .unwrap()
+        };
+        (get_share(self.base_amount), get_share(self.quote_amount))
+    }
+
+    /// Moves the reserves held back for withdraw tickets in line with fills
+    /// that took reserves from `base_before` and `quote_before` to their
+    /// current amounts, so the tickets keep the same share of them.
+    pub fn scale_queued_withdrawals(&mut self, base_before: u64, quote_before: u64) -> () {
+        let scale = |queued: u64, before: u64, after: u64| -> u64 {
+            if before == 0 {
+                return queued;
+            }
+            (queued as u128)
+                .checked_mul(after.into())
+                .unwrap()
+                .checked_div(before.into())
+                .unwrap()
+                .try_into()
+                .unwrap()
+        };
+        self.queued_withdraw_base_amount = cmp::min(
+            scale(self.queued_withdraw_base_amount, base_before, self.base_amount),
+            self.base_amount,
+        );
+        self.queued_withdraw_quote_amount = cmp::min(
+            scale(self.queued_withdraw_quote_amount, quote_before, self.quote_amount),
+            self.quote_amount,
+        );
+    }
+
+    pub fn record_reserve_checkpoint(&mut self, slot: u64) -> () {
+        let i = (self.reserve_checkpoint_index % RESERVE_CHECKPOINTS as u64) as usize;
+        self.reserve_checkpoints[i] = ReserveCheckpoint {
+            base_amount: self.base_amount,
+            quote_amount: self.quote_amount,
+            slot,
+        };
+        self.reserve_checkpoint_index = self.reserve_checkpoint_index.wrapping_add(1);
+    }
+
+    pub fn reset_reserve_checkpoints(&mut self) -> () {
+        self.reserve_checkpoints = [ReserveCheckpoint::default(); RESERVE_CHECKPOINTS];
+        self.reserve_checkpoint_index = 0;
+    }
+
+    /// Rescales the checkpoints for an LP supply going from `supply_before`
+    /// to `supply_after`, so each checkpoint's reserves per LP token stay
+    /// the same.
+    pub fn scale_reserve_checkpoints(&mut self, supply_after: u64, supply_before: u64) -> () {
+        if supply_before == 0 {
+            return;
+        }
+        let scale = |amount: u64| -> u64 {
+            (amount as u128)
+                .checked_mul(supply_after.into())
+                .unwrap()
+                .checked_div(supply_before.into())
+                .unwrap()
+                .try_into()
+                .unwrap()
+        };
+        for checkpoint in self.reserve_checkpoints.iter_mut() {
+            checkpoint.base_amount = scale(checkpoint.base_amount);
+            checkpoint.quote_amount = scale(checkpoint.quote_amount);
+        }
+    }
+
+    /// Reserves averaged over the checkpoints, each weighted by the slots it
+    /// was quoted for up to `slot`. Falls back to the current reserves until
+    /// the checkpoints span a slot.
+    pub fn get_twap_reserves(&self, slot: u64) -> (u64, u64) {
+        let count = cmp::min(self.reserve_checkpoint_index, RESERVE_CHECKPOINTS as u64);
+        let mut checkpoints = (0..count)
+            .map(|i| {
+                let i = self.reserve_checkpoint_index.wrapping_sub(count).wrapping_add(i);
+                self.reserve_checkpoints[(i % RESERVE_CHECKPOINTS as u64) as usize]
+            })
+            .collect::<Vec<ReserveCheckpoint>>();
+        checkpoints.sort_by_key(|c| c.slot);
+
+        let mut weighted_base: u128 = 0;
+        let mut weighted_quote: u128 = 0;
+        let mut total_slots: u128 = 0;
+        for (i, checkpoint) in checkpoints.iter().enumerate() {
+            let end_slot = checkpoints.get(i + 1).map_or(slot, |next| next.slot);
+            let slots = end_slot.saturating_sub(checkpoint.slot) as u128;
+            weighted_base = weighted_base
+                .checked_add((checkpoint.base_amount as u128).checked_mul(slots).unwrap())
+                .unwrap();
+            weighted_quote = weighted_quote
+                .checked_add((checkpoint.quote_amount as u128).checked_mul(slots).unwrap())
+                .unwrap();
+            total_slots = total_slots.checked_add(slots).unwrap();
+        }
+        if total_slots == 0 {
+            return (self.base_amount, self.quote_amount);
+        }
+        let twap_base = (weighted_base / total_slots) as u64;
+        let twap_quote = (weighted_quote / total_slots) as u64;
+        if twap_base == 0 || twap_quote == 0 {
+            return (self.base_amount, self.quote_amount);
+        }
+        (twap_base, twap_quote)
+    }
+
+    /// Stores a fresh read of the book's top, first crediting the previous
+    /// mid to the TWAP accumulator for the slots since it was taken.
+    pub fn record_book_top(
+        &mut self,
+        best_bid_price: Option<u64>,
+        best_ask_price: Option<u64>,
+        slot: u64,
+    ) -> () {
+        if self.mid_price_last_slot != 0 {
+            let elapsed_slots = slot.saturating_sub(self.mid_price_last_slot);
+            self.mid_price_cumulative = self
+                .mid_price_cumulative
+                .wrapping_add(self.mid_price.wrapping_mul(elapsed_slots));
+        }
+        self.mid_price_last_slot = slot;
+
+        let last_mid_price = self.mid_price;
+        self.best_bid_price = best_bid_price.unwrap_or(0);
+        self.best_ask_price = best_ask_price.unwrap_or(0);
+        self.mid_price = match (best_bid_price, best_ask_price) {
+            (Some(best_bid_price), Some(best_ask_price)) => {
+                ((best_bid_price as u128 + best_ask_price as u128) / 2) as u64
+            }
+            _ => 0,
+        };
+
+        if last_mid_price != 0 && self.mid_price != 0 {
+            let move_bps = (last_mid_price.abs_diff(self.mid_price) as u128)
+                .checked_mul(VOLATILITY_DENOMINATOR.into())
+                .unwrap()
+                .checked_div(last_mid_price.into())
+                .unwrap();
+            self.volatility_bps = (self.volatility_bps as u128)
+                .checked_mul((VOLATILITY_DENOMINATOR - VOLATILITY_EWMA_WEIGHT_BPS).into())
+                .unwrap()
+                .checked_add(
+                    move_bps
+                        .checked_mul(VOLATILITY_EWMA_WEIGHT_BPS.into())
+                        .unwrap(),
+                )
+                .unwrap()
+                .checked_div(VOLATILITY_DENOMINATOR.into())
+                .unwrap()
+                .try_into()
+                .unwrap_or(u64::MAX);
+        }
+    }
+
+    /// Folds the quote notional reconciled since the last refresh into
+    /// `fill_velocity_bps`.
+    pub fn record_fill_velocity(&mut self, reconciled_quote_amount: u64, slot: u64) -> () {
+        if self.last_refresh_slot == 0 || self.quote_amount == 0 {
+            return;
+        }
+        let elapsed_slots = cmp::max(slot.saturating_sub(self.last_refresh_slot), 1);
+        let velocity_bps = (reconciled_quote_amount as u128)
+            .checked_mul(VOLATILITY_DENOMINATOR.into())
+            .unwrap()
+            .checked_mul(FILL_VELOCITY_SLOTS.into())
+            .unwrap()
+            .checked_div(self.quote_amount.into())
+            .unwrap()
+            .checked_div(elapsed_slots.into())
+            .unwrap();
+        self.fill_velocity_bps = (self.fill_velocity_bps as u128)
+            .checked_mul((VOLATILITY_DENOMINATOR - FILL_VELOCITY_EWMA_WEIGHT_BPS).into())
+            .unwrap()
+            .checked_add(
+                velocity_bps
+                    .checked_mul(FILL_VELOCITY_EWMA_WEIGHT_BPS.into())
+                    .unwrap(),
+            )
+            .unwrap()
+            .checked_div(VOLATILITY_DENOMINATOR.into())
+            .unwrap()
+            .try_into()
+            .unwrap_or(u64::MAX);
+    }
+
+    /// How much ladder sizes are cut while fills are churning through
+    /// inventory, in bps of `DELEVERAGE_DENOMINATOR`.
+    pub fn get_deleverage_bps(&self) -> u16 {
+        if self.deleverage_velocity_bps == 0
+            || self.fill_velocity_bps <= self.deleverage_velocity_bps
+        {
+            return 0;
+        }
+        cmp::min(
+            self.fill_velocity_bps - self.deleverage_velocity_bps,
+            self.max_deleverage_bps.into(),
+        ) as u16
+    }
+
+    /// Starts a cool-down if `reconciled_quote_amount` of fills is a large
+    /// enough share of the quote reserves they were reconciled into.
+    pub fn record_fill_cooldown(&mut self, reconciled_quote_amount: u64, slot: u64) {
+        if self.cooldown_fill_bps == 0 {
+            return;
+        }
+        let toxic = (reconciled_quote_amount as u128)
+            .checked_mul(COOLDOWN_FILL_DENOMINATOR.into())
+            .unwrap()
+            > (self.quote_amount as u128)
+                .checked_mul(self.cooldown_fill_bps.into())
+                .unwrap();
+        if toxic {
+            self.cooldown_until_slot = cmp::max(
+                self.cooldown_until_slot,
+                slot.checked_add(self.cooldown_slots).unwrap(),
+            );
+        }
+    }
+
+    /// Whether the pool's schedule lets it quote now: inside its quoting
+    /// hours and not cooling down after a large fill.
+    pub fn is_quoting_scheduled(&self, slot: u64, unix_timestamp: i64) -> bool {
+        if slot < self.cooldown_until_slot {
+            return false;
+        }
+        let (open, close) = (self.quoting_open_secs, self.quoting_close_secs);
+        if open == close {
+            return true;
+        }
+        let secs = unix_timestamp.rem_euclid(SECONDS_PER_DAY.into()) as u32;
+        if open < close {
+            open <= secs && secs < close
+        } else {
+            secs >= open || secs < close
+        }
+    }
+
+    /// The bucket for the UTC day of `unix_timestamp`, cleared first if it
+    /// still holds an older day.
+    fn get_daily_stats_mut(&mut self, unix_timestamp: i64) -> &mut DailyStats {
+        let day = unix_timestamp.div_euclid(SECONDS_PER_DAY.into()) as u64;
+        let stats = &mut self.daily_stats[day as usize % STATS_DAYS];
+        if stats.day != day {
+            *stats = DailyStats {
+                day,
+                ..DailyStats::default()
+            };
+        }
+        stats
+    }
+
+    pub fn record_daily_fills(
+        &mut self,
+        volume_quote_amount: u64,
+        lp_fee_quote_amount: u64,
+        unix_timestamp: i64,
+    ) -> () {
+        let stats = self.get_daily_stats_mut(unix_timestamp);
+        stats.volume_quote_amount = stats
+            .volume_quote_amount
+            .checked_add(volume_quote_amount)
+            .unwrap();
+        stats.lp_fee_quote_amount = stats
+            .lp_fee_quote_amount
+            .checked_add(lp_fee_quote_amount)
+            .unwrap();
+    }
+
+    pub fn record_daily_tvl(&mut self, tvl_quote_amount: u64, unix_timestamp: i64) -> () {
+        self.get_daily_stats_mut(unix_timestamp).tvl_quote_amount = tvl_quote_amount;
+    }
+
+    /// Buckets for the `STATS_DAYS` days up to and including the UTC day of
+    /// `unix_timestamp`.
+    pub fn get_recent_daily_stats(&self, unix_timestamp: i64) -> Vec<DailyStats> {
+        let today = unix_timestamp.div_euclid(SECONDS_PER_DAY.into()) as u64;
+        self.daily_stats
+            .iter()
+            .filter(|stats| stats.day <= today && today - stats.day < STATS_DAYS as u64)
+            .copied()
+            .collect()
+    }
+
+    /// LP fees over the recent days annualized against the average TVL
+    /// recorded over them, in bps. 0 until a TVL has been recorded.
+    pub fn get_fee_apr_bps(&self, unix_timestamp: i64) -> u64 {
+        const DAYS_PER_YEAR: u64 = 365;
+
+        let recent = self.get_recent_daily_stats(unix_timestamp);
+        let today = unix_timestamp.div_euclid(SECONDS_PER_DAY.into()) as u64;
+        let first_day = match recent.iter().map(|stats| stats.day).min() {
+            Some(first_day) => first_day,
+            None => return 0,
+        };
+        let days = today - first_day + 1;
+        let lp_fee_quote_amount: u128 = recent
+            .iter()
+            .map(|stats| stats.lp_fee_quote_amount as u128)
+            .sum();
+        let tvl_days = recent
+            .iter()
+            .filter(|stats| stats.tvl_quote_amount > 0)
+            .count();
+        let tvl_quote_amount: u128 = recent
+            .iter()
+            .map(|stats| stats.tvl_quote_amount as u128)
+            .sum();
+        if tvl_quote_amount == 0 {
+            return 0;
+        }
+
+        // fees / days * 365 over tvl_quote_amount / tvl_days
+        lp_fee_quote_amount
+            .checked_mul((DAYS_PER_YEAR * FEE_APR_DENOMINATOR as u64).into())
+            .unwrap()
+            .checked_mul(tvl_days as u128)
+            .unwrap()
+            .checked_div(tvl_quote_amount.checked_mul(days.into()).unwrap())
+            .unwrap()
+            .try_into()
+            .unwrap_or(u64::MAX)
+    }
+
+    /// How many orders fit in the pool's compute budget after cancelling
+    /// with `cancel_cpis` CPIs, or `None` without a budget.
+    pub fn get_compute_budget_orders(&self, cancel_cpis: u64) -> Option<usize> {
+        if self.compute_budget_units == 0 {
+            return None;
+        }
+        let spent_units = cancel_cpis
+            .checked_mul(self.cancel_compute_units.into())
+            .unwrap()
+            .checked_add(self.base_compute_units.into())
+            .unwrap();
+        let left_units = u64::from(self.compute_budget_units).saturating_sub(spent_units);
+        Some(
+            left_units
+                .checked_div(self.place_compute_units.into())
+                .unwrap() as usize,
+        )
+    }
+
+    /// Fee the ladder adds on top of the curve's while the mid is moving.
+    pub fn get_volatility_fee_bps(&self) -> u16 {
+        let fee_bps = (self.volatility_bps as u128)
+            .checked_mul(self.volatility_fee_scale_bps.into())
+            .unwrap()
+            .checked_div(VOLATILITY_DENOMINATOR.into())
+            .unwrap();
+        cmp::min(fee_bps, self.max_volatility_fee_bps.into()) as u16
+    }
+
+    /// Fee rung `rung` adds on top of the ladder's.
+    pub fn get_rung_fee_bps(&self, rung: usize) -> u16 {
+        self.rung_fee_bps.get(rung).copied().unwrap_or(0)
     }
 }
@@ -0,0 +1,106 @@
+use crate::errors::OpenAmmErrorCode;
+use crate::state::{OpenAmmPool, MAX_ORDERS_PER_SIDE};
+use crate::util::{
+    MAX_JITTER_BPS, MAX_KEEPER_REFUND_BPS, MAX_RUNG_FEE_BPS, MAX_VOLATILITY_FEE_BPS,
+};
+use anchor_lang::prelude::*;
+
+/// A pool setting a proposal can change, with the value it would be set to.
+/// Each variant mirrors the authority's setter of the same name.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoolParameter {
+    OrdersPerSide {
+        orders_per_side: u8,
+    },
+    VolatilitySpread {
+        volatility_fee_scale_bps: u16,
+        max_volatility_fee_bps: u16,
+    },
+    Jitter {
+        jitter_bps: u16,
+    },
+    KeeperRefund {
+        keeper_refund_bps: u16,
+    },
+    RungFees {
+        rung_fee_bps: [u16; MAX_ORDERS_PER_SIDE],
+    },
+}
+
+impl Default for PoolParameter {
+    fn default() -> Self {
+        PoolParameter::OrdersPerSide { orders_per_side: 0 }
+    }
+}
+
+impl PoolParameter {
+    /// Checks the value against the same bounds its setter enforces.
+    pub fn validate(&self) -> Result<()> {
+        match *self {
+            PoolParameter::OrdersPerSide { orders_per_side } => require!(
+                orders_per_side >= 2 && orders_per_side as usize <= MAX_ORDERS_PER_SIDE,
+                OpenAmmErrorCode::InvalidOrdersPerSide
+            ),
+            PoolParameter::VolatilitySpread {
+                max_volatility_fee_bps,
+                ..
+            } => require!(
+                max_volatility_fee_bps <= MAX_VOLATILITY_FEE_BPS,
+                OpenAmmErrorCode::InvalidVolatilityFee
+            ),
+            PoolParameter::Jitter { jitter_bps } => require!(
+                jitter_bps <= MAX_JITTER_BPS,
+                OpenAmmErrorCode::InvalidJitter
+            ),
+            PoolParameter::KeeperRefund { keeper_refund_bps } => require!(
+                keeper_refund_bps <= MAX_KEEPER_REFUND_BPS,
+                OpenAmmErrorCode::InvalidKeeperRefund
+            ),
+            PoolParameter::RungFees { rung_fee_bps } => require!(
+                rung_fee_bps.windows(2).all(|w| w[0] <= w[1])
+                    && rung_fee_bps[MAX_ORDERS_PER_SIDE - 1] <= MAX_RUNG_FEE_BPS,
+                OpenAmmErrorCode::InvalidRungFees
+            ),
+        }
+        Ok(())
+    }
+
+    pub fn apply(&self, pool: &mut OpenAmmPool) -> Result<()> {
+        self.validate()?;
+        match *self {
+            PoolParameter::OrdersPerSide { orders_per_side } => {
+                pool.orders_per_side = orders_per_side;
+            }
+            PoolParameter::VolatilitySpread {
+                volatility_fee_scale_bps,
+                max_volatility_fee_bps,
+            } => {
+                pool.volatility_fee_scale_bps = volatility_fee_scale_bps;
+                pool.max_volatility_fee_bps = max_volatility_fee_bps;
+            }
+            PoolParameter::Jitter { jitter_bps } => {
+                pool.jitter_bps = jitter_bps;
+            }
+            PoolParameter::KeeperRefund { keeper_refund_bps } => {
+                pool.keeper_refund_bps = keeper_refund_bps;
+            }
+            PoolParameter::RungFees { rung_fee_bps } => {
+                pool.rung_fee_bps = rung_fee_bps;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A change to a pool's settings put forward by anyone, waiting on the pool's
+/// authority. Each proposer has at most one open proposal per pool; the rent
+/// goes back to them once the authority approves or rejects it.
+#[account]
+#[derive(Default)]
+pub struct ParameterProposal {
+    pub pool: Pubkey,
+    pub proposer: Pubkey,
+    pub parameter: PoolParameter,
+    pub proposed_at: i64,
+    pub bump: u8,
+}
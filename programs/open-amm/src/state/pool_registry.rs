@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+
+pub const MAX_REGISTRY_POOLS: usize = 16;
+
+/// The openAMM pools on a market, so clients can find every pool of a type
+/// rather than only the one at index 0.
+#[account]
+#[derive(Default)]
+pub struct PoolRegistry {
+    pub market: Pubkey,
+    pub pool_count: u8,
+    pub pools: [Pubkey; MAX_REGISTRY_POOLS],
+    pub bump: u8,
+}
+
+impl PoolRegistry {
+    pub fn pool_index(&self, pool: &Pubkey) -> Option<usize> {
+        self.pools[..self.pool_count as usize]
+            .iter()
+            .position(|p| p == pool)
+    }
+}
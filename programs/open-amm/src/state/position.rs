@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+
+/// Fixed-point scale for `Position::entry_price`.
+pub const POSITION_PRICE_SCALE: u64 = 1_000_000_000;
+
+/// Per-owner deposit receipt for a pool. Tracks what the owner has put in
+/// alongside the fungible LP tokens minted for the same deposits.
+#[account]
+#[derive(Default)]
+pub struct Position {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub lp_amount: u64,
+    pub base_deposited: u64,
+    pub quote_deposited: u64,
+    /// Average quote paid per base deposited, scaled by `POSITION_PRICE_SCALE`.
+    pub entry_price: u64,
+    pub opened_at: i64,
+    pub last_deposit_at: i64,
+    pub bump: u8,
+}
+
+impl Position {
+    pub fn record_deposit(
+        &mut self,
+        base_amount: u64,
+        quote_amount: u64,
+        lp_amount: u64,
+        now: i64,
+    ) {
+        self.lp_amount = self.lp_amount.checked_add(lp_amount).unwrap();
+        self.base_deposited = self.base_deposited.checked_add(base_amount).unwrap();
+        self.quote_deposited = self.quote_deposited.checked_add(quote_amount).unwrap();
+        if self.base_deposited > 0 {
+            self.entry_price = (self.quote_deposited as u128)
+                .checked_mul(POSITION_PRICE_SCALE.into())
+                .unwrap()
+                .checked_div(self.base_deposited.into())
+                .unwrap()
+                .try_into()
+                .unwrap();
+        }
+        self.last_deposit_at = now;
+    }
+
+    /// Releases the share of deposited amounts backing `lp_amount` and returns
+    /// the released base and quote. The entry price is left unchanged.
+    pub fn record_withdraw(&mut self, lp_amount: u64) -> (u64, u64) {
+        let base_amount: u64 = (self.base_deposited as u128)
+            .checked_mul(lp_amount.into())
+            .unwrap()
+            .checked_div(self.lp_amount.into())
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let quote_amount: u64 = (self.quote_deposited as u128)
+            .checked_mul(lp_amount.into())
+            .unwrap()
+            .checked_div(self.lp_amount.into())
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        self.lp_amount = self.lp_amount.checked_sub(lp_amount).unwrap();
+        self.base_deposited = self.base_deposited.checked_sub(base_amount).unwrap();
+        self.quote_deposited = self.quote_deposited.checked_sub(quote_amount).unwrap();
+        (base_amount, quote_amount)
+    }
+}
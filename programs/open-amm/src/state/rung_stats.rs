@@ -0,0 +1,35 @@
+use crate::state::MAX_ORDERS_PER_SIDE;
+use anchor_lang::prelude::*;
+
+/// Which rungs of a pool's ladder actually trade, so operators can tune how
+/// the ladder's size is spread across them. Counts fills on both of the
+/// pool's markets, and only those reconciled by instructions the account
+/// is passed to.
+#[account]
+#[derive(Default)]
+pub struct RungStats {
+    pub pool: Pubkey,
+    /// Slot counting started at.
+    pub since_slot: u64,
+    pub last_fill_slot: u64,
+    pub ask_fill_count: [u64; MAX_ORDERS_PER_SIDE],
+    /// Quote notional filled at each ask rung.
+    pub ask_fill_quote_amount: [u64; MAX_ORDERS_PER_SIDE],
+    pub bid_fill_count: [u64; MAX_ORDERS_PER_SIDE],
+    /// Quote notional filled at each bid rung.
+    pub bid_fill_quote_amount: [u64; MAX_ORDERS_PER_SIDE],
+    pub bump: u8,
+}
+
+impl RungStats {
+    pub fn record_fill(&mut self, sold_base: bool, rung: usize, quote_amount: u64, slot: u64) {
+        let (counts, quote_amounts) = if sold_base {
+            (&mut self.ask_fill_count, &mut self.ask_fill_quote_amount)
+        } else {
+            (&mut self.bid_fill_count, &mut self.bid_fill_quote_amount)
+        };
+        counts[rung] = counts[rung].checked_add(1).unwrap();
+        quote_amounts[rung] = quote_amounts[rung].checked_add(quote_amount).unwrap();
+        self.last_fill_slot = slot;
+    }
+}
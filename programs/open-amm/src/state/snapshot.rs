@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+
+/// A checkpoint of a pool's LP holders, committed as the merkle root of
+/// `(owner, lp_amount)` leaves, with rewards escrowed for `claim_airdrop` to
+/// pay out pro rata. The amounts in the tree sum to `lp_supply`, the LP
+/// supply when it was taken.
+#[account]
+#[derive(Default)]
+pub struct Snapshot {
+    pub pool: Pubkey,
+    pub index: u64,
+    pub merkle_root: [u8; 32],
+    pub lp_supply: u64,
+    pub reward_mint: Pubkey,
+    pub reward_amount: u64,
+    pub claimed_amount: u64,
+    pub slot: u64,
+    pub bump: u8,
+}
+
+impl Snapshot {
+    /// Rewards owed to a holder of `lp_amount` LP tokens at the snapshot.
+    pub fn get_airdrop_amount(&self, lp_amount: u64) -> u64 {
+        (self.reward_amount as u128)
+            .checked_mul(lp_amount.into())
+            .unwrap()
+            .checked_div(self.lp_supply.into())
+            .unwrap_or(0) as u64
+    }
+}
+
+/// Receipt of an owner's claim on a snapshot. Its existence is what stops a
+/// second claim, so it's never closed.
+#[account]
+#[derive(Default)]
+pub struct AirdropClaim {
+    pub snapshot: Pubkey,
+    pub owner: Pubkey,
+    pub lp_amount: u64,
+    pub reward_amount: u64,
+    pub bump: u8,
+}
@@ -0,0 +1,14 @@
+use anchor_lang::prelude::*;
+
+/// LP tokens an owner has escrowed with `request_withdraw`. They're burned
+/// for their share of reserves by `claim_withdraw`, once a refresh has left
+/// that share in the vaults instead of quoting it.
+#[account]
+#[derive(Default)]
+pub struct WithdrawTicket {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub lp_amount: u64,
+    pub requested_slot: u64,
+    pub bump: u8,
+}
@@ -1,67 +1,135 @@
-use crate::instructions::create_pool::POOL_SEED;
-use crate::stableswap::{calc_d, calc_dy, get_token_decs_fac, STABLESWAP_AMP_COEFFICIENT};
+use crate::errors::OpenAmmErrorCode;
+use crate::instructions::create_pool::{get_pool_index_seed, MINIMUM_LIQUIDITY, POOL_SEED};
+use crate::math::{isqrt, U256};
+use crate::oracle::{
+    get_anchor_price, get_anchored_reserves, get_oracle_price, is_within_oracle_band,
+};
+use crate::curve::{get_curve, get_curve_fee_bps};
+use crate::stableswap::{
+    calc_d, calc_marginal_price, calc_swap_out, get_token_decs_fac, STABLESWAP_AMP_COEFFICIENT,
+};
 use crate::state::*;
 use anchor_lang::prelude::*;
 use anchor_spl::dex;
-use anchor_spl::token::{Token, TokenAccount};
+use anchor_spl::token::{self, transfer, Token, TokenAccount, Transfer};
 use serum_dex::critbit::*;
 use serum_dex::instruction::MarketInstruction;
-use serum_dex::instruction::{CancelOrderInstructionV2, NewOrderInstructionV3, SelfTradeBehavior};
+use serum_dex::instruction::{NewOrderInstructionV3, SelfTradeBehavior};
 use serum_dex::matching::OrderType;
 use serum_dex::matching::{OrderBookState, Side};
-use serum_dex::state::Market;
+use serum_dex::state::{strip_header, Event, EventQueueHeader, Market, MarketState, QueueHeader};
 use solana_program::instruction::{AccountMeta, Instruction};
 use std::cmp;
+use std::convert::identity;
 use std::num::NonZeroU64;
 
-const ORDER_NUMERATORS: [u16; 10] = [8, 15, 30, 50, 125, 300, 500, 750, 1000, 1250];
-
-const LP_FEE_BPS: u16 = 20;
-const STABLESWAP_FEE_BPS: u16 = 4;
+// Rungs past the tenth taper off so a full ladder never offers more than the
+// reserve it is quoting
+pub const ORDER_NUMERATORS: [u16; MAX_ORDERS_PER_SIDE] = [
+    8, 15, 30, 50, 125, 300, 500, 750, 1000, 1250, 1000, 750, 500, 500, 250, 250,
+];
+pub const ORDER_DENOMINATOR: u16 = 10_000;
+
+pub const LADDER_STEP_DENOMINATOR: u16 = 10_000;
+pub const MAX_LADDER_STEP_BPS: u16 = 1_000;
+
+pub const MAX_JITTER_BPS: u16 = 500;
+
+// Share of each fill's incoming side paid to whoever triggers the reconcile
+// that credits it
+pub const DEFAULT_KEEPER_REFUND_BPS: u16 = 1;
+pub const MAX_KEEPER_REFUND_BPS: u16 = 10;
+
+// Keeps the widest bid fee numerator positive
+pub const MAX_VOLATILITY_FEE_BPS: u16 = 2_000;
+
+// Stacked on the volatility fee, still leaves the deepest bid's numerator
+// positive
+pub const MAX_RUNG_FEE_BPS: u16 = 1_000;
+
+// Leaves some of the ladder quoted however fast fills come in
+pub const MAX_DELEVERAGE_BPS: u16 = 9_000;
+
+pub const SECONDARY_SHARE_DENOMINATOR: u16 = 10_000;
+
+pub const DRIFT_DENOMINATOR: u16 = 10_000;
+
+/// Fixed-point scale for `FeesCompoundedEvent::lp_value_increment`.
+pub const LP_VALUE_SCALE: u64 = 1_000_000_000_000;
+
+pub const LP_FEE_BPS: u16 = 20;
+pub const STABLESWAP_FEE_BPS: u16 = 4;
+// Portion of each reconciled fill, taken from the incoming side, that is moved
+// to the pool's fee vaults instead of its reserves. Capped at the fill's
+// spread, see `split_fill_spread`.
+pub(crate) const PROTOCOL_FEE_BPS: u16 = 2;
+
+// The dex's base fee tier, which applies to the pool since it holds no SRM.
+// Rates are in tenths of a bp of the quote traded.
+const DEX_MAKER_REBATE_TENTH_BPS: u64 = 20;
+const DEX_TAKER_FEE_TENTH_BPS: u64 = 40;
+const DEX_FEE_DENOMINATOR: u64 = 100_000;
+
+// Client order ids the dex accepts per CancelOrdersByClientIds, which is
+// fixed by its instruction layout
+const CANCEL_BATCH_SIZE: usize = 8;
+
+/// Fails unless `market_state` has the lot sizes cached on `pool`, which
+/// Serum never changes, so a mismatch means the wrong market was passed.
+pub fn check_lot_sizes(pool: &OpenAmmPool, market_state: &MarketState) -> Result<()> {
+    require!(
+        market_state.coin_lot_size == pool.base_lot_size
+            && market_state.pc_lot_size == pool.quote_lot_size,
+        OpenAmmErrorCode::LotSizeMismatch
+    );
+    Ok(())
+}
 
 pub fn get_orderbook<'info>(
     curr_client_order_id: u64,
     pool_bump: u8,
     pool_type: PoolType,
+    pool_index: u16,
     pool: AccountLoader<'info, OpenAmmPool>,
     market_accounts: MarketAccounts<'info>,
     base_wallet: Account<'info, TokenAccount>,
     quote_wallet: Account<'info, TokenAccount>,
+    base_fee_vault: Account<'info, TokenAccount>,
+    quote_fee_vault: Account<'info, TokenAccount>,
     dex_program: Program<'info, dex::Dex>,
     token_program: Program<'info, Token>,
     rent: Sysvar<'info, Rent>,
-    should_print_orders: bool,
-) -> OrderbookClient<'info> {
+    debug_logging: bool,
+) -> Result<OrderbookClient<'info>> {
     let should_load_orders = true;
-    let base_lot_size;
-    let quote_lot_size;
-    let mut native_base_total = 0;
-    let mut native_quote_total = 0;
     let mut native_base_free = 0;
     let mut native_quote_free = 0;
-    let mut best_bid_price = None;
-    let mut best_ask_price = None;
     let mut orders = vec![];
-    let should_load_price = false;
     let market = market_accounts.market.clone();
-    let mut market_state = Market::load(&market, &dex::ID, true).unwrap();
-
-    base_lot_size = market_state.coin_lot_size;
-    quote_lot_size = market_state.pc_lot_size;
+    let mut market_state = Market::load(&market, &dex_program.key(), true).unwrap();
+
+    // The primary market's lot sizes are cached on the pool. A secondary
+    // market's are only on the market.
+    let pool_state = pool.load()?;
+    let (base_lot_size, quote_lot_size) = if market.key() == pool_state.market {
+        check_lot_sizes(&pool_state, &market_state)?;
+        (pool_state.base_lot_size, pool_state.quote_lot_size)
+    } else {
+        (market_state.coin_lot_size, market_state.pc_lot_size)
+    };
+    drop(pool_state);
 
-    if should_load_orders || should_load_price {
+    if should_load_orders {
         let open_orders = Market::load_orders_mut(
             &market_state,
             &market_accounts.open_orders,
             None,
-            &dex::ID,
+            &dex_program.key(),
             None,
             None,
         )
         .unwrap();
 
-        native_base_total = open_orders.native_coin_total;
-        native_quote_total = open_orders.native_pc_total;
         native_base_free = open_orders.native_coin_free;
         native_quote_free = open_orders.native_pc_free;
 
@@ -73,31 +141,30 @@ pub fn get_orderbook<'info>(
             market_state: &mut market_state,
         };
 
-        if should_load_price {
-            let bid_id = orderbook_state.bids.find_max();
-            let ask_id = orderbook_state.asks.find_min();
-            if bid_id.is_some() && ask_id.is_some() {
-                let best_bid = orderbook_state
-                    .orders_mut(Side::Bid)
-                    .get_mut(bid_id.unwrap())
-                    .unwrap()
-                    .as_leaf_mut()
-                    .unwrap()
-                    .clone();
-                let best_ask = orderbook_state
-                    .orders_mut(Side::Ask)
-                    .get_mut(ask_id.unwrap())
-                    .unwrap()
-                    .as_leaf_mut()
-                    .unwrap()
-                    .clone();
-                best_bid_price = u64::from(best_bid.price()).into();
-                best_ask_price = u64::from(best_ask.price()).into();
-            }
+        if debug_logging {
+            let book_best_bid = orderbook_state
+                .bids
+                .find_max()
+                .and_then(|h| orderbook_state.bids.get(h))
+                .and_then(|n| n.as_leaf())
+                .map(|l| u64::from(l.price()));
+            let book_best_ask = orderbook_state
+                .asks
+                .find_min()
+                .and_then(|h| orderbook_state.asks.get(h))
+                .and_then(|n| n.as_leaf())
+                .map(|l| u64::from(l.price()));
+            msg!(
+                "openamm:book best_bid={:?} best_ask={:?} base_total={} quote_total={}",
+                book_best_bid,
+                book_best_ask,
+                open_orders.native_coin_total,
+                open_orders.native_pc_total
+            );
         }
 
         if should_load_orders {
-            let max_orders: u64 = (ORDER_NUMERATORS.len() * 2).try_into().unwrap();
+            let max_orders: u64 = (MAX_ORDERS_PER_SIDE * 2).try_into().unwrap();
 
             let slots = open_orders.iter_filled_slots();
             for slot in slots {
@@ -125,13 +192,8 @@ pub fn get_orderbook<'info>(
                     let limit_price: u64 = order.price().into();
                     let base_qty: u64 = order.quantity().into();
 
-                    if should_print_orders {
-                        msg!("{:?} {} {}", side, limit_price, base_qty);
-                    }
-
                     orders.push(CurrentOrder {
                         side,
-                        order_id,
                         limit_price,
                         base_qty,
                         client_order_id: order.client_order_id(),
@@ -142,26 +204,29 @@ pub fn get_orderbook<'info>(
     }
     drop(market_state);
 
-    OrderbookClient {
+    Ok(OrderbookClient {
+        pool_market: market_accounts.market.key(),
+        secondary: false,
+        rung_stats: None,
         market_accounts,
         pool,
         pool_bump,
         pool_type,
+        pool_index,
         dex_program,
         token_program,
         rent,
         base_lot_size,
         quote_lot_size,
         orders,
-        native_base_total,
-        native_quote_total,
         native_base_free,
         native_quote_free,
         base_wallet,
         quote_wallet,
-        best_bid_price,
-        best_ask_price,
-    }
+        base_fee_vault,
+        quote_fee_vault,
+        debug_logging,
+    })
 }
 
 #[derive(Clone)]
@@ -169,24 +234,75 @@ pub struct OrderbookClient<'info> {
     pub market_accounts: MarketAccounts<'info>,
     pub base_wallet: Account<'info, TokenAccount>,
     pub quote_wallet: Account<'info, TokenAccount>,
+    pub base_fee_vault: Account<'info, TokenAccount>,
+    pub quote_fee_vault: Account<'info, TokenAccount>,
     pub dex_program: Program<'info, dex::Dex>,
     pub token_program: Program<'info, Token>,
     pub pool: AccountLoader<'info, OpenAmmPool>,
     pub rent: Sysvar<'info, Rent>,
     pub base_lot_size: u64,
     pub quote_lot_size: u64,
-    pub native_base_total: u64,
-    pub native_quote_total: u64,
     pub native_base_free: u64,
     pub native_quote_free: u64,
     pub orders: Vec<CurrentOrder>,
-    pub best_bid_price: Option<u64>,
-    pub best_ask_price: Option<u64>,
     pub pool_bump: u8,
     pub pool_type: PoolType,
+    pub pool_index: u16,
+    pub debug_logging: bool,
+    /// Market the pool PDA is derived from, used for signing.
+    pub pool_market: Pubkey,
+    /// Whether this client quotes the pool's secondary market.
+    pub secondary: bool,
+    /// The pool's rung stats account, when fills reconciled are counted.
+    pub rung_stats: Option<AccountInfo<'info>>,
+}
+
+/// Like `get_orderbook`, but for the pool's secondary market. Fills are
+/// reconciled against the secondary placed orders and the ladder placed is
+/// the secondary market's share of the pool's ladder.
+pub fn get_secondary_orderbook<'info>(
+    curr_client_order_id: u64,
+    pool_bump: u8,
+    pool_type: PoolType,
+    pool_index: u16,
+    pool_market: Pubkey,
+    pool: AccountLoader<'info, OpenAmmPool>,
+    market_accounts: MarketAccounts<'info>,
+    base_wallet: Account<'info, TokenAccount>,
+    quote_wallet: Account<'info, TokenAccount>,
+    base_fee_vault: Account<'info, TokenAccount>,
+    quote_fee_vault: Account<'info, TokenAccount>,
+    dex_program: Program<'info, dex::Dex>,
+    token_program: Program<'info, Token>,
+    rent: Sysvar<'info, Rent>,
+    debug_logging: bool,
+) -> Result<OrderbookClient<'info>> {
+    Ok(OrderbookClient {
+        pool_market,
+        secondary: true,
+        ..get_orderbook(
+            curr_client_order_id,
+            pool_bump,
+            pool_type,
+            pool_index,
+            pool,
+            market_accounts,
+            base_wallet,
+            quote_wallet,
+            base_fee_vault,
+            quote_fee_vault,
+            dex_program,
+            token_program,
+            rent,
+            debug_logging,
+        )?
+    })
 }
 
 impl<'info> OrderbookClient<'info> {
+    /// Places each order with its own CPI. A dex error fails the whole
+    /// instruction, so orders that could be rejected are left out of the
+    /// ladder beforehand.
     pub fn place_orders(
         &self,
         place_ixs: Vec<NewOrderInstructionV3>,
@@ -229,11 +345,13 @@ impl<'info> OrderbookClient<'info> {
             accounts,
         };
 
-        let market_key = self.market_accounts.market.key();
+        let market_key = self.pool_market;
         let pool_type_bytes = (self.pool_type as u8).to_le_bytes();
+        let pool_index_seed = get_pool_index_seed(self.pool_index);
         let seeds = pool_authority_seeds!(
             market_key = market_key,
             pool_type_bytes = pool_type_bytes,
+            index_seed = pool_index_seed,
             bump = self.pool_bump
         );
         let pool_signer = &[&seeds[..]];
@@ -257,7 +375,10 @@ impl<'info> OrderbookClient<'info> {
         Ok(())
     }
 
-    pub fn cancel_orders(&self, cancel_ixs: Vec<CancelOrderInstructionV2>) -> Result<()> {
+    /// Cancels orders by client order id, up to eight per CPI, so a ladder of
+    /// n orders takes ceil(n / 8) CPIs: 3 for ten rungs a side. Ids with no
+    /// matching order on the book are skipped by the dex.
+    pub fn cancel_orders(&self, client_order_ids: Vec<u64>) -> Result<()> {
         let mut instruction = Instruction {
             program_id: self.dex_program.key(),
             data: vec![],
@@ -281,193 +402,526 @@ impl<'info> OrderbookClient<'info> {
             self.market_accounts.event_queue.clone(),
         ];
 
-        let market_key = self.market_accounts.market.key();
+        let market_key = self.pool_market;
         let pool_type_bytes = (self.pool_type as u8).to_le_bytes();
+        let pool_index_seed = get_pool_index_seed(self.pool_index);
         let seeds = pool_authority_seeds!(
             market_key = market_key,
             pool_type_bytes = pool_type_bytes,
+            index_seed = pool_index_seed,
             bump = self.pool_bump
         );
         let pool_signer = &[&seeds[..]];
 
-        for cancel in cancel_ixs.iter() {
-            let cancel_instruction = MarketInstruction::CancelOrderV2(cancel.clone());
+        for batch in client_order_ids.chunks(CANCEL_BATCH_SIZE) {
+            let mut batch_ids = [0; CANCEL_BATCH_SIZE];
+            batch_ids[..batch.len()].copy_from_slice(batch);
+            let cancel_instruction = MarketInstruction::CancelOrdersByClientIds(batch_ids);
             instruction.data = cancel_instruction.pack();
-            solana_program::program::invoke_signed(&instruction, &account_infos, pool_signer).ok();
+            solana_program::program::invoke_signed(&instruction, &account_infos, pool_signer)?;
         }
 
         Ok(())
     }
 
-    pub fn cancel_all_and_settle(&self) -> Result<()> {
-        const REFUND_DENOMINATOR: u16 = 10_000;
-        let mut pool = self.pool.load_mut().unwrap();
-
-        let curr_asks = self
-            .orders
-            .iter()
-            .filter(|o| o.side == Side::Ask)
-            .cloned()
-            .collect::<Vec<CurrentOrder>>();
-
-        let curr_bids = self
-            .orders
-            .iter()
-            .filter(|o| o.side == Side::Bid)
-            .cloned()
-            .collect::<Vec<CurrentOrder>>();
+    /// Credits the pool with whatever its placed orders filled since the last
+    /// refresh, by comparing them with what's still on the book, and clears
+    /// them. Only `pool` is changed, so this can run against a copy.
+    pub fn reconcile(&self, pool: &mut OpenAmmPool) -> Result<Reconciliation> {
+        reconcile_orders(
+            &self.orders,
+            self.base_lot_size,
+            self.quote_lot_size,
+            self.secondary,
+            self.debug_logging,
+            pool,
+        )
+    }
 
-        let non_zero_asks = pool
-            .placed_asks
+    /// Counts the fills this client reconciles in the pool's rung stats
+    /// account, if it's among `remaining_accounts` and writable.
+    pub fn with_rung_stats(self, remaining_accounts: &[AccountInfo<'info>]) -> Self {
+        let pool_key = self.pool.key();
+        let rung_stats = remaining_accounts
             .iter()
-            .filter(|o| o.base_qty != 0)
-            .cloned()
-            .collect::<Vec<PlacedOrder>>();
+            .filter(|a| *a.owner == crate::ID && a.is_writable)
+            .find(|a| Account::<RungStats>::try_from(a).map_or(false, |s| s.pool == pool_key))
+            .cloned();
+        OrderbookClient { rung_stats, ..self }
+    }
 
-        let non_zero_bids = pool
-            .placed_bids
-            .iter()
-            .filter(|o| o.base_qty != 0)
-            .cloned()
-            .collect::<Vec<PlacedOrder>>();
+    /// Reconciles fills against the placed ladder, cancels the pool's orders
+    /// and settles. Returns the quote notional of the fills reconciled.
+    pub fn cancel_all_and_settle(&self) -> Result<u64> {
+        let (reconciled_quote_amount, _) = self.cancel_and_settle(None)?;
+        Ok(reconciled_quote_amount)
+    }
 
-        let mut moved_base_amount: u64 = 0;
-        let mut moved_quote_amount: u64 = 0;
+    /// Like `cancel_all_and_settle`, but only cancels as many of the primary
+    /// market's deepest rungs as it takes for the vaults to cover paying out
+    /// `lp_amount` of an LP supply of `lp_supply`, on top of what the pool
+    /// already owes from them. The rest keep resting, tracked at what's left
+    /// of them. Cancels everything if that isn't enough or the pool was
+    /// evicted, and returns whether it trimmed.
+    pub fn trim_and_settle(&self, lp_amount: u64, lp_supply: u64) -> Result<bool> {
+        let (_, trimmed) = self.cancel_and_settle(Some((lp_amount, lp_supply)))?;
+        Ok(trimmed)
+    }
 
-        for (i, placed_ask) in non_zero_asks.iter().enumerate() {
-            let placed_base_amount = placed_ask.base_qty.checked_mul(self.base_lot_size).unwrap();
-            let found_curr_ask = curr_asks
+    fn cancel_and_settle(&self, withdraw: Option<(u64, u64)>) -> Result<(u64, bool)> {
+        let mut pool = self.pool.load_mut().unwrap();
+        let invariant_before = get_invariant(&pool);
+        let was_active = pool.mm_active;
+        let refund_base_before = pool.refund_base_amount;
+        let refund_quote_before = pool.refund_quote_amount;
+        let placed_asks = pool.placed_asks;
+        let placed_bids = pool.placed_bids;
+        let Reconciliation {
+            reconciled_quote_amount,
+            protocol_fee_base_amount,
+            protocol_fee_quote_amount,
+            mut cancel_client_order_ids,
+            fills,
+        } = self.reconcile(&mut pool)?;
+        let slot = Clock::get()?.slot;
+        if !self.secondary {
+            pool.record_fill_velocity(reconciled_quote_amount, slot);
+        }
+        pool.record_fill_cooldown(reconciled_quote_amount, slot);
+        if !fills.is_empty() {
+            pool.swap_log
+                .record(fills.len() as u64, Pubkey::default(), slot);
+            // Takers buying base pay their fees in quote, and sellers in base
+            let lp_fee_quote_amount = fills
                 .iter()
-                .find(|&&o| o.client_order_id == placed_ask.client_order_id);
-
-            let less_base_amount = if let Some(found_curr_ask) = found_curr_ask {
-                let curr_base_amount = found_curr_ask
-                    .base_qty
-                    .checked_mul(self.base_lot_size)
-                    .unwrap();
-
-                placed_base_amount.checked_sub(curr_base_amount).unwrap()
-            }
-            else {
-                if i == non_zero_asks.len() - 1 {
-                    pool.mm_active = false;
+                .map(|fill| {
+                    if fill.sold_base {
+                        fill.lp_fee
+                    } else {
+                        get_base_value_in_quote(&pool, fill.lp_fee)
+                    }
+                })
+                .fold(0u64, |total, fee| total.checked_add(fee).unwrap());
+            pool.record_daily_fills(
+                reconciled_quote_amount,
+                lp_fee_quote_amount,
+                Clock::get()?.unix_timestamp,
+            );
+            if let Some(rung_stats_info) = &self.rung_stats {
+                let mut rung_stats = Account::<RungStats>::try_from(rung_stats_info)?;
+                for fill in fills.iter() {
+                    rung_stats.record_fill(
+                        fill.sold_base,
+                        fill.rung.into(),
+                        fill.notional_quote_amount(),
+                        slot,
+                    );
                 }
-                placed_base_amount
-            };
+                rung_stats.exit(&crate::ID)?;
+            }
+        }
 
-            let more_quote_amount = less_base_amount
-                .checked_mul(placed_ask.limit_price)
+        let mut trimmed = false;
+        let withdraw = withdraw.filter(|_| pool.mm_active && !self.secondary);
+        if let Some((lp_amount, lp_supply)) = withdraw {
+            let (base_amount, quote_amount) = pool.get_lp_share_amounts(lp_amount, lp_supply);
+            let base_needed = base_amount
+                .checked_add(pool.refund_base_amount)
                 .unwrap()
-                .checked_mul(self.quote_lot_size)
+                .checked_add(pool.keeper_owed_base_amount)
                 .unwrap()
-                .checked_div(self.base_lot_size)
-                .unwrap();
-
-            let refund_amount = more_quote_amount
-                .checked_div(REFUND_DENOMINATOR.into())
-                .unwrap();
-
-            pool.base_amount = pool.base_amount.checked_sub(less_base_amount).unwrap();
-            pool.quote_amount = pool
-                .quote_amount
-                .checked_add(more_quote_amount)
+                .checked_add(pool.queued_withdraw_base_amount)
                 .unwrap()
-                .checked_sub(refund_amount)
+                .checked_add(protocol_fee_base_amount)
                 .unwrap();
-
-            moved_quote_amount = moved_quote_amount.checked_add(more_quote_amount).unwrap();
-            pool.cumulative_quote_volume = pool
-                .cumulative_quote_volume
-                .checked_add(more_quote_amount)
+            let quote_needed = quote_amount
+                .checked_add(pool.refund_quote_amount)
+                .unwrap()
+                .checked_add(pool.keeper_owed_quote_amount)
+                .unwrap()
+                .checked_add(pool.queued_withdraw_quote_amount)
+                .unwrap()
+                .checked_add(protocol_fee_quote_amount)
                 .unwrap();
+            let mut trim_client_order_ids = vec![];
+            let kept_asks = trim_side(
+                &placed_asks,
+                &self.orders,
+                Side::Ask,
+                |o| o.base_qty.checked_mul(self.base_lot_size).unwrap(),
+                self.base_wallet
+                    .amount
+                    .checked_add(self.native_base_free)
+                    .unwrap(),
+                base_needed,
+                &mut trim_client_order_ids,
+            );
+            let kept_bids = trim_side(
+                &placed_bids,
+                &self.orders,
+                Side::Bid,
+                |o| {
+                    o.base_qty
+                        .checked_mul(o.limit_price)
+                        .unwrap()
+                        .checked_mul(self.quote_lot_size)
+                        .unwrap()
+                },
+                self.quote_wallet
+                    .amount
+                    .checked_add(self.native_quote_free)
+                    .unwrap(),
+                quote_needed,
+                &mut trim_client_order_ids,
+            );
+            if let (Some(kept_asks), Some(kept_bids)) = (kept_asks, kept_bids) {
+                debug_msg!(
+                    self.debug_logging,
+                    "openamm:trim cancelled={} kept_asks={} kept_bids={}",
+                    trim_client_order_ids.len(),
+                    kept_asks.len,
+                    kept_bids.len
+                );
+                pool.placed_asks = kept_asks;
+                pool.placed_bids = kept_bids;
+                cancel_client_order_ids = trim_client_order_ids;
+                trimmed = true;
+            }
         }
+        // The dex credits makers a rebate on top of the limit price, which
+        // reconciling doesn't count
+        let maker_rebate_amount = get_dex_fee(reconciled_quote_amount, DEX_MAKER_REBATE_TENTH_BPS);
+        pool.dex_maker_rebates_quote_amount = pool
+            .dex_maker_rebates_quote_amount
+            .checked_add(maker_rebate_amount)
+            .unwrap();
+        pool.unswept_rebates_quote_amount = pool
+            .unswept_rebates_quote_amount
+            .checked_add(maker_rebate_amount)
+            .unwrap();
 
-        for (i, placed_bid) in non_zero_bids.iter().enumerate() {
-            let max_base_qty = placed_bid
-                .max_native_quote_qty_including_fees
-                .checked_div(placed_bid.limit_price)
-                .unwrap();
+        let invariant_after = get_invariant(&pool);
+        let base_amount = pool.base_amount;
+        let quote_amount = pool.quote_amount;
+        let refund_base_amount = pool.refund_base_amount;
+        let refund_quote_amount = pool.refund_quote_amount;
+        let evicted = was_active && !pool.mm_active && !self.secondary;
+        drop(pool);
+        self.cancel_orders(cancel_client_order_ids)?;
 
-            let base_qty = cmp::min(max_base_qty, placed_bid.base_qty);
-            let placed_base_amount = base_qty.checked_mul(self.base_lot_size).unwrap();
+        self.settle()?;
 
-            let found_curr_bid = curr_bids
-                .iter()
-                .find(|&&o| o.client_order_id == placed_bid.client_order_id);
+        self.transfer_protocol_fees(protocol_fee_base_amount, protocol_fee_quote_amount)?;
 
-            let more_base_amount = if let Some(found_curr_bid) = found_curr_bid {
-                let curr_base_amount = found_curr_bid
-                    .base_qty
-                    .checked_mul(self.base_lot_size)
-                    .unwrap();
-                placed_base_amount.checked_sub(curr_base_amount).unwrap()
-            }
-            else {
-                if i == non_zero_bids.len() - 1 {
-                    pool.mm_active = false
-                }
-                placed_base_amount
-            };
+        if evicted {
+            self.record_eviction_exposure()?;
+        }
+
+        let clock = Clock::get()?;
+        for fill in fills {
+            emit!(SwapEvent {
+                pool: self.pool.key(),
+                market: self.market_accounts.market.key(),
+                sold_base: fill.sold_base,
+                amount_in: fill.amount_in,
+                amount_out: fill.amount_out,
+                limit_price: fill.limit_price,
+                lp_fee: fill.lp_fee,
+                protocol_fee: fill.protocol_fee,
+                keeper_fee: fill.keeper_fee,
+                base_amount: fill.base_amount,
+                quote_amount: fill.quote_amount,
+                slot: clock.slot,
+                unix_timestamp: clock.unix_timestamp,
+            });
+        }
+
+        if refund_base_amount > refund_base_before || refund_quote_amount > refund_quote_before {
+            emit!(RefundAccruedEvent {
+                pool: self.pool.key(),
+                market: self.market_accounts.market.key(),
+                base_amount: refund_base_amount - refund_base_before,
+                quote_amount: refund_quote_amount - refund_quote_before,
+                refund_base_amount,
+                refund_quote_amount,
+                slot: clock.slot,
+                unix_timestamp: clock.unix_timestamp,
+            });
+        }
 
-            let less_quote_amount = more_base_amount
-                .checked_mul(placed_bid.limit_price)
+        if maker_rebate_amount > 0 {
+            emit!(DexFeesEvent {
+                pool: self.pool.key(),
+                market: self.market_accounts.market.key(),
+                maker_rebate_amount,
+                taker_fee_amount: 0,
+                slot: clock.slot,
+                unix_timestamp: clock.unix_timestamp,
+            });
+        }
+
+        // Supply doesn't change while reconciling, so the invariant's relative
+        // growth is also the growth of each LP token's share of it.
+        if invariant_before > 0 && invariant_after > invariant_before {
+            let lp_value_increment: u64 = ((invariant_after - invariant_before) as u128)
+                .checked_mul(LP_VALUE_SCALE.into())
                 .unwrap()
-                .checked_mul(self.quote_lot_size)
+                .checked_div(invariant_before.into())
                 .unwrap()
-                .checked_div(self.base_lot_size)
+                .try_into()
                 .unwrap();
+            emit!(FeesCompoundedEvent {
+                pool: self.pool.key(),
+                base_amount,
+                quote_amount,
+                invariant_before,
+                invariant_after,
+                lp_value_increment,
+                slot: clock.slot,
+                unix_timestamp: clock.unix_timestamp,
+            });
+        }
 
-            let refund_amount = more_base_amount
-                .checked_div(REFUND_DENOMINATOR.into())
-                .unwrap();
+        Ok((reconciled_quote_amount, trimmed))
+    }
 
-            moved_base_amount = moved_base_amount.checked_add(more_base_amount).unwrap();
+    /// Base and quote still held by the open orders account. Once every order
+    /// has been cancelled and settled this is zero, unless a cancel failed or
+    /// fills are still waiting in the event queue to be credited. Read from the
+    /// account rather than kept on the client, as cancels and settles change it.
+    pub fn load_open_orders_totals(&self) -> Result<(u64, u64)> {
+        let market_state =
+            Market::load(&self.market_accounts.market, &self.dex_program.key(), false).unwrap();
+        let open_orders = Market::load_orders_mut(
+            &market_state,
+            &self.market_accounts.open_orders,
+            None,
+            &self.dex_program.key(),
+            None,
+            None,
+        )
+        .unwrap();
+        Ok((open_orders.native_coin_total, open_orders.native_pc_total))
+    }
 
-            pool.base_amount = pool
-                .base_amount
-                .checked_add(more_base_amount)
-                .unwrap()
-                .checked_sub(refund_amount)
-                .unwrap();
-            pool.quote_amount = pool.quote_amount.checked_sub(less_quote_amount).unwrap();
-            pool.cumulative_base_volume = pool
-                .cumulative_base_volume
-                .checked_add(more_base_amount)
+    /// Base and quote held across the pool's vaults and open orders account.
+    fn load_holdings(&self) -> Result<(u64, u64)> {
+        let (base_total, quote_total) = self.load_open_orders_totals()?;
+        let base_vault_amount = token::accessor::amount(&self.base_wallet.to_account_info())?;
+        let quote_vault_amount = token::accessor::amount(&self.quote_wallet.to_account_info())?;
+        Ok((
+            base_vault_amount.checked_add(base_total).unwrap(),
+            quote_vault_amount.checked_add(quote_total).unwrap(),
+        ))
+    }
+
+    /// Best bid and ask resting on the market, in quote lots per base lot. A
+    /// side whose best order is the pool's own reads as `None`, since the
+    /// price behind it can't be seen until the pool's orders are cancelled.
+    pub fn load_best_prices(&self) -> Result<(Option<u64>, Option<u64>)> {
+        let open_orders_key = self.market_accounts.open_orders.key();
+        let is_other = |leaf: &LeafNode| {
+            leaf.owner()
+                .iter()
+                .flat_map(|word| word.to_le_bytes())
+                .ne(open_orders_key.to_bytes())
+        };
+        let market_state =
+            Market::load(&self.market_accounts.market, &self.dex_program.key(), false).unwrap();
+        let bids = market_state
+            .load_bids_mut(&self.market_accounts.bids)
+            .unwrap();
+        let asks = market_state
+            .load_asks_mut(&self.market_accounts.asks)
+            .unwrap();
+        let best_bid_price = bids
+            .find_max()
+            .and_then(|h| bids.get(h))
+            .and_then(|n| n.as_leaf())
+            .filter(|l| is_other(l))
+            .map(|l| u64::from(l.price()));
+        let best_ask_price = asks
+            .find_min()
+            .and_then(|h| asks.get(h))
+            .and_then(|n| n.as_leaf())
+            .filter(|l| is_other(l))
+            .map(|l| u64::from(l.price()));
+        Ok((best_bid_price, best_ask_price))
+    }
+
+    /// Events the market's crank hasn't consumed yet. Fills among them haven't
+    /// been credited to any open orders account.
+    pub fn load_event_queue_len(&self) -> Result<u64> {
+        let market_state =
+            Market::load(&self.market_accounts.market, &self.dex_program.key(), false).unwrap();
+        require!(
+            identity(market_state.event_q)
+                .iter()
+                .flat_map(|word| word.to_le_bytes())
+                .eq(self.market_accounts.event_queue.key().to_bytes()),
+            OpenAmmErrorCode::WrongEventQueueAccount
+        );
+        let (header, _) =
+            strip_header::<EventQueueHeader, Event>(&self.market_accounts.event_queue, false)
                 .unwrap();
-        }
+        Ok(header.count())
+    }
 
-        let mut cancel_ixs = vec![];
-        for order in self.orders.iter() {
-            let cancel_ix = CancelOrderInstructionV2 {
-                side: order.side,
-                order_id: order.order_id,
-            };
-            cancel_ixs.push(cancel_ix);
+    /// Cranks up to `limit` events off the market's event queue, crediting
+    /// fills to `open_orders_accounts`. The dex stops at the first event
+    /// whose owner isn't among them, so they should cover the queue's head.
+    pub fn consume_events(
+        &self,
+        open_orders_accounts: &[AccountInfo<'info>],
+        limit: u16,
+    ) -> Result<()> {
+        let mut accounts = open_orders_accounts
+            .iter()
+            .map(|acc| AccountMeta::new(acc.key(), false))
+            .collect::<Vec<AccountMeta>>();
+        // The dex ignores the fee receivable accounts, but they must be
+        // writable
+        accounts.extend([
+            AccountMeta::new(self.market_accounts.market.key(), false),
+            AccountMeta::new(self.market_accounts.event_queue.key(), false),
+            AccountMeta::new(self.base_wallet.key(), false),
+            AccountMeta::new(self.quote_wallet.key(), false),
+        ]);
+        let mut account_infos = vec![self.dex_program.to_account_info()];
+        account_infos.extend(open_orders_accounts.iter().cloned());
+        account_infos.extend([
+            self.market_accounts.market.clone(),
+            self.market_accounts.event_queue.clone(),
+            self.base_wallet.to_account_info(),
+            self.quote_wallet.to_account_info(),
+        ]);
+
+        let instruction = Instruction {
+            program_id: self.dex_program.key(),
+            data: MarketInstruction::ConsumeEvents(limit).pack(),
+            accounts,
+        };
+        solana_program::program::invoke(&instruction, &account_infos)?;
+        Ok(())
+    }
+
+    /// Whether the market's event queue holds more events than the pool's
+    /// `max_event_queue_backlog`, emitting `EventQueueBacklogEvent` if so.
+    /// Fills stuck behind a backlog can't settle, so what reconciliation sees
+    /// may be stale, and a fresh ladder would only add to the queue.
+    pub fn check_event_queue_backlog(&self) -> Result<bool> {
+        let max_event_queue_backlog = self.pool.load()?.max_event_queue_backlog;
+        if max_event_queue_backlog == 0 {
+            return Ok(false);
+        }
+        let event_queue_len = self.load_event_queue_len()?;
+        if event_queue_len <= max_event_queue_backlog {
+            return Ok(false);
         }
 
-        pool.reset_placed_orders();
+        debug_msg!(
+            self.debug_logging,
+            "openamm:skip_placement event_queue_len={} max={}",
+            event_queue_len,
+            max_event_queue_backlog
+        );
+        let clock = Clock::get()?;
+        emit!(EventQueueBacklogEvent {
+            pool: self.pool.key(),
+            market: self.market_accounts.market.key(),
+            event_queue_len,
+            max_event_queue_backlog,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(true)
+    }
 
-        pool.refund_quote_amount = pool
-            .refund_quote_amount
-            .checked_add(
-                moved_quote_amount
-                    .checked_div(REFUND_DENOMINATOR.into())
-                    .unwrap(),
-            )
+    /// Order slots the open orders account has free.
+    pub fn load_free_order_slots(&self) -> Result<usize> {
+        let market_state =
+            Market::load(&self.market_accounts.market, &self.dex_program.key(), false).unwrap();
+        let open_orders = Market::load_orders_mut(
+            &market_state,
+            &self.market_accounts.open_orders,
+            None,
+            &self.dex_program.key(),
+            None,
+            None,
+        )
+        .unwrap();
+        Ok(identity(open_orders.free_slot_bits).count_ones() as usize)
+    }
+
+    /// Stores what an eviction left stuck in the open orders account, along
+    /// with the vault balances it should eventually be added to.
+    fn record_eviction_exposure(&self) -> Result<()> {
+        let (base_exposure, quote_exposure) = self.load_open_orders_totals()?;
+        let base_vault_amount = token::accessor::amount(&self.base_wallet.to_account_info())?;
+        let quote_vault_amount = token::accessor::amount(&self.quote_wallet.to_account_info())?;
+
+        let mut pool = self.pool.load_mut()?;
+        pool.eviction_base_exposure = base_exposure;
+        pool.eviction_quote_exposure = quote_exposure;
+        pool.eviction_base_baseline = base_vault_amount
+            .checked_add(pool.keeper_owed_base_amount)
             .unwrap();
-        pool.refund_base_amount = pool
-            .refund_base_amount
-            .checked_add(
-                moved_base_amount
-                    .checked_div(REFUND_DENOMINATOR.into())
-                    .unwrap(),
-            )
+        pool.eviction_quote_baseline = quote_vault_amount
+            .checked_add(pool.keeper_owed_quote_amount)
             .unwrap();
+        debug_msg!(
+            self.debug_logging,
+            "openamm:eviction base_exposure={} quote_exposure={}",
+            base_exposure,
+            quote_exposure
+        );
+        Ok(())
+    }
 
-        drop(pool);
-        self.cancel_orders(cancel_ixs)?;
+    /// Moves the protocol's share of the fills just reconciled from the vaults
+    /// to the fee vaults. It can't be settled there directly: the dex's
+    /// `settle_funds` sweeps every free balance to a single pair of wallets
+    /// and takes no amounts, so splitting a settlement would mean routing all
+    /// of it through the fee vaults and moving the LPs' share back. Settling
+    /// into the vaults and then moving only the fees takes at most one
+    /// transfer per side, and none for a side with no fees.
+    fn transfer_protocol_fees(&self, base_amount: u64, quote_amount: u64) -> Result<()> {
+        let market_key = self.pool_market;
+        let pool_type_bytes = (self.pool_type as u8).to_le_bytes();
+        let pool_index_seed = get_pool_index_seed(self.pool_index);
+        let seeds = pool_authority_seeds!(
+            market_key = market_key,
+            pool_type_bytes = pool_type_bytes,
+            index_seed = pool_index_seed,
+            bump = self.pool_bump
+        );
+        let pool_signer = &[&seeds[..]];
 
-        self.settle()?;
+        if base_amount > 0 {
+            let transfer_base_fee_cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                Transfer {
+                    from: self.base_wallet.to_account_info(),
+                    to: self.base_fee_vault.to_account_info(),
+                    authority: self.pool.to_account_info(),
+                },
+                pool_signer,
+            );
+            transfer(transfer_base_fee_cpi_ctx, base_amount)?;
+        }
+
+        if quote_amount > 0 {
+            let transfer_quote_fee_cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                Transfer {
+                    from: self.quote_wallet.to_account_info(),
+                    to: self.quote_fee_vault.to_account_info(),
+                    authority: self.pool.to_account_info(),
+                },
+                pool_signer,
+            );
+            transfer(transfer_quote_fee_cpi_ctx, quote_amount)?;
+        }
 
         Ok(())
     }
@@ -484,11 +938,13 @@ impl<'info> OrderbookClient<'info> {
             vault_signer: self.market_accounts.vault_signer.clone(),
             token_program: self.token_program.to_account_info(),
         };
-        let market_key = self.market_accounts.market.key();
+        let market_key = self.pool_market;
         let pool_type_bytes = (self.pool_type as u8).to_le_bytes();
+        let pool_index_seed = get_pool_index_seed(self.pool_index);
         let seeds = pool_authority_seeds!(
             market_key = market_key,
             pool_type_bytes = pool_type_bytes,
+            index_seed = pool_index_seed,
             bump = self.pool_bump
         );
         let pool_signer = &[&seeds[..]];
@@ -501,439 +957,1702 @@ impl<'info> OrderbookClient<'info> {
         dex::settle_funds(ctx)
     }
 
-    pub fn place_new_orders(
+    /// Computes the orders to place on this market for the pool's reserves,
+    /// after splitting the ladder between markets and applying the pool's
+    /// jitter, oracle band, notional cap and the open orders account's
+    /// `free_slots`, innermost rungs first. `remaining_accounts` must include
+    /// the pool's oracle and anchor pool, if it has them.
+    pub fn build_ladder(
         &self,
-        base_vault: &Account<'info, TokenAccount>,
-        quote_vault: &Account<'info, TokenAccount>,
-    ) -> Result<()> {
-        let mut pool_loader = self.pool.load_init();
-        if pool_loader.is_err() {
-            pool_loader = self.pool.load_mut();
-        }
-        let pool = pool_loader?;
-        match pool.pool_type {
-            PoolType::XYK => {
-                drop(pool);
-                self.place_xyk_orders(base_vault, quote_vault)
-            }
-            PoolType::STABLE => {
-                drop(pool);
-                self.place_stableswap_orders(base_vault, quote_vault)
+        pool: &mut OpenAmmPool,
+        best_bid_price: Option<u64>,
+        best_ask_price: Option<u64>,
+        free_slots: usize,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<Vec<LadderOrder>> {
+        let share_bps = if self.secondary {
+            pool.secondary_share_bps
+        } else if pool.has_secondary_market() {
+            SECONDARY_SHARE_DENOMINATOR
+                .checked_sub(pool.secondary_share_bps)
+                .unwrap()
+        } else {
+            SECONDARY_SHARE_DENOMINATOR
+        };
+        // Fast one-way flow is mostly informed, so quote less into it
+        let deleverage_bps = pool.get_deleverage_bps();
+        let share_bps = (share_bps as u64)
+            .checked_mul(
+                DELEVERAGE_DENOMINATOR
+                    .checked_sub(deleverage_bps)
+                    .unwrap()
+                    .into(),
+            )
+            .unwrap()
+            .checked_div(DELEVERAGE_DENOMINATOR.into())
+            .unwrap() as u16;
+        // Reserves queued for withdraw tickets stay in the vaults
+        let mut quoted_pool = *pool;
+        quoted_pool.base_amount = pool
+            .base_amount
+            .saturating_sub(pool.queued_withdraw_base_amount);
+        quoted_pool.quote_amount = pool
+            .quote_amount
+            .saturating_sub(pool.queued_withdraw_quote_amount);
+        // An anchored pool's own reserves are a poor price signal, so its
+        // ladder is centred on the anchor's mid instead. Without a usable mid
+        // it falls back to its reserves.
+        if pool.has_anchor_pool() {
+            let anchor_info = remaining_accounts
+                .iter()
+                .find(|a| a.key() == pool.anchor_pool)
+                .ok_or(OpenAmmErrorCode::MissingAnchorPoolAccount)?;
+            let anchored_reserves = get_anchor_price(pool, anchor_info, Clock::get()?.slot)
+                .and_then(|price| {
+                    get_anchored_reserves(quoted_pool.base_amount, quoted_pool.quote_amount, price)
+                });
+            debug_msg!(
+                self.debug_logging,
+                "openamm:anchor base={} quote={} anchored={:?}",
+                quoted_pool.base_amount,
+                quoted_pool.quote_amount,
+                anchored_reserves
+            );
+            if let Some((base_amount, quote_amount)) = anchored_reserves {
+                quoted_pool.base_amount = base_amount;
+                quoted_pool.quote_amount = quote_amount;
             }
         }
-    }
-
-    fn place_stableswap_orders(
-        &self,
-        pool_base_vault: &Account<'info, TokenAccount>,
-        pool_quote_vault: &Account<'info, TokenAccount>,
-    ) -> Result<()> {
-        let mut pool_loader = self.pool.load_init();
-        if pool_loader.is_err() {
-            pool_loader = self.pool.load_mut();
-        }
-        let mut pool = pool_loader?;
-        const FEE_DENOMINATOR: u16 = 10_000;
-        const ORDER_DENOMINATOR: u16 = 10_000;
-
-        let base_reserve = pool.base_amount;
-        let quote_reserve = pool.quote_amount;
-
-        let (base_decs_fac, quote_decs_fac) =
-            get_token_decs_fac(pool.base_decimals, pool.quote_decimals);
-
-        let (base_reserve, quote_reserve) = (
-            base_reserve.checked_mul(base_decs_fac).unwrap(),
-            quote_reserve.checked_mul(quote_decs_fac).unwrap(),
+        let mut ladder = scale_ladder(
+            get_ladder(
+                &quoted_pool,
+                self.base_lot_size,
+                self.quote_lot_size,
+                best_bid_price,
+                best_ask_price,
+            )?,
+            share_bps,
         );
-
-        if base_reserve == 0 || quote_reserve == 0 {
-            return Ok(());
+        if pool.jitter_bps > 0 {
+            let slot = Clock::get()?.slot;
+            jitter_ladder(
+                &mut ladder,
+                pool.jitter_bps,
+                &[self.pool.key().as_ref(), &slot.to_le_bytes()],
+            );
         }
 
-        let ask_fee_numerator = FEE_DENOMINATOR
-            .checked_add(STABLESWAP_FEE_BPS.into())
-            .unwrap();
-
-        let bid_fee_numerator = (FEE_DENOMINATOR)
-            .checked_sub(STABLESWAP_FEE_BPS.into())
-            .unwrap();
-
-        let mut place_ixs = vec![];
-
-        let OrderbookClient {
-            best_bid_price,
-            best_ask_price,
-            ..
-        } = self;
-
-        let mut last_ask_base = base_reserve;
-        let mut last_ask_quote = quote_reserve;
-        let mut last_bid_base = base_reserve;
-        let mut last_bid_quote = quote_reserve;
-
-        let d = calc_d(last_ask_base, last_ask_quote, STABLESWAP_AMP_COEFFICIENT).unwrap();
-
-        for i in 0..ORDER_NUMERATORS.len() {
-            let a_size: u64 = (base_reserve as u128)
-                .checked_mul(ORDER_NUMERATORS[i].into())
-                .unwrap()
-                .checked_div(ORDER_DENOMINATOR.into())
-                .unwrap()
-                .try_into()
-                .unwrap();
-            let end_a_amount = last_ask_base.checked_sub(a_size).unwrap_or(0);
-
-            if end_a_amount > 0 && a_size > 0 {
-                let b_size = calc_dy(
-                    last_ask_base,
-                    last_ask_quote,
-                    STABLESWAP_AMP_COEFFICIENT,
-                    d,
-                    a_size,
-                )
-                .unwrap_or(0);
-                let end_b_amount = last_ask_quote + b_size;
-
-                let (a_size, b_size) = (a_size / base_decs_fac, b_size / quote_decs_fac);
-
-                let a_lots = a_size.checked_div(self.base_lot_size).unwrap();
-
-                let mut limit_price: u64 = (b_size as u128)
-                    .checked_mul(ask_fee_numerator.into())
-                    .unwrap()
-                    .checked_mul(self.base_lot_size.into())
-                    .unwrap()
-                    .checked_div(a_size.into())
-                    .unwrap()
-                    .checked_div(FEE_DENOMINATOR.into())
-                    .unwrap()
-                    .checked_div(self.quote_lot_size.into())
-                    .unwrap()
-                    .try_into()
-                    .unwrap();
-
-                last_ask_base = end_a_amount;
-                last_ask_quote = end_b_amount;
-
-                if limit_price != 0 && a_lots != 0 && b_size != 0 {
-                    if best_bid_price.is_some() && limit_price <= best_bid_price.unwrap() {
-                        limit_price = best_bid_price.unwrap().checked_add(1).unwrap();
-                    }
-
-                    let client_order_id = pool.client_order_id;
-                    let place_ix = NewOrderInstructionV3 {
-                        side: Side::Ask,
-                        limit_price: NonZeroU64::new(limit_price).unwrap(),
-                        max_coin_qty: NonZeroU64::new(a_lots).unwrap(),
-                        max_native_pc_qty_including_fees: NonZeroU64::new(b_size).unwrap(),
-                        self_trade_behavior: SelfTradeBehavior::DecrementTake,
-                        order_type: OrderType::PostOnly,
-                        client_order_id: pool.client_order_id,
-                        limit: 0,
-                        max_ts: i64::MAX,
-                    };
-                    pool.placed_asks[i] = PlacedOrder {
-                        limit_price: place_ix.limit_price.into(),
-                        base_qty: place_ix.max_coin_qty.into(),
-                        max_native_quote_qty_including_fees: place_ix
-                            .max_native_pc_qty_including_fees
-                            .into(),
-                        client_order_id,
-                    };
-
-                    place_ixs.push(place_ix);
-                    pool.client_order_id += 1;
+        // A book walked away from fair value between cranks would otherwise
+        // have the ladder quote straight into it. Without a usable oracle
+        // price nothing is quoted.
+        if pool.has_oracle() {
+            let oracle_info = remaining_accounts
+                .iter()
+                .find(|a| a.key() == pool.oracle)
+                .ok_or(OpenAmmErrorCode::MissingOracleAccount)?;
+            let oracle_price = get_oracle_price(
+                &pool,
+                oracle_info,
+                self.base_lot_size,
+                self.quote_lot_size,
+                Clock::get()?.slot,
+            );
+            let band_bps = pool.oracle_band_bps;
+            let rungs = ladder.len();
+            ladder.retain(|order| match oracle_price {
+                Some(oracle_price) => {
+                    is_within_oracle_band(oracle_price, band_bps, order.side, order.limit_price)
                 }
-            }
+                None => false,
+            });
+            debug_msg!(
+                self.debug_logging,
+                "openamm:oracle price={:?} band_bps={} skipped_rungs={}",
+                oracle_price,
+                band_bps,
+                rungs - ladder.len()
+            );
         }
 
-        for i in 0..ORDER_NUMERATORS.len() - 1 {
-            let b_size: u64 = (quote_reserve as u128)
-                .checked_mul(ORDER_NUMERATORS[i].into())
-                .unwrap()
-                .checked_div(ORDER_DENOMINATOR.into())
+        // Innermost rungs first, an ask and a bid at a time. Orders are placed
+        // in this order, so if placement stops partway the book is left with
+        // a tight two-sided quote rather than one full side. The caps below
+        // also keep the innermost rungs.
+        ladder.sort_by_key(|o| o.rung);
+
+        // Quote the innermost rungs up to the pool's notional limit, split
+        // between markets like the ladder itself, and leave the rest of the
+        // reserves off the book.
+        if pool.max_quote_at_risk > 0 {
+            let max_quote_at_risk = (pool.max_quote_at_risk as u128)
+                .checked_mul(share_bps.into())
                 .unwrap()
-                .try_into()
+                .checked_div(SECONDARY_SHARE_DENOMINATOR.into())
                 .unwrap();
-
-            let end_b_amount = last_bid_quote.checked_sub(b_size).unwrap_or_else(|| 0);
-
-            if end_b_amount > 0 && b_size > 0 {
-                let a_size = calc_dy(
-                    last_bid_quote,
-                    last_bid_base,
-                    STABLESWAP_AMP_COEFFICIENT,
-                    d,
-                    b_size,
-                )
-                .unwrap_or(0);
-                let end_a_amount = last_bid_base + a_size;
-
-                let (a_size, b_size) = (a_size / base_decs_fac, b_size / quote_decs_fac);
-
-                let a_lots = a_size.checked_div(self.base_lot_size).unwrap();
-
-                let mut limit_price: u64 = (b_size as u128)
-                    .checked_mul(bid_fee_numerator.into())
-                    .unwrap()
-                    .checked_mul(self.base_lot_size.into())
-                    .unwrap()
-                    .checked_div(a_size.into())
-                    .unwrap()
-                    .checked_div(FEE_DENOMINATOR.into())
-                    .unwrap()
-                    .checked_div(self.quote_lot_size.into())
-                    .unwrap()
-                    .try_into()
+            let mut quote_at_risk: u128 = 0;
+            let rungs = ladder.iter().position(|order| {
+                quote_at_risk = quote_at_risk
+                    .checked_add(get_order_notional(order, self.quote_lot_size).into())
                     .unwrap();
+                quote_at_risk > max_quote_at_risk
+            });
+            if let Some(rungs) = rungs {
+                debug_msg!(
+                    self.debug_logging,
+                    "openamm:skip max_quote_at_risk={} capped_rungs={}",
+                    max_quote_at_risk,
+                    ladder.len() - rungs
+                );
+                ladder.truncate(rungs);
+            }
+        }
 
-                last_bid_base = end_a_amount;
-                last_bid_quote = end_b_amount;
+        // The dex drops a post-only order that would cross without an error,
+        // so the pool would record it as resting and reconcile it as filled
+        // on the next refresh. Leave those rungs off instead.
+        let rungs = ladder.len();
+        retain_uncrossed_orders(&mut ladder, best_bid_price, best_ask_price);
+        pool.crossed_rungs = (rungs - ladder.len()) as u8;
+        if pool.crossed_rungs > 0 {
+            debug_msg!(
+                self.debug_logging,
+                "openamm:skip best_bid={:?} best_ask={:?} crossed_rungs={}",
+                best_bid_price,
+                best_ask_price,
+                pool.crossed_rungs
+            );
+        }
 
-                if limit_price != 0 && a_lots != 0 && b_size != 0 {
-                    if best_ask_price.is_some()
-                        && limit_price >= best_ask_price.unwrap()
-                        && best_ask_price.unwrap() > 1
-                    {
-                        limit_price = best_ask_price.unwrap().checked_sub(1).unwrap();
-                    }
+        // Every resting order takes one of the open orders account's slots,
+        // and slots held by uncranked fills aren't freed by cancelling. Rather
+        // than fail on the first order that doesn't fit, quote the innermost
+        // rungs that do.
+        let rungs = ladder.len();
+        if ladder.len() > free_slots {
+            ladder.truncate(free_slots);
+            debug_msg!(
+                self.debug_logging,
+                "openamm:skip free_slots={} skipped_rungs={}",
+                free_slots,
+                rungs - ladder.len()
+            );
+        }
 
-                    let client_order_id = pool.client_order_id;
-                    let place_ix = NewOrderInstructionV3 {
-                        side: Side::Bid,
-                        limit_price: NonZeroU64::new(limit_price).unwrap(),
-                        max_coin_qty: NonZeroU64::new(a_lots).unwrap(),
-                        max_native_pc_qty_including_fees: NonZeroU64::new(b_size).unwrap(),
-                        self_trade_behavior: SelfTradeBehavior::DecrementTake,
-                        order_type: OrderType::PostOnly,
-                        client_order_id,
-                        limit: 0,
-                        max_ts: i64::MAX,
-                    };
-                    pool.placed_bids[i] = PlacedOrder {
-                        limit_price: place_ix.limit_price.into(),
-                        base_qty: place_ix.max_coin_qty.into(),
-                        max_native_quote_qty_including_fees: place_ix
-                            .max_native_pc_qty_including_fees
-                            .into(),
-                        client_order_id,
-                    };
-
-                    place_ixs.push(place_ix);
-                    pool.client_order_id += 1;
-                }
+        // Placements cost more on markets with deep slabs, and running out of
+        // compute would fail the whole instruction, so likewise only quote
+        // what the pool's compute budget fits
+        let cancel_cpis = (self.orders.len() + CANCEL_BATCH_SIZE - 1) / CANCEL_BATCH_SIZE;
+        if let Some(budget_orders) = pool.get_compute_budget_orders(cancel_cpis as u64) {
+            if ladder.len() > budget_orders {
+                debug_msg!(
+                    self.debug_logging,
+                    "openamm:skip budget_orders={} skipped_rungs={}",
+                    budget_orders,
+                    ladder.len() - budget_orders
+                );
+                ladder.truncate(budget_orders);
             }
         }
-        drop(pool);
+        pool.skipped_rungs = (rungs - ladder.len()) as u8;
 
-        self.place_orders(
-            place_ixs,
-            pool_base_vault.to_account_info(),
-            pool_quote_vault.to_account_info(),
-        )
-        .unwrap();
-        Ok(())
+        Ok(ladder)
     }
 
-    fn place_xyk_orders(
+    /// Places the pool's ladder. `remaining_accounts` must include the pool's
+    /// oracle and anchor pool, if it has them.
+    pub fn place_new_orders(
         &self,
-        pool_base_vault: &Account<'info, TokenAccount>,
-        pool_quote_vault: &Account<'info, TokenAccount>,
-    ) -> Result<()> {
+        base_vault: &Account<'info, TokenAccount>,
+        quote_vault: &Account<'info, TokenAccount>,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<LadderPlacement> {
         let mut pool_loader = self.pool.load_init();
         if pool_loader.is_err() {
             pool_loader = self.pool.load_mut();
         }
         let mut pool = pool_loader?;
-        const FEE_DENOMINATOR: u16 = 10_000;
-        const ORDER_DENOMINATOR: u16 = 10_000;
-
-        let ask_fee_numerator = FEE_DENOMINATOR.checked_add(LP_FEE_BPS.into()).unwrap();
-
-        let bid_fee_numerator = (FEE_DENOMINATOR).checked_sub(LP_FEE_BPS.into()).unwrap();
-
-        let base_reserve = pool.base_amount;
-        let quote_reserve = pool.quote_amount;
-
-        if base_reserve == 0 || quote_reserve == 0 {
-            return Ok(());
+        if pool.safe_mode || pool.paused {
+            return Ok(LadderPlacement::default());
+        }
+        let clock = Clock::get()?;
+        if !pool.is_quoting_scheduled(clock.slot, clock.unix_timestamp) {
+            debug_msg!(
+                self.debug_logging,
+                "openamm:skip_placement cooldown_until_slot={} quoting_open_secs={} quoting_close_secs={}",
+                pool.cooldown_until_slot,
+                pool.quoting_open_secs,
+                pool.quoting_close_secs
+            );
+            return Ok(LadderPlacement::default());
         }
 
-        let mut place_ixs = vec![];
+        // The pool's own orders have been cancelled by now, so this is the
+        // rest of the market's top of book.
+        let (best_bid_price, best_ask_price) = self.load_best_prices()?;
+        if !self.secondary {
+            pool.record_book_top(best_bid_price, best_ask_price, Clock::get()?.slot);
+            debug_msg!(
+                self.debug_logging,
+                "openamm:book_top best_bid={:?} best_ask={:?} mid={}",
+                best_bid_price,
+                best_ask_price,
+                pool.mid_price
+            );
+        }
 
-        let OrderbookClient {
+        let ladder = self.build_ladder(
+            &mut pool,
             best_bid_price,
             best_ask_price,
-            ..
-        } = self;
+            self.load_free_order_slots()?,
+            remaining_accounts,
+        )?;
 
-        let mut last_ask_base = base_reserve;
-        let mut last_ask_quote = quote_reserve;
-        let mut last_bid_base = base_reserve;
-        let mut last_bid_quote = quote_reserve;
-
-        for i in 0..ORDER_NUMERATORS.len() {
-            let a_size: u64 = (base_reserve as u128)
-                .checked_mul(ORDER_NUMERATORS[i].into())
-                .unwrap()
-                .checked_div(ORDER_DENOMINATOR.into())
+        let mut place_ixs = vec![];
+        let mut client_order_ids = vec![];
+        for order in ladder.iter() {
+            let client_order_id = if self.secondary {
+                pool.secondary_client_order_id
+            } else {
+                pool.client_order_id
+            };
+            let place_ix = NewOrderInstructionV3 {
+                side: order.side,
+                limit_price: NonZeroU64::new(order.limit_price).unwrap(),
+                max_coin_qty: NonZeroU64::new(order.base_qty).unwrap(),
+                max_native_pc_qty_including_fees: NonZeroU64::new(
+                    order.max_native_quote_qty_including_fees,
+                )
+                .unwrap(),
+                self_trade_behavior: SelfTradeBehavior::DecrementTake,
+                order_type: if order.take {
+                    OrderType::ImmediateOrCancel
+                } else {
+                    OrderType::PostOnly
+                },
+                client_order_id,
+                limit: 0,
+                max_ts: i64::MAX,
+            };
+            place_ixs.push(place_ix);
+            client_order_ids.push(client_order_id);
+            if self.secondary {
+                pool.secondary_client_order_id += 1;
+            } else {
+                pool.client_order_id += 1;
+            }
+        }
+        if !self.secondary {
+            pool.last_refresh_slot = clock.slot;
+            pool.record_reserve_checkpoint(clock.slot);
+            let tvl_quote_amount = get_base_value_in_quote(&pool, pool.base_amount)
+                .checked_add(pool.quote_amount)
+                .unwrap();
+            pool.record_daily_tvl(tvl_quote_amount, clock.unix_timestamp);
+        }
+        drop(pool);
+
+        // Resting orders only move funds between the vaults and the open
+        // orders account. Whatever IOC orders fill shows up as a change in the
+        // two together.
+        let has_take = ladder.iter().any(|o| o.take);
+        let holdings_before = if has_take {
+            self.load_holdings()?
+        } else {
+            (0, 0)
+        };
+
+        self.place_orders(
+            place_ixs,
+            base_vault.to_account_info(),
+            quote_vault.to_account_info(),
+        )?;
+
+        let holdings_after = if has_take {
+            self.load_holdings()?
+        } else {
+            (0, 0)
+        };
+
+        let mut pool_loader = self.pool.load_init();
+        if pool_loader.is_err() {
+            pool_loader = self.pool.load_mut();
+        }
+        let mut pool = pool_loader?;
+        if has_take {
+            pool.base_amount = pool
+                .base_amount
+                .checked_add(holdings_after.0)
                 .unwrap()
-                .try_into()
+                .checked_sub(holdings_before.0)
+                .unwrap();
+            pool.quote_amount = pool
+                .quote_amount
+                .checked_add(holdings_after.1)
+                .unwrap()
+                .checked_sub(holdings_before.1)
                 .unwrap();
-            let k = (last_ask_base as u128)
-                .checked_mul(last_ask_quote.into())
+            debug_msg!(
+                self.debug_logging,
+                "openamm:take base_before={} base_after={} quote_before={} quote_after={}",
+                holdings_before.0,
+                holdings_after.0,
+                holdings_before.1,
+                holdings_after.1
+            );
+
+            // Estimated from the quote that changed hands, net of the fee
+            let taker_fee_amount = get_dex_fee(
+                holdings_before.1.abs_diff(holdings_after.1),
+                DEX_TAKER_FEE_TENTH_BPS,
+            );
+            pool.dex_taker_fees_quote_amount = pool
+                .dex_taker_fees_quote_amount
+                .checked_add(taker_fee_amount)
                 .unwrap();
-            let end_a_amount = last_ask_base.checked_sub(a_size).unwrap_or_else(|| 0);
+            let clock = Clock::get()?;
+            emit!(DexFeesEvent {
+                pool: self.pool.key(),
+                market: self.market_accounts.market.key(),
+                maker_rebate_amount: 0,
+                taker_fee_amount,
+                slot: clock.slot,
+                unix_timestamp: clock.unix_timestamp,
+            });
+        }
+        let mut placed_asks = vec![];
+        let mut placed_bids = vec![];
+        let mut placement = LadderPlacement::default();
+        let mut committed_base_amount: u64 = 0;
+        let mut committed_quote_amount: u64 = 0;
+        for (order, client_order_id) in ladder.iter().zip(client_order_ids) {
+            // IOC orders never rest, so there's nothing to reconcile later
+            if order.take {
+                continue;
+            }
 
-            if end_a_amount > 0 {
-                let end_b_amount: u64 = k
-                    .checked_div(end_a_amount.into())
-                    .unwrap()
-                    .try_into()
-                    .unwrap();
-                let delta_b = end_b_amount.checked_sub(last_ask_quote).unwrap();
-                let b_size = delta_b;
-                let a_lots = a_size.checked_div(self.base_lot_size).unwrap();
+            let notional = get_order_notional(order, self.quote_lot_size);
+            placement.placed_notional = placement.placed_notional.checked_add(notional).unwrap();
+            // A rung moved if none of the cancelled orders sat at its price
+            if !self
+                .orders
+                .iter()
+                .any(|o| o.side == order.side && o.limit_price == order.limit_price)
+            {
+                placement.repriced_notional =
+                    placement.repriced_notional.checked_add(notional).unwrap();
+            }
 
-                let mut limit_price: u64 = (delta_b as u128)
-                    .checked_mul(self.base_lot_size.into())
-                    .unwrap()
-                    .checked_mul(ask_fee_numerator.into())
-                    .unwrap()
-                    .checked_div(a_size.into())
-                    .unwrap()
-                    .checked_div(self.quote_lot_size.into())
-                    .unwrap()
-                    .checked_div(FEE_DENOMINATOR.into())
-                    .unwrap()
-                    .try_into()
-                    .unwrap();
+            let placed_order = PlacedOrder {
+                limit_price: order.limit_price,
+                base_qty: order.base_qty,
+                max_native_quote_qty_including_fees: order.max_native_quote_qty_including_fees,
+                client_order_id,
+                fee_bps: order.fee_bps.into(),
+            };
+            let placed_rung = PlacedRung {
+                rung: order.rung as u8,
+                limit_price: order.limit_price,
+                base_qty: order.base_qty,
+                client_order_id,
+            };
+            match (order.side, self.secondary) {
+                (Side::Ask, false) => pool.placed_asks.set(order.rung, placed_order),
+                (Side::Bid, false) => pool.placed_bids.set(order.rung, placed_order),
+                (Side::Ask, true) => pool.secondary_placed_asks.set(order.rung, placed_order),
+                (Side::Bid, true) => pool.secondary_placed_bids.set(order.rung, placed_order),
+            }
+            match order.side {
+                Side::Ask => {
+                    committed_base_amount = committed_base_amount
+                        .checked_add(order.base_qty.checked_mul(self.base_lot_size).unwrap())
+                        .unwrap();
+                    placed_asks.push(placed_rung);
+                }
+                Side::Bid => {
+                    committed_quote_amount = committed_quote_amount
+                        .checked_add(order.max_native_quote_qty_including_fees)
+                        .unwrap();
+                    placed_bids.push(placed_rung);
+                }
+            }
+        }
+        if !self.secondary {
+            pool.base_utilization_bps =
+                get_utilization_bps(committed_base_amount, pool.base_amount);
+            pool.quote_utilization_bps =
+                get_utilization_bps(committed_quote_amount, pool.quote_amount);
+        }
+        drop(pool);
 
-                last_ask_base = end_a_amount;
-                last_ask_quote = end_b_amount;
+        let clock = Clock::get()?;
+        emit!(OrdersPlacedEvent {
+            pool: self.pool.key(),
+            asks: placed_asks,
+            bids: placed_bids,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
 
-                if limit_price != 0 && a_lots != 0 && b_size != 0 {
-                    if best_bid_price.is_some() && limit_price <= best_bid_price.unwrap() {
-                        limit_price = best_bid_price.unwrap().checked_add(1).unwrap();
-                    }
+        Ok(placement)
+    }
+}
 
-                    let client_order_id = pool.client_order_id;
-                    let place_ix = NewOrderInstructionV3 {
-                        side: Side::Ask,
-                        limit_price: NonZeroU64::new(limit_price).unwrap(),
-                        max_coin_qty: NonZeroU64::new(a_lots).unwrap(),
-                        max_native_pc_qty_including_fees: NonZeroU64::new(b_size).unwrap(),
-                        self_trade_behavior: SelfTradeBehavior::DecrementTake,
-                        order_type: OrderType::PostOnly,
-                        client_order_id,
-                        limit: 0,
-                        max_ts: i64::MAX,
-                    };
-                    pool.placed_asks[i] = PlacedOrder {
-                        limit_price: place_ix.limit_price.into(),
-                        base_qty: place_ix.max_coin_qty.into(),
-                        max_native_quote_qty_including_fees: place_ix
-                            .max_native_pc_qty_including_fees
-                            .into(),
-                        client_order_id,
-                    };
-
-                    place_ixs.push(place_ix);
-                    pool.client_order_id += 1;
-                }
+/// What reconciling a market's placed orders did to the pool.
+pub struct Reconciliation {
+    /// Quote notional of the fills credited.
+    pub reconciled_quote_amount: u64,
+    pub protocol_fee_base_amount: u64,
+    pub protocol_fee_quote_amount: u64,
+    /// The pool's orders still on the book, all of which a refresh cancels.
+    pub cancel_client_order_ids: Vec<u64>,
+    /// Fills against the placed ladder, in the order they were credited.
+    pub fills: Vec<ReconciledFill>,
+}
+
+/// A taker's trade against one of the pool's rungs. Fees are in the token
+/// the taker paid in and split the fill's spread, and the reserves are the
+/// pool's after the fill.
+pub struct ReconciledFill {
+    /// Whether the rung was an ask, i.e. the taker paid quote for base.
+    pub sold_base: bool,
+    pub rung: u8,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub limit_price: u64,
+    pub lp_fee: u64,
+    pub protocol_fee: u64,
+    pub keeper_fee: u64,
+    pub base_amount: u64,
+    pub quote_amount: u64,
+}
+
+impl ReconciledFill {
+    /// The fill's notional, whichever side the taker was on.
+    pub fn notional_quote_amount(&self) -> u64 {
+        if self.sold_base {
+            self.amount_in
+        } else {
+            self.amount_out
+        }
+    }
+}
+
+/// How a fill's spread is shared out, in the fill's incoming token.
+pub struct FillFees {
+    pub lp_fee: u64,
+    pub protocol_fee: u64,
+    pub keeper_fee: u64,
+}
+
+/// Splits `spread_amount`, what a fill of `amount_in` earned over the curve,
+/// between the protocol, the keeper refund and LPs. The protocol's and
+/// keeper's cuts are charged on `amount_in` but come out of the spread, the
+/// protocol's first, so on thin spreads they shrink rather than eat into LP
+/// principal. The three always add up to the spread.
+pub fn split_fill_spread(amount_in: u64, spread_amount: u64, keeper_refund_bps: u16) -> FillFees {
+    const REFUND_DENOMINATOR: u16 = 10_000;
+    const PROTOCOL_FEE_DENOMINATOR: u16 = 10_000;
+
+    let protocol_fee = cmp::min(
+        amount_in
+            .checked_mul(PROTOCOL_FEE_BPS.into())
+            .unwrap()
+            .checked_div(PROTOCOL_FEE_DENOMINATOR.into())
+            .unwrap(),
+        spread_amount,
+    );
+    let keeper_fee = cmp::min(
+        amount_in
+            .checked_mul(keeper_refund_bps.into())
+            .unwrap()
+            .checked_div(REFUND_DENOMINATOR.into())
+            .unwrap(),
+        spread_amount - protocol_fee,
+    );
+    FillFees {
+        lp_fee: spread_amount - protocol_fee - keeper_fee,
+        protocol_fee,
+        keeper_fee,
+    }
+}
+
+/// Where in the ladder the placed order with `client_order_id` sits.
+fn get_rung(placed: &PlacedOrders, client_order_id: u64) -> u8 {
+    placed
+        .as_slice()
+        .iter()
+        .position(|o| o.client_order_id == client_order_id)
+        .unwrap() as u8
+}
+
+/// The work of `OrderbookClient::reconcile`, given the pool's orders still on
+/// the book and the market's lot sizes.
+pub fn reconcile_orders(
+    orders: &[CurrentOrder],
+    base_lot_size: u64,
+    quote_lot_size: u64,
+    secondary: bool,
+    debug_logging: bool,
+    pool: &mut OpenAmmPool,
+) -> Result<Reconciliation> {
+    const FEE_DENOMINATOR: u16 = 10_000;
+
+    let base_reserve_before = pool.base_amount;
+    let quote_reserve_before = pool.quote_amount;
+
+    let curr_asks = orders
+        .iter()
+        .filter(|o| o.side == Side::Ask)
+        .cloned()
+        .collect::<Vec<CurrentOrder>>();
+
+    let curr_bids = orders
+        .iter()
+        .filter(|o| o.side == Side::Bid)
+        .cloned()
+        .collect::<Vec<CurrentOrder>>();
+
+    let (placed_asks, placed_bids) = if secondary {
+        (pool.secondary_placed_asks, pool.secondary_placed_bids)
+    } else {
+        (pool.placed_asks, pool.placed_bids)
+    };
+
+    let non_zero_asks = placed_asks
+        .as_slice()
+        .iter()
+        .filter(|o| o.base_qty != 0)
+        .cloned()
+        .collect::<Vec<PlacedOrder>>();
+
+    let non_zero_bids = placed_bids
+        .as_slice()
+        .iter()
+        .filter(|o| o.base_qty != 0)
+        .cloned()
+        .collect::<Vec<PlacedOrder>>();
+
+    let mut refunded_base_amount: u64 = 0;
+    let mut refunded_quote_amount: u64 = 0;
+    let mut reconciled_quote_amount: u64 = 0;
+    let mut protocol_fee_base_amount: u64 = 0;
+    let mut protocol_fee_quote_amount: u64 = 0;
+    let mut fills = vec![];
+
+    for (i, placed_ask) in non_zero_asks.iter().enumerate() {
+        let placed_base_amount = placed_ask.base_qty.checked_mul(base_lot_size).unwrap();
+        let found_curr_ask = curr_asks
+            .iter()
+            .find(|&&o| o.client_order_id == placed_ask.client_order_id);
+
+        let less_base_amount = if let Some(found_curr_ask) = found_curr_ask {
+            let curr_base_amount = found_curr_ask.base_qty.checked_mul(base_lot_size).unwrap();
+
+            placed_base_amount
+                .checked_sub(curr_base_amount)
+                .ok_or(OpenAmmErrorCode::LotConversionUnderflow)?
+        } else {
+            if i == non_zero_asks.len() - 1 {
+                pool.mm_active = false;
             }
+            placed_base_amount
+        };
+
+        let more_quote_amount = less_base_amount
+            .checked_mul(placed_ask.limit_price)
+            .unwrap()
+            .checked_mul(quote_lot_size)
+            .unwrap()
+            .checked_div(base_lot_size)
+            .unwrap();
+
+        let rung = get_rung(&placed_asks, placed_ask.client_order_id);
+        // The spread the order was priced with, which is what LPs earn on
+        // the fill even if the pool's fees have changed since
+        let rung_fee_bps = placed_ask.fee_bps as u16;
+        let FillFees {
+            lp_fee,
+            protocol_fee: protocol_fee_amount,
+            keeper_fee: refund_amount,
+        } = split_fill_spread(
+            more_quote_amount,
+            (more_quote_amount as u128)
+                .checked_mul(rung_fee_bps.into())
+                .unwrap()
+                .checked_div(FEE_DENOMINATOR.checked_add(rung_fee_bps).unwrap().into())
+                .unwrap() as u64,
+            pool.keeper_refund_bps,
+        );
+
+        debug_msg!(
+            debug_logging,
+            "openamm:reconcile side=ask client_order_id={} found={} base_out={} quote_in={} refund={}",
+            placed_ask.client_order_id,
+            found_curr_ask.is_some(),
+            less_base_amount,
+            more_quote_amount,
+            refund_amount
+        );
+
+        pool.base_amount = pool.base_amount.checked_sub(less_base_amount).unwrap();
+        pool.quote_amount = pool
+            .quote_amount
+            .checked_add(more_quote_amount)
+            .unwrap()
+            .checked_sub(refund_amount)
+            .unwrap()
+            .checked_sub(protocol_fee_amount)
+            .unwrap();
+        protocol_fee_quote_amount = protocol_fee_quote_amount
+            .checked_add(protocol_fee_amount)
+            .unwrap();
+
+        refunded_quote_amount = refunded_quote_amount.checked_add(refund_amount).unwrap();
+        reconciled_quote_amount = reconciled_quote_amount
+            .checked_add(more_quote_amount)
+            .unwrap();
+        pool.cumulative_quote_volume = pool
+            .cumulative_quote_volume
+            .checked_add(more_quote_amount)
+            .unwrap();
+
+        if less_base_amount > 0 {
+            fills.push(ReconciledFill {
+                sold_base: true,
+                rung,
+                amount_in: more_quote_amount,
+                amount_out: less_base_amount,
+                limit_price: placed_ask.limit_price,
+                lp_fee,
+                protocol_fee: protocol_fee_amount,
+                keeper_fee: refund_amount,
+                base_amount: pool.base_amount,
+                quote_amount: pool.quote_amount,
+            });
         }
+    }
+
+    for (i, placed_bid) in non_zero_bids.iter().enumerate() {
+        let max_base_qty = placed_bid
+            .max_native_quote_qty_including_fees
+            .checked_div(placed_bid.limit_price)
+            .unwrap();
+
+        let base_qty = cmp::min(max_base_qty, placed_bid.base_qty);
+        let placed_base_amount = base_qty.checked_mul(base_lot_size).unwrap();
+
+        let found_curr_bid = curr_bids
+            .iter()
+            .find(|&&o| o.client_order_id == placed_bid.client_order_id);
+
+        let more_base_amount = if let Some(found_curr_bid) = found_curr_bid {
+            let curr_base_amount = found_curr_bid.base_qty.checked_mul(base_lot_size).unwrap();
+            placed_base_amount
+                .checked_sub(curr_base_amount)
+                .ok_or(OpenAmmErrorCode::LotConversionUnderflow)?
+        } else {
+            if i == non_zero_bids.len() - 1 {
+                pool.mm_active = false
+            }
+            placed_base_amount
+        };
 
-        for i in 0..ORDER_NUMERATORS.len() - 1 {
-            let b_size: u64 = (quote_reserve as u128)
-                .checked_mul(ORDER_NUMERATORS[i].into())
+        let less_quote_amount = more_base_amount
+            .checked_mul(placed_bid.limit_price)
+            .unwrap()
+            .checked_mul(quote_lot_size)
+            .unwrap()
+            .checked_div(base_lot_size)
+            .unwrap();
+
+        let rung = get_rung(&placed_bids, placed_bid.client_order_id);
+        // The spread the order was priced with, which is what LPs earn on
+        // the fill even if the pool's fees have changed since
+        let rung_fee_bps = placed_bid.fee_bps as u16;
+        let FillFees {
+            lp_fee,
+            protocol_fee: protocol_fee_amount,
+            keeper_fee: refund_amount,
+        } = split_fill_spread(
+            more_base_amount,
+            (more_base_amount as u128)
+                .checked_mul(rung_fee_bps.into())
                 .unwrap()
-                .checked_div(ORDER_DENOMINATOR.into())
+                .checked_div(FEE_DENOMINATOR.into())
+                .unwrap() as u64,
+            pool.keeper_refund_bps,
+        );
+
+        debug_msg!(
+            debug_logging,
+            "openamm:reconcile side=bid client_order_id={} found={} base_in={} quote_out={} refund={}",
+            placed_bid.client_order_id,
+            found_curr_bid.is_some(),
+            more_base_amount,
+            less_quote_amount,
+            refund_amount
+        );
+
+        refunded_base_amount = refunded_base_amount.checked_add(refund_amount).unwrap();
+        reconciled_quote_amount = reconciled_quote_amount
+            .checked_add(less_quote_amount)
+            .unwrap();
+
+        pool.base_amount = pool
+            .base_amount
+            .checked_add(more_base_amount)
+            .unwrap()
+            .checked_sub(refund_amount)
+            .unwrap()
+            .checked_sub(protocol_fee_amount)
+            .unwrap();
+        protocol_fee_base_amount = protocol_fee_base_amount
+            .checked_add(protocol_fee_amount)
+            .unwrap();
+        pool.quote_amount = pool.quote_amount.checked_sub(less_quote_amount).unwrap();
+        pool.cumulative_base_volume = pool
+            .cumulative_base_volume
+            .checked_add(more_base_amount)
+            .unwrap();
+
+        if more_base_amount > 0 {
+            fills.push(ReconciledFill {
+                sold_base: false,
+                rung,
+                amount_in: more_base_amount,
+                amount_out: less_quote_amount,
+                limit_price: placed_bid.limit_price,
+                lp_fee,
+                protocol_fee: protocol_fee_amount,
+                keeper_fee: refund_amount,
+                base_amount: pool.base_amount,
+                quote_amount: pool.quote_amount,
+            });
+        }
+    }
+
+    let cancel_client_order_ids = orders
+        .iter()
+        .map(|o| o.client_order_id)
+        .collect::<Vec<u64>>();
+
+    if secondary {
+        pool.reset_secondary_placed_orders();
+    } else {
+        pool.reset_placed_orders();
+    }
+
+    // Credited as the sum of what each order took out of reserves, since
+    // rounding the combined fill instead can owe keepers more than was set aside
+    pool.refund_quote_amount = pool
+        .refund_quote_amount
+        .checked_add(refunded_quote_amount)
+        .unwrap();
+    pool.refund_base_amount = pool
+        .refund_base_amount
+        .checked_add(refunded_base_amount)
+        .unwrap();
+    pool.scale_queued_withdrawals(base_reserve_before, quote_reserve_before);
+
+    Ok(Reconciliation {
+        reconciled_quote_amount,
+        protocol_fee_base_amount,
+        protocol_fee_quote_amount,
+        cancel_client_order_ids,
+        fills,
+    })
+}
+
+/// Quote notional of a freshly placed ladder, and of the part of it at
+/// prices the pool wasn't already quoting.
+#[derive(Default, Clone, Copy)]
+pub struct LadderPlacement {
+    pub placed_notional: u64,
+    pub repriced_notional: u64,
+}
+
+/// `committed` as a share of `reserve`, in bps, capped at 100%.
+fn get_utilization_bps(committed: u64, reserve: u64) -> u16 {
+    if reserve == 0 {
+        return 0;
+    }
+    cmp::min(
+        (committed as u128)
+            .checked_mul(UTILIZATION_DENOMINATOR.into())
+            .unwrap()
+            .checked_div(reserve.into())
+            .unwrap(),
+        UTILIZATION_DENOMINATOR.into(),
+    ) as u16
+}
+
+/// Share of the accrued keeper refund paid out for a refresh. A refresh that
+/// reconciled or repriced at least the ladder's notional earns all of it, a
+/// no-op earns nothing, and whatever isn't paid stays accrued.
+pub fn get_keeper_payout(accrued: u64, useful_notional: u64, placed_notional: u64) -> u64 {
+    if useful_notional == 0 {
+        return 0;
+    }
+    if useful_notional >= placed_notional {
+        return accrued;
+    }
+    (accrued as u128)
+        .checked_mul(useful_notional.into())
+        .unwrap()
+        .checked_div(placed_notional.into())
+        .unwrap()
+        .try_into()
+        .unwrap()
+}
+
+/// Accrues a refresh payout to `operator`'s keeper account when it is passed
+/// as the first remaining account and has the pool registered, leaving the
+/// tokens in the pool's vaults until claimed. Returns whether it was accrued;
+/// otherwise the caller transfers the payout directly.
+pub fn credit_keeper_payout<'info>(
+    remaining_accounts: &[AccountInfo<'info>],
+    operator: Pubkey,
+    pool_loader: &AccountLoader<'info, OpenAmmPool>,
+    base_amount: u64,
+    quote_amount: u64,
+) -> Result<bool> {
+    // The pool's oracle, anchor pool or rung stats may be passed alongside
+    // the keeper
+    let mut keeper = match remaining_accounts
+        .iter()
+        .filter(|a| *a.owner == crate::ID)
+        .find_map(|a| Account::<Keeper>::try_from(a).ok())
+    {
+        Some(keeper) => keeper,
+        None => return Ok(false),
+    };
+    require_keys_eq!(
+        keeper.operator,
+        operator,
+        OpenAmmErrorCode::WrongKeeperClaimAccounts
+    );
+    let index = keeper
+        .pool_index(&pool_loader.key())
+        .ok_or(OpenAmmErrorCode::WrongKeeperClaimAccounts)?;
+    keeper.owed_base[index] = keeper.owed_base[index].checked_add(base_amount).unwrap();
+    keeper.owed_quote[index] = keeper.owed_quote[index].checked_add(quote_amount).unwrap();
+    keeper.exit(&crate::ID)?;
+
+    let mut pool = pool_loader.load_mut()?;
+    pool.keeper_owed_base_amount = pool
+        .keeper_owed_base_amount
+        .checked_add(base_amount)
+        .unwrap();
+    pool.keeper_owed_quote_amount = pool
+        .keeper_owed_quote_amount
+        .checked_add(quote_amount)
+        .unwrap();
+    Ok(true)
+}
+
+/// Adds a keeper refund paid to `recipient` to the pool's lifetime totals
+/// and emits it. `credited` is whether it went to a keeper account rather
+/// than straight to the recipient.
+pub fn record_keeper_payment(
+    pool_loader: &AccountLoader<OpenAmmPool>,
+    recipient: Pubkey,
+    base_amount: u64,
+    quote_amount: u64,
+    credited: bool,
+) -> Result<()> {
+    if base_amount == 0 && quote_amount == 0 {
+        return Ok(());
+    }
+    let mut pool = pool_loader.load_mut()?;
+    pool.lifetime_keeper_paid_base_amount = pool
+        .lifetime_keeper_paid_base_amount
+        .checked_add(base_amount)
+        .unwrap();
+    pool.lifetime_keeper_paid_quote_amount = pool
+        .lifetime_keeper_paid_quote_amount
+        .checked_add(quote_amount)
+        .unwrap();
+
+    let clock = Clock::get()?;
+    emit!(KeeperPaidEvent {
+        pool: pool_loader.key(),
+        recipient,
+        base_amount,
+        quote_amount,
+        credited,
+        lifetime_base_amount: pool.lifetime_keeper_paid_base_amount,
+        lifetime_quote_amount: pool.lifetime_keeper_paid_quote_amount,
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct PlacedRung {
+    pub rung: u8,
+    pub limit_price: u64,
+    pub base_qty: u64,
+    pub client_order_id: u64,
+}
+
+#[event]
+pub struct OrdersPlacedEvent {
+    pool: Pubkey,
+    asks: Vec<PlacedRung>,
+    bids: Vec<PlacedRung>,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+/// Emitted when reconciled fills grow the pool's invariant, i.e. spread
+/// income was folded back into reserves. `lp_value_increment` is the growth
+/// in value per LP token as a fraction of its previous value, scaled by
+/// `LP_VALUE_SCALE`.
+#[event]
+pub struct FeesCompoundedEvent {
+    pool: Pubkey,
+    base_amount: u64,
+    quote_amount: u64,
+    invariant_before: u64,
+    invariant_after: u64,
+    lp_value_increment: u64,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+/// Emitted for each fill against the pool's ladder when it is reconciled.
+/// The pool is only ever swapped against through its orders, so this is the
+/// swap record. `limit_price` is in quote lots per base lot, fees are in the
+/// taker's input token and `keeper_fee` is the refund set aside for keepers.
+/// The three fees add up to the spread the fill captured.
+#[event]
+pub struct SwapEvent {
+    pool: Pubkey,
+    market: Pubkey,
+    sold_base: bool,
+    amount_in: u64,
+    amount_out: u64,
+    limit_price: u64,
+    lp_fee: u64,
+    protocol_fee: u64,
+    keeper_fee: u64,
+    base_amount: u64,
+    quote_amount: u64,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+/// Quote the dex paid the pool in maker rebates, or charged it in taker fees,
+/// on one market during a refresh.
+#[event]
+pub struct DexFeesEvent {
+    pool: Pubkey,
+    market: Pubkey,
+    maker_rebate_amount: u64,
+    taker_fee_amount: u64,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+/// Emitted when reconciling sets aside keeper refunds on one market.
+/// `refund_base_amount` and `refund_quote_amount` are the totals still
+/// waiting to be paid out.
+#[event]
+pub struct RefundAccruedEvent {
+    pool: Pubkey,
+    market: Pubkey,
+    base_amount: u64,
+    quote_amount: u64,
+    refund_base_amount: u64,
+    refund_quote_amount: u64,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+/// Emitted when a refresh pays out keeper refunds, either straight to
+/// `recipient` or credited to its keeper account to claim later.
+#[event]
+pub struct KeeperPaidEvent {
+    pool: Pubkey,
+    recipient: Pubkey,
+    base_amount: u64,
+    quote_amount: u64,
+    credited: bool,
+    lifetime_base_amount: u64,
+    lifetime_quote_amount: u64,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+/// Marks the pool as mid-instruction. Handlers making CPIs take the lock
+/// before their first CPI and release it before returning, so nothing reached
+/// through those CPIs can re-enter the program against the same pool.
+pub fn lock_pool(pool_loader: &AccountLoader<OpenAmmPool>) -> Result<()> {
+    let mut pool = pool_loader.load_mut()?;
+    require!(!pool.in_progress, OpenAmmErrorCode::PoolLocked);
+    pool.in_progress = true;
+    Ok(())
+}
+
+pub fn unlock_pool(pool_loader: &AccountLoader<OpenAmmPool>) -> Result<()> {
+    let mut pool = pool_loader.load_mut()?;
+    pool.in_progress = false;
+    Ok(())
+}
+
+/// Emitted when a refresh leaves the pool's ladder off a market because
+/// its event queue is backed up past `max_event_queue_backlog`.
+#[event]
+pub struct EventQueueBacklogEvent {
+    pool: Pubkey,
+    market: Pubkey,
+    event_queue_len: u64,
+    max_event_queue_backlog: u64,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+/// Emitted when a pool is funded at a price where its fee is narrower than
+/// the market's tick, so its best rungs round to quoting without one.
+/// `price` is in quote lots per base lot, which is also the fee in ticks
+/// per 10,000 bps.
+#[event]
+pub struct FeeBelowTickEvent {
+    pool: Pubkey,
+    market: Pubkey,
+    fee_bps: u16,
+    price: u64,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+#[event]
+pub struct DriftDetectedEvent {
+    pool: Pubkey,
+    expected_base: u64,
+    actual_base: u64,
+    expected_quote: u64,
+    actual_quote: u64,
+    drift_bps: u64,
+    paused: bool,
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+fn get_drift_bps(expected: u64, actual: u64, deficit_tolerance: u64) -> u64 {
+    let drift = if actual > expected {
+        actual - expected
+    } else {
+        (expected - actual).saturating_sub(deficit_tolerance)
+    };
+    if expected == 0 {
+        return if drift > 0 { u64::MAX } else { 0 };
+    }
+    (drift as u128)
+        .checked_mul(DRIFT_DENOMINATOR.into())
+        .unwrap()
+        .checked_div(expected.into())
+        .unwrap()
+        .try_into()
+        .unwrap_or(u64::MAX)
+}
+
+/// Whether `fee_bps` of the price `quote_amount` / `base_amount` is at least
+/// one tick on a market with the given lot sizes. Rung prices are rounded to
+/// whole ticks, so a narrower fee can round away entirely.
+pub fn fee_spans_tick(
+    fee_bps: u16,
+    base_amount: u64,
+    quote_amount: u64,
+    base_lot_size: u64,
+    quote_lot_size: u64,
+) -> bool {
+    const FEE_DENOMINATOR: u16 = 10_000;
+
+    U256::from(quote_amount) * U256::from(base_lot_size) * U256::from(fee_bps)
+        >= U256::from(base_amount) * U256::from(quote_lot_size) * U256::from(FEE_DENOMINATOR)
+}
+
+/// Warns with `FeeBelowTickEvent` when the pool's fee doesn't span a tick at
+/// its reserves' price. The pool is still created, since a curve's deeper
+/// rungs are wider than its fee, but its touch quotes fee-less.
+pub fn check_fee_tick_floor(pool_loader: &AccountLoader<OpenAmmPool>) -> Result<()> {
+    let pool = pool_loader.load()?;
+    let fee_bps = get_curve_fee_bps(&pool);
+    if pool.base_amount == 0
+        || fee_spans_tick(
+            fee_bps,
+            pool.base_amount,
+            pool.quote_amount,
+            pool.base_lot_size,
+            pool.quote_lot_size,
+        )
+    {
+        return Ok(());
+    }
+
+    let price = (pool.quote_amount as u128)
+        .checked_mul(pool.base_lot_size.into())
+        .unwrap()
+        .checked_div(
+            (pool.base_amount as u128)
+                .checked_mul(pool.quote_lot_size.into())
+                .unwrap(),
+        )
+        .unwrap() as u64;
+    msg!("openamm:fee_below_tick fee_bps={} price={}", fee_bps, price);
+    let clock = Clock::get()?;
+    emit!(FeeBelowTickEvent {
+        pool: pool_loader.key(),
+        market: pool.market,
+        fee_bps,
+        price,
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Compares reserves and owed refunds against the vault balances once all of
+/// the pool's orders on the primary market are cancelled and settled. Emits a
+/// `DriftDetectedEvent` past the pool's alarm threshold and, if configured,
+/// stops market making. Funds resting on a secondary market are tolerated up
+/// to that market's share of the reserves. Fills still waiting in the event
+/// queue are not settled yet, so thresholds should leave room for them.
+pub fn check_reserve_drift(
+    pool_loader: &AccountLoader<OpenAmmPool>,
+    base_vault_amount: u64,
+    quote_vault_amount: u64,
+) -> Result<()> {
+    let mut pool = pool_loader.load_mut()?;
+    if pool.drift_alarm_bps == 0 {
+        return Ok(());
+    }
+
+    let expected_base = pool
+        .base_amount
+        .checked_add(pool.refund_base_amount)
+        .unwrap()
+        .checked_add(pool.keeper_owed_base_amount)
+        .unwrap();
+    let expected_quote = pool
+        .quote_amount
+        .checked_add(pool.refund_quote_amount)
+        .unwrap()
+        .checked_add(pool.keeper_owed_quote_amount)
+        .unwrap();
+    let secondary_share_bps = if pool.has_secondary_market() {
+        pool.secondary_share_bps
+    } else {
+        0
+    };
+    let tolerance = |expected: u64| -> u64 {
+        (expected as u128)
+            .checked_mul(secondary_share_bps.into())
+            .unwrap()
+            .checked_div(SECONDARY_SHARE_DENOMINATOR.into())
+            .unwrap()
+            .try_into()
+            .unwrap()
+    };
+
+    let drift_bps = cmp::max(
+        get_drift_bps(expected_base, base_vault_amount, tolerance(expected_base)),
+        get_drift_bps(expected_quote, quote_vault_amount, tolerance(expected_quote)),
+    );
+    if drift_bps <= pool.drift_alarm_bps.into() {
+        return Ok(());
+    }
+
+    let paused = pool.pause_on_drift;
+    if paused {
+        pool.mm_active = false;
+    }
+    drop(pool);
+
+    let clock = Clock::get()?;
+    emit!(DriftDetectedEvent {
+        pool: pool_loader.key(),
+        expected_base,
+        actual_base: base_vault_amount,
+        expected_quote,
+        actual_quote: quote_vault_amount,
+        drift_bps,
+        paused,
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Base and quote held beyond what the pool has recorded: its reserves,
+/// refunds and payouts owed to keepers and stranded open orders balances.
+/// Donations, maker rebates and rounding end up here, and only become
+/// reserves through `sync_reserves`, so sending tokens to the vaults can't
+/// move the price LP tokens are minted and burned at.
+pub fn get_unrecorded_surplus(pool: &OpenAmmPool, base_held: u64, quote_held: u64) -> (u64, u64) {
+    let recorded_base = pool
+        .base_amount
+        .checked_add(pool.refund_base_amount)
+        .unwrap()
+        .checked_add(pool.keeper_owed_base_amount)
+        .unwrap()
+        .checked_add(pool.stranded_base_amount)
+        .unwrap();
+    let recorded_quote = pool
+        .quote_amount
+        .checked_add(pool.refund_quote_amount)
+        .unwrap()
+        .checked_add(pool.keeper_owed_quote_amount)
+        .unwrap()
+        .checked_add(pool.stranded_quote_amount)
+        .unwrap();
+    (
+        base_held.saturating_sub(recorded_base),
+        quote_held.saturating_sub(recorded_quote),
+    )
+}
+
+/// Values `base_amount` in quote at the pool's own marginal price: the
+/// reserve ratio for XYK pools and the slope of the curve for stable pools.
+pub fn get_base_value_in_quote(pool: &OpenAmmPool, base_amount: u64) -> u64 {
+    if pool.base_amount == 0 || pool.quote_amount == 0 {
+        return 0;
+    }
+
+    match pool.pool_type {
+        PoolType::XYK => (base_amount as u128)
+            .checked_mul(pool.quote_amount.into())
+            .unwrap()
+            .checked_div(pool.base_amount.into())
+            .unwrap()
+            .try_into()
+            .unwrap(),
+        PoolType::STABLE => {
+            let (base_decs_fac, quote_decs_fac) =
+                get_token_decs_fac(pool.base_decimals, pool.quote_decimals);
+            let x = pool.base_amount.checked_mul(base_decs_fac).unwrap();
+            let y = pool.quote_amount.checked_mul(quote_decs_fac).unwrap();
+            let d = match calc_d(x, y, STABLESWAP_AMP_COEFFICIENT) {
+                Some(d) => d,
+                None => return 0,
+            };
+            let price = calc_marginal_price(x, y, STABLESWAP_AMP_COEFFICIENT, d);
+            (base_amount.checked_mul(base_decs_fac).unwrap() as f64 * price
+                / quote_decs_fac as f64) as u64
+        }
+    }
+}
+
+/// Pool invariant for the current reserves: sqrt(x * y) for XYK pools and D
+/// for stable pools.
+pub fn get_invariant(pool: &OpenAmmPool) -> u64 {
+    match pool.pool_type {
+        PoolType::XYK => isqrt(
+            (pool.base_amount as u128)
+                .checked_mul(pool.quote_amount.into())
+                .unwrap(),
+        ) as u64,
+        PoolType::STABLE => {
+            let (base_decs_fac, quote_decs_fac) =
+                get_token_decs_fac(pool.base_decimals, pool.quote_decimals);
+            calc_d(
+                pool.base_amount.checked_mul(base_decs_fac).unwrap(),
+                pool.quote_amount.checked_mul(quote_decs_fac).unwrap(),
+                STABLESWAP_AMP_COEFFICIENT,
+            )
+            .unwrap_or(0)
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct LadderOrder {
+    pub rung: usize,
+    pub side: Side,
+    pub limit_price: u64,
+    pub base_qty: u64,
+    pub max_native_quote_qty_including_fees: u64,
+    /// Crosses the book and is sent as an IOC order instead of resting.
+    pub take: bool,
+    /// Spread the order was priced with, the rung's own fee included.
+    pub fee_bps: u16,
+}
+
+fn get_dex_fee(quote_amount: u64, rate_tenth_bps: u64) -> u64 {
+    (quote_amount as u128)
+        .checked_mul(rate_tenth_bps.into())
+        .unwrap()
+        .checked_div(DEX_FEE_DENOMINATOR.into())
+        .unwrap() as u64
+}
+
+/// Quote value of an order at its limit price.
+pub fn get_order_notional(order: &LadderOrder, quote_lot_size: u64) -> u64 {
+    (order.base_qty as u128)
+        .checked_mul(order.limit_price.into())
+        .unwrap()
+        .checked_mul(quote_lot_size.into())
+        .unwrap()
+        .try_into()
+        .unwrap()
+}
+
+/// Drops resting orders that would cross the rest of the book's top, which
+/// the dex would drop as post-only without placing them. Orders that take
+/// are kept, since they're meant to cross and never rest.
+pub fn retain_uncrossed_orders(
+    ladder: &mut Vec<LadderOrder>,
+    best_bid_price: Option<u64>,
+    best_ask_price: Option<u64>,
+) {
+    ladder.retain(|order| {
+        order.take
+            || match order.side {
+                Side::Ask => best_bid_price.map_or(true, |bid| order.limit_price > bid),
+                Side::Bid => best_ask_price.map_or(true, |ask| order.limit_price < ask),
+            }
+    });
+}
+
+/// Nudges each resting order up to one tick away from the touch and trims its
+/// size by up to `jitter_bps`, so bots can't predict the exact quotes a
+/// refresh will place. Neither change makes an order worse for the pool.
+/// `seeds` vary the offsets from refresh to refresh.
+pub fn jitter_ladder(ladder: &mut [LadderOrder], jitter_bps: u16, seeds: &[&[u8]]) {
+    for order in ladder.iter_mut().filter(|o| !o.take) {
+        let side_bytes = match order.side {
+            Side::Bid => [0u8],
+            Side::Ask => [1u8],
+        };
+        let rung_bytes = (order.rung as u64).to_le_bytes();
+        let mut hash_seeds = seeds.to_vec();
+        hash_seeds.push(&side_bytes);
+        hash_seeds.push(&rung_bytes);
+        let hash = solana_program::hash::hashv(&hash_seeds).to_bytes();
+
+        if hash[0] & 1 == 1 {
+            order.limit_price = match order.side {
+                Side::Ask => order.limit_price.checked_add(1).unwrap(),
+                Side::Bid => cmp::max(order.limit_price - 1, 1),
+            };
+        }
+
+        let trim_bps = u16::from_le_bytes([hash[1], hash[2]]) % jitter_bps.checked_add(1).unwrap();
+        let trim_qty = (order.base_qty as u128)
+            .checked_mul(trim_bps.into())
+            .unwrap()
+            .checked_div(ORDER_DENOMINATOR.into())
+            .unwrap() as u64;
+        order.base_qty = cmp::max(order.base_qty - trim_qty, 1);
+    }
+}
+
+/// Cancels `side`'s resting orders from the deepest rung in, adding what
+/// each one locks to `free`, until `needed` is covered. Orders that aren't in
+/// `placed` are always cancelled. Returns the rungs left resting, sized to
+/// what's left of them, or `None` if every rung isn't enough.
+fn trim_side(
+    placed: &PlacedOrders,
+    orders: &[CurrentOrder],
+    side: Side,
+    locked: impl Fn(&CurrentOrder) -> u64,
+    free: u64,
+    needed: u64,
+    cancel_client_order_ids: &mut Vec<u64>,
+) -> Option<PlacedOrders> {
+    let mut resting = vec![];
+    for order in orders.iter().filter(|o| o.side == side) {
+        let rung = placed
+            .as_slice()
+            .iter()
+            .position(|p| p.base_qty != 0 && p.client_order_id == order.client_order_id);
+        match rung {
+            Some(rung) => resting.push((rung, order)),
+            None => cancel_client_order_ids.push(order.client_order_id),
+        }
+    }
+    resting.sort_by_key(|(rung, _)| cmp::Reverse(*rung));
+
+    let mut free = free;
+    let mut kept = PlacedOrders::default();
+    for (rung, order) in resting {
+        if free >= needed {
+            kept.set(
+                rung,
+                PlacedOrder {
+                    base_qty: order.base_qty,
+                    ..placed.orders[rung]
+                },
+            );
+        } else {
+            free = free.checked_add(locked(order)).unwrap();
+            cancel_client_order_ids.push(order.client_order_id);
+        }
+    }
+    (free >= needed).then(|| kept)
+}
+
+/// Scales a ladder down to `share_bps` of its size, dropping orders that
+/// round to zero. Used to split one pool's ladder across two markets.
+pub fn scale_ladder(ladder: Vec<LadderOrder>, share_bps: u16) -> Vec<LadderOrder> {
+    if share_bps == SECONDARY_SHARE_DENOMINATOR {
+        return ladder;
+    }
+
+    ladder
+        .into_iter()
+        .map(|order| LadderOrder {
+            base_qty: order
+                .base_qty
+                .checked_mul(share_bps.into())
                 .unwrap()
-                .try_into()
-                .unwrap();
-            let k = (last_bid_base as u128)
-                .checked_mul(last_bid_quote.into())
-                .unwrap();
-            let end_b_amount = last_bid_quote.checked_sub(b_size).unwrap_or_else(|| 0);
+                .checked_div(SECONDARY_SHARE_DENOMINATOR.into())
+                .unwrap(),
+            max_native_quote_qty_including_fees: order
+                .max_native_quote_qty_including_fees
+                .checked_mul(share_bps.into())
+                .unwrap()
+                .checked_div(SECONDARY_SHARE_DENOMINATOR.into())
+                .unwrap(),
+            ..order
+        })
+        .filter(|order| order.base_qty != 0 && order.max_native_quote_qty_including_fees != 0)
+        .collect()
+}
 
-            if end_b_amount > 0 {
-                let end_a_amount: u64 = k
-                    .checked_div(end_b_amount.into())
-                    .unwrap()
-                    .try_into()
-                    .unwrap();
-                let delta_a = end_a_amount.checked_sub(last_bid_base).unwrap();
-                let a_size = delta_a;
-                let a_lots = a_size.checked_div(self.base_lot_size).unwrap();
-                let mut limit_price: u64 = (b_size as u128)
-                    .checked_mul(self.base_lot_size.into())
-                    .unwrap()
-                    .checked_mul(bid_fee_numerator.into())
-                    .unwrap()
-                    .checked_div(delta_a.into())
-                    .unwrap()
-                    .checked_div(self.quote_lot_size.into())
-                    .unwrap()
-                    .checked_div(FEE_DENOMINATOR.into())
-                    .unwrap()
-                    .try_into()
-                    .unwrap();
+/// Computes the orders the pool would place for its current reserves, asks
+/// first and then bids, without touching the book.
+pub fn get_ladder(
+    pool: &OpenAmmPool,
+    base_lot_size: u64,
+    quote_lot_size: u64,
+    best_bid_price: Option<u64>,
+    best_ask_price: Option<u64>,
+) -> Result<Vec<LadderOrder>> {
+    const FEE_DENOMINATOR: u16 = 10_000;
+
+    let mut curve = match get_curve(pool) {
+        Some(curve) => curve,
+        None => return Ok(vec![]),
+    };
+    let fee_bps = curve
+        .fee_bps()
+        .checked_add(pool.get_volatility_fee_bps())
+        .unwrap();
+    let rung_fee_bps = |rung: usize| fee_bps.checked_add(pool.get_rung_fee_bps(rung)).unwrap();
+    let get_limit_price = |a_size: u64, b_size: u64, fee_numerator: u16| -> Result<u64> {
+        let price = (b_size as u128)
+            .checked_mul(base_lot_size.into())
+            .ok_or(OpenAmmErrorCode::PriceOverflow)?
+            .checked_mul(fee_numerator.into())
+            .ok_or(OpenAmmErrorCode::PriceOverflow)?
+            .checked_div(a_size.into())
+            .ok_or(OpenAmmErrorCode::DivisionByZero)?
+            .checked_div(quote_lot_size.into())
+            .ok_or(OpenAmmErrorCode::DivisionByZero)?
+            .checked_div(FEE_DENOMINATOR.into())
+            .unwrap();
+        price
+            .try_into()
+            .map_err(|_| OpenAmmErrorCode::PriceOverflow.into())
+    };
 
-                last_bid_base = end_a_amount;
-                last_bid_quote = end_b_amount;
+    let orders_per_side = cmp::min(pool.orders_per_side as usize, MAX_ORDERS_PER_SIDE);
+    let mut ladder = vec![];
 
-                if limit_price != 0 && a_lots != 0 && b_size != 0 {
-                    if best_ask_price.is_some()
-                        && limit_price >= best_ask_price.unwrap()
-                        && best_ask_price.unwrap() > 1
-                    {
-                        limit_price = best_ask_price.unwrap().checked_sub(1).unwrap();
+    for i in 0..orders_per_side {
+        let (a_size, b_size) = match curve.ladder_ask(i)? {
+            Some(sizes) => sizes,
+            None => {
+                debug_msg!(pool.debug_logging, "openamm:skip side=ask rung={} empty", i);
+                continue;
+            }
+        };
+        let a_lots = a_size
+            .checked_div(base_lot_size)
+            .ok_or(OpenAmmErrorCode::DivisionByZero)?;
+        let mut limit_price = get_limit_price(
+            a_size,
+            b_size,
+            FEE_DENOMINATOR.checked_add(rung_fee_bps(i)).unwrap(),
+        )?;
+
+        if limit_price != 0 && a_lots != 0 && b_size != 0 {
+            let mut take = false;
+            if best_bid_price.is_some() && limit_price <= best_bid_price.unwrap() {
+                match pool.crossing_policy {
+                    CrossingPolicy::REPRICE => {
+                        limit_price = best_bid_price.unwrap().checked_add(1).unwrap();
                     }
+                    CrossingPolicy::SKIP => {
+                        debug_msg!(
+                            pool.debug_logging,
+                            "openamm:skip side=ask rung={} limit_price={} crosses",
+                            i,
+                            limit_price
+                        );
+                        continue;
+                    }
+                    CrossingPolicy::TAKE => take = true,
+                }
+            }
+
+            ladder.push(LadderOrder {
+                rung: i,
+                side: Side::Ask,
+                limit_price,
+                base_qty: a_lots,
+                max_native_quote_qty_including_fees: b_size,
+                take,
+                fee_bps: rung_fee_bps(i),
+            });
+        } else {
+            debug_msg!(
+                pool.debug_logging,
+                "openamm:skip side=ask rung={} limit_price={} base_lots={} quote_size={}",
+                i,
+                limit_price,
+                a_lots,
+                b_size
+            );
+        }
+    }
 
-                    let place_ix = NewOrderInstructionV3 {
-                        side: Side::Bid,
-                        limit_price: NonZeroU64::new(limit_price).unwrap(),
-                        max_coin_qty: NonZeroU64::new(a_lots).unwrap(),
-                        max_native_pc_qty_including_fees: NonZeroU64::new(b_size).unwrap(),
-                        self_trade_behavior: SelfTradeBehavior::DecrementTake,
-                        order_type: OrderType::PostOnly,
-                        client_order_id: pool.client_order_id,
-                        limit: 0,
-                        max_ts: i64::MAX,
-                    };
-
-                    pool.placed_bids[i] = PlacedOrder {
-                        limit_price: place_ix.limit_price.into(),
-                        base_qty: place_ix.max_coin_qty.into(),
-                        max_native_quote_qty_including_fees: place_ix
-                            .max_native_pc_qty_including_fees
-                            .into(),
-                        client_order_id: pool.client_order_id,
-                    };
-
-                    place_ixs.push(place_ix);
-                    pool.client_order_id += 1;
+    for i in 0..orders_per_side.saturating_sub(1) {
+        let (a_size, b_size) = match curve.ladder_bid(i)? {
+            Some(sizes) => sizes,
+            None => {
+                debug_msg!(pool.debug_logging, "openamm:skip side=bid rung={} empty", i);
+                continue;
+            }
+        };
+        let a_lots = a_size
+            .checked_div(base_lot_size)
+            .ok_or(OpenAmmErrorCode::DivisionByZero)?;
+        let mut limit_price = get_limit_price(
+            a_size,
+            b_size,
+            FEE_DENOMINATOR.checked_sub(rung_fee_bps(i)).unwrap(),
+        )?;
+
+        if limit_price != 0 && a_lots != 0 && b_size != 0 {
+            let mut take = false;
+            if best_ask_price.is_some()
+                && limit_price >= best_ask_price.unwrap()
+                && best_ask_price.unwrap() > 1
+            {
+                match pool.crossing_policy {
+                    CrossingPolicy::REPRICE => {
+                        limit_price = best_ask_price.unwrap().checked_sub(1).unwrap();
+                    }
+                    CrossingPolicy::SKIP => {
+                        debug_msg!(
+                            pool.debug_logging,
+                            "openamm:skip side=bid rung={} limit_price={} crosses",
+                            i,
+                            limit_price
+                        );
+                        continue;
+                    }
+                    CrossingPolicy::TAKE => take = true,
                 }
             }
+
+            ladder.push(LadderOrder {
+                rung: i,
+                side: Side::Bid,
+                limit_price,
+                base_qty: a_lots,
+                max_native_quote_qty_including_fees: b_size,
+                take,
+                fee_bps: rung_fee_bps(i),
+            });
+        } else {
+            debug_msg!(
+                pool.debug_logging,
+                "openamm:skip side=bid rung={} limit_price={} base_lots={} quote_size={}",
+                i,
+                limit_price,
+                a_lots,
+                b_size
+            );
         }
-        drop(pool);
+    }
+    Ok(ladder)
+}
 
-        self.place_orders(
-            place_ixs,
-            pool_base_vault.to_account_info(),
-            pool_quote_vault.to_account_info(),
-        )
-        .unwrap();
-        Ok(())
+/// Amount of the opposite token the pool's curve pays out for `amount_in`,
+/// net of the pool's fee.
+pub fn get_swap_amount_out(pool: &OpenAmmPool, amount_in: u64, base_in: bool) -> u64 {
+    const FEE_DENOMINATOR: u16 = 10_000;
+
+    let (reserve_in, reserve_out) = if base_in {
+        (pool.base_amount, pool.quote_amount)
+    } else {
+        (pool.quote_amount, pool.base_amount)
+    };
+    if reserve_in == 0 || reserve_out == 0 || amount_in == 0 {
+        return 0;
+    }
+
+    match pool.pool_type {
+        PoolType::XYK => {
+            let amount_in_after_fee = (amount_in as u128)
+                .checked_mul(FEE_DENOMINATOR.checked_sub(LP_FEE_BPS).unwrap().into())
+                .unwrap()
+                .checked_div(FEE_DENOMINATOR.into())
+                .unwrap();
+            (reserve_out as u128)
+                .checked_mul(amount_in_after_fee)
+                .unwrap()
+                .checked_div(
+                    (reserve_in as u128)
+                        .checked_add(amount_in_after_fee)
+                        .unwrap(),
+                )
+                .unwrap()
+                .try_into()
+                .unwrap()
+        }
+        PoolType::STABLE => {
+            let (base_decs_fac, quote_decs_fac) =
+                get_token_decs_fac(pool.base_decimals, pool.quote_decimals);
+            let (in_decs_fac, out_decs_fac) = if base_in {
+                (base_decs_fac, quote_decs_fac)
+            } else {
+                (quote_decs_fac, base_decs_fac)
+            };
+
+            let amount_in_after_fee: u64 = (amount_in as u128)
+                .checked_mul(FEE_DENOMINATOR.checked_sub(STABLESWAP_FEE_BPS).unwrap().into())
+                .unwrap()
+                .checked_div(FEE_DENOMINATOR.into())
+                .unwrap()
+                .try_into()
+                .unwrap();
+
+            let x = reserve_in.checked_mul(in_decs_fac).unwrap();
+            let y = reserve_out.checked_mul(out_decs_fac).unwrap();
+            let d = calc_d(x, y, STABLESWAP_AMP_COEFFICIENT).unwrap();
+            calc_swap_out(
+                x,
+                y,
+                STABLESWAP_AMP_COEFFICIENT,
+                d,
+                amount_in_after_fee.checked_mul(in_decs_fac).unwrap(),
+            )
+            .unwrap_or(0)
+                / out_decs_fac
+        }
     }
 }
 
 #[derive(Clone, Copy)]
 pub struct CurrentOrder {
     pub side: Side,
-    pub order_id: u128,
     pub client_order_id: u64,
     pub limit_price: u64,
     pub base_qty: u64,
@@ -957,15 +2676,97 @@ fn gcd(a: u64, b: u64) -> u64 {
     }
 }
 
+/// LP tokens minted for depositing into an XYK pool. The first deposit mints
+/// sqrt(x * y - MINIMUM_LIQUIDITY), later ones the smaller of the deposit's
+/// shares of each reserve.
+pub fn get_xyk_lp_minted(
+    lp_mint_supply: u64,
+    reserve_base_amount: u64,
+    reserve_quote_amount: u64,
+    deposit_base_amount: u64,
+    deposit_quote_amount: u64,
+) -> Result<u64> {
+    let lp_minted = match lp_mint_supply {
+        0 => {
+            let liquidity = (deposit_base_amount as u128)
+                .checked_mul(deposit_quote_amount.into())
+                .unwrap()
+                .checked_sub(MINIMUM_LIQUIDITY.into())
+                .ok_or(OpenAmmErrorCode::InitialLiquidityTooSmall)?;
+            isqrt(liquidity)
+        }
+        lp_mint_supply => cmp::min(
+            (lp_mint_supply as u128)
+                .checked_mul(deposit_base_amount.into())
+                .unwrap()
+                .checked_div(reserve_base_amount.into())
+                .unwrap(),
+            (lp_mint_supply as u128)
+                .checked_mul(deposit_quote_amount.into())
+                .unwrap()
+                .checked_div(reserve_quote_amount.into())
+                .unwrap(),
+        ),
+    };
+    let lp_minted: u64 = lp_minted
+        .try_into()
+        .map_err(|_| OpenAmmErrorCode::LpSupplyOverflow)?;
+    check_lp_supply(lp_mint_supply, lp_minted)?;
+    Ok(lp_minted)
+}
+
+/// Errors if minting `lp_minted` would take the LP supply past u64::MAX,
+/// rather than letting the mint fail or the amount get truncated.
+pub fn check_lp_supply(lp_mint_supply: u64, lp_minted: u64) -> Result<()> {
+    require!(
+        lp_mint_supply.checked_add(lp_minted).is_some(),
+        OpenAmmErrorCode::LpSupplyOverflow
+    );
+    Ok(())
+}
+
+/// Leaf of a snapshot's merkle tree for `owner` holding `lp_amount` LP
+/// tokens. Leaves and nodes hash under different prefixes, so a node can't
+/// be passed off as a leaf.
+pub fn get_airdrop_leaf(owner: &Pubkey, lp_amount: u64) -> [u8; 32] {
+    solana_program::hash::hashv(&[&[0u8], owner.as_ref(), &lp_amount.to_le_bytes()]).to_bytes()
+}
+
+/// Whether `proof` leads from `leaf` to `root`. Each pair of siblings is
+/// hashed in sorted order, so proofs don't need to say which side they're on.
+pub fn verify_merkle_proof(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        let (left, right) = if node <= *sibling {
+            (node, *sibling)
+        } else {
+            (*sibling, node)
+        };
+        solana_program::hash::hashv(&[&[1u8], &left, &right]).to_bytes()
+    });
+    computed == root
+}
+
+macro_rules! debug_msg {
+    ($enabled:expr, $($arg:tt)*) => {
+        if $enabled {
+            msg!($($arg)*);
+        }
+    };
+}
+
+pub(crate) use debug_msg;
+
 macro_rules! pool_authority_seeds {
     (
         market_key = $market_key:expr,
         pool_type_bytes = $pool_type_bytes:expr,
+        index_seed = $index_seed:expr,
         bump = $bump:expr
     ) => {
         &[
             $market_key.as_ref(),
             $pool_type_bytes.as_ref(),
+            $index_seed.as_ref(),
             POOL_SEED.as_bytes(),
             &[$bump],
         ]
@@ -991,3 +2792,688 @@ macro_rules! init {
     };
 }
 pub(crate) use init;
+
+#[cfg(test)]
+mod util_tests {
+    use super::*;
+    use crate::oracle::get_quote_usd_value;
+    use anchor_lang::__private::bytemuck::Zeroable;
+
+    const BASE_LOT_SIZE: u64 = 100;
+    const QUOTE_LOT_SIZE: u64 = 10;
+
+    fn placed(client_order_id: u64, limit_price: u64, base_qty: u64) -> PlacedOrder {
+        PlacedOrder {
+            limit_price,
+            base_qty,
+            max_native_quote_qty_including_fees: limit_price * base_qty * QUOTE_LOT_SIZE,
+            client_order_id,
+            fee_bps: LP_FEE_BPS.into(),
+        }
+    }
+
+    fn resting(side: Side, order: &PlacedOrder, base_qty: u64) -> CurrentOrder {
+        CurrentOrder {
+            side,
+            client_order_id: order.client_order_id,
+            limit_price: order.limit_price,
+            base_qty,
+        }
+    }
+
+    /// An active pool quoting two rungs a side, deepest last.
+    fn quoting_pool() -> OpenAmmPool {
+        let mut pool = OpenAmmPool::zeroed();
+        pool.base_amount = 1_000_000;
+        pool.quote_amount = 1_000_000;
+        pool.mm_active = true;
+        pool.placed_asks.set(0, placed(1, 11, 10));
+        pool.placed_asks.set(1, placed(2, 12, 10));
+        pool.placed_bids.set(0, placed(3, 9, 10));
+        pool.placed_bids.set(1, placed(4, 8, 10));
+        pool
+    }
+
+    /// The book with every rung of `pool` still resting in full.
+    fn untouched_book(pool: &OpenAmmPool) -> Vec<CurrentOrder> {
+        let asks = pool
+            .placed_asks
+            .as_slice()
+            .iter()
+            .map(|o| resting(Side::Ask, o, o.base_qty));
+        let bids = pool
+            .placed_bids
+            .as_slice()
+            .iter()
+            .map(|o| resting(Side::Bid, o, o.base_qty));
+        asks.chain(bids).collect()
+    }
+
+    fn reconcile(orders: &[CurrentOrder], pool: &mut OpenAmmPool) -> Result<Reconciliation> {
+        reconcile_orders(orders, BASE_LOT_SIZE, QUOTE_LOT_SIZE, false, false, pool)
+    }
+
+    #[test]
+    fn untouched_ladder_stays_active_test() {
+        let mut pool = quoting_pool();
+        let orders = untouched_book(&pool);
+        let reconciliation = reconcile(&orders, &mut pool).unwrap();
+
+        assert!(pool.mm_active);
+        assert_eq!(
+            (pool.base_amount, pool.quote_amount),
+            (1_000_000, 1_000_000)
+        );
+        assert_eq!(reconciliation.reconciled_quote_amount, 0);
+        assert_eq!(reconciliation.cancel_client_order_ids, vec![1, 2, 3, 4]);
+        assert_eq!(pool.placed_asks.len + pool.placed_bids.len, 0);
+    }
+
+    #[test]
+    fn partial_fill_credits_reserves_and_stays_active_test() {
+        let mut pool = quoting_pool();
+        let mut orders = untouched_book(&pool);
+        // 6 of the top ask's 10 lots filled
+        orders[0].base_qty = 4;
+        let reconciliation = reconcile(&orders, &mut pool).unwrap();
+
+        assert!(pool.mm_active);
+        assert_eq!(reconciliation.reconciled_quote_amount, 660);
+        assert_eq!(pool.base_amount, 1_000_000 - 600);
+        assert_eq!(
+            pool.quote_amount,
+            1_000_000 + 660 - reconciliation.protocol_fee_quote_amount
+        );
+    }
+
+    #[test]
+    fn fills_count_towards_their_rung_test() {
+        let mut pool = quoting_pool();
+        let mut orders = untouched_book(&pool);
+        // 6 lots of the top ask and 5 of the deepest bid filled
+        orders[0].base_qty = 4;
+        orders[3].base_qty = 5;
+        let reconciliation = reconcile(&orders, &mut pool).unwrap();
+
+        let mut rung_stats = RungStats::default();
+        for fill in reconciliation.fills.iter() {
+            rung_stats.record_fill(
+                fill.sold_base,
+                fill.rung.into(),
+                fill.notional_quote_amount(),
+                7,
+            );
+        }
+        assert_eq!(rung_stats.ask_fill_count[..2], [1, 0]);
+        assert_eq!(rung_stats.ask_fill_quote_amount[0], 660);
+        assert_eq!(rung_stats.bid_fill_count[..2], [0, 1]);
+        assert_eq!(rung_stats.bid_fill_quote_amount[1], 400);
+        assert_eq!(rung_stats.last_fill_slot, 7);
+    }
+
+    #[test]
+    fn fill_fees_add_up_to_spread_test() {
+        // 20 bps of spread covers the protocol's 2 bps and a 10 bps refund
+        let fees = split_fill_spread(1_000_000, 2_000, 10);
+        assert_eq!(
+            (fees.lp_fee, fees.protocol_fee, fees.keeper_fee),
+            (800, 200, 1_000)
+        );
+        // A 4 bps stable spread runs out partway through the refund
+        let fees = split_fill_spread(1_000_000, 400, 10);
+        assert_eq!(
+            (fees.lp_fee, fees.protocol_fee, fees.keeper_fee),
+            (0, 200, 200)
+        );
+        let fees = split_fill_spread(1_000_000, 100, 10);
+        assert_eq!(
+            (fees.lp_fee, fees.protocol_fee, fees.keeper_fee),
+            (0, 100, 0)
+        );
+
+        let mut pool = quoting_pool();
+        pool.keeper_refund_bps = 10;
+        let mut orders = untouched_book(&pool);
+        orders[0].base_qty = 0;
+        orders[2].base_qty = 0;
+        let base_before = pool.base_amount;
+        let quote_before = pool.quote_amount;
+        let reconciliation = reconcile(&orders, &mut pool).unwrap();
+        let fee_bps = get_curve_fee_bps(&pool);
+        for fill in reconciliation.fills.iter() {
+            let spread = if fill.sold_base {
+                fill.amount_in * fee_bps as u64 / (10_000 + fee_bps as u64)
+            } else {
+                fill.amount_in * fee_bps as u64 / 10_000
+            };
+            assert_eq!(fill.lp_fee + fill.protocol_fee + fill.keeper_fee, spread);
+        }
+        // Everything that came in but the protocol's and keeper's cuts is
+        // credited to the reserves
+        let (ask, bid) = (&reconciliation.fills[0], &reconciliation.fills[1]);
+        assert_eq!(
+            pool.quote_amount,
+            quote_before + ask.amount_in - ask.protocol_fee - ask.keeper_fee - bid.amount_out
+        );
+        assert_eq!(
+            pool.base_amount,
+            base_before + bid.amount_in - bid.protocol_fee - bid.keeper_fee - ask.amount_out
+        );
+    }
+
+    #[test]
+    fn rung_fees_widen_deeper_rungs_test() {
+        let mut pool = OpenAmmPool::zeroed();
+        pool.base_amount = 1_000_000_000;
+        pool.quote_amount = 1_000_000_000_000;
+        pool.orders_per_side = 3;
+        let flat = get_ladder(&pool, BASE_LOT_SIZE, QUOTE_LOT_SIZE, None, None).unwrap();
+        pool.rung_fee_bps[1] = 50;
+        pool.rung_fee_bps[2] = 100;
+        let tiered = get_ladder(&pool, BASE_LOT_SIZE, QUOTE_LOT_SIZE, None, None).unwrap();
+        assert_eq!(flat.len(), 5);
+        assert_eq!(tiered.len(), 5);
+        for (flat, tiered) in flat.iter().zip(tiered.iter()) {
+            assert_eq!(
+                tiered.fee_bps,
+                flat.fee_bps + pool.get_rung_fee_bps(flat.rung)
+            );
+            match (flat.side, flat.rung) {
+                (_, 0) => assert_eq!(tiered.limit_price, flat.limit_price),
+                (Side::Ask, _) => assert!(tiered.limit_price > flat.limit_price),
+                (Side::Bid, _) => assert!(tiered.limit_price < flat.limit_price),
+            }
+        }
+
+        // The deeper ask's fill earns its rung's fee on top of the ladder's
+        let mut pool = quoting_pool();
+        pool.placed_asks.orders[1].fee_bps += 100;
+        let mut orders = untouched_book(&pool);
+        orders[1].base_qty = 0;
+        let reconciliation = reconcile(&orders, &mut pool).unwrap();
+        let fill = &reconciliation.fills[0];
+        let fee_bps = get_curve_fee_bps(&pool) as u64 + 100;
+        assert_eq!(fill.rung, 1);
+        assert_eq!(
+            fill.lp_fee + fill.protocol_fee + fill.keeper_fee,
+            fill.amount_in * fee_bps / (10_000 + fee_bps)
+        );
+    }
+
+    #[test]
+    fn fills_keep_the_rung_fee_they_were_placed_with_test() {
+        // Rung fees set while the ladder rests don't reprice its fills
+        let mut pool = quoting_pool();
+        let mut orders = untouched_book(&pool);
+        orders[1].base_qty = 0;
+        orders[3].base_qty = 0;
+        pool.rung_fee_bps[1] = 100;
+        let reconciliation = reconcile(&orders, &mut pool).unwrap();
+        let fee_bps = LP_FEE_BPS as u64;
+        let (ask, bid) = (&reconciliation.fills[0], &reconciliation.fills[1]);
+        assert_eq!((ask.rung, bid.rung), (1, 1));
+        assert_eq!(
+            ask.lp_fee + ask.protocol_fee + ask.keeper_fee,
+            ask.amount_in * fee_bps / (10_000 + fee_bps)
+        );
+        assert_eq!(
+            bid.lp_fee + bid.protocol_fee + bid.keeper_fee,
+            bid.amount_in * fee_bps / 10_000
+        );
+
+        // And fills of a ladder placed with them earn them after they're
+        // cleared
+        let mut pool = quoting_pool();
+        pool.placed_asks.orders[1].fee_bps += 100;
+        pool.placed_bids.orders[1].fee_bps += 100;
+        let reconciliation = reconcile(&orders, &mut pool).unwrap();
+        let fee_bps = LP_FEE_BPS as u64 + 100;
+        let (ask, bid) = (&reconciliation.fills[0], &reconciliation.fills[1]);
+        assert_eq!(
+            ask.lp_fee + ask.protocol_fee + ask.keeper_fee,
+            ask.amount_in * fee_bps / (10_000 + fee_bps)
+        );
+        assert_eq!(
+            bid.lp_fee + bid.protocol_fee + bid.keeper_fee,
+            bid.amount_in * fee_bps / 10_000
+        );
+    }
+
+    #[test]
+    fn fills_keep_the_volatility_fee_they_were_placed_with_test() {
+        // The ladder is priced with the volatility fee of the moment
+        let mut pool = OpenAmmPool::zeroed();
+        pool.base_amount = 1_000_000_000;
+        pool.quote_amount = 1_000_000_000_000;
+        pool.orders_per_side = 2;
+        pool.volatility_bps = 100;
+        pool.volatility_fee_scale_bps = 5_000;
+        pool.max_volatility_fee_bps = 100;
+        let ladder = get_ladder(&pool, BASE_LOT_SIZE, QUOTE_LOT_SIZE, None, None).unwrap();
+        assert!(!ladder.is_empty());
+        for order in ladder.iter() {
+            assert_eq!(order.fee_bps, LP_FEE_BPS + 50);
+        }
+
+        // Its fills keep that fee after the estimate or the spread settings
+        // move while it rests
+        let mut pool = quoting_pool();
+        for rung in 0..2 {
+            pool.placed_asks.orders[rung].fee_bps += 50;
+            pool.placed_bids.orders[rung].fee_bps += 50;
+        }
+        pool.volatility_bps = 1_000;
+        pool.volatility_fee_scale_bps = 10_000;
+        pool.max_volatility_fee_bps = 200;
+        let mut orders = untouched_book(&pool);
+        orders[0].base_qty = 0;
+        orders[2].base_qty = 0;
+        let reconciliation = reconcile(&orders, &mut pool).unwrap();
+        let fee_bps = LP_FEE_BPS as u64 + 50;
+        let (ask, bid) = (&reconciliation.fills[0], &reconciliation.fills[1]);
+        assert_eq!(
+            ask.lp_fee + ask.protocol_fee + ask.keeper_fee,
+            ask.amount_in * fee_bps / (10_000 + fee_bps)
+        );
+        assert_eq!(
+            bid.lp_fee + bid.protocol_fee + bid.keeper_fee,
+            bid.amount_in * fee_bps / 10_000
+        );
+    }
+
+    #[test]
+    fn missing_inner_rung_counts_as_filled_test() {
+        let mut pool = quoting_pool();
+        let orders = untouched_book(&pool)
+            .into_iter()
+            .filter(|o| o.client_order_id != 1)
+            .collect::<Vec<_>>();
+        reconcile(&orders, &mut pool).unwrap();
+
+        assert!(pool.mm_active);
+        assert_eq!(pool.base_amount, 1_000_000 - 1_000);
+    }
+
+    #[test]
+    fn evicted_deepest_ask_stops_market_making_test() {
+        let mut pool = quoting_pool();
+        let orders = untouched_book(&pool)
+            .into_iter()
+            .filter(|o| o.client_order_id != 2)
+            .collect::<Vec<_>>();
+        reconcile(&orders, &mut pool).unwrap();
+
+        // Treated as filled until a restart checks the vaults, so the
+        // reserves still add up and shares of them can be paid out
+        assert!(!pool.mm_active);
+        assert_eq!(pool.base_amount, 1_000_000 - 1_000);
+        assert!(pool.quote_amount > 1_000_000);
+        let (base_share, quote_share) = pool.get_lp_share_amounts(1, 10);
+        assert_eq!(base_share, pool.base_amount / 10);
+        assert_eq!(quote_share, pool.quote_amount / 10);
+    }
+
+    #[test]
+    fn evicted_deepest_bid_stops_market_making_test() {
+        let mut pool = quoting_pool();
+        let orders = untouched_book(&pool)
+            .into_iter()
+            .filter(|o| o.client_order_id != 4)
+            .collect::<Vec<_>>();
+        reconcile(&orders, &mut pool).unwrap();
+
+        assert!(!pool.mm_active);
+        assert!(pool.base_amount > 1_000_000);
+        assert_eq!(pool.quote_amount, 1_000_000 - 800);
+    }
+
+    #[test]
+    fn reconciling_after_eviction_changes_nothing_test() {
+        let mut pool = quoting_pool();
+        let orders = untouched_book(&pool)
+            .into_iter()
+            .filter(|o| o.client_order_id != 2)
+            .collect::<Vec<_>>();
+        reconcile(&orders, &mut pool).unwrap();
+        let (base_amount, quote_amount) = (pool.base_amount, pool.quote_amount);
+
+        // What a withdrawal or restart does first, with the ladder cleared
+        let reconciliation = reconcile(&[], &mut pool).unwrap();
+        assert!(!pool.mm_active);
+        assert_eq!(
+            (pool.base_amount, pool.quote_amount),
+            (base_amount, quote_amount)
+        );
+        assert_eq!(reconciliation.reconciled_quote_amount, 0);
+    }
+
+    #[test]
+    fn grown_order_errors_test() {
+        let mut pool = quoting_pool();
+        let mut orders = untouched_book(&pool);
+        orders[2].base_qty = 11;
+
+        assert!(reconcile(&orders, &mut pool).is_err());
+    }
+
+    #[test]
+    fn secondary_eviction_leaves_primary_ladder_test() {
+        let mut pool = quoting_pool();
+        pool.secondary_placed_asks.set(0, placed(5, 11, 10));
+        reconcile_orders(&[], BASE_LOT_SIZE, QUOTE_LOT_SIZE, true, false, &mut pool).unwrap();
+
+        assert!(!pool.mm_active);
+        assert_eq!(pool.secondary_placed_asks.len, 0);
+        assert_eq!(pool.placed_asks.len, 2);
+    }
+
+    #[test]
+    fn drift_threshold_test() {
+        // Surpluses always count, deficits only past the tolerance
+        assert_eq!(get_drift_bps(10_000, 10_100, 0), 100);
+        assert_eq!(get_drift_bps(10_000, 9_900, 100), 0);
+        assert_eq!(get_drift_bps(10_000, 9_800, 100), 100);
+        assert_eq!(get_drift_bps(0, 0, 0), 0);
+        assert_eq!(get_drift_bps(0, 1, 0), u64::MAX);
+    }
+
+    #[test]
+    fn fee_tick_floor_test() {
+        // 1:1 reserves on a 100,000 / 100 lot market price at 1,000 ticks
+        let at_par = |fee_bps, quote_lot_size| {
+            fee_spans_tick(
+                fee_bps,
+                1_000_000_000,
+                1_000_000_000,
+                100_000,
+                quote_lot_size,
+            )
+        };
+        assert!(at_par(LP_FEE_BPS, 100));
+        assert!(!at_par(STABLESWAP_FEE_BPS, 100));
+        assert!(at_par(STABLESWAP_FEE_BPS, 10));
+
+        // Exactly a tick is enough, a cheap token on coarse ticks isn't
+        assert!(fee_spans_tick(20, 1_000, 500, 1_000, 1));
+        assert!(!fee_spans_tick(20, 1_000, 10, 1_000, 1));
+        let max = u64::MAX;
+        assert!(fee_spans_tick(10_000, max, max, max, max));
+    }
+
+    #[test]
+    fn quoting_schedule_test() {
+        let mut pool = quoting_pool();
+        let day = i64::from(SECONDS_PER_DAY);
+        assert!(pool.is_quoting_scheduled(0, 12 * 3_600));
+
+        // 14:30-21:00 UTC, and an overnight window wrapping past midnight
+        pool.quoting_open_secs = 52_200;
+        pool.quoting_close_secs = 75_600;
+        assert!(pool.is_quoting_scheduled(0, 3 * day + 52_200));
+        assert!(!pool.is_quoting_scheduled(0, 3 * day + 75_600));
+        assert!(!pool.is_quoting_scheduled(0, -1));
+        (pool.quoting_open_secs, pool.quoting_close_secs) = (75_600, 52_200);
+        assert!(pool.is_quoting_scheduled(0, -1));
+        assert!(!pool.is_quoting_scheduled(0, 60_000));
+
+        // Fills past 5% of quote reserves cool the pool down for 100 slots
+        (pool.quoting_open_secs, pool.quoting_close_secs) = (0, 0);
+        pool.cooldown_fill_bps = 500;
+        pool.cooldown_slots = 100;
+        pool.record_fill_cooldown(50_000, 10);
+        assert!(pool.is_quoting_scheduled(10, 0));
+        pool.record_fill_cooldown(50_001, 10);
+        assert!(!pool.is_quoting_scheduled(109, 0));
+        assert!(pool.is_quoting_scheduled(110, 0));
+
+        // Another trigger only ever extends a cool-down
+        pool.record_fill_cooldown(60_000, 20);
+        pool.record_fill_cooldown(1_000_000, 5);
+        assert_eq!(pool.cooldown_until_slot, 120);
+    }
+
+    #[test]
+    fn reserve_price_band_test() {
+        use crate::oracle::is_reserve_price_within_band;
+
+        // 1:1 reserves on a 100,000 / 100 lot market price at 1,000 ticks
+        let at_par = |oracle_price, band_bps| {
+            is_reserve_price_within_band(
+                oracle_price,
+                band_bps,
+                1_000_000_000,
+                1_000_000_000,
+                100_000,
+                100,
+            )
+        };
+        assert!(at_par(1_000, 0));
+        assert!(at_par(1_050, 500));
+        assert!(at_par(953, 500));
+        assert!(!at_par(1_053, 500));
+        assert!(!at_par(950, 500));
+        let max = u64::MAX;
+        assert!(is_reserve_price_within_band(1, 0, max, max, max, max));
+    }
+
+    #[test]
+    fn daily_stats_test() {
+        let mut pool = OpenAmmPool::zeroed();
+        let day = i64::from(SECONDS_PER_DAY);
+        assert_eq!(pool.get_fee_apr_bps(10 * day), 0);
+
+        // 100 in fees a day on 1,000,000 of TVL is 3.65% a year
+        pool.record_daily_tvl(1_000_000, 10 * day + 100);
+        pool.record_daily_fills(5_000, 60, 10 * day + 200);
+        pool.record_daily_fills(5_000, 40, 10 * day + 300);
+        assert_eq!(pool.get_fee_apr_bps(10 * day + 400), 365);
+        pool.record_daily_tvl(1_000_000, 11 * day);
+        pool.record_daily_fills(10_000, 100, 11 * day);
+        assert_eq!(pool.get_fee_apr_bps(11 * day), 365);
+        // A quiet day still counts towards the window
+        assert_eq!(pool.get_fee_apr_bps(12 * day), 243);
+
+        let recent = pool.get_recent_daily_stats(11 * day);
+        assert_eq!(recent.len(), 2);
+        assert_eq!(
+            recent.iter().map(|s| s.volume_quote_amount).sum::<u64>(),
+            20_000
+        );
+
+        // Day 17 reuses day 10's bucket, and day 11 has aged out by day 18
+        pool.record_daily_fills(1_000, 10, 17 * day);
+        assert_eq!(pool.get_recent_daily_stats(17 * day).len(), 2);
+        assert_eq!(pool.get_recent_daily_stats(18 * day).len(), 1);
+        assert_eq!(pool.get_fee_apr_bps(18 * day), 0);
+    }
+
+    #[test]
+    fn quote_usd_value_test() {
+        // A quote token of 6 decimals at $0.50, published at slot 50
+        let mut data = vec![0u8; 240];
+        data[0..4].copy_from_slice(&0xa1b2c3d4u32.to_le_bytes());
+        data[8..12].copy_from_slice(&3u32.to_le_bytes());
+        data[20..24].copy_from_slice(&(-8i32).to_le_bytes());
+        data[208..216].copy_from_slice(&50_000_000i64.to_le_bytes());
+        data[224..228].copy_from_slice(&1u32.to_le_bytes());
+        data[232..240].copy_from_slice(&50u64.to_le_bytes());
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let quote_oracle_info =
+            AccountInfo::new(&key, false, false, &mut lamports, &mut data, &key, false, 0);
+
+        let mut pool = OpenAmmPool::zeroed();
+        pool.quote_decimals = 6;
+        pool.quote_oracle_max_age_slots = 10;
+        assert_eq!(
+            get_quote_usd_value(&pool, &quote_oracle_info, 2_000_000, 60),
+            Some(1_000_000)
+        );
+        assert_eq!(
+            get_quote_usd_value(&pool, &quote_oracle_info, 2_000_000, 61),
+            None
+        );
+    }
+
+    #[test]
+    fn compute_budget_test() {
+        let mut pool = OpenAmmPool::zeroed();
+        assert_eq!(pool.get_compute_budget_orders(3), None);
+
+        pool.compute_budget_units = 400_000;
+        pool.base_compute_units = 100_000;
+        pool.cancel_compute_units = 20_000;
+        pool.place_compute_units = 25_000;
+        assert_eq!(pool.get_compute_budget_orders(0), Some(12));
+        // Another cancel CPI costs most of a placement
+        assert_eq!(pool.get_compute_budget_orders(1), Some(11));
+        assert_eq!(pool.get_compute_budget_orders(3), Some(9));
+        // Spent before placing anything
+        assert_eq!(pool.get_compute_budget_orders(20), Some(0));
+    }
+
+    #[test]
+    fn retain_uncrossed_orders_test() {
+        let order = |side: Side, limit_price: u64, take: bool| LadderOrder {
+            rung: 0,
+            side,
+            limit_price,
+            base_qty: 1,
+            max_native_quote_qty_including_fees: 1,
+            take,
+            fee_bps: 0,
+        };
+        let mut ladder = vec![
+            order(Side::Ask, 10, false),
+            order(Side::Ask, 11, false),
+            order(Side::Bid, 9, false),
+            order(Side::Bid, 1, false),
+            order(Side::Bid, 12, true),
+        ];
+        // An ask at the lowest price crosses every bid but the taking one
+        retain_uncrossed_orders(&mut ladder, Some(10), Some(1));
+        let kept: Vec<(u64, bool)> = ladder.iter().map(|o| (o.limit_price, o.take)).collect();
+        assert_eq!(kept, vec![(11, false), (12, true)]);
+
+        // Nothing crosses an empty book
+        let mut ladder = vec![order(Side::Ask, 1, false), order(Side::Bid, 100, false)];
+        retain_uncrossed_orders(&mut ladder, None, None);
+        assert_eq!(ladder.len(), 2);
+    }
+
+    #[test]
+    fn merkle_proof_test() {
+        let holders = [
+            (Pubkey::new_unique(), 600u64),
+            (Pubkey::new_unique(), 300),
+            (Pubkey::new_unique(), 100),
+        ];
+        let leaves: Vec<[u8; 32]> = holders
+            .iter()
+            .map(|(owner, lp_amount)| get_airdrop_leaf(owner, *lp_amount))
+            .collect();
+        let hash_pair = |a: [u8; 32], b: [u8; 32]| {
+            let (left, right) = if a <= b { (a, b) } else { (b, a) };
+            solana_program::hash::hashv(&[&[1u8], &left, &right]).to_bytes()
+        };
+        // The odd leaf out is carried up a level unpaired
+        let node = hash_pair(leaves[0], leaves[1]);
+        let root = hash_pair(node, leaves[2]);
+
+        assert!(verify_merkle_proof(&[leaves[1], leaves[2]], root, leaves[0]));
+        assert!(verify_merkle_proof(&[leaves[0], leaves[2]], root, leaves[1]));
+        assert!(verify_merkle_proof(&[node], root, leaves[2]));
+        // Claiming more than was held
+        assert!(!verify_merkle_proof(
+            &[leaves[1], leaves[2]],
+            root,
+            get_airdrop_leaf(&holders[0].0, 601)
+        ));
+    }
+
+    #[test]
+    fn migrate_legacy_pool_test() {
+        use crate::instructions::migrate_pool::{migrate_legacy_pool, LegacyOpenAmmPool};
+
+        // A pool as the original layout stored it: six amounts, seven keys,
+        // the order id and four bytes, then two ten-order arrays aligned to
+        // 8 bytes and `mm_active`, padded to 936 bytes.
+        let mut data = vec![0u8; 936];
+        for i in 0..6 {
+            data[i * 8..i * 8 + 8].copy_from_slice(&(100 + i as u64).to_le_bytes());
+        }
+        for i in 0..7 {
+            data[48 + i * 32..80 + i * 32].copy_from_slice(&[i as u8 + 1; 32]);
+        }
+        data[272..280].copy_from_slice(&77u64.to_le_bytes());
+        data[280..284].copy_from_slice(&[1, 6, 9, 254]);
+        for i in 0..20 {
+            let at = 288 + i * 32;
+            for (j, word) in [1000 + i as u64, 10, 500, 1 + i as u64].iter().enumerate() {
+                data[at + j * 8..at + j * 8 + 8].copy_from_slice(&word.to_le_bytes());
+            }
+        }
+        data[928] = 1;
+
+        assert_eq!(std::mem::size_of::<LegacyOpenAmmPool>(), data.len());
+        let legacy =
+            anchor_lang::__private::bytemuck::pod_read_unaligned::<LegacyOpenAmmPool>(&data);
+        let pool = migrate_legacy_pool(&legacy, BASE_LOT_SIZE, QUOTE_LOT_SIZE);
+
+        assert_eq!(
+            [
+                pool.base_amount,
+                pool.quote_amount,
+                pool.cumulative_quote_volume,
+                pool.cumulative_base_volume,
+                pool.refund_base_amount,
+                pool.refund_quote_amount,
+            ],
+            [100, 101, 102, 103, 104, 105]
+        );
+        let keys = [
+            pool.base_mint,
+            pool.quote_mint,
+            pool.market,
+            pool.open_orders,
+            pool.base_vault,
+            pool.quote_vault,
+            pool.lp_mint,
+        ];
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(key.to_bytes(), [i as u8 + 1; 32]);
+        }
+        assert_eq!(pool.client_order_id, 77);
+        assert!(matches!(pool.pool_type, PoolType::STABLE));
+        assert_eq!(
+            (pool.base_decimals, pool.quote_decimals, pool.bump),
+            (6, 9, 254)
+        );
+        assert!(pool.mm_active);
+
+        for (orders, first) in [(&pool.placed_asks, 0), (&pool.placed_bids, 10)] {
+            assert_eq!(orders.len as usize, LEGACY_ORDERS_PER_SIDE);
+            for (i, order) in orders.as_slice().iter().enumerate() {
+                assert_eq!(order.limit_price, 1000 + (first + i) as u64);
+                assert_eq!(order.base_qty, 10);
+                assert_eq!(order.max_native_quote_qty_including_fees, 500);
+                assert_eq!(order.client_order_id, 1 + (first + i) as u64);
+                assert_eq!(order.fee_bps, get_curve_fee_bps(&pool) as u64);
+            }
+        }
+
+        // Fields added since start where create_pool starts them
+        assert_eq!(pool.authority, Pubkey::default());
+        assert_eq!(pool.index, 0);
+        assert_eq!(pool.secondary_client_order_id, 1);
+        assert_eq!(
+            (pool.base_lot_size, pool.quote_lot_size),
+            (BASE_LOT_SIZE, QUOTE_LOT_SIZE)
+        );
+        assert_eq!(pool.orders_per_side as usize, LEGACY_ORDERS_PER_SIDE);
+        assert_eq!(pool.keeper_refund_bps, DEFAULT_KEEPER_REFUND_BPS);
+        assert_eq!(pool.dex_program, dex::ID);
+        assert_eq!(pool.secondary_placed_asks.len, 0);
+        assert!(!pool.in_progress && !pool.paused);
+    }
+}
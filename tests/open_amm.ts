@@ -2,7 +2,13 @@ import * as anchor from '@project-serum/anchor'
 import { Program } from '@project-serum/anchor'
 import { Market } from '@project-serum/serum'
 import { OpenAmm } from '../target/types/open_amm'
-import { PublicKey, Keypair, ComputeBudgetProgram } from '@solana/web3.js'
+import {
+  PublicKey,
+  Keypair,
+  ComputeBudgetProgram,
+  Transaction,
+  TransactionInstruction,
+} from '@solana/web3.js'
 import {
   getVaultOwnerAndNonce,
   setupTestMarket,
@@ -16,11 +22,17 @@ import { Account } from '@solana/spl-token'
 import {
   mintTo,
   createMint,
+  createMintToInstruction,
   getOrCreateAssociatedTokenAccount,
   TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
   getAssociatedTokenAddress,
+  transfer,
+  approve,
+  createTransferInstruction,
 } from '@solana/spl-token'
 import { assert } from 'chai'
+import { createHash } from 'crypto'
 
 const QUOTE_VAULT_SEED = 'pool-quote-vault'
 const BASE_VAULT_SEED = 'pool-base-vault'
@@ -139,7 +151,10 @@ describe('openamm', () => {
         /// CHECK: typescript error
         { xYK: {} },
         new anchor.BN('1000000000'),
-        new anchor.BN('1000000000')
+        new anchor.BN('1000000000'),
+        { lINEAR: {} },
+        0,
+        0
       )
       .accounts({
         baseMint,
@@ -186,6 +201,76 @@ describe('openamm', () => {
     assert.strictEqual(poolAccount.quoteAmount.toString(), '1000000000')
   })
 
+  it('Can preview the ladder for a pool', async () => {
+    const preview = await program.methods
+      .previewLadder()
+      .accounts({
+        pool,
+      })
+      .view()
+
+    assert.strictEqual(preview.asks.length, 10)
+    assert.strictEqual(preview.bids.length, 9)
+  })
+
+  it('Can check pool health', async () => {
+    const health = await program.methods
+      .checkPoolHealth(new anchor.BN('1000000'), 10000)
+      .accounts({
+        pool,
+        market: market.publicKey,
+        openOrders,
+        bids: market.decoded.bids,
+        asks: market.decoded.asks,
+        baseVault,
+        quoteVault,
+      })
+      .view()
+
+    // Market making is active and nothing is stale
+    assert.strictEqual(health & 0b11, 0)
+  })
+
+  it('Can get the total value locked in a pool', async () => {
+    const tvl = await program.methods
+      .getTvl()
+      .accounts({
+        pool,
+        market: market.publicKey,
+        openOrders,
+        baseVault,
+        quoteVault,
+      })
+      .view()
+
+    // Both sides of the initial deposit, valued at the pool price of 1
+    assert.strictEqual(tvl.toString(), '2000000000')
+  })
+
+  it('Can get the spot price of a pool', async () => {
+    const spotPrice = await program.methods
+      .getSpotPrice()
+      .accounts({ pool })
+      .view()
+
+    assert.strictEqual(spotPrice.feeBps, 20)
+    assert.isTrue(spotPrice.bestBidPrice.lt(spotPrice.price))
+    assert.isTrue(spotPrice.bestAskPrice.gt(spotPrice.price))
+  })
+
+  it('Can derive the addresses of a pool', async () => {
+    const addresses = await program.methods
+      .deriveAddresses(market.publicKey, { xYK: {} }, 0)
+      .accounts({})
+      .view()
+
+    assert.ok(addresses.pool.equals(pool))
+    assert.ok(addresses.baseVault.equals(baseVault))
+    assert.ok(addresses.quoteVault.equals(quoteVault))
+    assert.ok(addresses.lpMint.equals(lpMint))
+    assert.ok(addresses.openOrders.equals(openOrders))
+  })
+
   it('Can deposit to a pool', async () => {
     await Promise.all([
       mintTo(
@@ -248,6 +333,18 @@ describe('openamm', () => {
     assert.strictEqual(poolAccount.quoteAmount.toString(), '2000000000')
   })
 
+  it('Can get the reserves LP tokens are worth', async () => {
+    const lpValue = await program.methods
+      .getLpValue(new anchor.BN('1000000000'))
+      .accounts({ pool, lpMint })
+      .view()
+
+    // The wallet holds the whole supply of 1999999998, so this is just
+    // over half the reserves, rounded down
+    assert.strictEqual(lpValue.baseAmount.toString(), '1000000000')
+    assert.strictEqual(lpValue.quoteAmount.toString(), '1000000000')
+  })
+
   it('Can withdraw from a pool', async () => {
     const additionalComputeBudgetInstruction =
       ComputeBudgetProgram.setComputeUnitLimit({ units: 800000 })
@@ -287,6 +384,91 @@ describe('openamm', () => {
 
     assert.strictEqual(poolAccount.baseAmount.toString(), '1000000000')
     assert.strictEqual(poolAccount.quoteAmount.toString(), '1000000000')
+    assert.strictEqual(poolAccount.depositLog.count.toString(), '1')
+    assert.strictEqual(poolAccount.withdrawLog.count.toString(), '1')
+    assert.ok(poolAccount.withdrawLog.lastCaller.equals(wallet.publicKey))
+  })
+
+  it('Can wrap LP tokens into a priced Token-2022 mint and back', async () => {
+    const wrappedLpMint = PublicKey.findProgramAddressSync(
+      [pool.toBuffer(), Buffer.from('pool-wrapped-lp-mint')],
+      program.programId
+    )[0]
+    const wrappedLpVault = PublicKey.findProgramAddressSync(
+      [pool.toBuffer(), Buffer.from('pool-wrapped-lp-vault')],
+      program.programId
+    )[0]
+
+    await program.methods
+      .initWrappedLp()
+      .accounts({
+        pool,
+        lpMint,
+        wrappedLpMint,
+        wrappedLpVault,
+        payer: wallet.publicKey,
+        token2022Program: TOKEN_2022_PROGRAM_ID,
+      })
+      .rpc()
+
+    const signerWrappedLp = await getOrCreateAssociatedTokenAccount(
+      program.provider.connection,
+      wallet,
+      wrappedLpMint,
+      wallet.publicKey,
+      false,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    )
+    const accounts = {
+      pool,
+      lpMint,
+      wrappedLpMint,
+      wrappedLpVault,
+      signerLp,
+      signerWrappedLp: signerWrappedLp.address,
+      signer: wallet.publicKey,
+      token2022Program: TOKEN_2022_PROGRAM_ID,
+    }
+
+    await program.methods
+      .wrapLp(new anchor.BN(1000000))
+      .accounts(accounts)
+      .rpc()
+    let wrappedAmount =
+      await program.provider.connection.getTokenAccountBalance(
+        signerWrappedLp.address
+      )
+    assert.strictEqual(wrappedAmount.value.amount, '1000000')
+
+    const price = await program.methods
+      .getWrappedLpPrice()
+      .accounts({
+        tvl: {
+          pool,
+          market: market.publicKey,
+          openOrders,
+          baseVault,
+          quoteVault,
+        },
+        lpMint,
+      })
+      .view()
+    assert.isTrue(price.gt(new anchor.BN(0)))
+
+    await program.methods
+      .unwrapLp(new anchor.BN(1000000))
+      .accounts(accounts)
+      .rpc()
+    wrappedAmount = await program.provider.connection.getTokenAccountBalance(
+      signerWrappedLp.address
+    )
+    assert.strictEqual(wrappedAmount.value.amount, '0')
+    const lpAmount = await program.provider.connection.getTokenAccountBalance(
+      signerLp
+    )
+    assert.strictEqual(lpAmount.value.amount, '999999999')
   })
 
   it('Can place an order that matches against program orders', async () => {
@@ -346,6 +528,73 @@ describe('openamm', () => {
     assert.strictEqual(baseAmount.value.amount, '1010000000')
   })
 
+  it('Mints deposits against reserves with earlier fills reconciled', async () => {
+    // The last test filled the pool's asks, which nothing has reconciled yet
+    const poolBefore = await program.account.openAmmPool.fetch(pool)
+    const lpSupply = new anchor.BN(
+      (await program.provider.connection.getTokenSupply(lpMint)).value.amount
+    )
+    const getBalance = async (account: PublicKey) =>
+      new anchor.BN(
+        (
+          await program.provider.connection.getTokenAccountBalance(account)
+        ).value.amount
+      )
+    const getBalances = () =>
+      Promise.all(
+        [baseMintWalletAta.address, quoteMintWalletAta.address, signerLp].map(
+          getBalance
+        )
+      )
+    const [baseBefore, quoteBefore, lpBefore] = await getBalances()
+
+    await program.methods
+      .depositPassive(
+        new anchor.BN('1000000'),
+        new anchor.BN('1000000'),
+        new anchor.BN('0'),
+        new anchor.BN('0')
+      )
+      .accounts({
+        pool,
+        lpMint,
+        signerLp,
+        signerBase: baseMintWalletAta.address,
+        signerQuote: quoteMintWalletAta.address,
+        dexProgram: DEX_PID,
+        marketAccounts: {
+          market: market.publicKey,
+          requestQueue: market.decoded.requestQueue,
+          eventQueue: market.decoded.eventQueue,
+          bids: market.decoded.bids,
+          asks: market.decoded.asks,
+          baseVault: market.decoded.baseVault,
+          quoteVault: market.decoded.quoteVault,
+          vaultSigner: marketVaultSigner,
+          openOrders,
+        },
+      })
+      .preInstructions([
+        ComputeBudgetProgram.setComputeUnitLimit({ units: 800000 }),
+      ])
+      .rpc()
+
+    const [baseAfter, quoteAfter, lpAfter] = await getBalances()
+    const baseDeposited = baseBefore.sub(baseAfter)
+    const quoteDeposited = quoteBefore.sub(quoteAfter)
+    const lpMinted = lpAfter.sub(lpBefore)
+
+    // The fill paid the pool quote, fees included, that its stale reserves
+    // don't count yet. Minting against them would hand the depositor a share
+    // of it.
+    const staleLp = anchor.BN.min(
+      lpSupply.mul(baseDeposited).div(poolBefore.baseAmount),
+      lpSupply.mul(quoteDeposited).div(poolBefore.quoteAmount)
+    )
+    assert.isTrue(lpMinted.lt(staleLp))
+    assert.isTrue(lpMinted.gtn(0))
+  })
+
   it('Can refresh orders', async () => {
     const additionalComputeBudgetInstruction =
       ComputeBudgetProgram.setComputeUnitLimit({ units: 800000 })
@@ -381,128 +630,45 @@ describe('openamm', () => {
     assert.strictEqual(asks.length, 10)
   })
 
-  it('Can track cumulative volume correctly', async () => {
-    const poolAccount = await program.account.openAmmPool.fetch(pool)
-    const quoteAmount =
-      await program.provider.connection.getTokenAccountBalance(
-        quoteMintWalletAta.address
-      )
-
-    const quoteAmountChanged = new anchor.BN('1000000000').sub(
-      new anchor.BN(quoteAmount.value.amount)
-    )
-
-    // Check: Will be slightly less than because of taker fees
-    assert.ok(poolAccount.cumulativeQuoteVolume.lte(quoteAmountChanged))
-    assert.strictEqual(poolAccount.cumulativeBaseVolume.toString(), '0')
-  })
-
-  it('Can create a test market with bids and asks', async () => {
-    baseMint = await createMint(
-      program.provider.connection,
-      wallet,
-      wallet.publicKey,
-      wallet.publicKey,
-      6
-    )
-
-    baseMintWalletAta = await getOrCreateAssociatedTokenAccount(
-      program.provider.connection,
-      wallet,
-      baseMint,
-      wallet.publicKey
-    )
-
-    await mintTo(
-      program.provider.connection,
-      wallet,
-      baseMint,
-      baseMintWalletAta.address,
-      wallet,
-      1000000000
-    )
-
-    quoteMint = await createMint(
-      program.provider.connection,
-      wallet,
-      wallet.publicKey,
-      wallet.publicKey,
-      6
-    )
-
-    quoteMintWalletAta = await getOrCreateAssociatedTokenAccount(
-      program.provider.connection,
-      wallet,
-      quoteMint,
-      wallet.publicKey
-    )
-
-    await mintTo(
-      program.provider.connection,
-      wallet,
-      quoteMint,
-      quoteMintWalletAta.address,
-      wallet,
-      1000000000
-    )
+  it('Can simulate a refresh without touching the book', async () => {
+    const simulation = await program.methods
+      .simulateRefresh()
+      .accounts({
+        pool,
+        marketAccounts: {
+          market: market.publicKey,
+          requestQueue: market.decoded.requestQueue,
+          eventQueue: market.decoded.eventQueue,
+          bids: market.decoded.bids,
+          asks: market.decoded.asks,
+          baseVault: market.decoded.baseVault,
+          quoteVault: market.decoded.quoteVault,
+          vaultSigner: marketVaultSigner,
+          openOrders,
+        },
+        baseVault,
+        quoteVault,
+        dexProgram: DEX_PID,
+      })
+      .preInstructions([
+        ComputeBudgetProgram.setComputeUnitLimit({ units: 800000 }),
+      ])
+      .view()
 
-    market = await setupTestMarketWithOrders(
-      program.provider,
-      wallet,
-      baseMint,
-      quoteMint
-    )
+    assert.strictEqual(simulation.cancelClientOrderIds.length, 19)
+    assert.strictEqual(simulation.asks.length, 10)
+    assert.strictEqual(simulation.bids.length, 9)
 
     const [bids, asks] = await getAllOrders(market, program.provider)
-
-    assert.strictEqual(bids.length, 6)
-    assert.strictEqual(asks.length, 9)
+    assert.strictEqual(bids.length, 9)
+    assert.strictEqual(asks.length, 10)
   })
 
-  it('Can create a pool on a market that already has orders', async () => {
-    pool = PublicKey.findProgramAddressSync(
-      [
-        market.publicKey.toBuffer(),
-        new Uint8Array([0]),
-        Buffer.from(POOL_SEED),
-      ],
-      program.programId
-    )[0]
-
-    openOrders = PublicKey.findProgramAddressSync(
-      [pool.toBuffer(), Buffer.from('pool-open-orders')],
-      program.programId
-    )[0]
-
-    lpMint = PublicKey.findProgramAddressSync(
-      [pool.toBuffer(), Buffer.from('pool-lp-mint')],
-      program.programId
-    )[0]
-
-    signerLp = await getAssociatedTokenAddress(lpMint, wallet.publicKey)
-
-    marketVaultSigner = (await getVaultOwnerAndNonce(market.publicKey))[0]
-
-    const additionalComputeBudgetInstruction =
-      ComputeBudgetProgram.setComputeUnitLimit({ units: 800000 })
-
-    const createPoolMethod = program.methods
-      .createPool(
-        { xYK: {} },
-        new anchor.BN('1000000000'),
-        new anchor.BN('1000000000')
-      )
+  it('Can get the placed ladder with what rests of it', async () => {
+    const state = await program.methods
+      .getOpenOrdersState()
       .accounts({
-        baseMint,
-        quoteMint,
         pool,
-        signerBase: baseMintWalletAta.address,
-        signerQuote: quoteMintWalletAta.address,
-        lpMint: lpMint,
-        signerLp,
-        openOrders,
-        dexProgram: DEX_PID,
-        tokenProgram: TOKEN_PROGRAM_ID,
         marketAccounts: {
           market: market.publicKey,
           requestQueue: market.decoded.requestQueue,
@@ -514,255 +680,2485 @@ describe('openamm', () => {
           vaultSigner: marketVaultSigner,
           openOrders,
         },
+        baseVault,
+        quoteVault,
+        dexProgram: DEX_PID,
       })
-      .preInstructions([additionalComputeBudgetInstruction])
-
-    await createPoolMethod.rpc()
-
-    const [bids, asks] = await getAllOrders(market, program.provider)
+      .view()
+
+    assert.ok(state.market.equals(market.publicKey))
+    assert.strictEqual(state.asks.length, 10)
+    assert.strictEqual(state.bids.length, 9)
+    // Nothing has traded against the ladder since the refresh
+    for (const rung of [...state.asks, ...state.bids]) {
+      assert.strictEqual(
+        rung.restingBaseQty.toString(),
+        rung.placedBaseQty.toString()
+      )
+    }
+  })
 
-    assert.strictEqual(asks.length, 9)
-    assert.strictEqual(bids.length, 15)
+  it('Moves protocol fees into the fee vaults', async () => {
+    const quoteFeeVault = PublicKey.findProgramAddressSync(
+      [pool.toBuffer(), Buffer.from('pool-quote-fee-vault')],
+      program.programId
+    )[0]
 
-    const lpAmount = await program.provider.connection.getTokenAccountBalance(
-      signerLp
-    )
-    assert.strictEqual(lpAmount.value.amount, '999999999')
-    const poolAccount = await program.account.openAmmPool.fetch(pool)
+    const quoteFeeAmount =
+      await program.provider.connection.getTokenAccountBalance(quoteFeeVault)
 
-    assert.strictEqual(poolAccount.baseAmount.toString(), '1000000000')
-    assert.strictEqual(poolAccount.quoteAmount.toString(), '1000000000')
+    assert.ok(new anchor.BN(quoteFeeAmount.value.amount).gtn(0))
   })
 
-  it('Can create a stable market', async () => {
-    baseMint = await createMint(
-      program.provider.connection,
-      wallet,
-      wallet.publicKey,
-      wallet.publicKey,
-      6
-    )
+  it('Keeps withdrawals open while paused, then resumes quoting', async () => {
+    const marketAccounts = {
+      market: market.publicKey,
+      requestQueue: market.decoded.requestQueue,
+      eventQueue: market.decoded.eventQueue,
+      bids: market.decoded.bids,
+      asks: market.decoded.asks,
+      baseVault: market.decoded.baseVault,
+      quoteVault: market.decoded.quoteVault,
+      vaultSigner: marketVaultSigner,
+      openOrders,
+    }
+    const computeBudget = ComputeBudgetProgram.setComputeUnitLimit({
+      units: 800000,
+    })
+
+    await program.methods
+      .pausePool()
+      .accounts({
+        pool,
+        marketAccounts,
+        baseVault,
+        quoteVault,
+        dexProgram: DEX_PID,
+      })
+      .preInstructions([computeBudget])
+      .rpc()
+
+    let poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.isTrue(poolAccount.paused)
+    assert.isTrue(poolAccount.mmActive)
+    let [bids, asks] = await getAllOrders(market, program.provider)
+    assert.strictEqual(bids.length + asks.length, 0)
+
+    const lpBefore = await program.provider.connection.getTokenAccountBalance(
+      signerLp
+    )
+    await program.methods
+      .withdraw(new anchor.BN('1000'))
+      .accounts({
+        pool,
+        baseVault,
+        quoteVault,
+        lpMint,
+        signerLp,
+        signerBase: baseMintWalletAta.address,
+        signerQuote: quoteMintWalletAta.address,
+        dexProgram: DEX_PID,
+        marketAccounts,
+      })
+      .preInstructions([computeBudget])
+      .rpc()
+
+    // Paid out from the vaults without putting anything back on the book
+    const lpAfter = await program.provider.connection.getTokenAccountBalance(
+      signerLp
+    )
+    assert.strictEqual(
+      new anchor.BN(lpBefore.value.amount)
+        .sub(new anchor.BN(lpAfter.value.amount))
+        .toString(),
+      '1000'
+    )
+    ;[bids, asks] = await getAllOrders(market, program.provider)
+    assert.strictEqual(bids.length + asks.length, 0)
+
+    await program.methods.resumePool().accounts({ pool }).rpc()
+    await program.methods
+      .refreshOrders()
+      .accounts({
+        pool,
+        marketAccounts,
+        baseVault,
+        quoteVault,
+        signerBase: baseMintWalletAta.address,
+        signerQuote: quoteMintWalletAta.address,
+        dexProgram: DEX_PID,
+      })
+      .preInstructions([computeBudget])
+      .rpc()
+
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.isFalse(poolAccount.paused)
+    assert.isTrue(poolAccount.mmActive)
+    ;[bids, asks] = await getAllOrders(market, program.provider)
+    assert.isTrue(bids.length > 0 && asks.length > 0)
+  })
+
+  it('Leaves rungs that would cross the book off the ladder', async () => {
+    const walletAsAccount = new anchor.web3.Account(wallet.secretKey)
+    const marketAccounts = {
+      market: market.publicKey,
+      requestQueue: market.decoded.requestQueue,
+      eventQueue: market.decoded.eventQueue,
+      bids: market.decoded.bids,
+      asks: market.decoded.asks,
+      baseVault: market.decoded.baseVault,
+      quoteVault: market.decoded.quoteVault,
+      vaultSigner: marketVaultSigner,
+      openOrders,
+    }
+    const computeBudget = ComputeBudgetProgram.setComputeUnitLimit({
+      units: 800000,
+    })
+    const refreshMethod = () =>
+      program.methods
+        .refreshOrders()
+        .accounts({
+          pool,
+          marketAccounts,
+          baseVault,
+          quoteVault,
+          signerBase: baseMintWalletAta.address,
+          signerQuote: quoteMintWalletAta.address,
+          dexProgram: DEX_PID,
+        })
+        .preInstructions([computeBudget])
+
+    // Pull the pool's bids so an ask can rest at the lowest price, where
+    // every bid the pool could post crosses it
+    await program.methods
+      .pausePool()
+      .accounts({
+        pool,
+        marketAccounts,
+        baseVault,
+        quoteVault,
+        dexProgram: DEX_PID,
+      })
+      .preInstructions([computeBudget])
+      .rpc()
+    await market.placeOrder(program.provider.connection, {
+      owner: walletAsAccount,
+      payer: baseMintWalletAta.address,
+      side: 'sell',
+      price: market.tickSize,
+      size: 1,
+      orderType: 'postOnly',
+      feeDiscountPubkey: null,
+    })
+    await program.methods.resumePool().accounts({ pool }).rpc()
+    await refreshMethod().rpc()
+
+    let poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.ok(poolAccount.crossedRungs > 0)
+    assert.strictEqual(poolAccount.placedBids.len.toString(), '0')
+    assert.ok(poolAccount.placedAsks.len.gtn(0))
+    const baseAmount = poolAccount.baseAmount.toString()
+    const quoteAmount = poolAccount.quoteAmount.toString()
+    let [bids] = await getAllOrders(market, program.provider)
+    assert.strictEqual(bids.length, 0)
+
+    // Nothing was recorded for the crossed rungs, so reconciling them
+    // credits no fills
+    await refreshMethod().rpc()
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.strictEqual(poolAccount.baseAmount.toString(), baseAmount)
+    assert.strictEqual(poolAccount.quoteAmount.toString(), quoteAmount)
+
+    const [walletAsk] = await market.loadOrdersForOwner(
+      program.provider.connection,
+      wallet.publicKey
+    )
+    await market.cancelOrder(
+      program.provider.connection,
+      walletAsAccount,
+      walletAsk
+    )
+    const [walletOpenOrders] = await market.findOpenOrdersAccountsForOwner(
+      program.provider.connection,
+      wallet.publicKey
+    )
+    await market.settleFunds(
+      program.provider.connection,
+      walletAsAccount,
+      walletOpenOrders,
+      baseMintWalletAta.address,
+      quoteMintWalletAta.address
+    )
+    await refreshMethod().rpc()
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.strictEqual(poolAccount.crossedRungs, 0)
+    ;[bids] = await getAllOrders(market, program.provider)
+    assert.ok(bids.length > 0)
+  })
+
+  it('Can toggle debug logging as the pool authority', async () => {
+    await program.methods
+      .setDebugLogging(true)
+      .accounts({
+        pool,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+
+    let poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.strictEqual(poolAccount.debugLogging, true)
+
+    await program.methods
+      .setDebugLogging(false)
+      .accounts({
+        pool,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.strictEqual(poolAccount.debugLogging, false)
+  })
+
+  it('Can configure the keeper refund as the pool authority', async () => {
+    let poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.strictEqual(poolAccount.keeperRefundBps, 1)
+
+    try {
+      await program.methods
+        .setKeeperRefund(11)
+        .accounts({
+          pool,
+          authority: wallet.publicKey,
+        })
+        .rpc()
+      assert.fail('set a keeper refund above the cap')
+    } catch (e) {
+      assert.include(e.toString(), 'InvalidKeeperRefund')
+    }
+
+    await program.methods
+      .setKeeperRefund(0)
+      .accounts({
+        pool,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.strictEqual(poolAccount.keeperRefundBps, 0)
+
+    await program.methods
+      .setKeeperRefund(1)
+      .accounts({
+        pool,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.strictEqual(poolAccount.keeperRefundBps, 1)
+  })
+
+  it('Can set rung fees as the pool authority', async () => {
+    const flat = new Array(16).fill(0)
+    const tiered = flat.map((_, rung) => rung * 10)
+
+    try {
+      await program.methods
+        .setRungFees([...tiered].reverse())
+        .accounts({
+          pool,
+          authority: wallet.publicKey,
+        })
+        .rpc()
+      assert.fail('set rung fees that fall with depth')
+    } catch (e) {
+      assert.include(e.toString(), 'InvalidRungFees')
+    }
+
+    await program.methods
+      .setRungFees(tiered)
+      .accounts({
+        pool,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+    let poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.deepEqual(poolAccount.rungFeeBps, tiered)
+
+    await program.methods
+      .setRungFees(flat)
+      .accounts({
+        pool,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.deepEqual(poolAccount.rungFeeBps, flat)
+  })
+
+  it('Can approve a proposed parameter change as the pool authority', async () => {
+    const proposal = PublicKey.findProgramAddressSync(
+      [
+        pool.toBuffer(),
+        wallet.publicKey.toBuffer(),
+        Buffer.from('pool-proposal'),
+      ],
+      program.programId
+    )[0]
+
+    try {
+      await program.methods
+        .proposeParameters({ jitter: { jitterBps: 501 } })
+        .accounts({
+          pool,
+          proposal,
+          proposer: wallet.publicKey,
+        })
+        .rpc()
+      assert.fail('proposed a jitter above the cap')
+    } catch (e) {
+      assert.include(e.toString(), 'InvalidJitter')
+    }
+
+    await program.methods
+      .proposeParameters({ keeperRefund: { keeperRefundBps: 2 } })
+      .accounts({
+        pool,
+        proposal,
+        proposer: wallet.publicKey,
+      })
+      .rpc()
+    const proposalAccount = await program.account.parameterProposal.fetch(
+      proposal
+    )
+    assert.ok(proposalAccount.pool.equals(pool))
+    assert.strictEqual(
+      proposalAccount.parameter.keeperRefund.keeperRefundBps,
+      2
+    )
+
+    await program.methods
+      .approveProposal()
+      .accounts({
+        pool,
+        proposal,
+        proposer: wallet.publicKey,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+    let poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.strictEqual(poolAccount.keeperRefundBps, 2)
+    assert.isNull(
+      await program.account.parameterProposal.fetchNullable(proposal)
+    )
+
+    await program.methods
+      .proposeParameters({ keeperRefund: { keeperRefundBps: 10 } })
+      .accounts({
+        pool,
+        proposal,
+        proposer: wallet.publicKey,
+      })
+      .rpc()
+    await program.methods
+      .rejectProposal()
+      .accounts({
+        pool,
+        proposal,
+        proposer: wallet.publicKey,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.strictEqual(poolAccount.keeperRefundBps, 2)
+
+    await program.methods
+      .setKeeperRefund(1)
+      .accounts({
+        pool,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+  })
+
+  it('Leaves the ladder off while the event queue is backed up', async () => {
+    // Nothing in these tests cranks the market, so its event queue still
+    // holds the fills and cancels from earlier tests
+    const refreshMethod = () =>
+      program.methods
+        .refreshOrders()
+        .accounts({
+          pool,
+          marketAccounts: {
+            market: market.publicKey,
+            requestQueue: market.decoded.requestQueue,
+            eventQueue: market.decoded.eventQueue,
+            bids: market.decoded.bids,
+            asks: market.decoded.asks,
+            baseVault: market.decoded.baseVault,
+            quoteVault: market.decoded.quoteVault,
+            vaultSigner: marketVaultSigner,
+            openOrders,
+          },
+          baseVault,
+          quoteVault,
+          signerBase: baseMintWalletAta.address,
+          signerQuote: quoteMintWalletAta.address,
+          dexProgram: DEX_PID,
+        })
+        .preInstructions([
+          ComputeBudgetProgram.setComputeUnitLimit({ units: 800000 }),
+        ])
+
+    await program.methods
+      .setEventQueueLimit(new anchor.BN(1))
+      .accounts({
+        pool,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+    let poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.strictEqual(poolAccount.maxEventQueueBacklog.toString(), '1')
+
+    await refreshMethod().rpc()
+    let [bids, asks] = await getAllOrders(market, program.provider)
+    assert.strictEqual(bids.length + asks.length, 0)
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.ok(poolAccount.mmActive)
+
+    await program.methods
+      .setEventQueueLimit(new anchor.BN(0))
+      .accounts({
+        pool,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+    await refreshMethod().rpc()
+    ;[bids, asks] = await getAllOrders(market, program.provider)
+    assert.ok(bids.length > 0 && asks.length > 0)
+  })
+
+  it('Stops quoting outside the pool\'s quoting hours', async () => {
+    const refreshMethod = () =>
+      program.methods
+        .refreshOrders()
+        .accounts({
+          pool,
+          marketAccounts: {
+            market: market.publicKey,
+            requestQueue: market.decoded.requestQueue,
+            eventQueue: market.decoded.eventQueue,
+            bids: market.decoded.bids,
+            asks: market.decoded.asks,
+            baseVault: market.decoded.baseVault,
+            quoteVault: market.decoded.quoteVault,
+            vaultSigner: marketVaultSigner,
+            openOrders,
+          },
+          baseVault,
+          quoteVault,
+          signerBase: baseMintWalletAta.address,
+          signerQuote: quoteMintWalletAta.address,
+          dexProgram: DEX_PID,
+        })
+        .preInstructions([
+          ComputeBudgetProgram.setComputeUnitLimit({ units: 800000 }),
+        ])
+    const setScheduleMethod = (openSecs: number, closeSecs: number) =>
+      program.methods
+        .setQuotingSchedule(openSecs, closeSecs, 0, new anchor.BN(0))
+        .accounts({
+          pool,
+          authority: wallet.publicKey,
+        })
+
+    try {
+      await setScheduleMethod(86400, 0).rpc()
+      assert.fail('set quoting hours past the end of the day')
+    } catch (e) {
+      assert.include(e.toString(), 'InvalidQuotingSchedule')
+    }
+
+    // A window opening an hour from now
+    const secondsIntoDay = Math.floor(Date.now() / 1000) % 86400
+    await setScheduleMethod(
+      (secondsIntoDay + 3600) % 86400,
+      (secondsIntoDay + 7200) % 86400
+    ).rpc()
+    await refreshMethod().rpc()
+    let [bids, asks] = await getAllOrders(market, program.provider)
+    assert.strictEqual(bids.length + asks.length, 0)
+
+    await setScheduleMethod(0, 0).rpc()
+    await refreshMethod().rpc()
+    ;[bids, asks] = await getAllOrders(market, program.provider)
+    assert.ok(bids.length > 0 && asks.length > 0)
+  })
+
+  it('Fits the ladder in the pool\'s compute budget', async () => {
+    const refreshMethod = () =>
+      program.methods
+        .refreshOrders()
+        .accounts({
+          pool,
+          marketAccounts: {
+            market: market.publicKey,
+            requestQueue: market.decoded.requestQueue,
+            eventQueue: market.decoded.eventQueue,
+            bids: market.decoded.bids,
+            asks: market.decoded.asks,
+            baseVault: market.decoded.baseVault,
+            quoteVault: market.decoded.quoteVault,
+            vaultSigner: marketVaultSigner,
+            openOrders,
+          },
+          baseVault,
+          quoteVault,
+          signerBase: baseMintWalletAta.address,
+          signerQuote: quoteMintWalletAta.address,
+          dexProgram: DEX_PID,
+        })
+        .preInstructions([
+          ComputeBudgetProgram.setComputeUnitLimit({ units: 800000 }),
+        ])
+    const setBudgetMethod = (budgetUnits: number, placeUnits: number) =>
+      program.methods
+        .setComputeBudget(budgetUnits, 0, 0, placeUnits)
+        .accounts({
+          pool,
+          authority: wallet.publicKey,
+        })
+
+    try {
+      await setBudgetMethod(800000, 0).rpc()
+      assert.fail('set a compute budget with free placements')
+    } catch (e) {
+      assert.include(e.toString(), 'InvalidComputeBudget')
+    }
+
+    // Room for an ask and a bid
+    await setBudgetMethod(800000, 400000).rpc()
+    await refreshMethod().rpc()
+    let [bids, asks] = await getAllOrders(market, program.provider)
+    assert.strictEqual(bids.length, 1)
+    assert.strictEqual(asks.length, 1)
+    const poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.ok(poolAccount.skippedRungs > 0)
+
+    await setBudgetMethod(0, 0).rpc()
+    await refreshMethod().rpc()
+    ;[bids, asks] = await getAllOrders(market, program.provider)
+    assert.ok(bids.length > 1 && asks.length > 1)
+  })
+
+  it('Can rescue a pool in one transaction as the authority', async () => {
+    const marketAccounts = {
+      market: market.publicKey,
+      requestQueue: market.decoded.requestQueue,
+      eventQueue: market.decoded.eventQueue,
+      bids: market.decoded.bids,
+      asks: market.decoded.asks,
+      baseVault: market.decoded.baseVault,
+      quoteVault: market.decoded.quoteVault,
+      vaultSigner: marketVaultSigner,
+      openOrders,
+    }
+    const computeBudget = ComputeBudgetProgram.setComputeUnitLimit({
+      units: 800000,
+    })
+
+    await program.methods
+      .rescue(10, false)
+      .accounts({
+        pool,
+        marketAccounts,
+        baseVault,
+        quoteVault,
+        authority: wallet.publicKey,
+        dexProgram: DEX_PID,
+      })
+      .remainingAccounts([
+        { pubkey: openOrders, isWritable: true, isSigner: false },
+      ])
+      .preInstructions([computeBudget])
+      .rpc()
+
+    let [bids, asks] = await getAllOrders(market, program.provider)
+    assert.strictEqual(bids.length + asks.length, 0)
+    const poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.isFalse(poolAccount.inProgress)
+    assert.isFalse(poolAccount.paused)
+
+    await program.methods
+      .refreshOrders()
+      .accounts({
+        pool,
+        marketAccounts,
+        baseVault,
+        quoteVault,
+        signerBase: baseMintWalletAta.address,
+        signerQuote: quoteMintWalletAta.address,
+        dexProgram: DEX_PID,
+      })
+      .preInstructions([computeBudget])
+      .rpc()
+    ;[bids, asks] = await getAllOrders(market, program.provider)
+    assert.ok(bids.length > 0 && asks.length > 0)
+  })
+
+  it('Tracks utilization and can configure deleveraging', async () => {
+    let poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.ok(poolAccount.baseUtilizationBps > 0)
+    assert.ok(poolAccount.quoteUtilizationBps > 0)
+    assert.ok(poolAccount.baseUtilizationBps <= 10000)
+
+    await program.methods
+      .setDeleverage(new anchor.BN(500), 5000)
+      .accounts({
+        pool,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.strictEqual(poolAccount.deleverageVelocityBps.toString(), '500')
+    assert.strictEqual(poolAccount.maxDeleverageBps, 5000)
+
+    await program.methods
+      .setDeleverage(new anchor.BN(0), 0)
+      .accounts({
+        pool,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.strictEqual(poolAccount.deleverageVelocityBps.toString(), '0')
+  })
+
+  it('Can point the fee router at a distribution account', async () => {
+    await program.methods
+      .setFeeRouter({ qUOTE: {} }, new anchor.BN(100))
+      .accounts({
+        pool,
+        authority: wallet.publicKey,
+        feeDistribution: quoteMintWalletAta.address,
+      })
+      .rpc()
+
+    const poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.ok(poolAccount.feeDistribution.equals(quoteMintWalletAta.address))
+    assert.strictEqual(poolAccount.feeProcessIntervalSlots.toString(), '100')
+    assert.deepEqual(poolAccount.feeRouterTarget, { qUOTE: {} })
+  })
+
+  it('Can label a pool with metadata', async () => {
+    const name = Buffer.alloc(32)
+    name.write('openAMM Test Pool')
+
+    await program.methods
+      .setPoolMetadata([...name], 'https://example.com/pool.json')
+      .accounts({
+        pool,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+
+    const poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.deepEqual(poolAccount.name, [...name])
+    assert.strictEqual(
+      Buffer.from(poolAccount.uri).toString().replace(/\0+$/, ''),
+      'https://example.com/pool.json'
+    )
+  })
+
+  it('Can hand the pool authority over and back', async () => {
+    // Stands in for a governance treasury, which signs the same way through
+    // invoke_signed when a proposal executes
+    const dao = Keypair.generate()
+    await program.methods
+      .setAuthority(dao.publicKey)
+      .accounts({ pool, authority: wallet.publicKey })
+      .rpc()
+
+    let poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.ok(poolAccount.authority.equals(wallet.publicKey))
+    assert.ok(poolAccount.pendingAuthority.equals(dao.publicKey))
+
+    await program.methods
+      .acceptAuthority()
+      .accounts({ pool, pendingAuthority: dao.publicKey })
+      .signers([dao])
+      .rpc()
+
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.ok(poolAccount.authority.equals(dao.publicKey))
+
+    await program.methods
+      .setDebugLogging(false)
+      .accounts({ pool, authority: dao.publicKey })
+      .signers([dao])
+      .rpc()
+
+    await program.methods
+      .setAuthority(wallet.publicKey)
+      .accounts({ pool, authority: dao.publicKey })
+      .signers([dao])
+      .rpc()
+    await program.methods
+      .acceptAuthority()
+      .accounts({ pool, pendingAuthority: wallet.publicKey })
+      .rpc()
+
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.ok(poolAccount.authority.equals(wallet.publicKey))
+    assert.ok(poolAccount.pendingAuthority.equals(PublicKey.default))
+  })
+
+  it('Can configure and clear an oracle band', async () => {
+    const oracle = Keypair.generate().publicKey
+    await program.methods
+      .setOracle(oracle, 100, new anchor.BN(25))
+      .accounts({
+        pool,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+
+    let poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.ok(poolAccount.oracle.equals(oracle))
+    assert.strictEqual(poolAccount.oracleBandBps, 100)
+
+    await program.methods
+      .setOracle(PublicKey.default, 0, new anchor.BN(0))
+      .accounts({
+        pool,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.ok(poolAccount.oracle.equals(PublicKey.default))
+  })
+
+  it('Can cap deposits while off the oracle and clear it', async () => {
+    try {
+      await program.methods
+        .setDepositCap(10001, 100)
+        .accounts({
+          pool,
+          authority: wallet.publicKey,
+        })
+        .rpc()
+      assert.fail('capped deposits above the LP supply')
+    } catch (e) {
+      assert.include(e.toString(), 'InvalidDepositCap')
+    }
+
+    await program.methods
+      .setDepositCap(500, 100)
+      .accounts({
+        pool,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+    let poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.strictEqual(poolAccount.maxDepositLpBps, 500)
+    assert.strictEqual(poolAccount.depositCapDeviationBps, 100)
+
+    await program.methods
+      .setDepositCap(0, 0)
+      .accounts({
+        pool,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.strictEqual(poolAccount.maxDepositLpBps, 0)
+  })
+
+  it('Can anchor a pool to another pool and clear it', async () => {
+    try {
+      await program.methods
+        .setAnchorPool(pool, new anchor.BN(25))
+        .accounts({
+          pool,
+          authority: wallet.publicKey,
+        })
+        .rpc()
+      assert.fail('anchored a pool to itself')
+    } catch (e) {
+      assert.include(e.toString(), 'InvalidAnchorPool')
+    }
+
+    const anchorPool = Keypair.generate().publicKey
+    await program.methods
+      .setAnchorPool(anchorPool, new anchor.BN(25))
+      .accounts({
+        pool,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+
+    let poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.ok(poolAccount.anchorPool.equals(anchorPool))
+    assert.strictEqual(poolAccount.anchorMaxAgeSlots.toString(), '25')
+
+    await program.methods
+      .setAnchorPool(PublicKey.default, new anchor.BN(0))
+      .accounts({
+        pool,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.ok(poolAccount.anchorPool.equals(PublicKey.default))
+  })
+
+  it('Can list a pool in its market registry', async () => {
+    const poolRegistry = PublicKey.findProgramAddressSync(
+      [market.publicKey.toBuffer(), Buffer.from('pool-registry')],
+      program.programId
+    )[0]
+
+    await program.methods
+      .createPoolRegistry()
+      .accounts({
+        poolRegistry,
+        market: market.publicKey,
+        payer: wallet.publicKey,
+      })
+      .rpc()
+    await program.methods
+      .registerPool()
+      .accounts({ pool, poolRegistry })
+      .rpc()
+
+    const registry = await program.account.poolRegistry.fetch(poolRegistry)
+    assert.strictEqual(registry.poolCount, 1)
+    assert.ok(registry.pools[0].equals(pool))
+    const poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.strictEqual(poolAccount.index, 0)
+
+    try {
+      await program.methods
+        .registerPool()
+        .accounts({ pool, poolRegistry })
+        .rpc()
+      assert.fail('registered a pool twice')
+    } catch (e) {
+      assert.include(e.toString(), 'PoolAlreadyRegistered')
+    }
+
+    const pools = await program.methods
+      .getPoolsForMarket()
+      .accounts({ poolRegistry, market: market.publicKey })
+      .remainingAccounts([{ pubkey: pool, isWritable: false, isSigner: false }])
+      .view()
+    assert.strictEqual(pools.length, 1)
+    assert.ok(pools[0].pool.equals(pool))
+    assert.deepEqual(pools[0].poolType, { xYK: {} })
+    assert.strictEqual(pools[0].index, 0)
+    assert.ok(pools[0].feeBps >= 20)
+    assert.ok(pools[0].active)
+    assert.strictEqual(
+      pools[0].baseAmount.toString(),
+      poolAccount.baseAmount.toString()
+    )
+  })
+
+  it('Can open a position for a pool', async () => {
+    const position = PublicKey.findProgramAddressSync(
+      [
+        pool.toBuffer(),
+        wallet.publicKey.toBuffer(),
+        Buffer.from('pool-position'),
+      ],
+      program.programId
+    )[0]
+
+    await program.methods
+      .openPosition()
+      .accounts({
+        pool,
+        position,
+        owner: wallet.publicKey,
+      })
+      .rpc()
+
+    const positionAccount = await program.account.position.fetch(position)
+    assert.ok(positionAccount.pool.equals(pool))
+    assert.ok(positionAccount.owner.equals(wallet.publicKey))
+    assert.strictEqual(positionAccount.lpAmount.toString(), '0')
+  })
+
+  it('Can register a pool with a keeper', async () => {
+    const keeper = PublicKey.findProgramAddressSync(
+      [wallet.publicKey.toBuffer(), Buffer.from('keeper')],
+      program.programId
+    )[0]
+
+    await program.methods
+      .createKeeper()
+      .accounts({
+        keeper,
+        operator: wallet.publicKey,
+      })
+      .rpc()
+
+    await program.methods
+      .registerKeeperPool()
+      .accounts({
+        pool,
+        keeper,
+        operator: wallet.publicKey,
+      })
+      .rpc()
+
+    const keeperAccount = await program.account.keeper.fetch(keeper)
+    assert.strictEqual(keeperAccount.poolCount, 1)
+    assert.ok(keeperAccount.pools[0].equals(pool))
+  })
+
+  it('Can track cumulative volume correctly', async () => {
+    const poolAccount = await program.account.openAmmPool.fetch(pool)
+    const quoteAmount =
+      await program.provider.connection.getTokenAccountBalance(
+        quoteMintWalletAta.address
+      )
+
+    const quoteAmountChanged = new anchor.BN('1000000000').sub(
+      new anchor.BN(quoteAmount.value.amount)
+    )
+
+    // Check: Will be slightly less than because of taker fees
+    assert.ok(poolAccount.cumulativeQuoteVolume.lte(quoteAmountChanged))
+    assert.strictEqual(poolAccount.cumulativeBaseVolume.toString(), '0')
+  })
+
+  it('Can get daily stats for a pool', async () => {
+    const stats = await program.methods
+      .getPoolStats()
+      .accounts({
+        pool,
+      })
+      .view()
+
+    // Everything in these tests happens on the same day
+    assert.ok(stats.tvlQuoteAmount.gtn(0))
+    assert.ok(stats.volumeQuoteAmount.gtn(0))
+    assert.strictEqual(
+      stats.todayVolumeQuoteAmount.toString(),
+      stats.volumeQuoteAmount.toString()
+    )
+    assert.ok(stats.lpFeeQuoteAmount.gtn(0))
+    // Without a quote oracle nothing is reported in USD
+    assert.strictEqual(stats.tvlUsdAmount.toString(), '0')
+  })
+
+  it('Needs the quote oracle to get stats once one is set', async () => {
+    const quoteOracle = Keypair.generate().publicKey
+    await program.methods
+      .setQuoteOracle(quoteOracle, new anchor.BN(25))
+      .accounts({
+        pool,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+    let poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.ok(poolAccount.quoteOracle.equals(quoteOracle))
+
+    try {
+      await program.methods
+        .getPoolStats()
+        .accounts({
+          pool,
+        })
+        .view()
+      assert.fail('got stats without the quote oracle')
+    } catch (e) {
+      assert.include(e.toString(), 'MissingOracleAccount')
+    }
+
+    // Not a Pyth price account, so there is no USD price to report with
+    const stats = await program.methods
+      .getPoolStats()
+      .accounts({
+        pool,
+      })
+      .remainingAccounts([
+        { pubkey: quoteOracle, isWritable: false, isSigner: false },
+      ])
+      .view()
+    assert.ok(stats.tvlQuoteAmount.gtn(0))
+    assert.strictEqual(stats.tvlUsdAmount.toString(), '0')
+
+    await program.methods
+      .setQuoteOracle(PublicKey.default, new anchor.BN(0))
+      .accounts({
+        pool,
+        authority: wallet.publicKey,
+      })
+      .rpc()
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.ok(poolAccount.quoteOracle.equals(PublicKey.default))
+  })
+
+  it('Can create a test market with bids and asks', async () => {
+    baseMint = await createMint(
+      program.provider.connection,
+      wallet,
+      wallet.publicKey,
+      wallet.publicKey,
+      6
+    )
+
+    baseMintWalletAta = await getOrCreateAssociatedTokenAccount(
+      program.provider.connection,
+      wallet,
+      baseMint,
+      wallet.publicKey
+    )
+
+    await mintTo(
+      program.provider.connection,
+      wallet,
+      baseMint,
+      baseMintWalletAta.address,
+      wallet,
+      1000000000
+    )
+
+    quoteMint = await createMint(
+      program.provider.connection,
+      wallet,
+      wallet.publicKey,
+      wallet.publicKey,
+      6
+    )
+
+    quoteMintWalletAta = await getOrCreateAssociatedTokenAccount(
+      program.provider.connection,
+      wallet,
+      quoteMint,
+      wallet.publicKey
+    )
+
+    await mintTo(
+      program.provider.connection,
+      wallet,
+      quoteMint,
+      quoteMintWalletAta.address,
+      wallet,
+      1000000000
+    )
+
+    market = await setupTestMarketWithOrders(
+      program.provider,
+      wallet,
+      baseMint,
+      quoteMint
+    )
+
+    const [bids, asks] = await getAllOrders(market, program.provider)
+
+    assert.strictEqual(bids.length, 6)
+    assert.strictEqual(asks.length, 9)
+  })
+
+  it('Can create a pool on a market that already has orders', async () => {
+    pool = PublicKey.findProgramAddressSync(
+      [
+        market.publicKey.toBuffer(),
+        new Uint8Array([0]),
+        Buffer.from(POOL_SEED),
+      ],
+      program.programId
+    )[0]
+
+    openOrders = PublicKey.findProgramAddressSync(
+      [pool.toBuffer(), Buffer.from('pool-open-orders')],
+      program.programId
+    )[0]
+
+    lpMint = PublicKey.findProgramAddressSync(
+      [pool.toBuffer(), Buffer.from('pool-lp-mint')],
+      program.programId
+    )[0]
+
+    signerLp = await getAssociatedTokenAddress(lpMint, wallet.publicKey)
+
+    marketVaultSigner = (await getVaultOwnerAndNonce(market.publicKey))[0]
+
+    const additionalComputeBudgetInstruction =
+      ComputeBudgetProgram.setComputeUnitLimit({ units: 800000 })
+
+    const createPoolMethod = program.methods
+      .createPool(
+        { xYK: {} },
+        new anchor.BN('1000000000'),
+        new anchor.BN('1000000000'),
+        { lINEAR: {} },
+        0,
+        0
+      )
+      .accounts({
+        baseMint,
+        quoteMint,
+        pool,
+        signerBase: baseMintWalletAta.address,
+        signerQuote: quoteMintWalletAta.address,
+        lpMint: lpMint,
+        signerLp,
+        openOrders,
+        dexProgram: DEX_PID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        marketAccounts: {
+          market: market.publicKey,
+          requestQueue: market.decoded.requestQueue,
+          eventQueue: market.decoded.eventQueue,
+          bids: market.decoded.bids,
+          asks: market.decoded.asks,
+          baseVault: market.decoded.baseVault,
+          quoteVault: market.decoded.quoteVault,
+          vaultSigner: marketVaultSigner,
+          openOrders,
+        },
+      })
+      .preInstructions([additionalComputeBudgetInstruction])
+
+    await createPoolMethod.rpc()
+
+    const [bids, asks] = await getAllOrders(market, program.provider)
+
+    assert.strictEqual(asks.length, 9)
+    assert.strictEqual(bids.length, 15)
+
+    const lpAmount = await program.provider.connection.getTokenAccountBalance(
+      signerLp
+    )
+    assert.strictEqual(lpAmount.value.amount, '999999999')
+    const poolAccount = await program.account.openAmmPool.fetch(pool)
+
+    assert.strictEqual(poolAccount.baseAmount.toString(), '1000000000')
+    assert.strictEqual(poolAccount.quoteAmount.toString(), '1000000000')
+  })
+
+  it('Can create a stable market', async () => {
+    baseMint = await createMint(
+      program.provider.connection,
+      wallet,
+      wallet.publicKey,
+      wallet.publicKey,
+      6
+    )
 
     baseMintWalletAta = await getOrCreateAssociatedTokenAccount(
       program.provider.connection,
       wallet,
-      baseMint,
-      wallet.publicKey
+      baseMint,
+      wallet.publicKey
+    )
+
+    await mintTo(
+      program.provider.connection,
+      wallet,
+      baseMint,
+      baseMintWalletAta.address,
+      wallet,
+      1000000000
+    )
+
+    quoteMint = await createMint(
+      program.provider.connection,
+      wallet,
+      wallet.publicKey,
+      wallet.publicKey,
+      6
+    )
+
+    quoteMintWalletAta = await getOrCreateAssociatedTokenAccount(
+      program.provider.connection,
+      wallet,
+      quoteMint,
+      wallet.publicKey
+    )
+
+    await mintTo(
+      program.provider.connection,
+      wallet,
+      quoteMint,
+      quoteMintWalletAta.address,
+      wallet,
+      1000000000
+    )
+
+    market = await setupStableMarketWithOrders(
+      program.provider,
+      wallet,
+      baseMint,
+      quoteMint
+    )
+
+    const [bids, asks] = await getAllOrders(market, program.provider)
+
+    assert.strictEqual(bids.length, 4)
+    assert.strictEqual(asks.length, 4)
+  })
+
+  it('Can create a stable pool', async () => {
+    pool = PublicKey.findProgramAddressSync(
+      [
+        market.publicKey.toBuffer(),
+        new Uint8Array([1]),
+        Buffer.from(POOL_SEED),
+      ],
+      program.programId
+    )[0]
+
+    openOrders = PublicKey.findProgramAddressSync(
+      [pool.toBuffer(), Buffer.from('pool-open-orders')],
+      program.programId
+    )[0]
+
+    lpMint = PublicKey.findProgramAddressSync(
+      [pool.toBuffer(), Buffer.from('pool-lp-mint')],
+      program.programId
+    )[0]
+
+    signerLp = await getAssociatedTokenAddress(lpMint, wallet.publicKey)
+
+    marketVaultSigner = (await getVaultOwnerAndNonce(market.publicKey))[0]
+
+    const additionalComputeBudgetInstruction =
+      ComputeBudgetProgram.setComputeUnitLimit({ units: 900000 })
+
+    const createPoolMethod = program.methods
+      .createPool(
+        { sTABLE: {} },
+        new anchor.BN('1000000000'),
+        new anchor.BN('1000000000'),
+        { lINEAR: {} },
+        0,
+        0
+      )
+      .accounts({
+        baseMint,
+        quoteMint,
+        pool,
+        signerBase: baseMintWalletAta.address,
+        signerQuote: quoteMintWalletAta.address,
+        lpMint: lpMint,
+        signerLp,
+        openOrders,
+        dexProgram: DEX_PID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        marketAccounts: {
+          market: market.publicKey,
+          requestQueue: market.decoded.requestQueue,
+          eventQueue: market.decoded.eventQueue,
+          bids: market.decoded.bids,
+          asks: market.decoded.asks,
+          baseVault: market.decoded.baseVault,
+          quoteVault: market.decoded.quoteVault,
+          vaultSigner: marketVaultSigner,
+          openOrders,
+        },
+      })
+      .preInstructions([additionalComputeBudgetInstruction])
+
+    const pubkeys = await createPoolMethod.pubkeys()
+    baseVault = pubkeys.baseVault
+    quoteVault = pubkeys.quoteVault
+
+    await createPoolMethod.rpc()
+
+    const [bids, asks] = await getAllOrders(market, program.provider)
+
+    assert.strictEqual(asks.length, 14)
+    assert.strictEqual(bids.length, 13)
+
+    const lpAmount = await program.provider.connection.getTokenAccountBalance(
+      signerLp
+    )
+    assert.strictEqual(lpAmount.value.amount, '2000000000')
+    const poolAccount = await program.account.openAmmPool.fetch(pool)
+
+    assert.strictEqual(poolAccount.baseAmount.toString(), '1000000000')
+    assert.strictEqual(poolAccount.quoteAmount.toString(), '1000000000')
+  })
+
+  it('Can replace orders after a market order is filled, refunding the cranker', async () => {
+    const walletAsAccount = new anchor.web3.Account(wallet.secretKey)
+    await mintTo(
+      program.provider.connection,
+      wallet,
+      quoteMint,
+      quoteMintWalletAta.address,
+      wallet,
+      100000000
     )
+    let quoteBalance = await program.provider.connection.getTokenAccountBalance(
+      quoteMintWalletAta.address
+    )
+    assert.strictEqual(quoteBalance.value.amount, '100000000')
+    let [bids, asks] = await getAllOrders(market, program.provider)
 
-    await mintTo(
+    assert.strictEqual(bids.length, 13)
+    assert.strictEqual(asks.length, 14)
+
+    let poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.strictEqual(poolAccount.refundBaseAmount.toString(), '0')
+    assert.strictEqual(poolAccount.refundQuoteAmount.toString(), '0')
+
+    await market.placeOrder(program.provider.connection, {
+      owner: walletAsAccount,
+      payer: quoteMintWalletAta.address,
+      side: 'buy', // 'buy' or 'sell'
+      price: 5,
+      size: 10,
+      orderType: 'ioc', // 'limit', 'ioc', 'postOnly'
+      feeDiscountPubkey: null,
+    })
+
+    ;[bids, asks] = await getAllOrders(market, program.provider)
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.strictEqual(poolAccount.refundBaseAmount.toString(), '0')
+    assert.strictEqual(poolAccount.refundQuoteAmount.toString(), '0')
+
+    assert.strictEqual(asks.length, 11)
+    assert.strictEqual(bids.length, 13)
+
+    const quoteAmountBefore =
+      await program.provider.connection.getTokenAccountBalance(
+        quoteMintWalletAta.address
+      )
+
+    const additionalComputeBudgetInstruction =
+      ComputeBudgetProgram.setComputeUnitLimit({ units: 900000 })
+
+    const refreshMethod = program.methods
+      .refreshOrders()
+      .accounts({
+        pool,
+        marketAccounts: {
+          market: market.publicKey,
+          requestQueue: market.decoded.requestQueue,
+          eventQueue: market.decoded.eventQueue,
+          bids: market.decoded.bids,
+          asks: market.decoded.asks,
+          baseVault: market.decoded.baseVault,
+          quoteVault: market.decoded.quoteVault,
+          vaultSigner: marketVaultSigner,
+          openOrders,
+        },
+        baseVault,
+        signerBase: baseMintWalletAta.address,
+        signerQuote: quoteMintWalletAta.address,
+        quoteVault,
+        dexProgram: DEX_PID,
+      })
+      .preInstructions([additionalComputeBudgetInstruction])
+
+    await refreshMethod.rpc()
+
+    ;[bids, asks] = await getAllOrders(market, program.provider)
+    assert.strictEqual(asks.length, 14)
+    assert.strictEqual(bids.length, 13)
+
+    const quoteAmountAfter =
+      await program.provider.connection.getTokenAccountBalance(
+        quoteMintWalletAta.address
+      )
+
+    // Should have gained some quote refund
+    assert.ok(
+      new anchor.BN(quoteAmountBefore.value.amount).lt(
+        new anchor.BN(quoteAmountAfter.value.amount)
+      )
+    )
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.strictEqual(
+      poolAccount.lifetimeKeeperPaidQuoteAmount.toString(),
+      new anchor.BN(quoteAmountAfter.value.amount)
+        .sub(new anchor.BN(quoteAmountBefore.value.amount))
+        .toString()
+    )
+  })
+
+  it('Ignores tokens sent straight to the pool vaults', async () => {
+    const marketAccounts = {
+      market: market.publicKey,
+      requestQueue: market.decoded.requestQueue,
+      eventQueue: market.decoded.eventQueue,
+      bids: market.decoded.bids,
+      asks: market.decoded.asks,
+      baseVault: market.decoded.baseVault,
+      quoteVault: market.decoded.quoteVault,
+      vaultSigner: marketVaultSigner,
+      openOrders,
+    }
+    const computeBudget = ComputeBudgetProgram.setComputeUnitLimit({
+      units: 900000,
+    })
+    const donation = [
+      createTransferInstruction(
+        baseMintWalletAta.address,
+        baseVault,
+        wallet.publicKey,
+        1000000
+      ),
+      createTransferInstruction(
+        quoteMintWalletAta.address,
+        quoteVault,
+        wallet.publicKey,
+        1000000
+      ),
+    ]
+    const balanceAccounts = [
+      signerLp,
+      baseMintWalletAta.address,
+      quoteMintWalletAta.address,
+    ]
+
+    // Runs `instructions` without committing them, and returns the balances
+    // of `balanceAccounts` afterwards along with the slot they ran in
+    const simulate = async (instructions: TransactionInstruction[]) => {
+      const tx = new Transaction().add(computeBudget, ...instructions)
+      tx.feePayer = wallet.publicKey
+      const { context, value } =
+        await program.provider.connection.simulateTransaction(
+          tx,
+          [wallet],
+          balanceAccounts
+        )
+      assert.isNull(value.err)
+      const balances = value.accounts.map((account) =>
+        new anchor.BN(
+          Buffer.from(account.data[0], 'base64').subarray(64, 72),
+          'le'
+        ).toString()
+      )
+      return { slot: context.slot, balances }
+    }
+
+    // The same instructions with and without a donation ahead of them, run
+    // in the same slot so the deposit price can't move between them
+    const compareWithDonation = async (instruction: TransactionInstruction) => {
+      for (let attempt = 0; attempt < 5; attempt++) {
+        const [plain, donated] = await Promise.all([
+          simulate([instruction]),
+          simulate([...donation, instruction]),
+        ])
+        if (plain.slot === donated.slot) {
+          return [plain.balances, donated.balances]
+        }
+      }
+      assert.fail('simulations kept landing in different slots')
+    }
+
+    // The donor's own balances drop by the donation either way, so compare
+    // LP minted and tokens taken or paid rather than the balances themselves
+    const before = (await simulate([])).balances.map((b) => new anchor.BN(b))
+    const getDeltas = (balances: string[], donated: boolean) =>
+      balances.map((balance, i) =>
+        new anchor.BN(balance)
+          .add(new anchor.BN(donated && i > 0 ? 1000000 : 0))
+          .sub(before[i])
+          .toString()
+      )
+
+    const depositInstruction = await program.methods
+      .deposit(
+        new anchor.BN('10000000'),
+        new anchor.BN('10000000'),
+        new anchor.BN('0'),
+        new anchor.BN('0')
+      )
+      .accounts({
+        pool,
+        lpMint,
+        signerLp,
+        signerBase: baseMintWalletAta.address,
+        signerQuote: quoteMintWalletAta.address,
+        dexProgram: DEX_PID,
+        marketAccounts,
+      })
+      .instruction()
+    let [plain, donated] = await compareWithDonation(depositInstruction)
+    assert.notStrictEqual(getDeltas(plain, false)[0], '0')
+    assert.deepEqual(getDeltas(donated, true), getDeltas(plain, false))
+
+    const withdrawInstruction = await program.methods
+      .withdraw(new anchor.BN('1000000'))
+      .accounts({
+        pool,
+        baseVault,
+        quoteVault,
+        lpMint,
+        signerLp,
+        signerBase: baseMintWalletAta.address,
+        signerQuote: quoteMintWalletAta.address,
+        dexProgram: DEX_PID,
+        marketAccounts,
+      })
+      .instruction()
+    ;[plain, donated] = await compareWithDonation(withdrawInstruction)
+    assert.notStrictEqual(getDeltas(plain, false)[1], '0')
+    assert.deepEqual(getDeltas(donated, true), getDeltas(plain, false))
+
+    const poolBefore = await program.account.openAmmPool.fetch(pool)
+    const priceBefore = await program.methods
+      .getSpotPrice()
+      .accounts({ pool })
+      .view()
+
+    await transfer(
       program.provider.connection,
       wallet,
-      baseMint,
       baseMintWalletAta.address,
+      baseVault,
       wallet,
-      1000000000
+      1000000
     )
 
-    quoteMint = await createMint(
-      program.provider.connection,
-      wallet,
-      wallet.publicKey,
-      wallet.publicKey,
-      6
+    // LP tokens are minted and burned against recorded reserves, which a
+    // donation doesn't touch
+    const poolAfter = await program.account.openAmmPool.fetch(pool)
+    const priceAfter = await program.methods
+      .getSpotPrice()
+      .accounts({ pool })
+      .view()
+    assert.strictEqual(
+      poolAfter.baseAmount.toString(),
+      poolBefore.baseAmount.toString()
     )
+    assert.strictEqual(
+      poolAfter.quoteAmount.toString(),
+      poolBefore.quoteAmount.toString()
+    )
+    assert.strictEqual(priceAfter.price.toString(), priceBefore.price.toString())
+  })
 
-    quoteMintWalletAta = await getOrCreateAssociatedTokenAccount(
-      program.provider.connection,
-      wallet,
-      quoteMint,
-      wallet.publicKey
+  it('Can sync donations into reserves as the pool authority', async () => {
+    const poolBefore = await program.account.openAmmPool.fetch(pool)
+
+    await program.methods
+      .syncReserves()
+      .accounts({
+        pool,
+        marketAccounts: {
+          market: market.publicKey,
+          requestQueue: market.decoded.requestQueue,
+          eventQueue: market.decoded.eventQueue,
+          bids: market.decoded.bids,
+          asks: market.decoded.asks,
+          baseVault: market.decoded.baseVault,
+          quoteVault: market.decoded.quoteVault,
+          vaultSigner: marketVaultSigner,
+          openOrders,
+        },
+        baseVault,
+        quoteVault,
+        authority: wallet.publicKey,
+        dexProgram: DEX_PID,
+      })
+      .preInstructions([
+        ComputeBudgetProgram.setComputeUnitLimit({ units: 900000 }),
+      ])
+      .rpc()
+
+    const poolAfter = await program.account.openAmmPool.fetch(pool)
+    assert.ok(
+      poolAfter.baseAmount.gte(
+        poolBefore.baseAmount.add(new anchor.BN(1000000))
+      )
+    )
+  })
+
+  it('Can deposit what an earlier instruction paid out', async () => {
+    const getBalance = async (account: PublicKey) =>
+      new anchor.BN(
+        (
+          await program.provider.connection.getTokenAccountBalance(account)
+        ).value.amount
+      )
+    const [baseBefore, quoteBefore, lpBefore] = await Promise.all(
+      [baseMintWalletAta.address, quoteMintWalletAta.address, signerLp].map(
+        getBalance
+      )
+    )
+    // Stands in for a withdraw from another AMM earlier in the transaction
+    const depositMigratedMethod = (minLpAmount: anchor.BN) =>
+      program.methods
+        .depositMigrated(baseBefore, quoteBefore, minLpAmount)
+        .accounts({
+          pool,
+          lpMint,
+          signerLp,
+          signerBase: baseMintWalletAta.address,
+          signerQuote: quoteMintWalletAta.address,
+          dexProgram: DEX_PID,
+          marketAccounts: {
+            market: market.publicKey,
+            requestQueue: market.decoded.requestQueue,
+            eventQueue: market.decoded.eventQueue,
+            bids: market.decoded.bids,
+            asks: market.decoded.asks,
+            baseVault: market.decoded.baseVault,
+            quoteVault: market.decoded.quoteVault,
+            vaultSigner: marketVaultSigner,
+            openOrders,
+          },
+        })
+        .preInstructions([
+          ComputeBudgetProgram.setComputeUnitLimit({ units: 900000 }),
+          createMintToInstruction(
+            baseMint,
+            baseMintWalletAta.address,
+            wallet.publicKey,
+            1000000
+          ),
+          createMintToInstruction(
+            quoteMint,
+            quoteMintWalletAta.address,
+            wallet.publicKey,
+            1000000
+          ),
+        ])
+
+    try {
+      await depositMigratedMethod(new anchor.BN('1000000000000')).rpc()
+      assert.fail('minted fewer LP tokens than the minimum')
+    } catch (e) {
+      assert.include(e.toString(), 'SlippageLpExceeded')
+    }
+
+    await depositMigratedMethod(new anchor.BN(1)).rpc()
+    const [baseAfter, quoteAfter, lpAfter] = await Promise.all(
+      [baseMintWalletAta.address, quoteMintWalletAta.address, signerLp].map(
+        getBalance
+      )
+    )
+    assert.ok(lpAfter.gt(lpBefore))
+    // At most what was minted in, and none of the balance from before
+    assert.ok(baseAfter.gte(baseBefore) && quoteAfter.gte(quoteBefore))
+    assert.ok(
+      baseAfter.add(quoteAfter).lt(baseBefore.add(quoteBefore).addn(2000000))
     )
+  })
 
+  it('Can deposit for an owner who approved the pool as delegate', async () => {
+    const connection = program.provider.connection
+    const owner = Keypair.generate()
+    const [ownerBase, ownerQuote, ownerLp] = await Promise.all(
+      [baseMint, quoteMint, lpMint].map((mint) =>
+        getOrCreateAssociatedTokenAccount(
+          connection,
+          wallet,
+          mint,
+          owner.publicKey
+        )
+      )
+    )
     await mintTo(
-      program.provider.connection,
+      connection,
+      wallet,
+      baseMint,
+      ownerBase.address,
+      wallet,
+      1000000
+    )
+    await mintTo(
+      connection,
       wallet,
       quoteMint,
-      quoteMintWalletAta.address,
+      ownerQuote.address,
       wallet,
-      1000000000
+      1000000
+    )
+    const getBalance = async (account: PublicKey) =>
+      new anchor.BN(
+        (await connection.getTokenAccountBalance(account)).value.amount
+      )
+    const relayerLpBefore = await getBalance(signerLp)
+    const [intent] = PublicKey.findProgramAddressSync(
+      [
+        pool.toBuffer(),
+        owner.publicKey.toBuffer(),
+        Buffer.from('deposit-intent'),
+      ],
+      program.programId
     )
+    // Signed by the owner, with the wallet as relayer sponsoring the rent
+    const createIntentMethod = async (relayer: PublicKey) =>
+      program.methods
+        .createDepositIntent(
+          relayer,
+          new anchor.BN(1000000),
+          new anchor.BN(1000000),
+          new anchor.BN(1),
+          new anchor.BN(1),
+          new anchor.BN((await connection.getSlot()) + 1000)
+        )
+        .accounts({
+          pool,
+          intent,
+          owner: owner.publicKey,
+          rentPayer: wallet.publicKey,
+        })
+        .signers([owner])
+    // Executed by the wallet as relayer, without the owner signing
+    const depositDelegatedMethod = () =>
+      program.methods
+        .depositDelegated()
+        .accounts({
+          deposit: {
+            pool,
+            lpMint,
+            signerLp,
+            signerBase: baseMintWalletAta.address,
+            signerQuote: quoteMintWalletAta.address,
+            dexProgram: DEX_PID,
+            marketAccounts: {
+              market: market.publicKey,
+              requestQueue: market.decoded.requestQueue,
+              eventQueue: market.decoded.eventQueue,
+              bids: market.decoded.bids,
+              asks: market.decoded.asks,
+              baseVault: market.decoded.baseVault,
+              quoteVault: market.decoded.quoteVault,
+              vaultSigner: marketVaultSigner,
+              openOrders,
+            },
+          },
+          ownerBase: ownerBase.address,
+          ownerQuote: ownerQuote.address,
+          ownerLp: ownerLp.address,
+          intent,
+          rentPayer: wallet.publicKey,
+        })
+        .preInstructions([
+          ComputeBudgetProgram.setComputeUnitLimit({ units: 900000 }),
+        ])
+
+    await (await createIntentMethod(wallet.publicKey)).rpc()
+    try {
+      await depositDelegatedMethod().rpc()
+      assert.fail('deposited without an approval')
+    } catch (e) {
+      assert.include(e.toString(), 'WrongDelegate')
+    }
+
+    // Only the relayer the owner named can use the intent
+    await program.methods
+      .cancelDepositIntent()
+      .accounts({
+        intent,
+        owner: owner.publicKey,
+        rentPayer: wallet.publicKey,
+      })
+      .signers([owner])
+      .rpc()
+    await (await createIntentMethod(Keypair.generate().publicKey)).rpc()
 
-    market = await setupStableMarketWithOrders(
-      program.provider,
+    await approve(
+      connection,
       wallet,
-      baseMint,
-      quoteMint
+      ownerBase.address,
+      pool,
+      owner,
+      1000000
+    )
+    await approve(
+      connection,
+      wallet,
+      ownerQuote.address,
+      pool,
+      owner,
+      1000000
+    )
+    try {
+      await depositDelegatedMethod().rpc()
+      assert.fail('deposited as a relayer the owner did not name')
+    } catch (e) {
+      assert.include(e.toString(), 'WrongRelayer')
+    }
+    await program.methods
+      .cancelDepositIntent()
+      .accounts({
+        intent,
+        owner: owner.publicKey,
+        rentPayer: wallet.publicKey,
+      })
+      .signers([owner])
+      .rpc()
+    await (await createIntentMethod(wallet.publicKey)).rpc()
+
+    await depositDelegatedMethod().rpc()
+    const [baseAfter, quoteAfter, lpAfter, relayerLpAfter] = await Promise.all(
+      [ownerBase.address, ownerQuote.address, ownerLp.address, signerLp].map(
+        getBalance
+      )
     )
+    assert.ok(lpAfter.gtn(0))
+    assert.ok(relayerLpAfter.eq(relayerLpBefore))
+    // Only what the reserve ratio had room for was spent
+    assert.ok(baseAfter.add(quoteAfter).ltn(2000000))
+    // Each intent is good for one deposit
+    assert.isNull(await connection.getAccountInfo(intent))
+  })
 
-    const [bids, asks] = await getAllOrders(market, program.provider)
+  it('Can withdraw in two steps around a refresh', async () => {
+    const [ticket] = PublicKey.findProgramAddressSync(
+      [
+        pool.toBuffer(),
+        wallet.publicKey.toBuffer(),
+        Buffer.from('withdraw-ticket'),
+      ],
+      program.programId
+    )
+    const [ticketLp] = PublicKey.findProgramAddressSync(
+      [ticket.toBuffer(), Buffer.from('withdraw-ticket-lp')],
+      program.programId
+    )
+    const marketAccounts = {
+      market: market.publicKey,
+      requestQueue: market.decoded.requestQueue,
+      eventQueue: market.decoded.eventQueue,
+      bids: market.decoded.bids,
+      asks: market.decoded.asks,
+      baseVault: market.decoded.baseVault,
+      quoteVault: market.decoded.quoteVault,
+      vaultSigner: marketVaultSigner,
+      openOrders,
+    }
+    const lpBefore = await program.provider.connection.getTokenAccountBalance(
+      signerLp
+    )
+    const lpAmount = new anchor.BN(lpBefore.value.amount).divn(2)
+    const claimMethod = () =>
+      program.methods.claimWithdraw().accounts({
+        pool,
+        ticket,
+        ticketLp,
+        lpMint,
+        baseVault,
+        quoteVault,
+        ownerBase: baseMintWalletAta.address,
+        ownerQuote: quoteMintWalletAta.address,
+        owner: wallet.publicKey,
+      })
+    const setWithdrawDelayMethod = (withdrawDelaySlots: number) =>
+      program.methods
+        .setWithdrawDelay(new anchor.BN(withdrawDelaySlots))
+        .accounts({
+          pool,
+          authority: wallet.publicKey,
+        })
+
+    await setWithdrawDelayMethod(216000).rpc()
+
+    await program.methods
+      .requestWithdraw(lpAmount)
+      .accounts({
+        pool,
+        lpMint,
+        ownerLp: signerLp,
+        ticket,
+        ticketLp,
+        owner: wallet.publicKey,
+      })
+      .rpc()
 
-    assert.strictEqual(bids.length, 4)
-    assert.strictEqual(asks.length, 4)
+    let poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.ok(poolAccount.queuedWithdrawBaseAmount.gtn(0))
+    assert.ok(poolAccount.queuedWithdrawQuoteAmount.gtn(0))
+
+    // Nothing can be claimed until a ladder has left the queued share off
+    // the book
+    try {
+      await claimMethod().rpc()
+      assert.fail('claimed before a refresh')
+    } catch (e) {
+      assert.include(e.toString(), 'WithdrawNotClaimable')
+    }
+
+    await program.methods
+      .refreshOrders()
+      .accounts({
+        pool,
+        marketAccounts,
+        baseVault,
+        quoteVault,
+        signerBase: baseMintWalletAta.address,
+        signerQuote: quoteMintWalletAta.address,
+        dexProgram: DEX_PID,
+      })
+      .preInstructions([
+        ComputeBudgetProgram.setComputeUnitLimit({ units: 900000 }),
+      ])
+      .rpc()
+
+    // The pool's withdraw delay still holds the claim back
+    try {
+      await claimMethod().rpc()
+      assert.fail('claimed before the withdraw delay')
+    } catch (e) {
+      assert.include(e.toString(), 'WithdrawDelayNotElapsed')
+    }
+    await setWithdrawDelayMethod(0).rpc()
+
+    const baseBefore = await program.provider.connection.getTokenAccountBalance(
+      baseMintWalletAta.address
+    )
+    await claimMethod().rpc()
+
+    const baseAfter = await program.provider.connection.getTokenAccountBalance(
+      baseMintWalletAta.address
+    )
+    assert.ok(
+      new anchor.BN(baseAfter.value.amount).gt(
+        new anchor.BN(baseBefore.value.amount)
+      )
+    )
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.strictEqual(poolAccount.queuedWithdrawBaseAmount.toString(), '0')
+    assert.isNull(await program.provider.connection.getAccountInfo(ticket))
   })
 
-  it('Can create a stable pool', async () => {
-    pool = PublicKey.findProgramAddressSync(
+  it('Can airdrop to LP holders in a snapshot', async () => {
+    const poolAccount = await program.account.openAmmPool.fetch(pool)
+    const [snapshot] = PublicKey.findProgramAddressSync(
       [
-        market.publicKey.toBuffer(),
-        new Uint8Array([1]),
-        Buffer.from(POOL_SEED),
+        pool.toBuffer(),
+        poolAccount.snapshotCount.toArrayLike(Buffer, 'le', 8),
+        Buffer.from('lp-snapshot'),
       ],
       program.programId
-    )[0]
-
-    openOrders = PublicKey.findProgramAddressSync(
-      [pool.toBuffer(), Buffer.from('pool-open-orders')],
+    )
+    const [snapshotRewards] = PublicKey.findProgramAddressSync(
+      [snapshot.toBuffer(), Buffer.from('lp-snapshot-rewards')],
       program.programId
-    )[0]
-
-    lpMint = PublicKey.findProgramAddressSync(
-      [pool.toBuffer(), Buffer.from('pool-lp-mint')],
+    )
+    const [claim] = PublicKey.findProgramAddressSync(
+      [
+        snapshot.toBuffer(),
+        wallet.publicKey.toBuffer(),
+        Buffer.from('airdrop-claim'),
+      ],
       program.programId
-    )[0]
+    )
 
-    signerLp = await getAssociatedTokenAddress(lpMint, wallet.publicKey)
+    // A tree of one holder owning the whole supply, whose root is its leaf
+    const lpSupply = new anchor.BN(
+      (await program.provider.connection.getTokenSupply(lpMint)).value.amount
+    )
+    const leaf = createHash('sha256')
+      .update(Buffer.from([0]))
+      .update(wallet.publicKey.toBuffer())
+      .update(lpSupply.toArrayLike(Buffer, 'le', 8))
+      .digest()
+    const snapshotMethod = (supply: anchor.BN) =>
+      program.methods
+        .createSnapshot([...leaf], supply, new anchor.BN(1000))
+        .accounts({
+          pool,
+          lpMint,
+          snapshot,
+          rewardMint: quoteMint,
+          snapshotRewards,
+          authorityRewards: quoteMintWalletAta.address,
+          authority: wallet.publicKey,
+        })
+
+    try {
+      await snapshotMethod(lpSupply.addn(1)).rpc()
+      assert.fail('snapshotted more LP than the supply')
+    } catch (e) {
+      assert.include(e.toString(), 'WrongSnapshotSupply')
+    }
+    await snapshotMethod(lpSupply).rpc()
+
+    const claimMethod = (lpAmount: anchor.BN) =>
+      program.methods.claimAirdrop(lpAmount, []).accounts({
+        pool,
+        snapshot,
+        snapshotRewards,
+        claim,
+        ownerRewards: quoteMintWalletAta.address,
+        owner: wallet.publicKey,
+      })
 
-    marketVaultSigner = (await getVaultOwnerAndNonce(market.publicKey))[0]
+    try {
+      await claimMethod(lpSupply.addn(1)).rpc()
+      assert.fail('claimed for more LP than held')
+    } catch (e) {
+      assert.include(e.toString(), 'InvalidAirdropProof')
+    }
+    await claimMethod(lpSupply).rpc()
+
+    const snapshotAccount = await program.account.snapshot.fetch(snapshot)
+    assert.strictEqual(snapshotAccount.claimedAmount.toString(), '1000')
+    const rewards = await program.provider.connection.getTokenAccountBalance(
+      snapshotRewards
+    )
+    assert.strictEqual(rewards.value.amount, '0')
+
+    // The claim receipt stops a second claim
+    try {
+      await claimMethod(lpSupply).rpc()
+      assert.fail('claimed twice')
+    } catch (e) {
+      assert.include(e.toString(), 'already in use')
+    }
+  })
 
-    const additionalComputeBudgetInstruction =
-      ComputeBudgetProgram.setComputeUnitLimit({ units: 900000 })
+  it('Pays withdrawals from the vaults after an eviction, then restarts', async () => {
+    const walletAsAccount = new anchor.web3.Account(wallet.secretKey)
+    const marketAccounts = {
+      market: market.publicKey,
+      requestQueue: market.decoded.requestQueue,
+      eventQueue: market.decoded.eventQueue,
+      bids: market.decoded.bids,
+      asks: market.decoded.asks,
+      baseVault: market.decoded.baseVault,
+      quoteVault: market.decoded.quoteVault,
+      vaultSigner: marketVaultSigner,
+      openOrders,
+    }
+    const computeBudget = ComputeBudgetProgram.setComputeUnitLimit({
+      units: 800000,
+    })
 
-    const createPoolMethod = program.methods
-      .createPool(
-        { sTABLE: {} },
-        new anchor.BN('1000000000'),
-        new anchor.BN('1000000000')
-      )
+    // A fill refreshed with the keeper passed leaves its payout parked in
+    // the vaults alongside reserves
+    const keeper = PublicKey.findProgramAddressSync(
+      [wallet.publicKey.toBuffer(), Buffer.from('keeper')],
+      program.programId
+    )[0]
+    await program.methods
+      .registerKeeperPool()
+      .accounts({
+        pool,
+        keeper,
+        operator: wallet.publicKey,
+      })
+      .rpc()
+    let [bids, asks] = await getAllOrders(market, program.provider)
+    await market.placeOrder(program.provider.connection, {
+      owner: walletAsAccount,
+      payer: quoteMintWalletAta.address,
+      side: 'buy',
+      price: Math.min(
+        ...asks
+          .filter((ask) => ask.openOrdersAddress.equals(openOrders))
+          .map((ask) => ask.price)
+      ),
+      size: 1,
+      orderType: 'ioc',
+      feeDiscountPubkey: null,
+    })
+    await program.methods
+      .refreshOrders()
       .accounts({
-        baseMint,
-        quoteMint,
         pool,
+        marketAccounts,
+        baseVault,
+        quoteVault,
         signerBase: baseMintWalletAta.address,
         signerQuote: quoteMintWalletAta.address,
-        lpMint: lpMint,
-        signerLp,
-        openOrders,
         dexProgram: DEX_PID,
-        tokenProgram: TOKEN_PROGRAM_ID,
-        marketAccounts: {
-          market: market.publicKey,
-          requestQueue: market.decoded.requestQueue,
-          eventQueue: market.decoded.eventQueue,
-          bids: market.decoded.bids,
-          asks: market.decoded.asks,
-          baseVault: market.decoded.baseVault,
-          quoteVault: market.decoded.quoteVault,
-          vaultSigner: marketVaultSigner,
-          openOrders,
-        },
       })
-      .preInstructions([additionalComputeBudgetInstruction])
-
-    const pubkeys = await createPoolMethod.pubkeys()
-    baseVault = pubkeys.baseVault
-    quoteVault = pubkeys.quoteVault
-
-    await createPoolMethod.rpc()
-
-    const [bids, asks] = await getAllOrders(market, program.provider)
-
-    assert.strictEqual(asks.length, 14)
-    assert.strictEqual(bids.length, 13)
-
-    const lpAmount = await program.provider.connection.getTokenAccountBalance(
-      signerLp
+      .remainingAccounts([
+        { pubkey: keeper, isSigner: false, isWritable: true },
+      ])
+      .preInstructions([computeBudget])
+      .rpc()
+    let poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.isTrue(poolAccount.mmActive)
+    assert.ok(
+      poolAccount.keeperOwedBaseAmount
+        .add(poolAccount.keeperOwedQuoteAmount)
+        .gtn(0)
     )
-    assert.strictEqual(lpAmount.value.amount, '2000000000')
-    const poolAccount = await program.account.openAmmPool.fetch(pool)
-
-    assert.strictEqual(poolAccount.baseAmount.toString(), '1000000000')
-    assert.strictEqual(poolAccount.quoteAmount.toString(), '1000000000')
-  })
 
-  it('Can replace orders after a market order is filled, refunding the cranker', async () => {
-    const walletAsAccount = new anchor.web3.Account(wallet.secretKey)
+    // Taking every ask up to the pool's deepest one leaves the pool unable
+    // to tell a fill from an eviction, so the next refresh halts it
+    ;[bids, asks] = await getAllOrders(market, program.provider)
+    const poolAsks = asks.filter((ask) => ask.openOrdersAddress.equals(openOrders))
+    const deepestAskPrice = Math.max(...poolAsks.map((ask) => ask.price))
+    const sweptAsks = asks.filter((ask) => ask.price <= deepestAskPrice)
+    const sweepSize = sweptAsks.reduce((total, ask) => total + ask.size, 0)
+    const sweepCost = sweptAsks.reduce(
+      (total, ask) => total + ask.price * ask.size,
+      0
+    )
     await mintTo(
       program.provider.connection,
       wallet,
       quoteMint,
       quoteMintWalletAta.address,
       wallet,
-      100000000
-    )
-    let quoteBalance = await program.provider.connection.getTokenAccountBalance(
-      quoteMintWalletAta.address
+      Math.ceil(sweepCost * 2 * 10 ** 6)
     )
-    assert.strictEqual(quoteBalance.value.amount, '100000000')
-    let [bids, asks] = await getAllOrders(market, program.provider)
-
-    assert.strictEqual(bids.length, 13)
-    assert.strictEqual(asks.length, 14)
-
-    let poolAccount = await program.account.openAmmPool.fetch(pool)
-    assert.strictEqual(poolAccount.refundBaseAmount.toString(), '0')
-    assert.strictEqual(poolAccount.refundQuoteAmount.toString(), '0')
-
     await market.placeOrder(program.provider.connection, {
       owner: walletAsAccount,
       payer: quoteMintWalletAta.address,
-      side: 'buy', // 'buy' or 'sell'
-      price: 5,
-      size: 10,
-      orderType: 'ioc', // 'limit', 'ioc', 'postOnly'
+      side: 'buy',
+      price: deepestAskPrice,
+      size: sweepSize,
+      orderType: 'ioc',
       feeDiscountPubkey: null,
     })
 
+    await program.methods
+      .refreshOrders()
+      .accounts({
+        pool,
+        marketAccounts,
+        baseVault,
+        quoteVault,
+        signerBase: baseMintWalletAta.address,
+        signerQuote: quoteMintWalletAta.address,
+        dexProgram: DEX_PID,
+      })
+      .preInstructions([computeBudget])
+      .rpc()
+
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.isFalse(poolAccount.mmActive)
+    assert.ok(poolAccount.evictionBaseExposure.gtn(0))
     ;[bids, asks] = await getAllOrders(market, program.provider)
+    assert.isFalse(
+      [...bids, ...asks].some((order) => order.openOrdersAddress.equals(openOrders))
+    )
+
+    // Deposits can't be priced until a restart
+    try {
+      await program.methods
+        .deposit(
+          new anchor.BN('1000'),
+          new anchor.BN('1000'),
+          new anchor.BN('0'),
+          new anchor.BN('0')
+        )
+        .accounts({
+          pool,
+          lpMint,
+          signerLp,
+          signerBase: baseMintWalletAta.address,
+          signerQuote: quoteMintWalletAta.address,
+          dexProgram: DEX_PID,
+          marketAccounts,
+        })
+        .preInstructions([computeBudget])
+        .rpc()
+      assert.fail('deposited while market making was halted')
+    } catch (e) {
+      assert.include(e.toString(), 'MarketMakingInactive')
+    }
+
+    // Withdrawals pay out a share of what the vaults hold
+    const getBalance = async (account: PublicKey) =>
+      new anchor.BN(
+        (
+          await program.provider.connection.getTokenAccountBalance(account)
+        ).value.amount
+      )
+    const lpSupply = new anchor.BN(
+      (await program.provider.connection.getTokenSupply(lpMint)).value.amount
+    )
+    const lpAmount = lpSupply.divn(4)
+    const baseVaultBefore = await getBalance(baseVault)
+    const quoteVaultBefore = await getBalance(quoteVault)
+    const signerBaseBefore = await getBalance(baseMintWalletAta.address)
+    const signerQuoteBefore = await getBalance(quoteMintWalletAta.address)
+    await program.methods
+      .withdraw(lpAmount)
+      .accounts({
+        pool,
+        baseVault,
+        quoteVault,
+        lpMint,
+        signerLp,
+        signerBase: baseMintWalletAta.address,
+        signerQuote: quoteMintWalletAta.address,
+        dexProgram: DEX_PID,
+        marketAccounts,
+      })
+      .preInstructions([computeBudget])
+      .rpc()
+
+    const expectedBase = baseVaultBefore
+      .sub(poolAccount.refundBaseAmount)
+      .sub(poolAccount.keeperOwedBaseAmount)
+      .mul(lpAmount)
+      .div(lpSupply)
+    const expectedQuote = quoteVaultBefore
+      .sub(poolAccount.refundQuoteAmount)
+      .sub(poolAccount.keeperOwedQuoteAmount)
+      .mul(lpAmount)
+      .div(lpSupply)
+    assert.ok(expectedBase.gtn(0) && expectedQuote.gtn(0))
+    assert.strictEqual(
+      (await getBalance(baseMintWalletAta.address))
+        .sub(signerBaseBefore)
+        .toString(),
+      expectedBase.toString()
+    )
+    assert.strictEqual(
+      (await getBalance(quoteMintWalletAta.address))
+        .sub(signerQuoteBefore)
+        .toString(),
+      expectedQuote.toString()
+    )
     poolAccount = await program.account.openAmmPool.fetch(pool)
-    assert.strictEqual(poolAccount.refundBaseAmount.toString(), '0')
-    assert.strictEqual(poolAccount.refundQuoteAmount.toString(), '0')
+    assert.isFalse(poolAccount.mmActive)
+    ;[bids, asks] = await getAllOrders(market, program.provider)
+    assert.isFalse(
+      [...bids, ...asks].some((order) => order.openOrdersAddress.equals(openOrders))
+    )
 
-    assert.strictEqual(asks.length, 11)
-    assert.strictEqual(bids.length, 13)
+    const restartMethod = () =>
+      program.methods
+        .restartMarketMaking()
+        .accounts({
+          pool,
+          marketAccounts,
+          baseVault,
+          quoteVault,
+          signer: wallet.publicKey,
+          dexProgram: DEX_PID,
+        })
+        .preInstructions([computeBudget])
+
+    // Nothing cranks the market here, so the tokens the eviction left in
+    // open orders haven't come back yet
+    try {
+      await restartMethod().rpc()
+      assert.fail('restarted before the evicted tokens came back')
+    } catch (e) {
+      assert.include(e.toString(), 'OpenOrdersTokensLocked')
+    }
+
+    // Stand in for the evicted tokens settling back into the vaults
+    await Promise.all([
+      transfer(
+        program.provider.connection,
+        wallet,
+        baseMintWalletAta.address,
+        baseVault,
+        wallet,
+        BigInt(poolAccount.evictionBaseExposure.toString())
+      ),
+      transfer(
+        program.provider.connection,
+        wallet,
+        quoteMintWalletAta.address,
+        quoteVault,
+        wallet,
+        BigInt(poolAccount.evictionQuoteExposure.toString())
+      ),
+    ])
+    await restartMethod().rpc()
 
-    const quoteAmountBefore =
-      await program.provider.connection.getTokenAccountBalance(
-        quoteMintWalletAta.address
-      )
+    // Reserves leave out what the keeper is still owed, so claiming it
+    // doesn't take from the LPs
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.isTrue(poolAccount.mmActive)
+    assert.ok(
+      poolAccount.keeperOwedBaseAmount
+        .add(poolAccount.keeperOwedQuoteAmount)
+        .gtn(0)
+    )
+    assert.ok(
+      poolAccount.baseAmount
+        .add(poolAccount.refundBaseAmount)
+        .add(poolAccount.keeperOwedBaseAmount)
+        .lte(await getBalance(baseVault))
+    )
+    assert.ok(
+      poolAccount.quoteAmount
+        .add(poolAccount.refundQuoteAmount)
+        .add(poolAccount.keeperOwedQuoteAmount)
+        .lte(await getBalance(quoteVault))
+    )
 
-    const additionalComputeBudgetInstruction =
-      ComputeBudgetProgram.setComputeUnitLimit({ units: 900000 })
+    await program.methods
+      .claimKeeperRewards()
+      .accounts({
+        keeper,
+        operator: wallet.publicKey,
+      })
+      .remainingAccounts(
+        [
+          pool,
+          baseVault,
+          quoteVault,
+          baseMintWalletAta.address,
+          quoteMintWalletAta.address,
+        ].map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+      )
+      .rpc()
+    poolAccount = await program.account.openAmmPool.fetch(pool)
+    assert.strictEqual(poolAccount.keeperOwedBaseAmount.toString(), '0')
+    assert.strictEqual(poolAccount.keeperOwedQuoteAmount.toString(), '0')
+    assert.ok(
+      poolAccount.baseAmount
+        .add(poolAccount.refundBaseAmount)
+        .lte(await getBalance(baseVault))
+    )
+    assert.ok(
+      poolAccount.quoteAmount
+        .add(poolAccount.refundQuoteAmount)
+        .lte(await getBalance(quoteVault))
+    )
 
-    const refreshMethod = program.methods
+    await program.methods
       .refreshOrders()
       .accounts({
         pool,
-        marketAccounts: {
-          market: market.publicKey,
-          requestQueue: market.decoded.requestQueue,
-          eventQueue: market.decoded.eventQueue,
-          bids: market.decoded.bids,
-          asks: market.decoded.asks,
-          baseVault: market.decoded.baseVault,
-          quoteVault: market.decoded.quoteVault,
-          vaultSigner: marketVaultSigner,
-          openOrders,
-        },
+        marketAccounts,
         baseVault,
+        quoteVault,
         signerBase: baseMintWalletAta.address,
         signerQuote: quoteMintWalletAta.address,
-        quoteVault,
         dexProgram: DEX_PID,
       })
-      .preInstructions([additionalComputeBudgetInstruction])
-
-    await refreshMethod.rpc()
-
+      .preInstructions([computeBudget])
+      .rpc()
     ;[bids, asks] = await getAllOrders(market, program.provider)
-    assert.strictEqual(asks.length, 14)
-    assert.strictEqual(bids.length, 13)
+    assert.isTrue(
+      bids.some((bid) => bid.openOrdersAddress.equals(openOrders)) &&
+        asks.some((ask) => ask.openOrdersAddress.equals(openOrders))
+    )
+  })
 
-    const quoteAmountAfter =
-      await program.provider.connection.getTokenAccountBalance(
-        quoteMintWalletAta.address
-      )
+  it('Spaces a geometric ladder\'s rungs by the configured ratio', async () => {
+    // A market of its own, so no other pool's orders shape the ladder
+    const geometricMarket = await setupTestMarket(
+      program.provider,
+      wallet,
+      baseMint,
+      quoteMint
+    )
+    const [geometricPool] = PublicKey.findProgramAddressSync(
+      [
+        geometricMarket.publicKey.toBuffer(),
+        new Uint8Array([0]),
+        Buffer.from(POOL_SEED),
+      ],
+      program.programId
+    )
+    const [geometricOpenOrders] = PublicKey.findProgramAddressSync(
+      [geometricPool.toBuffer(), Buffer.from(OPEN_ORDERS_SEED)],
+      program.programId
+    )
+    const [geometricLpMint] = PublicKey.findProgramAddressSync(
+      [geometricPool.toBuffer(), Buffer.from('pool-lp-mint')],
+      program.programId
+    )
+    const marketAccounts = {
+      market: geometricMarket.publicKey,
+      requestQueue: geometricMarket.decoded.requestQueue,
+      eventQueue: geometricMarket.decoded.eventQueue,
+      bids: geometricMarket.decoded.bids,
+      asks: geometricMarket.decoded.asks,
+      baseVault: geometricMarket.decoded.baseVault,
+      quoteVault: geometricMarket.decoded.quoteVault,
+      vaultSigner: (await getVaultOwnerAndNonce(geometricMarket.publicKey))[0],
+      openOrders: geometricOpenOrders,
+    }
+    const computeBudget = ComputeBudgetProgram.setComputeUnitLimit({
+      units: 900000,
+    })
+    await Promise.all([
+      mintTo(
+        program.provider.connection,
+        wallet,
+        baseMint,
+        baseMintWalletAta.address,
+        wallet,
+        1000000000
+      ),
+      mintTo(
+        program.provider.connection,
+        wallet,
+        quoteMint,
+        quoteMintWalletAta.address,
+        wallet,
+        1000000000
+      ),
+    ])
 
-    // Should have gained some quote refund
-    assert.ok(
-      new anchor.BN(quoteAmountBefore.value.amount).lt(
-        new anchor.BN(quoteAmountAfter.value.amount)
+    const ladderStepBps = 200
+    const createPoolMethod = program.methods
+      .createPool(
+        { xYK: {} },
+        new anchor.BN('1000000000'),
+        new anchor.BN('1000000000'),
+        { gEOMETRIC: {} },
+        ladderStepBps,
+        0
       )
-    )
+      .accounts({
+        baseMint,
+        quoteMint,
+        pool: geometricPool,
+        signerBase: baseMintWalletAta.address,
+        signerQuote: quoteMintWalletAta.address,
+        lpMint: geometricLpMint,
+        signerLp: await getAssociatedTokenAddress(
+          geometricLpMint,
+          wallet.publicKey
+        ),
+        openOrders: geometricOpenOrders,
+        dexProgram: DEX_PID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        marketAccounts,
+      })
+      .preInstructions([computeBudget])
+    const pubkeys = await createPoolMethod.pubkeys()
+    await createPoolMethod.rpc()
+
+    await program.methods
+      .refreshOrders()
+      .accounts({
+        pool: geometricPool,
+        marketAccounts,
+        baseVault: pubkeys.baseVault,
+        quoteVault: pubkeys.quoteVault,
+        signerBase: baseMintWalletAta.address,
+        signerQuote: quoteMintWalletAta.address,
+        dexProgram: DEX_PID,
+      })
+      .preInstructions([computeBudget])
+      .rpc()
+
+    const poolAccount = await program.account.openAmmPool.fetch(geometricPool)
+    assert.deepEqual(poolAccount.ladderSpacing, { gEOMETRIC: {} })
+    assert.strictEqual(poolAccount.ladderStepBps, ladderStepBps)
+
+    // Each rung's price is the one before it moved by the step, up to a
+    // tick or two of rounding on prices of around a thousand ticks
+    const ratio = 1 + ladderStepBps / 10000
+    const getRungPrices = (placed: any) =>
+      placed.orders
+        .slice(0, placed.len.toNumber())
+        .filter((order: any) => order.baseQty.gtn(0))
+        .map((order: any) => order.limitPrice.toNumber())
+    const askPrices = getRungPrices(poolAccount.placedAsks)
+    const bidPrices = getRungPrices(poolAccount.placedBids)
+    assert.isTrue(askPrices.length > 2 && bidPrices.length > 2)
+    for (let i = 1; i < askPrices.length; i++) {
+      assert.closeTo(askPrices[i] / askPrices[i - 1], ratio, 0.005)
+    }
+    for (let i = 1; i < bidPrices.length; i++) {
+      assert.closeTo(bidPrices[i - 1] / bidPrices[i], ratio, 0.005)
+    }
+
+    const [bids, asks] = await getAllOrders(geometricMarket, program.provider)
+    assert.strictEqual(asks.length, askPrices.length)
+    assert.strictEqual(bids.length, bidPrices.length)
   })
 })